@@ -0,0 +1,65 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use voice_core::audio::{convert_to_mono, convert_to_whisper_format, resample};
+
+const SOURCE_RATE: u32 = 48000;
+const SOURCE_CHANNELS: u16 = 2;
+
+/// Clip lengths in seconds: a quick dictation, a long dictation, and a
+/// meeting-sized chunk.
+const CLIP_SECONDS: [u32; 3] = [5, 60, 600];
+
+fn synthetic_clip(seconds: u32, rate: u32, channels: u16) -> Vec<f32> {
+    let len = (seconds * rate) as usize * channels as usize;
+    (0..len).map(|i| ((i as f32) * 0.01).sin() * 0.5).collect()
+}
+
+fn bench_convert_to_mono(c: &mut Criterion) {
+    let mut group = c.benchmark_group("convert_to_mono");
+    for seconds in CLIP_SECONDS {
+        let clip = synthetic_clip(seconds, SOURCE_RATE, SOURCE_CHANNELS);
+        group.throughput(Throughput::Elements(clip.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(seconds), &clip, |b, clip| {
+            b.iter(|| convert_to_mono(black_box(clip), SOURCE_CHANNELS as usize))
+        });
+    }
+    group.finish();
+}
+
+fn bench_resample(c: &mut Criterion) {
+    let mut group = c.benchmark_group("resample");
+    for (from_rate, label) in [(48000, "48k"), (44100, "44.1k")] {
+        for seconds in CLIP_SECONDS {
+            let clip = synthetic_clip(seconds, from_rate, 1);
+            group.throughput(Throughput::Elements(clip.len() as u64));
+            group.bench_with_input(BenchmarkId::new(label, seconds), &clip, |b, clip| {
+                b.iter(|| resample(black_box(clip), from_rate, 16000))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_convert_to_whisper_format(c: &mut Criterion) {
+    let mut group = c.benchmark_group("convert_to_whisper_format");
+    group.sample_size(10);
+    for seconds in CLIP_SECONDS {
+        let clip = synthetic_clip(seconds, SOURCE_RATE, SOURCE_CHANNELS);
+        group.throughput(Throughput::Elements(clip.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(seconds), &clip, |b, clip| {
+            b.iter_batched(
+                || clip.clone(),
+                |clip| convert_to_whisper_format(clip, SOURCE_RATE, SOURCE_CHANNELS),
+                criterion::BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_convert_to_mono,
+    bench_resample,
+    bench_convert_to_whisper_format
+);
+criterion_main!(benches);
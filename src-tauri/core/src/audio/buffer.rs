@@ -0,0 +1,277 @@
+//! Holds a recording's samples in fixed-size chunks. Once more than a set
+//! amount is in memory the oldest chunks move to a file only this user can
+//! read, so a recording of any length takes bounded RAM while it runs.
+
+use crate::files::{create_private, spill_dir};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Samples per chunk: 256 KiB, about 1.4 seconds of 48 kHz stereo.
+const CHUNK_SAMPLES: usize = 1 << 16;
+
+const SAMPLE_BYTES: usize = std::mem::size_of::<f32>();
+
+/// Tells apart the spill files of recorders in the same process.
+static SPILL_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// The oldest samples of a recording, moved out of memory.
+struct Spill {
+    file: File,
+    path: PathBuf,
+    /// Samples in the file.
+    len: usize,
+}
+
+impl Spill {
+    fn create() -> std::io::Result<Self> {
+        let path = spill_dir().join(format!(
+            "voice-recording-{}-{}.f32",
+            std::process::id(),
+            SPILL_FILES.fetch_add(1, Ordering::Relaxed)
+        ));
+        // Left behind by an earlier run that had the same process id.
+        let _ = std::fs::remove_file(&path);
+        let file = create_private(&path)?;
+        Ok(Self { file, path, len: 0 })
+    }
+
+    fn write(&mut self, samples: &[f32]) -> std::io::Result<()> {
+        let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        // Reading back moves the position, so it's set before each write.
+        self.file
+            .seek(SeekFrom::Start((self.len * SAMPLE_BYTES) as u64))?;
+        self.file.write_all(&bytes)?;
+        self.len += samples.len();
+        Ok(())
+    }
+
+    /// Appends the samples from `start` to the end of the file to `samples`,
+    /// a chunk at a time so reading back takes little more memory than the
+    /// samples themselves.
+    fn read_from(&mut self, start: usize, samples: &mut Vec<f32>) -> std::io::Result<()> {
+        self.file
+            .seek(SeekFrom::Start((start * SAMPLE_BYTES) as u64))?;
+        let mut bytes = vec![0u8; CHUNK_SAMPLES * SAMPLE_BYTES];
+        let mut left = self.len - start;
+        while left > 0 {
+            let count = left.min(CHUNK_SAMPLES);
+            let bytes = &mut bytes[..count * SAMPLE_BYTES];
+            self.file.read_exact(bytes)?;
+            samples.extend(
+                bytes
+                    .chunks_exact(SAMPLE_BYTES)
+                    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+            );
+            left -= count;
+        }
+        Ok(())
+    }
+}
+
+pub struct SampleBuffer {
+    chunks: VecDeque<Vec<f32>>,
+    /// Samples in `chunks`.
+    in_memory: usize,
+    /// Samples kept in memory before the oldest chunks are spilled.
+    memory_limit: usize,
+    spill: Option<Spill>,
+    /// Set once spilling fails, after which everything stays in memory.
+    spill_failed: bool,
+}
+
+impl SampleBuffer {
+    /// Keeps at most `memory_limit` samples in memory, give or take the
+    /// chunk being filled. `usize::MAX` keeps everything in memory.
+    pub fn new(memory_limit: usize) -> Self {
+        Self {
+            chunks: VecDeque::new(),
+            in_memory: 0,
+            memory_limit,
+            spill: None,
+            spill_failed: false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.spill.as_ref().map_or(0, |spill| spill.len) + self.in_memory
+    }
+
+    /// Samples held in memory rather than on disk.
+    pub fn in_memory(&self) -> usize {
+        self.in_memory
+    }
+
+    pub fn set_memory_limit(&mut self, memory_limit: usize) {
+        self.memory_limit = memory_limit;
+        self.spill_excess();
+    }
+
+    pub fn extend_from_slice(&mut self, mut data: &[f32]) {
+        while !data.is_empty() {
+            let chunk = match self.chunks.back_mut() {
+                Some(chunk) if chunk.len() < CHUNK_SAMPLES => chunk,
+                _ => {
+                    self.chunks.push_back(Vec::with_capacity(CHUNK_SAMPLES));
+                    self.chunks.back_mut().expect("a chunk was just added")
+                }
+            };
+            let (now, rest) = data.split_at((CHUNK_SAMPLES - chunk.len()).min(data.len()));
+            chunk.extend_from_slice(now);
+            self.in_memory += now.len();
+            data = rest;
+        }
+        self.spill_excess();
+    }
+
+    /// The last `count` samples, or all of them if there are fewer.
+    pub fn tail(&mut self, count: usize) -> Vec<f32> {
+        self.read_from(self.len().saturating_sub(count))
+    }
+
+    /// Everything recorded, leaving the buffer empty. Chunks are freed as
+    /// they are copied, so this takes little more memory than the result.
+    pub fn take(&mut self) -> Vec<f32> {
+        let mut samples = Vec::with_capacity(self.len());
+        self.read_spill(0, &mut samples);
+        while let Some(chunk) = self.chunks.pop_front() {
+            samples.extend_from_slice(&chunk);
+        }
+        self.clear();
+        samples
+    }
+
+    /// Moves everything but the chunk being filled to disk now, whatever the
+    /// limit, to free memory.
+    pub fn spill_all(&mut self) {
+        let memory_limit = self.memory_limit;
+        self.memory_limit = 0;
+        self.spill_excess();
+        self.memory_limit = memory_limit;
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+        self.in_memory = 0;
+        self.spill_failed = false;
+        self.remove_spill();
+    }
+
+    /// Moves whole chunks, oldest first, to the spill file until no more
+    /// than the limit is in memory. The chunk being filled always stays.
+    fn spill_excess(&mut self) {
+        while self.in_memory > self.memory_limit && self.chunks.len() > 1 && !self.spill_failed {
+            let spill = match self.spill.as_mut() {
+                Some(spill) => Ok(spill),
+                None => Spill::create().map(|spill| self.spill.insert(spill)),
+            };
+            match spill.and_then(|spill| spill.write(&self.chunks[0])) {
+                Ok(()) => {
+                    if let Some(chunk) = self.chunks.pop_front() {
+                        self.in_memory -= chunk.len();
+                    }
+                }
+                Err(e) => {
+                    eprintln!(
+                        "Failed to move recording audio to disk, keeping it in memory: {}",
+                        e
+                    );
+                    self.spill_failed = true;
+                }
+            }
+        }
+    }
+
+    fn read_from(&mut self, start: usize) -> Vec<f32> {
+        let mut samples = Vec::with_capacity(self.len() - start);
+        self.read_spill(start, &mut samples);
+        let mut skip = start.saturating_sub(self.spill.as_ref().map_or(0, |spill| spill.len));
+        for chunk in &self.chunks {
+            if skip < chunk.len() {
+                samples.extend_from_slice(&chunk[skip..]);
+            }
+            skip = skip.saturating_sub(chunk.len());
+        }
+        samples
+    }
+
+    /// Appends the spilled samples from `start` on to `samples`.
+    fn read_spill(&mut self, start: usize, samples: &mut Vec<f32>) {
+        let Some(spill) = self.spill.as_mut().filter(|spill| start < spill.len) else {
+            return;
+        };
+        let before = samples.len();
+        if let Err(e) = spill.read_from(start, samples) {
+            // Silence keeps the rest of the recording in its place.
+            eprintln!("Lost recording audio moved to disk: {}", e);
+            samples.resize(before + spill.len - start, 0.0);
+        }
+    }
+
+    fn remove_spill(&mut self) {
+        if let Some(Spill { file, path, .. }) = self.spill.take() {
+            // Windows won't remove a file that is still open.
+            drop(file);
+            if let Err(e) = std::fs::remove_file(&path) {
+                eprintln!("Failed to remove {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+impl Drop for SampleBuffer {
+    fn drop(&mut self) {
+        self.remove_spill();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two and a half chunks of samples counting up, with all but the one
+    /// being filled spilled to disk.
+    fn spilled() -> (SampleBuffer, Vec<f32>) {
+        let samples: Vec<f32> = (0..CHUNK_SAMPLES * 5 / 2).map(|i| i as f32).collect();
+        let mut buffer = SampleBuffer::new(1);
+        buffer.extend_from_slice(&samples);
+        assert_eq!(buffer.in_memory(), CHUNK_SAMPLES / 2);
+        (buffer, samples)
+    }
+
+    #[test]
+    fn take_returns_spilled_and_in_memory_samples_in_order() {
+        let (mut buffer, samples) = spilled();
+        let path = buffer.spill.as_ref().unwrap().path.clone();
+        assert_eq!(buffer.take(), samples);
+        assert_eq!(buffer.len(), 0);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn tail_reads_across_the_spill_boundary() {
+        let (mut buffer, samples) = spilled();
+        let count = CHUNK_SAMPLES;
+        assert_eq!(buffer.tail(count), &samples[samples.len() - count..]);
+        assert_eq!(buffer.tail(usize::MAX), samples);
+        // Reading back leaves the buffer as it was, and more can be added.
+        buffer.extend_from_slice(&[-1.0; CHUNK_SAMPLES]);
+        let mut expected = samples;
+        expected.extend([-1.0; CHUNK_SAMPLES]);
+        assert_eq!(buffer.take(), expected);
+    }
+
+    #[test]
+    fn spill_all_keeps_only_the_chunk_being_filled() {
+        let samples: Vec<f32> = (0..CHUNK_SAMPLES * 3 / 2).map(|i| i as f32).collect();
+        let mut buffer = SampleBuffer::new(usize::MAX);
+        buffer.extend_from_slice(&samples);
+        buffer.spill_all();
+        assert_eq!(buffer.in_memory(), CHUNK_SAMPLES / 2);
+        buffer.extend_from_slice(&samples);
+        assert_eq!(buffer.in_memory(), CHUNK_SAMPLES * 2);
+        assert_eq!(buffer.tail(usize::MAX).len(), samples.len() * 2);
+    }
+}
@@ -0,0 +1,1302 @@
+use super::buffer::SampleBuffer;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use serde::Serialize;
+use std::any::Any;
+use std::collections::VecDeque;
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// The device name that records what the machine is playing (calls, videos)
+/// instead of a microphone. Listed by [`list_input_devices`] where it works.
+pub const SYSTEM_AUDIO_DEVICE: &str = "System Audio";
+
+/// The device name that records audio streamed from another machine on the
+/// network, such as a phone with a better microphone. It receives RTP (RFC
+/// 3550) carrying 16-bit big-endian mono PCM at [`NETWORK_SAMPLE_RATE`] on
+/// UDP port [`NETWORK_MICROPHONE_PORT`], which is what
+/// `ffmpeg -i <input> -ac 1 -ar 48000 -acodec pcm_s16be -ssrc <ssrc> -f rtp rtp://<host>:47821`
+/// sends. Opened with [`AudioRecorder::network`].
+pub const NETWORK_MICROPHONE_DEVICE: &str = "Network Microphone";
+
+pub const NETWORK_MICROPHONE_PORT: u16 = 47821;
+
+pub const NETWORK_SAMPLE_RATE: u32 = 48000;
+
+/// How often the network receiver checks whether it should stop.
+const NETWORK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// RTP payload types from 96 up are assigned per session, which is where
+/// 16-bit PCM at 48 kHz ends up; the fixed ones below are other formats.
+const DYNAMIC_PAYLOAD_TYPES: std::ops::RangeInclusive<u8> = 96..=127;
+
+/// Packets that arrive after a missing one are held back this many at most
+/// waiting for it, before it is given up for lost.
+const REORDER_DEPTH: usize = 8;
+
+/// How [`NETWORK_MICROPHONE_DEVICE`] listens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkMicrophone {
+    /// The address to listen on: unspecified for every interface, or one
+    /// such as the machine's Tailscale address to only take audio from that
+    /// network.
+    pub address: IpAddr,
+    /// The RTP SSRC the sender has to use, which pairs it: streams with any
+    /// other are ignored.
+    pub ssrc: u32,
+}
+
+/// A random SSRC to pair a network microphone with.
+pub fn generate_ssrc() -> u32 {
+    let mut bytes = [0u8; 4];
+    getrandom::fill(&mut bytes).expect("the OS random number generator is available");
+    u32::from_be_bytes(bytes)
+}
+
+/// Virtual drivers that route the Mac's output back in as an input device.
+#[cfg(target_os = "macos")]
+const LOOPBACK_DRIVERS: [&str; 3] = ["BlackHole", "Loopback Audio", "Soundflower"];
+
+pub fn list_input_devices() -> Result<Vec<String>, String> {
+    let host = cpal::default_host();
+    let mut devices: Vec<String> = host
+        .input_devices()
+        .map_err(|e| format!("Failed to enumerate input devices: {}", e))?
+        .filter_map(|d| d.name().ok())
+        .collect();
+    if system_audio_source(&host).is_some() {
+        devices.push(SYSTEM_AUDIO_DEVICE.to_string());
+    }
+    devices.push(NETWORK_MICROPHONE_DEVICE.to_string());
+    Ok(devices)
+}
+
+/// On Windows, WASAPI records the default output device in loopback mode
+/// when it is opened for input.
+#[cfg(target_os = "windows")]
+fn system_audio_source(host: &cpal::Host) -> Option<(cpal::Device, cpal::SupportedStreamConfig)> {
+    let device = host.default_output_device()?;
+    let config = device.default_output_config().ok()?;
+    Some((device, config))
+}
+
+/// macOS only shares its output with apps through a loopback driver such as
+/// BlackHole, which shows up as an input device. Capturing it natively with
+/// ScreenCaptureKit or a Core Audio process tap needs the screen recording
+/// permission and Objective-C bindings Voice doesn't have, so it isn't done.
+#[cfg(target_os = "macos")]
+fn system_audio_source(host: &cpal::Host) -> Option<(cpal::Device, cpal::SupportedStreamConfig)> {
+    let device = host.input_devices().ok()?.find(|device| {
+        device
+            .name()
+            .is_ok_and(|name| LOOPBACK_DRIVERS.iter().any(|driver| name.contains(driver)))
+    })?;
+    let config = device.default_input_config().ok()?;
+    Some((device, config))
+}
+
+/// Why [`SYSTEM_AUDIO_DEVICE`] can't be opened.
+#[cfg(target_os = "macos")]
+const NO_SYSTEM_AUDIO: &str =
+    "Recording system audio on macOS needs a loopback driver such as BlackHole";
+
+#[cfg(not(target_os = "macos"))]
+const NO_SYSTEM_AUDIO: &str = "System audio can't be recorded on this machine";
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn system_audio_source(_host: &cpal::Host) -> Option<(cpal::Device, cpal::SupportedStreamConfig)> {
+    None
+}
+
+fn find_input_device(host: &cpal::Host, name: &str) -> Option<cpal::Device> {
+    host.input_devices()
+        .ok()?
+        .find(|device| device.name().is_ok_and(|n| n == name))
+}
+
+/// Sample rates a driver could plausibly be delivering when it misreports.
+const STANDARD_SAMPLE_RATES: [u32; 10] = [
+    8000, 11025, 16000, 22050, 24000, 32000, 44100, 48000, 88200, 96000,
+];
+
+/// Relative deviation between reported and measured rate that counts as a mismatch.
+const RATE_MISMATCH_TOLERANCE: f64 = 0.05;
+
+/// Minimum capture time before the measured rate is trusted.
+const RATE_PROBE_MIN_DURATION: Duration = Duration::from_secs(1);
+
+/// Audio arriving this much later than the previous callback's frames
+/// account for is counted as dropped. Smaller differences are timestamp
+/// jitter.
+const DROPOUT_TOLERANCE: Duration = Duration::from_millis(5);
+
+/// Dropouts longer than this are only marked, not filled in: a straight line
+/// across that much missing audio would be heard as a tone, not a blip.
+const MAX_REPAIRED_DROPOUT: Duration = Duration::from_millis(250);
+
+/// Dropouts remembered per recording. A driver failing this often has
+/// ruined the recording anyway; further dropouts are only counted.
+const MAX_DROPOUTS: usize = 1000;
+
+/// The most audio a recorder keeps from before each start.
+pub const MAX_PREROLL: Duration = Duration::from_secs(2);
+
+pub enum RecorderCommand {
+    /// Starts recording, for at most the given duration if there is one.
+    Start(Option<Sender<f32>>, Option<Duration>),
+    /// Sent by the stream itself once a recording reaches its limit.
+    LimitReached,
+    Stop(Sender<RecordedAudio>),
+    /// Copies up to this many of the latest frames without stopping.
+    Snapshot(usize, Sender<RecordedAudio>),
+    /// Takes everything recorded so far and keeps recording.
+    Drain(Sender<RecordedAudio>),
+    /// How much audio from before each start to keep, holding the stream
+    /// open while idle. Zero closes it between recordings again.
+    SetPreroll(Duration),
+    /// The most recorded samples to hold in memory before spilling the
+    /// oldest to disk.
+    SetMemoryLimit(usize),
+    /// Moves as much of the recording as it can to disk now.
+    Spill,
+}
+
+pub struct RecordedAudio {
+    pub samples: Vec<f32>,
+    /// Rate the samples should be interpreted at. Differs from
+    /// `reported_sample_rate` when the driver was caught misreporting.
+    pub sample_rate: u32,
+    pub reported_sample_rate: u32,
+    pub channels: u16,
+    /// How the recording's audio arrived, from its start until now.
+    pub stats: CaptureStats,
+    /// Where the driver lost audio in `samples`, in order.
+    pub dropouts: Vec<Dropout>,
+}
+
+/// Audio the driver lost between two callbacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dropout {
+    /// The recorded frame the lost audio belonged before.
+    pub frame: usize,
+    /// Frames lost, estimated from the capture timestamps.
+    pub missing: usize,
+}
+
+/// A stretch of a recording that a dropout may have damaged, in seconds from
+/// its start. Zero-length where the audio either side was simply joined.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct DropoutRange {
+    pub start: f32,
+    pub end: f32,
+}
+
+impl RecordedAudio {
+    pub fn sample_rate_mismatch(&self) -> bool {
+        self.sample_rate != self.reported_sample_rate
+    }
+
+    /// Where the audio either side of each dropout was joined.
+    pub fn dropout_ranges(&self) -> Vec<DropoutRange> {
+        let rate = self.sample_rate as f32;
+        self.dropouts
+            .iter()
+            .map(|dropout| {
+                let at = dropout.frame as f32 / rate;
+                DropoutRange { start: at, end: at }
+            })
+            .collect()
+    }
+
+    /// Fills each short dropout with a straight line from the frame before
+    /// it to the one after, so the audio after it is back in its place in
+    /// time, and returns the stretches filled in. Longer dropouts are left
+    /// joined and returned as zero-length ranges. Empties `dropouts`.
+    pub fn repair_dropouts(&mut self) -> Vec<DropoutRange> {
+        let dropouts = std::mem::take(&mut self.dropouts);
+        if dropouts.is_empty() {
+            return Vec::new();
+        }
+        let channels = self.channels.max(1) as usize;
+        let rate = self.sample_rate as f32;
+        let max_missing = (MAX_REPAIRED_DROPOUT.as_secs_f32() * rate) as usize;
+        let filled: usize = dropouts
+            .iter()
+            .map(|dropout| dropout.missing)
+            .filter(|missing| *missing <= max_missing)
+            .sum();
+
+        let mut repaired = Vec::with_capacity(self.samples.len() + filled * channels);
+        let mut ranges = Vec::with_capacity(dropouts.len());
+        let mut copied = 0;
+        for dropout in dropouts {
+            let at = (dropout.frame * channels).min(self.samples.len());
+            repaired.extend_from_slice(&self.samples[copied..at]);
+            copied = at;
+            let start = (repaired.len() / channels) as f32 / rate;
+            if dropout.missing > max_missing {
+                ranges.push(DropoutRange { start, end: start });
+                continue;
+            }
+            for frame in 1..=dropout.missing {
+                let t = frame as f32 / (dropout.missing + 1) as f32;
+                for channel in 0..channels {
+                    let after = self.samples.get(at + channel).copied();
+                    let before = at
+                        .checked_sub(channels)
+                        .map(|previous| self.samples[previous + channel]);
+                    let (before, after) = match (before, after) {
+                        (Some(before), Some(after)) => (before, after),
+                        (Some(only), None) | (None, Some(only)) => (only, only),
+                        (None, None) => (0.0, 0.0),
+                    };
+                    repaired.push(before + (after - before) * t);
+                }
+            }
+            let end = start + dropout.missing as f32 / rate;
+            ranges.push(DropoutRange { start, end });
+        }
+        repaired.extend_from_slice(&self.samples[copied..]);
+        self.samples = repaired;
+        ranges
+    }
+}
+
+/// Tracks how fast the driver actually delivers frames.
+#[derive(Default)]
+struct RateProbe {
+    first_callback: Option<Instant>,
+    last_callback: Option<Instant>,
+    frames_after_first: u64,
+}
+
+impl RateProbe {
+    fn record(&mut self, frames: usize) {
+        let now = Instant::now();
+        if self.first_callback.is_none() {
+            // Frames in the first callback were captured before we started
+            // timing, so only count what arrives afterwards.
+            self.first_callback = Some(now);
+        } else {
+            self.frames_after_first += frames as u64;
+        }
+        self.last_callback = Some(now);
+    }
+
+    fn measured_rate(&self) -> Option<f64> {
+        let elapsed = self.last_callback?.duration_since(self.first_callback?);
+        if elapsed < RATE_PROBE_MIN_DURATION {
+            return None;
+        }
+        Some(self.frames_after_first as f64 / elapsed.as_secs_f64())
+    }
+
+    /// Returns the rate to use for conversion, snapping to the nearest
+    /// standard rate when the measured delivery rate disagrees with the
+    /// reported one.
+    fn effective_rate(&self, reported: u32) -> u32 {
+        let Some(measured) = self.measured_rate() else {
+            return reported;
+        };
+
+        let deviation = (measured - reported as f64).abs() / reported as f64;
+        if deviation <= RATE_MISMATCH_TOLERANCE {
+            return reported;
+        }
+
+        let corrected = STANDARD_SAMPLE_RATES
+            .iter()
+            .copied()
+            .min_by(|a, b| {
+                let da = (*a as f64 - measured).abs();
+                let db = (*b as f64 - measured).abs();
+                da.total_cmp(&db)
+            })
+            .unwrap_or(reported);
+
+        eprintln!(
+            "Input device reports {} Hz but delivered ~{:.0} Hz; using {} Hz",
+            reported, measured, corrected
+        );
+        corrected
+    }
+}
+
+/// How smoothly a recording's audio arrived from the driver, so choppy audio
+/// can be told apart from a transcription problem.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CaptureStats {
+    /// Times the driver handed over audio.
+    pub callbacks: u64,
+    pub frames: u64,
+    /// Mean wall-clock time between callbacks.
+    pub average_interval_ms: f32,
+    /// The longest wait between callbacks; a stalled machine shows up here.
+    pub max_interval_ms: f32,
+    /// Frames missing between callbacks, estimated from the driver's capture
+    /// timestamps. Drivers that don't timestamp capture never report any.
+    pub dropped_frames: u64,
+    /// Errors the driver reported mid-stream, which are overruns (xruns)
+    /// on ALSA and JACK.
+    pub xruns: u64,
+}
+
+/// Keeps [`CaptureStats`] up to date from the recorder's callbacks.
+struct CaptureWatchdog {
+    stats: CaptureStats,
+    sample_rate: u32,
+    first_callback: Option<Instant>,
+    last_callback: Option<Instant>,
+    /// When the previous callback's audio was captured, counted from the
+    /// stream's first capture, and its frames.
+    last_capture: Option<(Duration, usize)>,
+    /// Dropouts in the audio not yet handed out.
+    dropouts: Vec<Dropout>,
+}
+
+impl CaptureWatchdog {
+    fn new(sample_rate: u32) -> Self {
+        Self {
+            stats: CaptureStats::default(),
+            sample_rate,
+            first_callback: None,
+            last_callback: None,
+            last_capture: None,
+            dropouts: Vec::new(),
+        }
+    }
+
+    /// `position` is the frame of the recorded samples this callback's audio
+    /// starts at.
+    fn record(&mut self, frames: usize, position: usize, captured: Duration) {
+        let now = Instant::now();
+        let first = *self.first_callback.get_or_insert(now);
+        if let Some(last) = self.last_callback {
+            let interval_ms = now.duration_since(last).as_secs_f32() * 1000.0;
+            self.stats.max_interval_ms = self.stats.max_interval_ms.max(interval_ms);
+            self.stats.average_interval_ms =
+                now.duration_since(first).as_secs_f32() * 1000.0 / self.stats.callbacks as f32;
+        }
+        self.last_callback = Some(now);
+
+        if let Some((previous, previous_frames)) = self.last_capture {
+            let expected =
+                Duration::from_secs_f64(previous_frames as f64 / self.sample_rate as f64);
+            let elapsed = captured.saturating_sub(previous);
+            if elapsed > expected + DROPOUT_TOLERANCE {
+                let missing = (elapsed - expected).as_secs_f64() * self.sample_rate as f64;
+                self.stats.dropped_frames += missing as u64;
+                if self.dropouts.len() < MAX_DROPOUTS {
+                    self.dropouts.push(Dropout {
+                        frame: position,
+                        missing: missing as usize,
+                    });
+                }
+            }
+        }
+        self.last_capture = Some((captured, frames));
+
+        self.stats.callbacks += 1;
+        self.stats.frames += frames as u64;
+    }
+
+    /// The dropouts from `first_frame` on, counted from there, for a
+    /// snapshot of the recording's tail.
+    fn dropouts_from(&self, first_frame: usize) -> Vec<Dropout> {
+        self.dropouts
+            .iter()
+            .filter(|dropout| dropout.frame >= first_frame)
+            .map(|dropout| Dropout {
+                frame: dropout.frame - first_frame,
+                ..*dropout
+            })
+            .collect()
+    }
+}
+
+pub struct AudioRecorder {
+    command_tx: Sender<RecorderCommand>,
+    sample_rate: u32,
+    channels: u16,
+    /// True only while a cpal input stream exists. The stream is created on
+    /// Start and dropped on Stop, so the microphone is never open while idle
+    /// unless a pre-roll is set.
+    stream_active: Arc<AtomicBool>,
+    /// Samples of the current recording held in memory.
+    buffered: Arc<AtomicUsize>,
+    /// Whether the current recording stopped at its maximum duration.
+    limit_reached: Arc<AtomicBool>,
+}
+
+impl AudioRecorder {
+    /// Opens the named input device, or the system default when no name is
+    /// given or the named device is no longer connected.
+    /// [`SYSTEM_AUDIO_DEVICE`] records the machine's output instead.
+    pub fn new(device_name: Option<&str>) -> Result<Self, String> {
+        if device_name == Some(NETWORK_MICROPHONE_DEVICE) {
+            return Err("The network microphone needs its pairing to open".to_string());
+        }
+
+        let host = cpal::default_host();
+        let (device, supported_config) = if device_name == Some(SYSTEM_AUDIO_DEVICE) {
+            system_audio_source(&host).ok_or_else(|| NO_SYSTEM_AUDIO.to_string())?
+        } else {
+            let device = match device_name.and_then(|name| find_input_device(&host, name)) {
+                Some(device) => device,
+                None => {
+                    if let Some(name) = device_name {
+                        eprintln!(
+                            "Input device {:?} not found, using the default device",
+                            name
+                        );
+                    }
+                    host.default_input_device()
+                        .ok_or_else(|| "No input device available".to_string())?
+                }
+            };
+            let config = device
+                .default_input_config()
+                .map_err(|e| format!("Failed to get default input config: {}", e))?;
+            (device, config)
+        };
+
+        let config: cpal::StreamConfig = supported_config.into();
+        Ok(Self::spawn(InputSource::Device(device, config)))
+    }
+
+    /// Records [`NETWORK_MICROPHONE_DEVICE`] audio from the sender paired
+    /// with `microphone`.
+    pub fn network(microphone: NetworkMicrophone) -> Self {
+        Self::spawn(InputSource::Network(microphone))
+    }
+
+    /// Starts the thread that records from `source`.
+    fn spawn(source: InputSource) -> Self {
+        let sample_rate = source.sample_rate();
+        let channels = source.channels();
+        let (command_tx, command_rx): (Sender<RecorderCommand>, Receiver<RecorderCommand>) =
+            channel();
+
+        let stream_active = Arc::new(AtomicBool::new(false));
+        let stream_active_clone = Arc::clone(&stream_active);
+        let buffered = Arc::new(AtomicUsize::new(0));
+        let buffered_clone = Arc::clone(&buffered);
+        let limit_reached = Arc::new(AtomicBool::new(false));
+        let limit_reached_clone = Arc::clone(&limit_reached);
+        let stream_command_tx = command_tx.clone();
+
+        thread::spawn(move || {
+            run_recorder_thread(
+                source,
+                command_rx,
+                stream_command_tx,
+                stream_active_clone,
+                buffered_clone,
+                limit_reached_clone,
+            );
+        });
+
+        Self {
+            command_tx,
+            sample_rate,
+            channels,
+            stream_active,
+            buffered,
+            limit_reached,
+        }
+    }
+
+    /// `level_tx` receives the RMS of the input about every 50 ms.
+    pub fn start_recording(&self, level_tx: Option<Sender<f32>>) -> Result<(), String> {
+        self.command_tx
+            .send(RecorderCommand::Start(level_tx, None))
+            .map_err(|e| format!("Failed to send start command: {}", e))
+    }
+
+    /// Like [`AudioRecorder::start_recording`], but capture stops by itself
+    /// after `max_duration`, keeping what was recorded for
+    /// [`AudioRecorder::stop_recording`]. `level_tx` is dropped when it does.
+    pub fn start_recording_with_limit(
+        &self,
+        level_tx: Option<Sender<f32>>,
+        max_duration: Duration,
+    ) -> Result<(), String> {
+        self.command_tx
+            .send(RecorderCommand::Start(level_tx, Some(max_duration)))
+            .map_err(|e| format!("Failed to send start command: {}", e))
+    }
+
+    /// Keeps the last `ms` milliseconds of input while idle and opens each
+    /// recording with them, so words spoken a beat before the start aren't
+    /// clipped. The microphone stays open while a pre-roll is set; 0 turns it
+    /// off. At most [`MAX_PREROLL`].
+    pub fn set_preroll_ms(&self, ms: u64) -> Result<(), String> {
+        let preroll = Duration::from_millis(ms).min(MAX_PREROLL);
+        self.command_tx
+            .send(RecorderCommand::SetPreroll(preroll))
+            .map_err(|e| format!("Failed to send pre-roll command: {}", e))
+    }
+
+    /// Holds at most `bytes` of a recording in memory, moving older audio
+    /// to a private file, so long recordings don't use up RAM. It is read
+    /// back when the recording stops. `None`, the default, keeps it all in
+    /// memory.
+    pub fn set_memory_limit(&self, bytes: Option<usize>) -> Result<(), String> {
+        let samples = bytes.map_or(usize::MAX, |bytes| bytes / std::mem::size_of::<f32>());
+        self.command_tx
+            .send(RecorderCommand::SetMemoryLimit(samples))
+            .map_err(|e| format!("Failed to send memory limit command: {}", e))
+    }
+
+    /// Moves the recording in progress to disk but for its last second or
+    /// so, whatever the memory limit, to make room.
+    pub fn spill(&self) -> Result<(), String> {
+        self.command_tx
+            .send(RecorderCommand::Spill)
+            .map_err(|e| format!("Failed to send spill command: {}", e))
+    }
+
+    /// Whether the current recording stopped at its maximum duration.
+    pub fn limit_reached(&self) -> bool {
+        self.limit_reached.load(Ordering::SeqCst)
+    }
+
+    pub fn stop_recording(&self) -> Result<RecordedAudio, String> {
+        let (response_tx, response_rx) = channel();
+        self.command_tx
+            .send(RecorderCommand::Stop(response_tx))
+            .map_err(|e| format!("Failed to send stop command: {}", e))?;
+
+        response_rx
+            .recv()
+            .map_err(|e| format!("Failed to receive samples: {}", e))
+    }
+
+    /// The last `max_frames` frames recorded so far, while recording goes on.
+    pub fn snapshot(&self, max_frames: usize) -> Result<RecordedAudio, String> {
+        let (response_tx, response_rx) = channel();
+        self.command_tx
+            .send(RecorderCommand::Snapshot(max_frames, response_tx))
+            .map_err(|e| format!("Failed to send snapshot command: {}", e))?;
+
+        response_rx
+            .recv()
+            .map_err(|e| format!("Failed to receive samples: {}", e))
+    }
+
+    /// Everything recorded since the start or the last drain, leaving the
+    /// recording running, so a long recording never has to be held at once.
+    pub fn drain(&self) -> Result<RecordedAudio, String> {
+        let (response_tx, response_rx) = channel();
+        self.command_tx
+            .send(RecorderCommand::Drain(response_tx))
+            .map_err(|e| format!("Failed to send drain command: {}", e))?;
+
+        response_rx
+            .recv()
+            .map_err(|e| format!("Failed to receive samples: {}", e))
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    pub fn is_stream_active(&self) -> bool {
+        self.stream_active.load(Ordering::SeqCst)
+    }
+
+    /// Samples held in memory for the recording in progress, at the device's
+    /// rate and channel count.
+    pub fn buffered_samples(&self) -> usize {
+        self.buffered.load(Ordering::Relaxed)
+    }
+}
+
+/// Where a recorder's audio comes from.
+enum InputSource {
+    Device(cpal::Device, cpal::StreamConfig),
+    /// RTP packets arriving on [`NETWORK_MICROPHONE_PORT`].
+    Network(NetworkMicrophone),
+}
+
+impl InputSource {
+    fn sample_rate(&self) -> u32 {
+        match self {
+            Self::Device(_, config) => config.sample_rate.0,
+            Self::Network(_) => NETWORK_SAMPLE_RATE,
+        }
+    }
+
+    fn channels(&self) -> u16 {
+        match self {
+            Self::Device(_, config) => config.channels,
+            Self::Network(_) => 1,
+        }
+    }
+}
+
+/// Receives [`NETWORK_MICROPHONE_DEVICE`] audio on its own thread.
+struct NetworkStream {
+    stopped: Arc<AtomicBool>,
+    receiver: Option<JoinHandle<()>>,
+}
+
+impl NetworkStream {
+    /// Hands each packet's samples to `on_input` in sequence order with its
+    /// RTP timestamp, counted from the first packet. Only packets with the
+    /// paired SSRC count, and only from the address the first of them came
+    /// from, so another sender can't talk over it.
+    fn open(
+        microphone: NetworkMicrophone,
+        mut on_input: impl FnMut(&[f32], Duration) + Send + 'static,
+    ) -> Result<Self, String> {
+        let address = SocketAddr::new(microphone.address, NETWORK_MICROPHONE_PORT);
+        let socket = UdpSocket::bind(address)
+            .map_err(|e| format!("Failed to listen on {}: {}", address, e))?;
+        socket
+            .set_read_timeout(Some(NETWORK_POLL_INTERVAL))
+            .map_err(|e| format!("Failed to listen on {}: {}", address, e))?;
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let stopped_clone = Arc::clone(&stopped);
+        let receiver = thread::spawn(move || {
+            let mut packet = vec![0u8; 65536];
+            let mut samples = Vec::new();
+            let mut sender = None;
+            let mut origin = None;
+            let mut reorder = Reorder::new();
+            while !stopped_clone.load(Ordering::SeqCst) {
+                // Timeouts only wake the loop to check for a stop.
+                let Ok((length, from)) = socket.recv_from(&mut packet) else {
+                    continue;
+                };
+                let Some(rtp) = parse_rtp(&packet[..length]) else {
+                    continue;
+                };
+                if rtp.ssrc != microphone.ssrc
+                    || !DYNAMIC_PAYLOAD_TYPES.contains(&rtp.payload_type)
+                    || *sender.get_or_insert(from) != from
+                {
+                    continue;
+                }
+                let mut deliver = |(timestamp, payload): (u32, Vec<u8>)| {
+                    let origin = *origin.get_or_insert(timestamp);
+                    samples.clear();
+                    samples.extend(
+                        payload
+                            .chunks_exact(2)
+                            .map(|pair| i16::from_be_bytes([pair[0], pair[1]]) as f32 / 32768.0),
+                    );
+                    let frames = timestamp.wrapping_sub(origin);
+                    on_input(
+                        &samples,
+                        Duration::from_secs_f64(frames as f64 / NETWORK_SAMPLE_RATE as f64),
+                    );
+                };
+                let packet = (rtp.timestamp, rtp.payload.to_vec());
+                reorder.push(rtp.sequence, packet, &mut deliver);
+            }
+        });
+
+        Ok(Self {
+            stopped,
+            receiver: Some(receiver),
+        })
+    }
+}
+
+impl Drop for NetworkStream {
+    /// Waits for the receiver to let go of the port, so the next recording
+    /// can open it again straight away.
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        if let Some(receiver) = self.receiver.take() {
+            let _ = receiver.join();
+        }
+    }
+}
+
+/// The parts of an RTP packet the network receiver reads.
+#[derive(Debug, PartialEq)]
+struct RtpPacket<'a> {
+    payload_type: u8,
+    sequence: u16,
+    timestamp: u32,
+    ssrc: u32,
+    payload: &'a [u8],
+}
+
+/// `packet` as RTP, or `None` if it isn't.
+fn parse_rtp(packet: &[u8]) -> Option<RtpPacket<'_>> {
+    let first = *packet.first()?;
+    if packet.len() < 12 || first >> 6 != 2 {
+        return None;
+    }
+    let payload_type = packet[1] & 0x7f;
+    let sequence = u16::from_be_bytes([packet[2], packet[3]]);
+    let timestamp = u32::from_be_bytes([packet[4], packet[5], packet[6], packet[7]]);
+    let ssrc = u32::from_be_bytes([packet[8], packet[9], packet[10], packet[11]]);
+    let mut start = 12 + 4 * (first & 0x0f) as usize;
+    if first & 0x10 != 0 {
+        let extension = packet.get(start..start + 4)?;
+        start += 4 + 4 * u16::from_be_bytes([extension[2], extension[3]]) as usize;
+    }
+    let mut end = packet.len();
+    if first & 0x20 != 0 {
+        end = end.checked_sub(*packet.last()? as usize)?;
+    }
+    Some(RtpPacket {
+        payload_type,
+        sequence,
+        timestamp,
+        ssrc,
+        payload: packet.get(start..end)?,
+    })
+}
+
+/// Puts packets back in RTP sequence order, dropping duplicates and any that
+/// arrive after the audio around them was passed on.
+struct Reorder<T> {
+    /// The sequence number passed on next, once the first packet is in.
+    next: Option<u16>,
+    /// Packets that arrived ahead of `next`.
+    waiting: Vec<(u16, T)>,
+}
+
+impl<T> Reorder<T> {
+    fn new() -> Self {
+        Self {
+            next: None,
+            waiting: Vec::new(),
+        }
+    }
+
+    /// Takes packet `sequence` and hands every packet now in order to
+    /// `deliver`. Once more than [`REORDER_DEPTH`] wait on a missing one, it
+    /// is given up for lost.
+    fn push(&mut self, sequence: u16, packet: T, mut deliver: impl FnMut(T)) {
+        let next = *self.next.get_or_insert(sequence);
+        // Sequence numbers wrap, so half the range counts as ahead and half
+        // as behind.
+        if (sequence.wrapping_sub(next) as i16) < 0
+            || self.waiting.iter().any(|(waiting, _)| *waiting == sequence)
+        {
+            return;
+        }
+        self.waiting.push((sequence, packet));
+        if self.waiting.len() > REORDER_DEPTH {
+            let skip = self
+                .waiting
+                .iter()
+                .map(|(waiting, _)| waiting.wrapping_sub(next))
+                .min()
+                .unwrap_or(0);
+            self.next = Some(next.wrapping_add(skip));
+        }
+        while let Some(next) = self.next {
+            let Some(index) = self
+                .waiting
+                .iter()
+                .position(|(waiting, _)| *waiting == next)
+            else {
+                break;
+            };
+            deliver(self.waiting.swap_remove(index).1);
+            self.next = Some(next.wrapping_add(1));
+        }
+    }
+}
+
+fn run_recorder_thread(
+    source: InputSource,
+    command_rx: Receiver<RecorderCommand>,
+    command_tx: Sender<RecorderCommand>,
+    stream_active: Arc<AtomicBool>,
+    buffered: Arc<AtomicUsize>,
+    limit_reached: Arc<AtomicBool>,
+) {
+    use parking_lot::Mutex;
+
+    let samples = Arc::new(Mutex::new(SampleBuffer::new(usize::MAX)));
+    // The latest input while idle, only touched under the samples lock so a
+    // start can't lose audio between the two.
+    let preroll: Arc<Mutex<VecDeque<f32>>> = Arc::new(Mutex::new(VecDeque::new()));
+    let preroll_len = Arc::new(AtomicUsize::new(0));
+    // Whether input goes to `samples` rather than `preroll`. Changed under
+    // the samples lock.
+    let recording = Arc::new(AtomicBool::new(false));
+    let max_frames = Arc::new(AtomicUsize::new(usize::MAX));
+    let level_sender: Arc<Mutex<Option<Sender<f32>>>> = Arc::new(Mutex::new(None));
+    let level_buffer: Arc<Mutex<Vec<f32>>> = Arc::new(Mutex::new(Vec::new()));
+    let last_emit: Arc<Mutex<Instant>> = Arc::new(Mutex::new(Instant::now()));
+    let rate_probe: Arc<Mutex<RateProbe>> = Arc::new(Mutex::new(RateProbe::default()));
+    let reported_sample_rate = source.sample_rate();
+    let watchdog = Arc::new(Mutex::new(CaptureWatchdog::new(reported_sample_rate)));
+    let channels = source.channels();
+    // Only held to keep the input open: a cpal stream or a network receiver.
+    let mut stream: Option<Box<dyn Any>> = None;
+
+    let open_stream = || {
+        let samples_clone = Arc::clone(&samples);
+        let preroll_clone = Arc::clone(&preroll);
+        let preroll_len_clone = Arc::clone(&preroll_len);
+        let recording_clone = Arc::clone(&recording);
+        let max_frames_clone = Arc::clone(&max_frames);
+        let level_sender_clone = Arc::clone(&level_sender);
+        let level_buffer_clone = Arc::clone(&level_buffer);
+        let last_emit_clone = Arc::clone(&last_emit);
+        let rate_probe_clone = Arc::clone(&rate_probe);
+        let buffered_clone = Arc::clone(&buffered);
+        let watchdog_clone = Arc::clone(&watchdog);
+        let errors_watchdog = Arc::clone(&watchdog);
+        let limit_reached_clone = Arc::clone(&limit_reached);
+        let limit_tx = command_tx.clone();
+        let on_input = move |data: &[f32], captured: Duration| {
+            let frames = data.len() / channels as usize;
+            {
+                let mut samples = samples_clone.lock();
+                if !recording_clone.load(Ordering::SeqCst) {
+                    let mut preroll = preroll_clone.lock();
+                    preroll.extend(data);
+                    let excess = preroll
+                        .len()
+                        .saturating_sub(preroll_len_clone.load(Ordering::Relaxed));
+                    preroll.drain(..excess);
+                    return;
+                }
+                let position = samples.len() / channels as usize;
+                if position >= max_frames_clone.load(Ordering::Relaxed) {
+                    recording_clone.store(false, Ordering::SeqCst);
+                    limit_reached_clone.store(true, Ordering::SeqCst);
+                    let _ = limit_tx.send(RecorderCommand::LimitReached);
+                    return;
+                }
+                samples.extend_from_slice(data);
+                buffered_clone.store(samples.in_memory(), Ordering::Relaxed);
+                // Under the samples lock, so a drain can't part a
+                // dropout from the audio it was found in.
+                watchdog_clone.lock().record(frames, position, captured);
+            }
+            rate_probe_clone.lock().record(frames);
+
+            if let Some(ref tx) = *level_sender_clone.lock() {
+                level_buffer_clone.lock().extend_from_slice(data);
+
+                let mut last = last_emit_clone.lock();
+                if last.elapsed() >= Duration::from_millis(50) {
+                    let mut buf = level_buffer_clone.lock();
+                    if !buf.is_empty() {
+                        let rms = (buf.iter().map(|s| s * s).sum::<f32>() / buf.len() as f32).sqrt();
+                        let _ = tx.send(rms);
+                        buf.clear();
+                    }
+                    *last = Instant::now();
+                }
+            }
+        };
+
+        let opened: Result<Box<dyn Any>, String> = match &source {
+            InputSource::Device(device, config) => {
+                let err_fn = move |err| {
+                    eprintln!("Audio stream error: {}", err);
+                    errors_watchdog.lock().stats.xruns += 1;
+                };
+                let mut origin = None;
+                device
+                    .build_input_stream(
+                        config,
+                        move |data: &[f32], info: &cpal::InputCallbackInfo| {
+                            let captured = info.timestamp().capture;
+                            let origin = *origin.get_or_insert(captured);
+                            on_input(data, captured.duration_since(&origin).unwrap_or_default());
+                        },
+                        err_fn,
+                        None,
+                    )
+                    .map(|s| {
+                        if let Err(e) = s.play() {
+                            eprintln!("Failed to start stream: {}", e);
+                        }
+                        Box::new(s) as Box<dyn Any>
+                    })
+                    .map_err(|e| format!("Failed to build input stream: {}", e))
+            }
+            InputSource::Network(microphone) => NetworkStream::open(*microphone, on_input)
+                .map(|receiver| Box::new(receiver) as Box<dyn Any>),
+        };
+        opened.map_err(|e| eprintln!("{}", e)).ok()
+    };
+
+    loop {
+        match command_rx.recv() {
+            Ok(RecorderCommand::Start(level_tx, max_duration)) => {
+                limit_reached.store(false, Ordering::SeqCst);
+                max_frames.store(
+                    max_duration.map_or(usize::MAX, |max| {
+                        (max.as_secs_f64() * reported_sample_rate as f64) as usize
+                    }),
+                    Ordering::Relaxed,
+                );
+                level_buffer.lock().clear();
+                *last_emit.lock() = Instant::now();
+                *rate_probe.lock() = RateProbe::default();
+                *watchdog.lock() = CaptureWatchdog::new(reported_sample_rate);
+                *level_sender.lock() = level_tx;
+                {
+                    // The recording opens with the pre-roll, if any.
+                    let mut samples = samples.lock();
+                    let mut preroll = preroll.lock();
+                    samples.clear();
+                    samples.extend_from_slice(preroll.make_contiguous());
+                    preroll.clear();
+                    buffered.store(samples.in_memory(), Ordering::Relaxed);
+                    recording.store(true, Ordering::SeqCst);
+                }
+
+                if stream.is_none() {
+                    stream = open_stream();
+                }
+                stream_active.store(stream.is_some(), Ordering::SeqCst);
+            }
+            // Skipped if a new recording started since the limit was hit.
+            Ok(RecorderCommand::LimitReached) if limit_reached.load(Ordering::SeqCst) => {
+                // Keeps the samples for the stop that follows.
+                if preroll_len.load(Ordering::Relaxed) == 0 {
+                    drop(stream.take());
+                    stream_active.store(false, Ordering::SeqCst);
+                }
+                *level_sender.lock() = None;
+            }
+            Ok(RecorderCommand::LimitReached) => {}
+            Ok(RecorderCommand::SetPreroll(length)) => {
+                let frames = (length.as_secs_f64() * reported_sample_rate as f64) as usize;
+                preroll_len.store(frames * channels as usize, Ordering::Relaxed);
+                {
+                    let _samples = samples.lock();
+                    preroll.lock().clear();
+                }
+                if !recording.load(Ordering::SeqCst) {
+                    match (frames > 0, stream.is_some()) {
+                        (true, false) => stream = open_stream(),
+                        (false, true) => drop(stream.take()),
+                        _ => {}
+                    }
+                    stream_active.store(stream.is_some(), Ordering::SeqCst);
+                }
+            }
+            Ok(RecorderCommand::SetMemoryLimit(limit)) => {
+                let mut samples = samples.lock();
+                samples.set_memory_limit(limit);
+                buffered.store(samples.in_memory(), Ordering::Relaxed);
+            }
+            Ok(RecorderCommand::Spill) => {
+                let mut samples = samples.lock();
+                samples.spill_all();
+                buffered.store(samples.in_memory(), Ordering::Relaxed);
+            }
+            Ok(RecorderCommand::Stop(response_tx)) => {
+                if preroll_len.load(Ordering::Relaxed) == 0 {
+                    drop(stream.take());
+                    stream_active.store(false, Ordering::SeqCst);
+                }
+                buffered.store(0, Ordering::Relaxed);
+                *level_sender.lock() = None;
+                let mut samples = samples.lock();
+                recording.store(false, Ordering::SeqCst);
+                let mut capture = watchdog.lock();
+                let recorded = RecordedAudio {
+                    samples: samples.take(),
+                    sample_rate: rate_probe.lock().effective_rate(reported_sample_rate),
+                    reported_sample_rate,
+                    channels,
+                    stats: capture.stats.clone(),
+                    dropouts: std::mem::take(&mut capture.dropouts),
+                };
+                drop((samples, capture));
+                let _ = response_tx.send(recorded);
+            }
+            Ok(RecorderCommand::Snapshot(max_frames, response_tx)) => {
+                let mut samples = samples.lock();
+                let start = samples
+                    .len()
+                    .saturating_sub(max_frames * channels as usize);
+                let first_frame = start / channels as usize;
+                let capture = watchdog.lock();
+                let recorded = RecordedAudio {
+                    samples: samples.tail(max_frames * channels as usize),
+                    sample_rate: rate_probe.lock().effective_rate(reported_sample_rate),
+                    reported_sample_rate,
+                    channels,
+                    stats: capture.stats.clone(),
+                    dropouts: capture.dropouts_from(first_frame),
+                };
+                drop(capture);
+                let _ = response_tx.send(recorded);
+            }
+            Ok(RecorderCommand::Drain(response_tx)) => {
+                let mut samples = samples.lock();
+                let mut capture = watchdog.lock();
+                let recorded = RecordedAudio {
+                    samples: samples.take(),
+                    sample_rate: rate_probe.lock().effective_rate(reported_sample_rate),
+                    reported_sample_rate,
+                    channels,
+                    stats: capture.stats.clone(),
+                    dropouts: std::mem::take(&mut capture.dropouts),
+                };
+                drop((samples, capture));
+                buffered.store(0, Ordering::Relaxed);
+                let _ = response_tx.send(recorded);
+            }
+            Err(_) => {
+                break;
+            }
+        }
+    }
+
+    drop(stream);
+    stream_active.store(false, Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// At 1 kHz a frame is a millisecond.
+    const RATE: u32 = 1000;
+
+    fn recording(samples: Vec<f32>, channels: u16, dropouts: Vec<Dropout>) -> RecordedAudio {
+        RecordedAudio {
+            samples,
+            sample_rate: RATE,
+            reported_sample_rate: RATE,
+            channels,
+            stats: CaptureStats::default(),
+            dropouts,
+        }
+    }
+
+    fn assert_samples(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len(), "{:?}", actual);
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-6, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn watchdog_places_a_gap_before_the_audio_after_it() {
+        let mut watchdog = CaptureWatchdog::new(RATE);
+        watchdog.record(100, 0, Duration::from_millis(0));
+        watchdog.record(100, 100, Duration::from_millis(100));
+        watchdog.record(100, 200, Duration::from_millis(250));
+        let dropout = Dropout {
+            frame: 200,
+            missing: 50,
+        };
+        assert_eq!(watchdog.dropouts, vec![dropout]);
+        assert_eq!(watchdog.stats.dropped_frames, 50);
+    }
+
+    #[test]
+    fn watchdog_ignores_jitter() {
+        let mut watchdog = CaptureWatchdog::new(RATE);
+        watchdog.record(100, 0, Duration::from_millis(0));
+        watchdog.record(100, 100, Duration::from_millis(103));
+        assert!(watchdog.dropouts.is_empty());
+        assert_eq!(watchdog.stats.dropped_frames, 0);
+    }
+
+    #[test]
+    fn watchdog_counts_dropouts_beyond_those_it_remembers() {
+        let mut watchdog = CaptureWatchdog::new(RATE);
+        let callbacks = MAX_DROPOUTS + 11;
+        for i in 0..callbacks {
+            watchdog.record(10, i * 10, Duration::from_millis(i as u64 * 20));
+        }
+        assert_eq!(watchdog.dropouts.len(), MAX_DROPOUTS);
+        assert_eq!(watchdog.stats.dropped_frames, (callbacks as u64 - 1) * 10);
+    }
+
+    #[test]
+    fn snapshot_dropouts_count_from_its_first_frame() {
+        let mut watchdog = CaptureWatchdog::new(RATE);
+        watchdog.dropouts = vec![
+            Dropout {
+                frame: 50,
+                missing: 5,
+            },
+            Dropout {
+                frame: 150,
+                missing: 7,
+            },
+        ];
+        let expected = Dropout {
+            frame: 50,
+            missing: 7,
+        };
+        assert_eq!(watchdog.dropouts_from(100), vec![expected]);
+    }
+
+    #[test]
+    fn dropout_ranges_mark_where_the_audio_was_joined() {
+        let dropout = Dropout {
+            frame: 500,
+            missing: 20,
+        };
+        let audio = recording(vec![0.0; 1000], 1, vec![dropout]);
+        let range = DropoutRange {
+            start: 0.5,
+            end: 0.5,
+        };
+        assert_eq!(audio.dropout_ranges(), vec![range]);
+    }
+
+    #[test]
+    fn repair_fills_a_short_dropout_with_a_straight_line() {
+        let dropout = Dropout {
+            frame: 3,
+            missing: 2,
+        };
+        let mut audio = recording(vec![0.0, 0.1, 0.2, 0.5, 0.6], 1, vec![dropout]);
+        let ranges = audio.repair_dropouts();
+        assert_samples(&audio.samples, &[0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6]);
+        assert_eq!(ranges.len(), 1);
+        assert!((ranges[0].start - 0.003).abs() < 1e-6);
+        assert!((ranges[0].end - 0.005).abs() < 1e-6);
+        assert!(audio.dropouts.is_empty());
+    }
+
+    #[test]
+    fn repair_interpolates_each_channel_separately() {
+        let dropout = Dropout {
+            frame: 1,
+            missing: 2,
+        };
+        let mut audio = recording(vec![0.0, 1.0, 0.3, -0.2], 2, vec![dropout]);
+        audio.repair_dropouts();
+        assert_samples(&audio.samples, &[0.0, 1.0, 0.1, 0.6, 0.2, 0.2, 0.3, -0.2]);
+    }
+
+    #[test]
+    fn repair_holds_the_first_sample_across_a_dropout_at_the_start() {
+        let dropout = Dropout {
+            frame: 0,
+            missing: 2,
+        };
+        let mut audio = recording(vec![0.5, 0.4], 1, vec![dropout]);
+        audio.repair_dropouts();
+        assert_samples(&audio.samples, &[0.5, 0.5, 0.5, 0.4]);
+    }
+
+    #[test]
+    fn repair_only_marks_long_dropouts_and_places_later_ones_after_them() {
+        let long = Dropout {
+            frame: 2,
+            missing: 300,
+        };
+        let short = Dropout {
+            frame: 5,
+            missing: 1,
+        };
+        let mut audio = recording(vec![0.0; 10], 1, vec![long, short]);
+        let ranges = audio.repair_dropouts();
+        assert_eq!(audio.samples.len(), 11);
+        assert_eq!(ranges[0].start, ranges[0].end);
+        assert!((ranges[0].start - 0.002).abs() < 1e-6);
+        assert!((ranges[1].start - 0.005).abs() < 1e-6);
+        assert!((ranges[1].end - 0.006).abs() < 1e-6);
+    }
+
+    /// An RTP header with marker bit clear: version 2, `flags` in the low
+    /// bits of the first byte, payload type 96.
+    fn rtp_header(flags: u8, sequence: u16) -> Vec<u8> {
+        let mut packet = vec![0x80 | flags, 96];
+        packet.extend(sequence.to_be_bytes());
+        packet.extend(4800u32.to_be_bytes());
+        packet.extend(0xdead_beefu32.to_be_bytes());
+        packet
+    }
+
+    #[test]
+    fn parse_rtp_reads_the_header() {
+        let mut packet = rtp_header(0, 7);
+        packet.extend([1, 2, 3, 4]);
+        assert_eq!(
+            parse_rtp(&packet),
+            Some(RtpPacket {
+                payload_type: 96,
+                sequence: 7,
+                timestamp: 4800,
+                ssrc: 0xdead_beef,
+                payload: &[1, 2, 3, 4],
+            })
+        );
+    }
+
+    #[test]
+    fn parse_rtp_skips_csrcs_and_extensions_and_padding() {
+        // Two CSRCs, then an extension one word long.
+        let mut packet = rtp_header(0x10 | 2, 0);
+        packet.extend([0; 8]);
+        packet.extend([0xbe, 0xde, 0, 1, 9, 9, 9, 9]);
+        packet.extend([1, 2]);
+        // Padding of three bytes, counting the count itself.
+        packet[0] |= 0x20;
+        packet.extend([0, 0, 3]);
+        assert_eq!(parse_rtp(&packet).unwrap().payload, &[1, 2]);
+    }
+
+    #[test]
+    fn parse_rtp_rejects_what_isnt_rtp() {
+        assert_eq!(parse_rtp(&[]), None);
+        assert_eq!(parse_rtp(&rtp_header(0, 0)[..11]), None);
+        let mut version_one = rtp_header(0, 0);
+        version_one[0] = 0x40;
+        assert_eq!(parse_rtp(&version_one), None);
+        let mut missing_csrcs = rtp_header(3, 0);
+        missing_csrcs.extend([0; 4]);
+        assert_eq!(parse_rtp(&missing_csrcs), None);
+        let mut cut_extension = rtp_header(0x10, 0);
+        cut_extension.extend([0xbe, 0xde]);
+        assert_eq!(parse_rtp(&cut_extension), None);
+        let mut overlong_padding = rtp_header(0x20, 0);
+        overlong_padding.push(200);
+        assert_eq!(parse_rtp(&overlong_padding), None);
+    }
+
+    fn reorder(sequences: &[u16]) -> Vec<u16> {
+        let mut reorder = Reorder::new();
+        let mut delivered = Vec::new();
+        for &sequence in sequences {
+            reorder.push(sequence, sequence, |packet| delivered.push(packet));
+        }
+        delivered
+    }
+
+    #[test]
+    fn reorder_puts_packets_in_order_and_drops_duplicates() {
+        assert_eq!(reorder(&[5, 7, 6, 6, 8, 7, 5]), [5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn reorder_follows_the_sequence_across_its_wrap() {
+        assert_eq!(reorder(&[65534, 0, 65535, 1]), [65534, 65535, 0, 1]);
+    }
+
+    #[test]
+    fn reorder_gives_up_on_a_lost_packet_and_drops_it_if_it_turns_up() {
+        let mut sequences = vec![0];
+        sequences.extend(2..=(REORDER_DEPTH as u16 + 2));
+        sequences.push(1);
+        let mut expected = vec![0];
+        expected.extend(2..=(REORDER_DEPTH as u16 + 2));
+        assert_eq!(reorder(&sequences), expected);
+    }
+}
@@ -0,0 +1,74 @@
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Loudness of a chime at full volume; chimes should sit well under speech.
+const PEAK: f32 = 0.3;
+
+/// Rise at the start of each note, so it doesn't click.
+const ATTACK: Duration = Duration::from_millis(5);
+
+/// Kept playing after the last note so the device doesn't cut its tail.
+const TAIL: Duration = Duration::from_millis(100);
+
+/// One tone of a chime, fading out over its duration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Note {
+    pub frequency: f32,
+    pub duration: Duration,
+}
+
+/// Plays `notes` one after the other on the default output device at
+/// `volume` (0 to 1), and returns once they have been heard.
+pub fn play_chime(notes: &[Note], volume: f32) -> Result<(), String> {
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or_else(|| "No output device available".to_string())?;
+    let config: cpal::StreamConfig = device
+        .default_output_config()
+        .map_err(|e| format!("Failed to get default output config: {}", e))?
+        .into();
+    let channels = config.channels as usize;
+    let chime = Arc::new(render(notes, config.sample_rate.0, volume.clamp(0.0, 1.0)));
+    let length = Duration::from_secs_f32(chime.len() as f32 / config.sample_rate.0 as f32);
+
+    let played = Arc::new(AtomicUsize::new(0));
+    let stream = device
+        .build_output_stream(
+            &config,
+            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                let start = played.fetch_add(data.len() / channels, Ordering::Relaxed);
+                for (index, frame) in data.chunks_mut(channels).enumerate() {
+                    frame.fill(chime.get(start + index).copied().unwrap_or(0.0));
+                }
+            },
+            |err| eprintln!("Chime playback error: {}", err),
+            None,
+        )
+        .map_err(|e| format!("Failed to open output stream: {}", e))?;
+    stream
+        .play()
+        .map_err(|e| format!("Failed to play chime: {}", e))?;
+    std::thread::sleep(length + TAIL);
+    Ok(())
+}
+
+/// The chime as mono samples: each note a sine that rises over `ATTACK` and
+/// then decays away.
+fn render(notes: &[Note], sample_rate: u32, volume: f32) -> Vec<f32> {
+    let rate = sample_rate as f32;
+    let attack = ATTACK.as_secs_f32() * rate;
+    let mut samples = Vec::new();
+    for note in notes {
+        let length = (note.duration.as_secs_f32() * rate) as usize;
+        for index in 0..length {
+            let position = index as f32;
+            let rise = (position / attack).min(1.0);
+            let decay = (-4.0 * position / length as f32).exp();
+            let phase = std::f32::consts::TAU * note.frequency * position / rate;
+            samples.push(phase.sin() * rise * decay * PEAK * volume);
+        }
+    }
+    samples
+}
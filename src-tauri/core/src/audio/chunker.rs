@@ -0,0 +1,116 @@
+use super::file::split_at_pauses;
+use super::processor::{SilenceDetector, WHISPER_SAMPLE_RATE};
+use std::time::Duration;
+
+/// A chunk isn't cut before this long, so a short breath doesn't leave
+/// Whisper a single word without context.
+const MIN_CHUNK_SECS: f32 = 5.0;
+/// Without a pause, a chunk is cut at the quietest moment before this, the
+/// most Whisper takes in one pass.
+const MAX_CHUNK_SECS: f32 = 30.0;
+/// Quiet this long after speech counts as a pause between sentences.
+const PAUSE: Duration = Duration::from_millis(700);
+/// Loudness is measured over windows this long.
+const WINDOW_SECS: f32 = 0.1;
+
+/// A stretch of a continuous recording, ready to transcribe.
+pub struct AudioChunk {
+    /// Seconds from the start of the recording.
+    pub start: f32,
+    /// Whisper-format samples.
+    pub samples: Vec<f32>,
+}
+
+/// Cuts a continuous stream of whisper-format audio into chunks that end at
+/// pauses, so each can be transcribed as soon as it is complete.
+pub struct SilenceChunker {
+    pending: Vec<f32>,
+    /// Samples already handed out in chunks.
+    consumed: usize,
+    /// Samples at the start of `pending` the detector has heard.
+    scanned: usize,
+    detector: SilenceDetector,
+}
+
+impl Default for SilenceChunker {
+    fn default() -> Self {
+        Self {
+            pending: Vec::new(),
+            consumed: 0,
+            scanned: 0,
+            detector: SilenceDetector::new(PAUSE),
+        }
+    }
+}
+
+impl SilenceChunker {
+    /// Adds the next samples and returns the chunks they complete.
+    pub fn push(&mut self, samples: &[f32]) -> Vec<AudioChunk> {
+        let rate = WHISPER_SAMPLE_RATE as f32;
+        let window = (WINDOW_SECS * rate) as usize;
+        let min_len = (MIN_CHUNK_SECS * rate) as usize;
+        let max_len = (MAX_CHUNK_SECS * rate) as usize;
+        self.pending.extend_from_slice(samples);
+
+        let mut chunks = Vec::new();
+        while self.scanned + window <= self.pending.len() {
+            let block = &self.pending[self.scanned..self.scanned + window];
+            let rms = (block.iter().map(|s| s * s).sum::<f32>() / window as f32).sqrt();
+            self.scanned += window;
+            let paused = self
+                .detector
+                .push(rms, Duration::from_secs_f32(WINDOW_SECS));
+            if paused && self.scanned >= min_len {
+                chunks.push(self.cut(self.scanned));
+            } else if self.scanned > max_len {
+                let end = split_at_pauses(&self.pending[..self.scanned], MAX_CHUNK_SECS)[0].end;
+                chunks.push(self.cut(end));
+            }
+        }
+        chunks
+    }
+
+    /// The audio after the last chunk, once the recording has ended.
+    pub fn finish(&mut self) -> Option<AudioChunk> {
+        let len = self.pending.len();
+        (len > 0).then(|| self.cut(len))
+    }
+
+    fn cut(&mut self, end: usize) -> AudioChunk {
+        let chunk = AudioChunk {
+            start: self.consumed as f32 / WHISPER_SAMPLE_RATE as f32,
+            samples: self.pending.drain(..end).collect(),
+        };
+        self.consumed += end;
+        self.scanned -= end;
+        self.detector.restart();
+        chunk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(rms: f32, seconds: f32) -> Vec<f32> {
+        let len = (seconds * WHISPER_SAMPLE_RATE as f32) as usize;
+        (0..len)
+            .map(|i| if i % 2 == 0 { rms } else { -rms })
+            .collect()
+    }
+
+    #[test]
+    fn cuts_at_pauses_when_speech_follows_a_cut() {
+        // Each cut comes at the end of a pause, so the next chunk starts
+        // with speech.
+        let mut chunker = SilenceChunker::default();
+        let mut chunks = chunker.push(&tone(0.001, 0.5));
+        for _ in 0..3 {
+            chunks.extend(chunker.push(&tone(0.2, 6.0)));
+            chunks.extend(chunker.push(&tone(0.001, 0.7)));
+        }
+        let starts: Vec<f32> = chunks.iter().map(|chunk| chunk.start).collect();
+        assert_eq!(chunks.len(), 3, "chunks at {:?}", starts);
+        assert!(chunks.iter().all(|chunk| chunk.samples.len() < 8 * 16000));
+    }
+}
@@ -0,0 +1,113 @@
+use super::processor::{convert_to_whisper_format, WHISPER_SAMPLE_RATE};
+use std::fs::File;
+use std::ops::Range;
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// Extensions of the audio files [`decode_audio_file`] reads.
+pub const AUDIO_FILE_EXTENSIONS: [&str; 4] = ["wav", "mp3", "m4a", "flac"];
+
+/// How far back from a chunk's end [`split_at_pauses`] looks for a pause.
+const PAUSE_SEARCH_SECS: f32 = 5.0;
+/// Loudness is compared over windows this long.
+const PAUSE_WINDOW_SECS: f32 = 0.1;
+
+/// Decodes a WAV, MP3, M4A or FLAC file into whisper-format samples.
+pub fn decode_audio_file(path: &Path) -> Result<Vec<f32>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open audio file: {}", e))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(extension);
+    }
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| format!("Unsupported audio file: {}", e))?;
+    let mut format = probed.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| "The file has no audio track".to_string())?;
+    let track_id = track.id;
+    let sample_rate = track
+        .codec_params
+        .sample_rate
+        .ok_or_else(|| "The audio track has no sample rate".to_string())?;
+    let channels = track
+        .codec_params
+        .channels
+        .map_or(1, |channels| channels.count() as u16);
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| format!("Unsupported audio codec: {}", e))?;
+
+    let mut samples = Vec::new();
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(format!("Failed to read audio file: {}", e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        match decoder.decode(&packet) {
+            Ok(decoded) => {
+                let mut buffer =
+                    SampleBuffer::<f32>::new(decoded.capacity() as u64, *decoded.spec());
+                buffer.copy_interleaved_ref(decoded);
+                samples.extend_from_slice(buffer.samples());
+            }
+            // A corrupt packet costs a few milliseconds, not the file.
+            Err(Error::DecodeError(e)) => eprintln!("Skipping undecodable audio: {}", e),
+            Err(e) => return Err(format!("Failed to decode audio file: {}", e)),
+        }
+    }
+
+    Ok(convert_to_whisper_format(samples, sample_rate, channels))
+}
+
+/// Splits whisper-format audio into chunks of at most `max_secs`, each
+/// ending at the quietest moment of its last few seconds so a cut rarely
+/// falls in the middle of a word.
+pub fn split_at_pauses(samples: &[f32], max_secs: f32) -> Vec<Range<usize>> {
+    let rate = WHISPER_SAMPLE_RATE as f32;
+    let max_len = (max_secs * rate) as usize;
+    let search_len = ((PAUSE_SEARCH_SECS * rate) as usize).min(max_len / 2);
+    let window = (PAUSE_WINDOW_SECS * rate) as usize;
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while samples.len() - start > max_len {
+        let search_start = start + max_len - search_len;
+        let end = (search_start..start + max_len - window)
+            .step_by(window)
+            .min_by(|a, b| {
+                energy(&samples[*a..*a + window]).total_cmp(&energy(&samples[*b..*b + window]))
+            })
+            .map_or(start + max_len, |quietest| quietest + window / 2);
+        chunks.push(start..end);
+        start = end;
+    }
+    if start < samples.len() {
+        chunks.push(start..samples.len());
+    }
+    chunks
+}
+
+fn energy(samples: &[f32]) -> f32 {
+    samples.iter().map(|s| s * s).sum()
+}
@@ -0,0 +1,29 @@
+mod buffer;
+mod capture;
+mod chime;
+mod chunker;
+mod file;
+mod pipeline;
+mod processor;
+mod storage;
+mod wakeword;
+
+pub use capture::{
+    generate_ssrc, list_input_devices, AudioRecorder, CaptureStats, Dropout, DropoutRange,
+    NetworkMicrophone, RecordedAudio, MAX_PREROLL, NETWORK_MICROPHONE_DEVICE,
+    NETWORK_MICROPHONE_PORT, NETWORK_SAMPLE_RATE, SYSTEM_AUDIO_DEVICE,
+};
+pub use chime::{play_chime, Note};
+pub use chunker::{AudioChunk, SilenceChunker};
+pub use file::{decode_audio_file, split_at_pauses, AUDIO_FILE_EXTENSIONS};
+pub use pipeline::AudioPipeline;
+pub use processor::{
+    convert_to_mono, convert_to_whisper_format, resample, SilenceDetector, StreamingConverter,
+    WHISPER_SAMPLE_RATE,
+};
+pub use storage::{
+    delete_recording, get_recordings_dir, list_recordings, load_recording, read_flac,
+    recording_audio_path, recording_to_wav, save_recording, write_flac, write_private_flac,
+    write_wav, RetainedRecording,
+};
+pub use wakeword::{is_wake_phrase, WakeWordDetector, WAKE_PHRASE};
@@ -0,0 +1,181 @@
+use super::processor::{
+    convert_to_mono, convert_to_whisper_format, resample, SPEECH_TO_NOISE_RATIO,
+    WHISPER_SAMPLE_RATE,
+};
+use nnnoiseless::DenoiseState;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// RNNoise only runs on 48 kHz audio.
+const DENOISE_SAMPLE_RATE: u32 = 48000;
+
+/// RNNoise works on 16-bit sample magnitudes rather than -1.0..1.0.
+const I16_SCALE: f32 = 32768.0;
+
+/// Silence is found in blocks of 20 ms.
+const TRIM_BLOCK: usize = WHISPER_SAMPLE_RATE as usize / 50;
+
+/// Kept either side of the speech so soft first and last sounds survive.
+const TRIM_PADDING: usize = WHISPER_SAMPLE_RATE as usize / 4;
+
+/// Quieter than this is silence, even in a silent room.
+const SILENCE_RMS: f32 = 1e-4;
+
+/// Speech is brought to this level, about -20 dBFS.
+const TARGET_RMS: f32 = 0.1;
+
+/// The most a quiet recording is amplified, so a recording of nothing but
+/// room noise isn't turned into something whisper tries to transcribe.
+const MAX_NORMALIZE_GAIN: f32 = 10.0;
+
+/// Optional clean-up stages run on a recording before it reaches whisper.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioPipeline {
+    /// Removes steady background noise (fans, traffic, keyboards).
+    pub noise_suppression: bool,
+    /// Multiplies the input, for microphones that record too quietly. 1.0
+    /// leaves it as it is.
+    pub gain: f32,
+    /// Cuts the silence before the first word and after the last, which
+    /// whisper otherwise fills with `[BLANK_AUDIO]` or loses the first word
+    /// in.
+    pub trim_silence: bool,
+    /// Brings speech to the same level however quiet the microphone is.
+    pub normalize_loudness: bool,
+}
+
+impl Default for AudioPipeline {
+    fn default() -> Self {
+        Self {
+            noise_suppression: false,
+            gain: 1.0,
+            trim_silence: true,
+            normalize_loudness: true,
+        }
+    }
+}
+
+impl AudioPipeline {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.1..=10.0).contains(&self.gain) {
+            return Err("Gain must be between 0.1 and 10".to_string());
+        }
+        Ok(())
+    }
+
+    /// Converts a recording to whisper's format like
+    /// `convert_to_whisper_format`, running the enabled stages.
+    pub fn process(&self, samples: Vec<f32>, sample_rate: u32, channels: u16) -> Vec<f32> {
+        self.process_timed(samples, sample_rate, channels).0
+    }
+
+    /// Like `process`, also returning the seconds of leading silence that
+    /// were trimmed, to place transcribed segments in the original audio.
+    pub fn process_timed(
+        &self,
+        mut samples: Vec<f32>,
+        sample_rate: u32,
+        channels: u16,
+    ) -> (Vec<f32>, f32) {
+        if self.gain != 1.0 {
+            for sample in &mut samples {
+                *sample = (*sample * self.gain).clamp(-1.0, 1.0);
+            }
+        }
+        let mut converted = if self.noise_suppression {
+            // Denoising runs before resampling so it sees the full band.
+            let mono = if channels > 1 {
+                convert_to_mono(&samples, channels as usize)
+            } else {
+                samples
+            };
+            let full_band = if sample_rate == DENOISE_SAMPLE_RATE {
+                mono
+            } else {
+                resample(&mono, sample_rate, DENOISE_SAMPLE_RATE)
+            };
+            resample(
+                &denoise(&full_band),
+                DENOISE_SAMPLE_RATE,
+                WHISPER_SAMPLE_RATE,
+            )
+        } else {
+            convert_to_whisper_format(samples, sample_rate, channels)
+        };
+
+        let mut start = 0.0;
+        if self.trim_silence {
+            let speech = speech_range(&converted);
+            converted.truncate(speech.end);
+            converted.drain(..speech.start);
+            start = speech.start as f32 / WHISPER_SAMPLE_RATE as f32;
+        }
+        if self.normalize_loudness {
+            normalize(&mut converted);
+        }
+        (converted, start)
+    }
+}
+
+/// The part of 16 kHz audio from just before the first block that stands
+/// out from the room's noise to just after the last. Audio where nothing
+/// stands out is kept whole.
+fn speech_range(samples: &[f32]) -> Range<usize> {
+    let levels: Vec<f32> = samples.chunks(TRIM_BLOCK).map(rms).collect();
+    let mut sorted = levels.clone();
+    sorted.sort_by(f32::total_cmp);
+    // The quietest tenth of the recording is taken to be the room.
+    let Some(&floor) = sorted.get(sorted.len() / 10) else {
+        return 0..0;
+    };
+    let threshold = (floor * SPEECH_TO_NOISE_RATIO).max(SILENCE_RMS);
+
+    let first = levels.iter().position(|&level| level > threshold);
+    let last = levels.iter().rposition(|&level| level > threshold);
+    match (first, last) {
+        (Some(first), Some(last)) => {
+            (first * TRIM_BLOCK).saturating_sub(TRIM_PADDING)
+                ..((last + 1) * TRIM_BLOCK + TRIM_PADDING).min(samples.len())
+        }
+        _ => 0..samples.len(),
+    }
+}
+
+/// Scales audio to `TARGET_RMS`, amplifying by at most `MAX_NORMALIZE_GAIN`
+/// and never so far that the loudest sample clips.
+fn normalize(samples: &mut [f32]) {
+    let level = rms(samples);
+    if level < SILENCE_RMS {
+        return;
+    }
+    let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+    let gain = (TARGET_RMS / level).min(MAX_NORMALIZE_GAIN).min(1.0 / peak);
+    for sample in samples {
+        *sample *= gain;
+    }
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    let sum: f64 = samples.iter().map(|&s| (s as f64) * (s as f64)).sum();
+    (sum / samples.len().max(1) as f64).sqrt() as f32
+}
+
+/// Runs 48 kHz mono audio through RNNoise a frame at a time.
+fn denoise(samples: &[f32]) -> Vec<f32> {
+    let mut state = DenoiseState::new();
+    let mut input = [0.0f32; DenoiseState::FRAME_SIZE];
+    let mut output = [0.0f32; DenoiseState::FRAME_SIZE];
+    let mut denoised = Vec::with_capacity(samples.len());
+
+    for chunk in samples.chunks(DenoiseState::FRAME_SIZE) {
+        // The last chunk is padded with silence and trimmed again below.
+        input.fill(0.0);
+        for (scaled, &sample) in input.iter_mut().zip(chunk) {
+            *scaled = sample * I16_SCALE;
+        }
+        state.process_frame(&mut output, &input);
+        denoised.extend(output[..chunk.len()].iter().map(|&s| s / I16_SCALE));
+    }
+    denoised
+}
@@ -0,0 +1,627 @@
+use rubato::{
+    calculate_cutoff, ResampleResult, Resampler as _, ResamplerConstructionError, SincFixedIn,
+    SincInterpolationParameters, SincInterpolationType, WindowFunction,
+};
+use std::time::Duration;
+
+pub const WHISPER_SAMPLE_RATE: u32 = 16000;
+
+/// Number of source frames converted per chunk by `convert_to_whisper_format`.
+const CHUNK_FRAMES: usize = 16384;
+
+pub fn convert_to_whisper_format(
+    samples: Vec<f32>,
+    source_sample_rate: u32,
+    source_channels: u16,
+) -> Vec<f32> {
+    if source_channels <= 1 && source_sample_rate == WHISPER_SAMPLE_RATE {
+        return samples;
+    }
+
+    let mut converter = StreamingConverter::new(source_sample_rate, source_channels);
+    let mut output = Vec::with_capacity(converter.output_len(samples.len()));
+
+    for chunk in samples.chunks(CHUNK_FRAMES * converter.channels) {
+        converter.push(chunk, &mut output);
+    }
+    converter.finish(&mut output);
+
+    output
+}
+
+pub fn convert_to_mono(samples: &[f32], channels: usize) -> Vec<f32> {
+    let mut mono = Vec::with_capacity(samples.len() / channels);
+    downmix_into(samples, channels, &mut mono);
+    mono
+}
+
+pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    let mut converter = StreamingConverter::with_target_rate(from_rate, 1, to_rate);
+    let mut resampled = Vec::with_capacity(converter.output_len(samples.len()));
+    converter.push(samples, &mut resampled);
+    converter.finish(&mut resampled);
+    resampled
+}
+
+/// Incremental mono downmix + resampler.
+///
+/// Audio is fed in whole-frame chunks via `push` and the converted samples are
+/// appended to a caller-owned buffer, so long recordings never need the full
+/// intermediate mono signal in memory. Call `finish` once after the last chunk.
+pub struct StreamingConverter {
+    channels: usize,
+    from_rate: u32,
+    to_rate: u32,
+    /// Reused downmix buffer for the current chunk.
+    mono: Vec<f32>,
+    resampler: Resampler,
+}
+
+enum Resampler {
+    Passthrough,
+    Sinc(Box<SincResampler>),
+    /// Used where the sinc resampler can't be set up.
+    Linear(LinearResampler),
+}
+
+impl StreamingConverter {
+    pub fn new(source_sample_rate: u32, source_channels: u16) -> Self {
+        Self::with_target_rate(source_sample_rate, source_channels, WHISPER_SAMPLE_RATE)
+    }
+
+    fn with_target_rate(from_rate: u32, channels: u16, to_rate: u32) -> Self {
+        let resampler = if from_rate == to_rate {
+            Resampler::Passthrough
+        } else {
+            match SincResampler::new(from_rate, to_rate) {
+                Ok(sinc) => Resampler::Sinc(Box::new(sinc)),
+                Err(e) => {
+                    eprintln!(
+                        "Falling back to linear resampling from {} Hz: {}",
+                        from_rate, e
+                    );
+                    Resampler::Linear(LinearResampler::new(from_rate, to_rate))
+                }
+            }
+        };
+        Self {
+            channels: channels.max(1) as usize,
+            from_rate,
+            to_rate,
+            mono: Vec::new(),
+            resampler,
+        }
+    }
+
+    /// Upper bound on the output samples produced for `input_len`
+    /// interleaved samples, for preallocating the output buffer.
+    pub fn output_len(&self, input_len: usize) -> usize {
+        let frames = (input_len / self.channels) as u64;
+        (frames * self.to_rate as u64 / self.from_rate as u64) as usize + 1
+    }
+
+    pub fn push(&mut self, samples: &[f32], output: &mut Vec<f32>) {
+        let mut mono = std::mem::take(&mut self.mono);
+        mono.clear();
+        let frames: &[f32] = if self.channels > 1 {
+            downmix_into(samples, self.channels, &mut mono);
+            &mono
+        } else {
+            samples
+        };
+
+        if !frames.is_empty() {
+            match &mut self.resampler {
+                Resampler::Passthrough => output.extend_from_slice(frames),
+                Resampler::Sinc(sinc) => sinc.push(frames, output),
+                Resampler::Linear(linear) => linear.push(frames, output),
+            }
+        }
+
+        self.mono = mono;
+    }
+
+    pub fn finish(&mut self, output: &mut Vec<f32>) {
+        match &mut self.resampler {
+            Resampler::Passthrough => {}
+            Resampler::Sinc(sinc) => sinc.finish(output),
+            Resampler::Linear(linear) => linear.finish(output),
+        }
+    }
+}
+
+/// Input frames the sinc resampler takes at a time.
+const SINC_CHUNK_FRAMES: usize = 1024;
+
+/// Taps in the sinc filter. Longer filters cut off more sharply below the
+/// target's Nyquist frequency, at more CPU.
+const SINC_LEN: usize = 128;
+
+/// Band-limited resampling with a windowed-sinc filter, which removes what
+/// lies above the target's Nyquist frequency instead of folding it back
+/// into the audible range the way interpolation does.
+struct SincResampler {
+    resampler: SincFixedIn<f32>,
+    from_rate: u32,
+    to_rate: u32,
+    /// Frames waiting for a whole chunk.
+    pending: Vec<f32>,
+    /// Reused output buffer.
+    out: Vec<f32>,
+    /// Input frames seen so far.
+    consumed: u64,
+    /// Output frames produced so far.
+    produced: u64,
+}
+
+impl SincResampler {
+    fn new(from_rate: u32, to_rate: u32) -> Result<Self, ResamplerConstructionError> {
+        let window = WindowFunction::BlackmanHarris2;
+        let parameters = SincInterpolationParameters {
+            sinc_len: SINC_LEN,
+            f_cutoff: calculate_cutoff(SINC_LEN, window),
+            oversampling_factor: 128,
+            interpolation: SincInterpolationType::Linear,
+            window,
+        };
+        let resampler = SincFixedIn::new(
+            to_rate as f64 / from_rate as f64,
+            1.0,
+            parameters,
+            SINC_CHUNK_FRAMES,
+            1,
+        )?;
+        Ok(Self {
+            out: vec![0.0; resampler.output_frames_max()],
+            resampler,
+            from_rate,
+            to_rate,
+            pending: Vec::with_capacity(SINC_CHUNK_FRAMES),
+            consumed: 0,
+            produced: 0,
+        })
+    }
+
+    fn push(&mut self, mut frames: &[f32], output: &mut Vec<f32>) {
+        self.consumed += frames.len() as u64;
+        while !frames.is_empty() {
+            // Whole chunks are resampled straight from the input.
+            if self.pending.is_empty() && frames.len() >= SINC_CHUNK_FRAMES {
+                let (chunk, rest) = frames.split_at(SINC_CHUNK_FRAMES);
+                let result =
+                    self.resampler
+                        .process_into_buffer(&[chunk], &mut [&mut self.out], None);
+                self.emit(result, output);
+                frames = rest;
+                continue;
+            }
+            let taken = (SINC_CHUNK_FRAMES - self.pending.len()).min(frames.len());
+            self.pending.extend_from_slice(&frames[..taken]);
+            frames = &frames[taken..];
+            if self.pending.len() == SINC_CHUNK_FRAMES {
+                let result = self.resampler.process_into_buffer(
+                    &[&self.pending],
+                    &mut [&mut self.out],
+                    None,
+                );
+                self.emit(result, output);
+                self.pending.clear();
+            }
+        }
+    }
+
+    /// Resamples what is left, then flushes the filter with silence until
+    /// the output is as long as the input calls for. The output lags the
+    /// input by half the filter, but is timed like it.
+    fn finish(&mut self, output: &mut Vec<f32>) {
+        // rubato rejects an empty chunk, and there is nothing left in it.
+        if !self.pending.is_empty() {
+            let pending = std::mem::take(&mut self.pending);
+            let result = self.resampler.process_partial_into_buffer(
+                Some(&[&pending]),
+                &mut [&mut self.out],
+                None,
+            );
+            if !self.emit(result, output) {
+                return;
+            }
+        }
+        while self.produced < self.expected() {
+            let result = self.resampler.process_partial_into_buffer(
+                None::<&[&[f32]]>,
+                &mut [&mut self.out],
+                None,
+            );
+            if !self.emit(result, output) {
+                return;
+            }
+        }
+    }
+
+    fn expected(&self) -> u64 {
+        self.consumed * self.to_rate as u64 / self.from_rate as u64
+    }
+
+    /// Appends what a call to the resampler wrote, up to the length the
+    /// input calls for, and returns whether it succeeded.
+    fn emit(&mut self, result: ResampleResult<(usize, usize)>, output: &mut Vec<f32>) -> bool {
+        let written = match result {
+            Ok((_, written)) => written,
+            Err(e) => {
+                eprintln!("Failed to resample audio: {}", e);
+                return false;
+            }
+        };
+        let wanted = (self.expected() - self.produced) as usize;
+        let kept = &self.out[..written.min(wanted)];
+        output.extend_from_slice(kept);
+        self.produced += kept.len() as u64;
+        true
+    }
+}
+
+/// Linear interpolation between neighbouring samples. Cheap, but anything
+/// above the target's Nyquist frequency aliases.
+struct LinearResampler {
+    from_rate: u32,
+    to_rate: u32,
+    step: f64,
+    /// Last sample of the previous chunk, needed to interpolate across chunk
+    /// boundaries.
+    carry: Option<f32>,
+    /// Frames seen before the current chunk.
+    consumed: u64,
+    /// Index of the next output sample.
+    next_out: u64,
+}
+
+impl LinearResampler {
+    fn new(from_rate: u32, to_rate: u32) -> Self {
+        Self {
+            from_rate,
+            to_rate,
+            step: from_rate as f64 / to_rate as f64,
+            carry: None,
+            consumed: 0,
+            next_out: 0,
+        }
+    }
+
+    fn push(&mut self, frames: &[f32], output: &mut Vec<f32>) {
+        let start = self.consumed;
+        let end = start + frames.len() as u64;
+        let carry = self.carry.unwrap_or(frames[0]);
+        let sample_at = |idx: u64| {
+            if idx < start {
+                carry
+            } else {
+                frames[(idx - start) as usize]
+            }
+        };
+        self.consumed = end;
+        self.carry = frames.last().copied();
+
+        // Integer decimation (e.g. 48 kHz -> 16 kHz) always lands on whole
+        // source samples, so the interpolation weights can be skipped.
+        if self.from_rate > self.to_rate && self.from_rate.is_multiple_of(self.to_rate) {
+            let factor = (self.from_rate / self.to_rate) as u64;
+            while self.next_out * factor + 1 < end {
+                output.push(sample_at(self.next_out * factor));
+                self.next_out += 1;
+            }
+            return;
+        }
+
+        loop {
+            let src_idx = self.next_out as f64 * self.step;
+            let idx_floor = src_idx as u64;
+            if idx_floor + 1 >= end {
+                break;
+            }
+            let frac = (src_idx - idx_floor as f64) as f32;
+
+            let a = sample_at(idx_floor);
+            let b = sample_at(idx_floor + 1);
+            output.push(a + (b - a) * frac);
+            self.next_out += 1;
+        }
+    }
+
+    fn finish(&mut self, output: &mut Vec<f32>) {
+        // Remaining outputs all sit on the final source sample.
+        let Some(last) = self.carry else { return };
+        let total = self.consumed * self.to_rate as u64 / self.from_rate as u64;
+        while self.next_out < total {
+            output.push(last);
+            self.next_out += 1;
+        }
+    }
+}
+
+fn downmix_into(samples: &[f32], channels: usize, mono: &mut Vec<f32>) {
+    // Stereo is by far the most common multi-channel input, so give it a
+    // branch-free loop the compiler can vectorize.
+    if channels == 2 {
+        mono.extend(
+            samples
+                .chunks_exact(2)
+                .map(|frame| (frame[0] + frame[1]) * 0.5),
+        );
+        return;
+    }
+
+    let scale = 1.0 / channels as f32;
+    mono.extend(
+        samples
+            .chunks_exact(channels)
+            .map(|frame| frame.iter().sum::<f32>() * scale),
+    );
+}
+
+/// How far above the noise floor a block must be to count as speech.
+pub(super) const SPEECH_TO_NOISE_RATIO: f32 = 3.0;
+
+/// Blocks quieter than this are never speech, however quiet the room is.
+const MIN_SPEECH_RMS: f32 = 0.01;
+
+/// Energy-based voice activity detection for hands-free recording. It is fed
+/// the RMS of successive blocks of audio, tracks the room's noise floor from
+/// the quiet ones, and reports when speech has been followed by `silence` of
+/// quiet. Leading silence before the first word never triggers it.
+pub struct SilenceDetector {
+    silence: Duration,
+    /// The quietest block of the warm-up, then an average of the quiet ones.
+    noise_floor: f32,
+    /// How much of the warm-up has been heard.
+    warmed_up: Duration,
+    /// The loudest block of the warm-up.
+    warm_up_peak: f32,
+    heard_speech: bool,
+    quiet_for: Duration,
+}
+
+/// The noise floor starts as the quietest block heard in this long, so a
+/// speaker who starts talking straight away doesn't set it to their voice.
+const WARM_UP: Duration = Duration::from_millis(500);
+
+impl SilenceDetector {
+    pub fn new(silence: Duration) -> Self {
+        Self {
+            silence,
+            noise_floor: f32::MAX,
+            warmed_up: Duration::ZERO,
+            warm_up_peak: 0.0,
+            heard_speech: false,
+            quiet_for: Duration::ZERO,
+        }
+    }
+
+    /// Adds a block of `duration` with the given RMS and returns true once the
+    /// speaker has gone quiet for long enough.
+    pub fn push(&mut self, rms: f32, duration: Duration) -> bool {
+        if self.warmed_up < WARM_UP {
+            self.warmed_up += duration;
+            self.noise_floor = self.noise_floor.min(rms);
+            self.warm_up_peak = self.warm_up_peak.max(rms);
+            if self.warmed_up >= WARM_UP {
+                self.heard_speech = self.is_speech(self.warm_up_peak);
+            }
+            return false;
+        }
+
+        if self.is_speech(rms) {
+            self.heard_speech = true;
+            self.quiet_for = Duration::ZERO;
+        } else {
+            self.noise_floor = self.noise_floor * 0.9 + rms * 0.1;
+            self.quiet_for += duration;
+        }
+        self.heard_speech && self.quiet_for >= self.silence
+    }
+
+    /// Waits for the next stretch of speech, keeping the noise floor learned
+    /// so far.
+    pub fn restart(&mut self) {
+        self.heard_speech = false;
+        self.quiet_for = Duration::ZERO;
+    }
+
+    fn is_speech(&self, rms: f32) -> bool {
+        rms > (self.noise_floor * SPEECH_TO_NOISE_RATIO).max(MIN_SPEECH_RMS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn sine(frequency: f64, rate: u32, seconds: f64) -> Vec<f32> {
+        (0..(rate as f64 * seconds) as usize)
+            .map(|i| ((2.0 * PI * frequency * i as f64 / rate as f64).sin() * 0.5) as f32)
+            .collect()
+    }
+
+    fn linear(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+        let mut resampler = LinearResampler::new(from_rate, to_rate);
+        let mut output = Vec::new();
+        resampler.push(samples, &mut output);
+        resampler.finish(&mut output);
+        output
+    }
+
+    /// The amplitude of the `frequency` tone in `samples`, and the RMS of
+    /// everything else, both away from the ends where the filter runs into
+    /// the edge of the signal. Fitting the tone rather than comparing with
+    /// a reference keeps a sub-sample shift from counting as an error.
+    fn analyze(samples: &[f32], frequency: f64, rate: u32) -> (f64, f64) {
+        let middle = &samples[samples.len() / 4..samples.len() * 3 / 4];
+        let phase = |i: usize| 2.0 * PI * frequency * i as f64 / rate as f64;
+        let n = middle.len() as f64;
+        let (mut sin, mut cos) = (0.0, 0.0);
+        for (i, &sample) in middle.iter().enumerate() {
+            sin += sample as f64 * phase(i).sin() * 2.0 / n;
+            cos += sample as f64 * phase(i).cos() * 2.0 / n;
+        }
+        let residual = middle
+            .iter()
+            .enumerate()
+            .map(|(i, &sample)| {
+                (sample as f64 - sin * phase(i).sin() - cos * phase(i).cos()).powi(2)
+            })
+            .sum::<f64>();
+        ((sin * sin + cos * cos).sqrt(), (residual / n).sqrt())
+    }
+
+    #[test]
+    fn removes_tones_above_the_target_nyquist() {
+        // 16 kHz audio holds nothing above 8 kHz; a 10 kHz tone must not
+        // come back as a 6 kHz one.
+        for from_rate in [44100, 48000] {
+            let tone = sine(10_000.0, from_rate, 1.0);
+            let (sinc, _) = analyze(&resample(&tone, from_rate, 16000), 6000.0, 16000);
+            let (linear, _) = analyze(&linear(&tone, from_rate, 16000), 6000.0, 16000);
+            assert!(sinc < 0.005, "{} Hz: aliased at {}", from_rate, sinc);
+            assert!(linear > 0.1, "{} Hz: aliased at {}", from_rate, linear);
+        }
+    }
+
+    #[test]
+    fn keeps_speech_frequencies_intact() {
+        for from_rate in [22050, 44100, 48000, 96000] {
+            for frequency in [300.0, 1000.0, 3000.0, 4000.0] {
+                let tone = sine(frequency, from_rate, 1.0);
+                let resampled = resample(&tone, from_rate, 16000);
+                let (amplitude, distortion) = analyze(&resampled, frequency, 16000);
+                assert!(
+                    (amplitude - 0.5).abs() < 0.01,
+                    "{} Hz at {} Hz: amplitude {}",
+                    frequency,
+                    from_rate,
+                    amplitude
+                );
+                assert!(
+                    distortion < 0.005,
+                    "{} Hz at {} Hz: distortion {}",
+                    frequency,
+                    from_rate,
+                    distortion
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn distorts_less_than_linear_off_the_sample_grid() {
+        let tone = sine(3000.0, 44100, 1.0);
+        let (_, sinc) = analyze(&resample(&tone, 44100, 16000), 3000.0, 16000);
+        let (_, linear) = analyze(&linear(&tone, 44100, 16000), 3000.0, 16000);
+        assert!(sinc * 4.0 < linear, "{} vs {}", sinc, linear);
+    }
+
+    #[test]
+    fn upsamples() {
+        let tone = sine(1000.0, 16000, 1.0);
+        let resampled = resample(&tone, 16000, 48000);
+        assert_eq!(resampled.len(), 48000);
+        let (amplitude, distortion) = analyze(&resampled, 1000.0, 48000);
+        assert!((amplitude - 0.5).abs() < 0.01, "amplitude {}", amplitude);
+        assert!(distortion < 0.005, "distortion {}", distortion);
+    }
+
+    #[test]
+    fn output_does_not_depend_on_chunking() {
+        let tone = sine(440.0, 44100, 2.3);
+        let whole = resample(&tone, 44100, 16000);
+        assert_eq!(whole.len(), tone.len() * 16000 / 44100);
+
+        let mut converter = StreamingConverter::with_target_rate(44100, 1, 16000);
+        let mut chunked = Vec::new();
+        for chunk in tone.chunks(777) {
+            converter.push(chunk, &mut chunked);
+        }
+        converter.finish(&mut chunked);
+        assert_eq!(chunked, whole);
+    }
+
+    #[test]
+    fn short_input_keeps_its_length() {
+        let resampled = resample(&[0.25; 100], 48000, 16000);
+        assert_eq!(resampled.len(), 33);
+    }
+
+    #[test]
+    fn whole_chunks_keep_their_length() {
+        assert!(resample(&[], 48000, 16000).is_empty());
+        let resampled = resample(&[0.25; SINC_CHUNK_FRAMES * 3], 48000, 16000);
+        assert_eq!(resampled.len(), SINC_CHUNK_FRAMES);
+    }
+
+    /// Feeds `rms` in 100 ms blocks for `seconds` and returns whether the
+    /// detector stopped.
+    fn listen(detector: &mut SilenceDetector, rms: f32, seconds: f32) -> bool {
+        let block = Duration::from_millis(100);
+        let mut stopped = false;
+        for _ in 0..(seconds * 10.0).round() as usize {
+            stopped |= detector.push(rms, block);
+        }
+        stopped
+    }
+
+    #[test]
+    fn leading_silence_never_stops() {
+        let mut detector = SilenceDetector::new(Duration::from_secs(1));
+        assert!(!listen(&mut detector, 0.002, 5.0));
+        assert!(!listen(&mut detector, 0.2, 1.0));
+        assert!(listen(&mut detector, 0.002, 1.0));
+    }
+
+    #[test]
+    fn stops_after_speech_from_the_first_block() {
+        // Seeding the floor from the first block made it the speaker's own
+        // voice, so nothing after it was ever loud enough to be speech.
+        let mut detector = SilenceDetector::new(Duration::from_secs(1));
+        assert!(!listen(&mut detector, 0.2, 0.2));
+        assert!(!listen(&mut detector, 0.002, 0.3));
+        assert!(!listen(&mut detector, 0.2, 2.0));
+        assert!(!listen(&mut detector, 0.002, 0.5));
+        assert!(listen(&mut detector, 0.002, 0.5));
+    }
+
+    #[test]
+    fn speech_only_in_the_warm_up_counts() {
+        let mut detector = SilenceDetector::new(Duration::from_secs(1));
+        assert!(!listen(&mut detector, 0.002, 0.2));
+        assert!(!listen(&mut detector, 0.2, 0.3));
+        assert!(listen(&mut detector, 0.002, 1.0));
+    }
+
+    #[test]
+    fn noise_is_not_speech() {
+        let mut detector = SilenceDetector::new(Duration::from_millis(500));
+        assert!(!listen(&mut detector, 0.03, 3.0));
+        assert!(!listen(&mut detector, 0.3, 1.0));
+        assert!(listen(&mut detector, 0.03, 0.5));
+    }
+
+    #[test]
+    fn restart_keeps_the_noise_floor() {
+        let mut detector = SilenceDetector::new(Duration::from_millis(500));
+        assert!(!listen(&mut detector, 0.002, 0.5));
+        assert!(!listen(&mut detector, 0.2, 1.0));
+        assert!(listen(&mut detector, 0.002, 0.5));
+        detector.restart();
+        assert!(!listen(&mut detector, 0.2, 0.3));
+        assert!(listen(&mut detector, 0.002, 0.5));
+    }
+
+    #[test]
+    fn downmixes_before_resampling() {
+        let mono = sine(1000.0, 48000, 0.5);
+        let stereo: Vec<f32> = mono.iter().flat_map(|&s| [s, s]).collect();
+        assert_eq!(
+            convert_to_whisper_format(stereo, 48000, 2),
+            convert_to_whisper_format(mono, 48000, 1)
+        );
+    }
+}
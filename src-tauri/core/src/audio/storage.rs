@@ -0,0 +1,213 @@
+use super::WHISPER_SAMPLE_RATE;
+use crate::files::create_private;
+use crate::focus::FocusedWindow;
+use flacenc::component::BitRepr;
+use flacenc::error::Verify;
+use serde::{Deserialize, Serialize};
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const BITS_PER_SAMPLE: usize = 16;
+
+/// A recording kept on disk after transcription, stored as 16 kHz mono FLAC
+/// with a JSON sidecar holding its metadata.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetainedRecording {
+    pub id: String,
+    /// Unix timestamp in milliseconds.
+    pub created_at: u64,
+    pub duration_secs: f32,
+    pub text: String,
+    /// Frontmost app/window when the dictation started, if capture was enabled.
+    #[serde(default)]
+    pub context: Option<FocusedWindow>,
+}
+
+impl RetainedRecording {
+    /// Case-insensitive match against the app name or window title.
+    pub fn matches_app(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        self.context.as_ref().is_some_and(|context| {
+            context.app_name.to_lowercase().contains(&query)
+                || context
+                    .window_title
+                    .as_ref()
+                    .is_some_and(|title| title.to_lowercase().contains(&query))
+        })
+    }
+}
+
+pub fn get_recordings_dir() -> PathBuf {
+    let dir = dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("voice")
+        .join("recordings");
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+/// Path of the FLAC file backing a retained recording.
+pub fn recording_audio_path(id: &str) -> Result<PathBuf, String> {
+    check_id(id)?;
+    Ok(get_recordings_dir().join(format!("{}.flac", id)))
+}
+
+fn metadata_path(id: &str) -> Result<PathBuf, String> {
+    check_id(id)?;
+    Ok(get_recordings_dir().join(format!("{}.json", id)))
+}
+
+/// Recording ids are the millisecond timestamps `save_recording` gives
+/// them. Anything else, such as `../`, would reach outside the folder.
+fn check_id(id: &str) -> Result<(), String> {
+    if id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("Not a recording id: {}", id));
+    }
+    Ok(())
+}
+
+/// Encodes whisper-format samples (16 kHz mono) to FLAC and stores them
+/// alongside the transcription.
+pub fn save_recording(
+    samples: &[f32],
+    text: &str,
+    context: Option<FocusedWindow>,
+) -> Result<RetainedRecording, String> {
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+
+    let recording = RetainedRecording {
+        id: created_at.to_string(),
+        created_at,
+        duration_secs: samples.len() as f32 / WHISPER_SAMPLE_RATE as f32,
+        text: text.to_string(),
+        context,
+    };
+
+    write_flac(&recording_audio_path(&recording.id)?, samples)?;
+
+    let metadata = serde_json::to_vec_pretty(&recording)
+        .map_err(|e| format!("Failed to serialize recording metadata: {}", e))?;
+    std::fs::write(metadata_path(&recording.id)?, metadata)
+        .map_err(|e| format!("Failed to write recording metadata: {}", e))?;
+
+    Ok(recording)
+}
+
+/// Lists retained recordings, newest first.
+pub fn list_recordings() -> Vec<RetainedRecording> {
+    let Ok(entries) = std::fs::read_dir(get_recordings_dir()) else {
+        return Vec::new();
+    };
+
+    let mut recordings: Vec<RetainedRecording> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| std::fs::read(path).ok())
+        .filter_map(|bytes| serde_json::from_slice(&bytes).ok())
+        .collect();
+
+    recordings.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    recordings
+}
+
+/// Decodes a retained recording back into whisper-format samples.
+pub fn load_recording(id: &str) -> Result<Vec<f32>, String> {
+    read_flac(&recording_audio_path(id)?)
+}
+
+/// Decodes a retained recording into an in-memory WAV file for playback.
+pub fn recording_to_wav(id: &str) -> Result<Vec<u8>, String> {
+    let samples = load_recording(id)?;
+    let mut cursor = std::io::Cursor::new(Vec::new());
+    write_wav(&mut cursor, &samples)?;
+    Ok(cursor.into_inner())
+}
+
+pub fn read_flac(path: &Path) -> Result<Vec<f32>, String> {
+    let mut reader =
+        claxon::FlacReader::open(path).map_err(|e| format!("Failed to open recording: {}", e))?;
+
+    let bits = reader.streaminfo().bits_per_sample;
+    let scale = 1.0 / (1u32 << (bits - 1)) as f32;
+
+    reader
+        .samples()
+        .map(|sample| sample.map(|s| s as f32 * scale))
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|e| format!("Failed to decode recording: {}", e))
+}
+
+/// Writes whisper-format samples to a FLAC file, for [`read_flac`] to read
+/// back.
+pub fn write_flac(path: &Path, samples: &[f32]) -> Result<(), String> {
+    std::fs::write(path, encode_flac(samples)?)
+        .map_err(|e| format!("Failed to write recording: {}", e))
+}
+
+/// Like [`write_flac`], into a new file only this user can read. Fails if
+/// something is already at `path`.
+pub fn write_private_flac(path: &Path, samples: &[f32]) -> Result<(), String> {
+    let bytes = encode_flac(samples)?;
+    create_private(path)
+        .and_then(|mut file| file.write_all(&bytes))
+        .map_err(|e| format!("Failed to write recording: {}", e))
+}
+
+/// Writes whisper-format samples as a 16-bit mono WAV.
+pub fn write_wav<W: Write + Seek>(writer: W, samples: &[f32]) -> Result<(), String> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: WHISPER_SAMPLE_RATE,
+        bits_per_sample: BITS_PER_SAMPLE as u16,
+        sample_format: hound::SampleFormat::Int,
+    };
+
+    let mut writer = hound::WavWriter::new(writer, spec)
+        .map_err(|e| format!("Failed to create WAV writer: {}", e))?;
+    for &sample in samples {
+        writer
+            .write_sample(to_i16(sample) as i16)
+            .map_err(|e| format!("Failed to write WAV sample: {}", e))?;
+    }
+    writer
+        .finalize()
+        .map_err(|e| format!("Failed to finalize WAV: {}", e))
+}
+
+pub fn delete_recording(id: &str) -> Result<(), String> {
+    std::fs::remove_file(recording_audio_path(id)?)
+        .map_err(|e| format!("Failed to delete recording: {}", e))?;
+    std::fs::remove_file(metadata_path(id)?).ok();
+    Ok(())
+}
+
+fn encode_flac(samples: &[f32]) -> Result<Vec<u8>, String> {
+    let pcm: Vec<i32> = samples.iter().map(|&s| to_i16(s)).collect();
+
+    let config = flacenc::config::Encoder::default()
+        .into_verified()
+        .map_err(|(_, e)| format!("Invalid FLAC encoder config: {:?}", e))?;
+    let source = flacenc::source::MemSource::from_samples(
+        &pcm,
+        1,
+        BITS_PER_SAMPLE,
+        WHISPER_SAMPLE_RATE as usize,
+    );
+    let stream = flacenc::encode_with_fixed_block_size(&config, source, config.block_size)
+        .map_err(|e| format!("Failed to encode FLAC: {:?}", e))?;
+
+    let mut sink = flacenc::bitsink::ByteSink::new();
+    stream
+        .write(&mut sink)
+        .map_err(|e| format!("Failed to write FLAC stream: {:?}", e))?;
+    Ok(sink.as_slice().to_vec())
+}
+
+fn to_i16(sample: f32) -> i32 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i32
+}
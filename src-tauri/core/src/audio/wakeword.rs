@@ -0,0 +1,134 @@
+use super::processor::WHISPER_SAMPLE_RATE;
+use std::collections::VecDeque;
+
+/// The phrase that starts a dictation hands-free.
+pub const WAKE_PHRASE: &str = "hey voice";
+
+/// Loudness is measured over frames this long.
+const FRAME_SECS: f32 = 0.03;
+/// Speech shorter than this is a click or a cough.
+const MIN_PHRASE_SECS: f32 = 0.3;
+/// Speech longer than this is a sentence, not the wake phrase.
+const MAX_PHRASE_SECS: f32 = 1.5;
+/// Quiet this long after speech ends it.
+const END_SILENCE_SECS: f32 = 0.3;
+/// Audio kept from before the gate opens, so the start of "hey" isn't lost.
+const PRE_ROLL_SECS: f32 = 0.2;
+/// Candidates are padded with quiet to this length, the least Whisper
+/// decodes.
+const MIN_CANDIDATE_SECS: f32 = 1.0;
+/// Speech is this many times louder than the background noise.
+const SPEECH_OVER_NOISE: f32 = 3.0;
+/// Quieter than this is never speech, however still the room.
+const MIN_SPEECH_RMS: f32 = 0.01;
+/// How quickly the noise floor follows the room, per quiet frame.
+const NOISE_ADAPTATION: f32 = 0.05;
+
+/// Listens to a continuous stream of whisper-format audio for short bursts
+/// of speech, the length of the wake phrase, standing alone between pauses.
+///
+/// This is the cheap first stage: it costs a few operations per sample and
+/// rejects almost everything, so only the rare candidate has to be checked
+/// against [`WAKE_PHRASE`] by a transcriber, with [`is_wake_phrase`].
+#[derive(Default)]
+pub struct WakeWordDetector {
+    /// Samples not yet making up a whole frame.
+    pending: Vec<f32>,
+    pre_roll: VecDeque<f32>,
+    /// The speech heard since the gate opened, with its pre-roll.
+    utterance: Vec<f32>,
+    speech_frames: usize,
+    silent_frames: usize,
+    noise_floor: Option<f32>,
+}
+
+impl WakeWordDetector {
+    /// Adds the next samples and returns the bursts of speech they complete
+    /// that could be the wake phrase.
+    pub fn push(&mut self, samples: &[f32]) -> Vec<Vec<f32>> {
+        let frame_len = sample_count(FRAME_SECS);
+        self.pending.extend_from_slice(samples);
+
+        let mut candidates = Vec::new();
+        let whole = self.pending.len() / frame_len * frame_len;
+        let pending = std::mem::take(&mut self.pending);
+        for frame in pending[..whole].chunks(frame_len) {
+            candidates.extend(self.push_frame(frame));
+        }
+        self.pending = pending[whole..].to_vec();
+        candidates
+    }
+
+    fn push_frame(&mut self, frame: &[f32]) -> Option<Vec<f32>> {
+        let rms = (frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt();
+        let noise_floor = *self.noise_floor.get_or_insert(rms);
+        let speech = rms > (noise_floor * SPEECH_OVER_NOISE).max(MIN_SPEECH_RMS);
+
+        if self.utterance.is_empty() {
+            if speech {
+                self.utterance.extend(self.pre_roll.drain(..));
+                self.utterance.extend_from_slice(frame);
+                self.speech_frames = 1;
+                self.silent_frames = 0;
+            } else {
+                self.noise_floor = Some(noise_floor + (rms - noise_floor) * NOISE_ADAPTATION);
+                self.pre_roll.extend(frame);
+                let excess = self
+                    .pre_roll
+                    .len()
+                    .saturating_sub(sample_count(PRE_ROLL_SECS));
+                self.pre_roll.drain(..excess);
+            }
+            return None;
+        }
+
+        // Talking on past the phrase's length only needs its end found, not
+        // its audio kept.
+        let too_long = self.speech_frames > frame_count(MAX_PHRASE_SECS);
+        if !too_long {
+            self.utterance.extend_from_slice(frame);
+        }
+        if speech {
+            self.speech_frames += 1;
+            self.silent_frames = 0;
+            return None;
+        }
+        self.silent_frames += 1;
+        if self.silent_frames < frame_count(END_SILENCE_SECS) {
+            return None;
+        }
+
+        let mut utterance = std::mem::take(&mut self.utterance);
+        if too_long || self.speech_frames < frame_count(MIN_PHRASE_SECS) {
+            return None;
+        }
+        utterance.resize(utterance.len().max(sample_count(MIN_CANDIDATE_SECS)), 0.0);
+        Some(utterance)
+    }
+}
+
+/// Whether a transcription of a candidate burst is the wake phrase, ignoring
+/// case and punctuation.
+pub fn is_wake_phrase(text: &str) -> bool {
+    let words = |text: &str| -> Vec<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    };
+    let heard = words(text);
+    let phrase = words(WAKE_PHRASE);
+    heard
+        .windows(phrase.len())
+        .any(|window| window == phrase.as_slice())
+}
+
+/// Samples in `secs` of whisper-format audio.
+fn sample_count(secs: f32) -> usize {
+    (secs * WHISPER_SAMPLE_RATE as f32) as usize
+}
+
+/// Frames in `secs`.
+fn frame_count(secs: f32) -> usize {
+    (secs / FRAME_SECS).round() as usize
+}
@@ -0,0 +1,61 @@
+//! Bridges stdin/stdout to a running Voice's RPC socket, doing the handshake
+//! on the client's behalf. Editors can spawn this instead of speaking TCP.
+
+use serde_json::json;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpStream};
+use voice_core::rpc::{read_discovery, PROTOCOL_VERSION};
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("voice-rpc: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let discovery = read_discovery()?;
+    let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, discovery.port))
+        .map_err(|e| format!("Failed to connect to Voice: {}", e))?;
+    let mut reader = BufReader::new(
+        stream
+            .try_clone()
+            .map_err(|e| format!("Failed to connect to Voice: {}", e))?,
+    );
+
+    let handshake = json!({
+        "jsonrpc": "2.0",
+        "id": 0,
+        "method": "handshake",
+        "params": { "version": PROTOCOL_VERSION, "token": discovery.token },
+    });
+    writeln!(stream, "{}", handshake).map_err(|e| format!("Handshake failed: {}", e))?;
+    let mut reply = String::new();
+    reader
+        .read_line(&mut reply)
+        .map_err(|e| format!("Handshake failed: {}", e))?;
+    let reply: serde_json::Value =
+        serde_json::from_str(&reply).map_err(|e| format!("Handshake failed: {}", e))?;
+    if let Some(error) = reply.get("error") {
+        return Err(format!("Handshake refused: {}", error["message"]));
+    }
+
+    std::thread::spawn(move || {
+        let mut stdout = std::io::stdout();
+        for line in reader.lines().map_while(Result::ok) {
+            if writeln!(stdout, "{}", line)
+                .and_then(|_| stdout.flush())
+                .is_err()
+            {
+                break;
+            }
+        }
+        std::process::exit(0);
+    });
+
+    for line in std::io::stdin().lock().lines() {
+        let line = line.map_err(|e| format!("Failed to read stdin: {}", e))?;
+        writeln!(stream, "{}", line).map_err(|e| format!("Lost connection to Voice: {}", e))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,47 @@
+//! Files only the current user may read, for access tokens and recorded
+//! audio that would otherwise land world-readable in shared folders.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Where audio that doesn't fit in memory waits: the user's cache folder,
+/// rather than a temporary folder every account on the machine can list.
+pub fn spill_dir() -> PathBuf {
+    let dir = dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("voice")
+        .join("spill");
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+/// Creates a new file at `path` that only this user can read and write.
+/// Fails if something is already there, so a file or link planted in its
+/// place is never written through.
+pub fn create_private(path: &Path) -> std::io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.read(true).write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
+}
+
+/// Replaces `path` with `bytes` in a file only this user can read. The file
+/// is written beside it and renamed over it, so readers never see half of
+/// it.
+pub fn write_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.tmp", std::process::id()));
+    let temp = path.with_file_name(name);
+    std::fs::remove_file(&temp).ok();
+
+    let written = create_private(&temp).and_then(|mut file| file.write_all(bytes));
+    match written.and_then(|()| std::fs::rename(&temp, path)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            std::fs::remove_file(&temp).ok();
+            Err(e)
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+
+/// The app (and window, when available) that had focus when a dictation began.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusedWindow {
+    pub app_name: String,
+    pub window_title: Option<String>,
+}
+
+#[cfg(target_os = "macos")]
+pub fn frontmost_window() -> Option<FocusedWindow> {
+    const SCRIPT: &str = r#"
+tell application "System Events"
+    set frontApp to first application process whose frontmost is true
+    set appName to name of frontApp
+    set winTitle to ""
+    try
+        set winTitle to name of front window of frontApp
+    end try
+end tell
+return appName & linefeed & winTitle"#;
+
+    let output = std::process::Command::new("osascript")
+        .args(["-e", SCRIPT])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines();
+    let app_name = lines.next()?.trim().to_string();
+    let window_title = lines.next().map(|t| t.trim().to_string());
+    from_parts(app_name, window_title)
+}
+
+#[cfg(target_os = "windows")]
+pub fn frontmost_window() -> Option<FocusedWindow> {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+        PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId,
+    };
+
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_null() {
+            return None;
+        }
+
+        let mut title = [0u16; 512];
+        let len = GetWindowTextW(hwnd, title.as_mut_ptr(), title.len() as i32);
+        let window_title = (len > 0).then(|| String::from_utf16_lossy(&title[..len as usize]));
+
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, &mut pid);
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if process.is_null() {
+            return from_parts(String::new(), window_title);
+        }
+
+        let mut path = [0u16; 1024];
+        let mut size = path.len() as u32;
+        let ok = QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, path.as_mut_ptr(), &mut size);
+        CloseHandle(process);
+
+        let app_name = if ok != 0 {
+            let path = String::from_utf16_lossy(&path[..size as usize]);
+            std::path::Path::new(&path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        } else {
+            String::new()
+        };
+        from_parts(app_name, window_title)
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub fn frontmost_window() -> Option<FocusedWindow> {
+    let xdotool = |args: &[&str]| {
+        std::process::Command::new("xdotool")
+            .args(args)
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+    };
+
+    let window_title = xdotool(&["getactivewindow", "getwindowname"]);
+    let app_name = xdotool(&["getactivewindow", "getwindowpid"])
+        .and_then(|pid| std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok())
+        .map(|comm| comm.trim().to_string())
+        .unwrap_or_default();
+    from_parts(app_name, window_title)
+}
+
+fn from_parts(app_name: String, window_title: Option<String>) -> Option<FocusedWindow> {
+    let window_title = window_title.filter(|t| !t.is_empty());
+    if app_name.is_empty() && window_title.is_none() {
+        return None;
+    }
+    Some(FocusedWindow {
+        app_name,
+        window_title,
+    })
+}
@@ -0,0 +1,74 @@
+//! Reads which keys are physically held, independent of the events a global
+//! shortcut delivers. Used to notice a key-up the shortcut never reported,
+//! and to hold synthetic keystrokes back while the user is pressing keys.
+
+/// A key of a shortcut chord. Modifiers match either the left or right key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChordKey {
+    Shift,
+    Control,
+    Alt,
+    /// ⌘ on macOS, the Windows key elsewhere.
+    Meta,
+    Space,
+}
+
+/// Whether any of `keys` is held right now, or `None` where the platform
+/// can't tell.
+pub fn any_held(keys: &[ChordKey]) -> Option<bool> {
+    let mut held = false;
+    for key in keys {
+        held |= chord_held(&[*key])?;
+    }
+    Some(held)
+}
+
+/// Whether every key of `chord` is held right now, or `None` where the
+/// platform can't tell.
+#[cfg(target_os = "macos")]
+pub fn chord_held(chord: &[ChordKey]) -> Option<bool> {
+    const COMBINED_SESSION_STATE: i32 = 0;
+    const FLAG_SHIFT: u64 = 0x0002_0000;
+    const FLAG_CONTROL: u64 = 0x0004_0000;
+    const FLAG_ALT: u64 = 0x0008_0000;
+    const FLAG_COMMAND: u64 = 0x0010_0000;
+    const KEY_SPACE: u16 = 49;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventSourceFlagsState(state: i32) -> u64;
+        fn CGEventSourceKeyState(state: i32, key: u16) -> bool;
+    }
+
+    let flags = unsafe { CGEventSourceFlagsState(COMBINED_SESSION_STATE) };
+    Some(chord.iter().all(|key| match key {
+        ChordKey::Shift => flags & FLAG_SHIFT != 0,
+        ChordKey::Control => flags & FLAG_CONTROL != 0,
+        ChordKey::Alt => flags & FLAG_ALT != 0,
+        ChordKey::Meta => flags & FLAG_COMMAND != 0,
+        ChordKey::Space => unsafe { CGEventSourceKeyState(COMBINED_SESSION_STATE, KEY_SPACE) },
+    }))
+}
+
+#[cfg(target_os = "windows")]
+pub fn chord_held(chord: &[ChordKey]) -> Option<bool> {
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+        GetAsyncKeyState, VK_CONTROL, VK_LWIN, VK_MENU, VK_RWIN, VK_SHIFT, VK_SPACE,
+    };
+
+    let down = |key| unsafe { GetAsyncKeyState(key as i32) } as u16 & 0x8000 != 0;
+    Some(chord.iter().all(|key| match key {
+        ChordKey::Shift => down(VK_SHIFT),
+        ChordKey::Control => down(VK_CONTROL),
+        ChordKey::Alt => down(VK_MENU),
+        ChordKey::Meta => down(VK_LWIN) || down(VK_RWIN),
+        ChordKey::Space => down(VK_SPACE),
+    }))
+}
+
+/// Wayland doesn't let apps read global key state, so Linux relies on the
+/// caller's timeout.
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub fn chord_held(_chord: &[ChordKey]) -> Option<bool> {
+    None
+}
@@ -0,0 +1,11 @@
+//! The recording and transcription engine behind Voice. Nothing here depends
+//! on Tauri, so the desktop app, tests and other frontends share one engine.
+
+pub mod audio;
+pub mod files;
+pub mod focus;
+pub mod keyboard;
+pub mod memory;
+pub mod rpc;
+pub mod transcription;
+pub mod typing;
@@ -0,0 +1,30 @@
+use std::process::Command;
+
+/// Memory this process holds in RAM, in bytes, or `None` where it can't be
+/// read.
+pub fn resident_bytes() -> Option<u64> {
+    let kib = if cfg!(target_os = "linux") {
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+        let line = status
+            .lines()
+            .find_map(|line| line.strip_prefix("VmRSS:"))?;
+        line.trim()
+            .trim_end_matches("kB")
+            .trim()
+            .parse::<u64>()
+            .ok()?
+    } else if cfg!(target_os = "macos") {
+        let pid = std::process::id().to_string();
+        let output = Command::new("ps")
+            .args(["-o", "rss=", "-p", &pid])
+            .output()
+            .ok()?;
+        String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse()
+            .ok()?
+    } else {
+        return None;
+    };
+    Some(kib * 1024)
+}
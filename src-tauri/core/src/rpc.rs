@@ -0,0 +1,309 @@
+//! The protocol alternative frontends (editor plugins, launchers) use to drive
+//! a running Voice: JSON-RPC 2.0, one message per line, over a localhost TCP
+//! socket. The port and an access token are published in `rpc.json` in the
+//! data directory, and a connection must open with a `handshake` call carrying
+//! that token. `voice-rpc` bridges the socket to stdio for clients that would
+//! rather spawn a process.
+
+use crate::files::write_private;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Bumped whenever a method is removed or changes shape. Adding methods,
+/// optional fields or new values of a state string doesn't change it, so
+/// clients should treat a state they don't know as busy.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+/// The engine refused or failed the operation; the message says why.
+pub const ENGINE_ERROR: i64 = -32000;
+/// The connection hasn't completed a handshake with the right token.
+pub const UNAUTHORIZED: i64 = -32001;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Request {
+    /// Absent for notifications, which get no response.
+    #[serde(default)]
+    pub id: Option<Value>,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_params(e: impl std::fmt::Display) -> Self {
+        Self::new(INVALID_PARAMS, format!("Invalid params: {}", e))
+    }
+}
+
+/// Engine operations report failures as plain strings.
+impl From<String> for RpcError {
+    fn from(message: String) -> Self {
+        Self::new(ENGINE_ERROR, message)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Response {
+    pub jsonrpc: &'static str,
+    pub id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl Response {
+    fn new(id: Value, outcome: Result<Value, RpcError>) -> Self {
+        let (result, error) = match outcome {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result,
+            error,
+        }
+    }
+}
+
+/// Describes a method for `schema`, so clients can be checked against the
+/// engine they talk to rather than against documentation.
+#[derive(Debug, Clone, Serialize)]
+pub struct MethodSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub params: &'static [ParamSpec],
+    /// Shape of the result, e.g. `{ text: string }`, or `null`.
+    pub result: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ParamSpec {
+    pub name: &'static str,
+    /// JSON type: `string`, `number`, `boolean`, `array` or `object`.
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub optional: bool,
+}
+
+/// What the frontend hosting the engine implements to answer requests.
+pub trait Handler: Send + Sync {
+    /// Methods this handler answers, besides `handshake` and `schema`.
+    fn methods(&self) -> &'static [MethodSpec];
+
+    fn handle(&self, method: &str, params: Value) -> Result<Value, RpcError>;
+}
+
+/// Deserializes a method's params, treating missing params as `{}`.
+pub fn parse_params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(RpcError::invalid_params)
+}
+
+#[derive(Deserialize)]
+struct Handshake {
+    version: u32,
+    #[serde(default)]
+    token: Option<String>,
+}
+
+/// Answers requests from `reader` on `writer` until the reader closes. With a
+/// token, every call before a successful `handshake` is refused.
+pub fn serve<R: BufRead, W: Write>(
+    handler: &dyn Handler,
+    reader: R,
+    mut writer: W,
+    token: Option<&str>,
+) -> std::io::Result<()> {
+    let mut authorized = token.is_none();
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let request: Request = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                let error = RpcError::new(PARSE_ERROR, format!("Invalid request: {}", e));
+                write_response(&mut writer, Response::new(Value::Null, Err(error)))?;
+                continue;
+            }
+        };
+
+        let outcome = if request.method == "handshake" {
+            handshake(handler, request.params, token).inspect(|_| authorized = true)
+        } else if !authorized {
+            Err(RpcError::new(
+                UNAUTHORIZED,
+                "Call handshake with the token from rpc.json first",
+            ))
+        } else if request.method == "schema" {
+            Ok(api_schema(handler.methods()))
+        } else {
+            handler.handle(&request.method, request.params)
+        };
+
+        if let Some(id) = request.id {
+            write_response(&mut writer, Response::new(id, outcome))?;
+        }
+    }
+    Ok(())
+}
+
+fn handshake(handler: &dyn Handler, params: Value, token: Option<&str>) -> Result<Value, RpcError> {
+    let handshake: Handshake = parse_params(params)?;
+    if token.is_some() && handshake.token.as_deref() != token {
+        return Err(RpcError::new(UNAUTHORIZED, "Wrong token"));
+    }
+    if handshake.version != PROTOCOL_VERSION {
+        return Err(RpcError::new(
+            INVALID_REQUEST,
+            format!(
+                "Unsupported protocol version {}, this engine speaks {}",
+                handshake.version, PROTOCOL_VERSION
+            ),
+        ));
+    }
+    let methods: Vec<&str> = handler.methods().iter().map(|m| m.name).collect();
+    Ok(json!({
+        "version": PROTOCOL_VERSION,
+        "methods": methods,
+    }))
+}
+
+/// The protocol version and every method with its params and result.
+pub fn api_schema(methods: &[MethodSpec]) -> Value {
+    json!({
+        "version": PROTOCOL_VERSION,
+        "methods": methods,
+    })
+}
+
+fn write_response<W: Write>(writer: &mut W, response: Response) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(&response)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    writer.flush()
+}
+
+/// Where a running server publishes how to reach it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Discovery {
+    pub version: u32,
+    pub port: u16,
+    pub token: String,
+}
+
+pub fn get_discovery_path() -> PathBuf {
+    let dir = dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("voice");
+    std::fs::create_dir_all(&dir).ok();
+    dir.join("rpc.json")
+}
+
+/// Reads the running server's port and token.
+pub fn read_discovery() -> Result<Discovery, String> {
+    let bytes = std::fs::read(get_discovery_path())
+        .map_err(|e| format!("Voice isn't serving RPC (no rpc.json): {}", e))?;
+    serde_json::from_slice(&bytes).map_err(|e| format!("Failed to parse rpc.json: {}", e))
+}
+
+/// A listening RPC socket. Each connection is served on its own thread.
+pub struct RpcServer {
+    port: u16,
+    stopped: Arc<AtomicBool>,
+}
+
+impl RpcServer {
+    /// Listens on a free localhost port and writes `rpc.json`.
+    pub fn start(handler: Arc<dyn Handler>) -> Result<Self, String> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .map_err(|e| format!("Failed to open RPC socket: {}", e))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to open RPC socket: {}", e))?
+            .port();
+
+        let discovery = Discovery {
+            version: PROTOCOL_VERSION,
+            port,
+            token: generate_token(),
+        };
+        let json = serde_json::to_vec_pretty(&discovery)
+            .map_err(|e| format!("Failed to serialize rpc.json: {}", e))?;
+        // The token lets its holder dictate and change settings, so only
+        // this user may read it.
+        write_private(&get_discovery_path(), &json)
+            .map_err(|e| format!("Failed to write rpc.json: {}", e))?;
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let stopped_clone = stopped.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stopped_clone.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                let handler = handler.clone();
+                let token = discovery.token.clone();
+                std::thread::spawn(move || {
+                    let Ok(reader) = stream.try_clone() else {
+                        return;
+                    };
+                    if let Err(e) = serve(&*handler, BufReader::new(reader), stream, Some(&token)) {
+                        eprintln!("RPC connection closed: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(Self { port, stopped })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Stops accepting connections and removes `rpc.json`. Open connections
+    /// finish on their own.
+    pub fn shutdown(self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wake the accept loop so it sees the flag.
+        let _ = TcpStream::connect((Ipv4Addr::LOCALHOST, self.port));
+        std::fs::remove_file(get_discovery_path()).ok();
+    }
+}
+
+/// 128 bits from the operating system's secure random number generator, as
+/// hex.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).expect("the OS random number generator is available");
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
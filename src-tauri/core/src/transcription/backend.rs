@@ -0,0 +1,358 @@
+use super::cloud::{check_endpoint, OpenAiTranscriber};
+use super::whisper::TranscriptionLanguage;
+use crate::audio::WHISPER_SAMPLE_RATE;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// What a transcription pass heard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transcript {
+    Speech(String),
+    /// The audio was silence or noise.
+    NoSpeech,
+}
+
+impl Transcript {
+    /// The spoken text; empty when there was no speech.
+    pub fn into_text(self) -> String {
+        match self {
+            Transcript::Speech(text) => text,
+            Transcript::NoSpeech => String::new(),
+        }
+    }
+
+    pub(super) fn from_text(text: &str, suppression: &Suppression) -> Self {
+        let text = suppression.filter(text);
+        if text.is_empty() || (suppression.noise_tags && is_annotation(&text)) {
+            Transcript::NoSpeech
+        } else {
+            Transcript::Speech(text)
+        }
+    }
+}
+
+/// A stretch of the audio and what was said in it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Segment {
+    /// Seconds from the start of the audio.
+    pub start: f32,
+    pub end: f32,
+    pub text: String,
+    /// The average probability of its words, from 0 to 1; `None` when the
+    /// backend doesn't say how sure it was.
+    #[serde(default)]
+    pub confidence: Option<f32>,
+    /// Empty when the backend doesn't report words.
+    #[serde(default)]
+    pub words: Vec<Word>,
+}
+
+/// A word as the model produced it, punctuation included, with the
+/// probability of its least likely token.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Word {
+    pub text: String,
+    pub probability: f32,
+}
+
+/// A transcription with its timing and language, for history, subtitles and
+/// anything else that needs to know when words were said.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptionResult {
+    pub text: String,
+    /// In the model's own words, before any clean-up of `text`. Empty when
+    /// the audio has no speech.
+    pub segments: Vec<Segment>,
+    /// ISO 639-1 code of the spoken language; `None` when it was detected
+    /// and the backend doesn't say what it found.
+    pub language: Option<String>,
+}
+
+impl TranscriptionResult {
+    /// A result for text without timings, as one segment spanning the audio.
+    pub fn untimed(text: String, samples: &[f32], language: &TranscriptionLanguage) -> Self {
+        let segments = if text.is_empty() {
+            Vec::new()
+        } else {
+            vec![Segment {
+                start: 0.0,
+                end: samples.len() as f32 / WHISPER_SAMPLE_RATE as f32,
+                text: text.clone(),
+                confidence: None,
+                words: Vec::new(),
+            }]
+        };
+        Self {
+            text,
+            segments,
+            language: match language {
+                TranscriptionLanguage::Auto => None,
+                TranscriptionLanguage::Code(code) => Some(code.clone()),
+            },
+        }
+    }
+}
+
+const MUSIC_NOTES: [char; 2] = ['♪', '♫'];
+
+/// Non-speech output kept out of transcriptions, since it regularly leaks
+/// into pasted text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Suppression {
+    /// Stop the local model from producing non-speech tokens at all.
+    pub non_speech_tokens: bool,
+    /// Remove ♪ and the lyrics Whisper hears between them during music.
+    pub music: bool,
+    /// Remove tags such as `[BLANK_AUDIO]`, `[laughs]` or `(wind blowing)`.
+    pub noise_tags: bool,
+}
+
+impl Default for Suppression {
+    fn default() -> Self {
+        Self {
+            non_speech_tokens: true,
+            music: true,
+            noise_tags: true,
+        }
+    }
+}
+
+impl Suppression {
+    /// Strips what is suppressed from `text` and tidies the spaces left
+    /// behind. Lyrics are only removed between a pair of notes and tags
+    /// between a pair of brackets, so a stray note or bracket costs nothing
+    /// after it. Parenthesised tags are only recognised as a whole segment,
+    /// so dictated parentheses survive.
+    pub(super) fn filter(&self, text: &str) -> String {
+        let chars: Vec<char> = text.chars().collect();
+        let mut out = String::with_capacity(text.len());
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let skip_to = if self.music && MUSIC_NOTES.contains(&c) {
+                // A note without a partner goes on its own.
+                Some(find_after(&chars, i, |c| MUSIC_NOTES.contains(&c)).unwrap_or(i))
+            } else if self.noise_tags && c == '[' {
+                find_after(&chars, i, |c| c == ']')
+            } else {
+                None
+            };
+            match skip_to {
+                Some(end) => i = end + 1,
+                None => {
+                    out.push(c);
+                    i += 1;
+                }
+            }
+        }
+        if out.len() == text.len() {
+            return out.trim().to_string();
+        }
+        out.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Where the first character after `start` that is `end` is.
+fn find_after(chars: &[char], start: usize, end: impl Fn(char) -> bool) -> Option<usize> {
+    chars[start + 1..]
+        .iter()
+        .position(|&c| end(c))
+        .map(|offset| start + 1 + offset)
+}
+
+/// Whether a segment is only a non-speech annotation such as `[BLANK_AUDIO]`
+/// or `(music)`.
+pub(super) fn is_annotation(text: &str) -> bool {
+    let text = text.trim();
+    (text.starts_with('[') && text.ends_with(']')) || (text.starts_with('(') && text.ends_with(')'))
+}
+
+/// Turns 16 kHz mono audio into text, on this machine or a service.
+pub trait Transcriber: Send + Sync {
+    fn transcribe(
+        &self,
+        samples: &[f32],
+        language: &TranscriptionLanguage,
+    ) -> Result<Transcript, String>;
+
+    /// Transcribes speech in `language` straight into English text.
+    fn translate(
+        &self,
+        samples: &[f32],
+        language: &TranscriptionLanguage,
+    ) -> Result<Transcript, String>;
+
+    /// Names and jargon to bias recognition towards, applied to later calls.
+    fn set_vocabulary(&self, vocabulary: &[String]);
+
+    /// What to keep out of later transcriptions.
+    fn set_suppression(&self, suppression: Suppression);
+
+    /// The transcription split into timed segments, with the language it was
+    /// in. Backends without timings give one segment spanning the audio.
+    fn transcribe_detailed(
+        &self,
+        samples: &[f32],
+        language: &TranscriptionLanguage,
+    ) -> Result<TranscriptionResult, String> {
+        let text = self.transcribe(samples, language)?.into_text();
+        Ok(TranscriptionResult::untimed(text, samples, language))
+    }
+
+    /// Up to `count` distinct readings of the audio, the usual transcription
+    /// first. Empty when the audio has no speech. Backends that can't offer
+    /// alternatives give just the one.
+    fn alternatives(
+        &self,
+        samples: &[f32],
+        language: &TranscriptionLanguage,
+        count: usize,
+    ) -> Result<Vec<String>, String> {
+        let _ = count;
+        Ok(match self.transcribe(samples, language)? {
+            Transcript::Speech(text) => vec![text],
+            Transcript::NoSpeech => Vec::new(),
+        })
+    }
+}
+
+/// Whisper reads at most this many tokens of prompt, keeping the last.
+const MAX_PROMPT_TOKENS: usize = 224;
+
+/// Phrases the vocabulary as a prompt, which the models treat as preceding
+/// text and so favour its spellings. Terms are dropped from the front until
+/// it fits in [`MAX_PROMPT_TOKENS`], so the user's own terms, which come
+/// after those of dictionary presets, are the ones kept.
+pub(super) fn vocabulary_prompt(vocabulary: &[String]) -> String {
+    let mut tokens = 0;
+    let kept = vocabulary
+        .iter()
+        .rev()
+        .take_while(|term| {
+            // One more for the comma between terms.
+            tokens += estimated_tokens(term) + 1;
+            tokens <= MAX_PROMPT_TOKENS
+        })
+        .count();
+    vocabulary[vocabulary.len() - kept..].join(", ")
+}
+
+/// A cautious token count: names and jargon split into about one token per
+/// three characters, more than everyday words do.
+fn estimated_tokens(term: &str) -> usize {
+    term.chars().count().div_ceil(3)
+}
+
+/// Where dictations are transcribed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum TranscriptionBackend {
+    /// The selected Whisper model, on this machine.
+    #[default]
+    Local,
+    /// OpenAI's transcription API, or a compatible service such as Groq when
+    /// `endpoint` is set. Audio leaves the machine.
+    OpenAi {
+        /// Kept in the OS credential store by the app, so it is never
+        /// written out with the rest of the backend.
+        #[serde(default, skip_serializing)]
+        api_key: String,
+        /// Base URL, `https://api.openai.com/v1` by default.
+        #[serde(default)]
+        endpoint: Option<String>,
+        /// `whisper-1` by default.
+        #[serde(default)]
+        model: Option<String>,
+    },
+}
+
+impl TranscriptionBackend {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            TranscriptionBackend::OpenAi {
+                endpoint: Some(endpoint),
+                ..
+            } => check_endpoint(endpoint),
+            _ => Ok(()),
+        }
+    }
+
+    /// The transcriber for a cloud backend; `None` for the local model, which
+    /// is loaded with the selected model instead.
+    pub fn remote(&self) -> Option<Arc<dyn Transcriber>> {
+        match self {
+            TranscriptionBackend::Local => None,
+            TranscriptionBackend::OpenAi {
+                api_key,
+                endpoint,
+                model,
+            } => Some(Arc::new(OpenAiTranscriber::new(
+                api_key.clone(),
+                endpoint.clone(),
+                model.clone(),
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_the_vocabulary_into_a_prompt() {
+        let vocabulary = ["Kubernetes".to_string(), "Stjernquist".to_string()];
+        assert_eq!(vocabulary_prompt(&vocabulary), "Kubernetes, Stjernquist");
+        assert_eq!(vocabulary_prompt(&[]), "");
+    }
+
+    #[test]
+    fn keeps_the_last_terms_of_a_long_vocabulary() {
+        let vocabulary: Vec<String> = (0..200).map(|n| format!("term{:03}", n)).collect();
+        let prompt = vocabulary_prompt(&vocabulary);
+        assert!(prompt.ends_with("term198, term199"));
+        assert!(!prompt.contains("term000"));
+        // Three tokens and a comma each.
+        assert_eq!(prompt.split(", ").count(), MAX_PROMPT_TOKENS / 4);
+    }
+
+    #[test]
+    fn removes_lyrics_between_notes() {
+        let suppression = Suppression::default();
+        assert_eq!(
+            suppression.filter("Okay. ♪ Never gonna give you up ♪ So anyway"),
+            "Okay. So anyway"
+        );
+        assert_eq!(suppression.filter("♫ la la ♫ Hi ♪ na na ♪"), "Hi");
+    }
+
+    #[test]
+    fn keeps_text_after_a_stray_note() {
+        let suppression = Suppression::default();
+        assert_eq!(
+            suppression.filter("♪ Thanks for watching, see you tomorrow"),
+            "Thanks for watching, see you tomorrow"
+        );
+    }
+
+    #[test]
+    fn removes_tags_between_brackets_only() {
+        let suppression = Suppression::default();
+        assert_eq!(
+            suppression.filter("[BLANK_AUDIO] Hello [laughs] there"),
+            "Hello there"
+        );
+        assert_eq!(suppression.filter("Use array[0 next"), "Use array[0 next");
+    }
+
+    #[test]
+    fn leaves_text_alone_when_off() {
+        let suppression = Suppression {
+            music: false,
+            noise_tags: false,
+            ..Suppression::default()
+        };
+        assert_eq!(suppression.filter(" ♪ la ♪ [x] "), "♪ la ♪ [x]");
+    }
+}
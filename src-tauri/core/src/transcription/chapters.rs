@@ -0,0 +1,133 @@
+use super::backend::Segment;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Transcripts shorter than this aren't split into chapters.
+const CHAPTER_THRESHOLD_SECS: f32 = 600.0;
+/// No chapter is shorter than this, so a long pause mid-topic can't leave a
+/// stub of a chapter.
+const MIN_CHAPTER_SECS: f32 = 120.0;
+/// A pause shorter than this is a breath, not a change of topic.
+const MIN_TOPIC_PAUSE_SECS: f32 = 2.0;
+/// Chapters are named after this many of their opening words.
+const TITLE_WORDS: usize = 6;
+
+/// A stretch of a long transcript about one topic.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chapter {
+    /// Seconds from the start of the audio.
+    pub start: f32,
+    pub title: String,
+    /// The index of the chapter's first segment.
+    pub first_segment: usize,
+}
+
+/// Splits a transcript of at least `CHAPTER_THRESHOLD_SECS` into chapters at
+/// its longest pauses, where speakers tend to change topic. Each chapter is
+/// named after its opening words. Empty for shorter transcripts, or when no
+/// pause is long enough to split at.
+pub fn find_chapters(segments: &[Segment]) -> Vec<Chapter> {
+    let (Some(first), Some(last)) = (segments.first(), segments.last()) else {
+        return Vec::new();
+    };
+    if last.end - first.start < CHAPTER_THRESHOLD_SECS {
+        return Vec::new();
+    }
+
+    let mut pauses: Vec<(usize, f32)> = segments
+        .windows(2)
+        .enumerate()
+        .map(|(index, pair)| (index + 1, pair[1].start - pair[0].end))
+        .filter(|(_, pause)| *pause >= MIN_TOPIC_PAUSE_SECS)
+        .collect();
+    pauses.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut starts = vec![0];
+    for (index, _) in pauses {
+        let start = segments[index].start;
+        let clear_of_others = starts
+            .iter()
+            .all(|other| (segments[*other].start - start).abs() >= MIN_CHAPTER_SECS);
+        if clear_of_others && last.end - start >= MIN_CHAPTER_SECS {
+            starts.push(index);
+        }
+    }
+    if starts.len() < 2 {
+        return Vec::new();
+    }
+    starts.sort_unstable();
+    starts
+        .into_iter()
+        .map(|index| Chapter {
+            start: segments[index].start,
+            title: opening_words(&segments[index..segments.len().min(index + TITLE_WORDS)]),
+            first_segment: index,
+        })
+        .collect()
+}
+
+/// The text of each chapter.
+pub fn chapter_texts(segments: &[Segment], chapters: &[Chapter]) -> Vec<String> {
+    chapters
+        .iter()
+        .enumerate()
+        .map(|(index, chapter)| {
+            let end = chapters
+                .get(index + 1)
+                .map_or(segments.len(), |next| next.first_segment);
+            join_text(&segments[chapter.first_segment.min(end)..end])
+        })
+        .collect()
+}
+
+/// The transcript as Markdown, one paragraph per chapter under a heading
+/// with its start time; a single paragraph without chapters.
+pub fn format_markdown(segments: &[Segment], chapters: &[Chapter]) -> String {
+    if chapters.is_empty() {
+        return format!("{}\n", join_text(segments));
+    }
+    let mut markdown = String::new();
+    for (chapter, text) in chapters.iter().zip(chapter_texts(segments, chapters)) {
+        let _ = writeln!(
+            markdown,
+            "## [{}] {}\n\n{}\n",
+            clock(chapter.start),
+            chapter.title,
+            text
+        );
+    }
+    markdown
+}
+
+/// `h:mm:ss`, or `m:ss` under an hour.
+fn clock(secs: f32) -> String {
+    let secs = secs.max(0.0) as u64;
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+fn join_text(segments: &[Segment]) -> String {
+    segments
+        .iter()
+        .map(|segment| segment.text.trim())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn opening_words(segments: &[Segment]) -> String {
+    let text = join_text(segments);
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let title = words[..words.len().min(TITLE_WORDS)]
+        .join(" ")
+        .trim_end_matches(|c: char| c.is_ascii_punctuation())
+        .to_string();
+    if words.len() > TITLE_WORDS {
+        format!("{}…", title)
+    } else {
+        title
+    }
+}
@@ -0,0 +1,154 @@
+use super::backend::{vocabulary_prompt, Suppression, Transcriber, Transcript};
+use super::whisper::TranscriptionLanguage;
+use crate::audio::write_wav;
+use parking_lot::Mutex;
+use reqwest::blocking::{multipart, Client};
+use serde::Deserialize;
+use std::io::Cursor;
+use std::net::IpAddr;
+
+const DEFAULT_ENDPOINT: &str = "https://api.openai.com/v1";
+const DEFAULT_MODEL: &str = "whisper-1";
+
+/// The API key and every recording go to the endpoint, so it must use HTTPS.
+/// Plain HTTP is only allowed to a server on this machine.
+pub(super) fn check_endpoint(endpoint: &str) -> Result<(), String> {
+    require_https(endpoint, "transcription endpoint")
+}
+
+/// Accepts `https://` URLs, and `http://` ones only to a server on this
+/// machine. `what` names the URL in the error.
+pub(super) fn require_https(url: &str, what: &str) -> Result<(), String> {
+    let url = reqwest::Url::parse(url).map_err(|e| format!("Invalid {}: {}", what, e))?;
+    let local = url
+        .host_str()
+        .map(|host| host.trim_start_matches('[').trim_end_matches(']'))
+        .is_some_and(|host| {
+            host == "localhost" || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+        });
+    match url.scheme() {
+        "https" => Ok(()),
+        "http" if local => Ok(()),
+        _ => Err(format!("The {} must be an https:// URL", what)),
+    }
+}
+
+/// Transcribes with OpenAI's `audio/transcriptions` API or a service that
+/// mirrors it. Each dictation is uploaded as a WAV file.
+pub struct OpenAiTranscriber {
+    client: Client,
+    endpoint: String,
+    model: String,
+    api_key: String,
+    prompt: Mutex<String>,
+    suppression: Mutex<Suppression>,
+}
+
+#[derive(Deserialize)]
+struct TranscriptionResponse {
+    text: String,
+}
+
+impl OpenAiTranscriber {
+    pub fn new(api_key: String, endpoint: Option<String>, model: Option<String>) -> Self {
+        Self {
+            client: Client::new(),
+            endpoint: endpoint.unwrap_or_else(|| DEFAULT_ENDPOINT.to_string()),
+            model: model.unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+            api_key,
+            prompt: Mutex::new(String::new()),
+            suppression: Mutex::new(Suppression::default()),
+        }
+    }
+
+    fn request(
+        &self,
+        path: &str,
+        samples: &[f32],
+        language: Option<&str>,
+    ) -> Result<Transcript, String> {
+        let mut wav = Cursor::new(Vec::new());
+        write_wav(&mut wav, samples)?;
+        let file = multipart::Part::bytes(wav.into_inner())
+            .file_name("dictation.wav")
+            .mime_str("audio/wav")
+            .map_err(|e| format!("Failed to prepare audio: {}", e))?;
+
+        let mut form = multipart::Form::new()
+            .part("file", file)
+            .text("model", self.model.clone())
+            .text("response_format", "json");
+        if let Some(language) = language {
+            form = form.text("language", language.to_string());
+        }
+        let prompt = self.prompt.lock().clone();
+        if !prompt.is_empty() {
+            form = form.text("prompt", prompt);
+        }
+
+        let response: TranscriptionResponse = self
+            .client
+            .post(format!("{}/{}", self.endpoint.trim_end_matches('/'), path))
+            .bearer_auth(&self.api_key)
+            .multipart(form)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.json())
+            .map_err(|e| format!("Cloud transcription failed: {}", e))?;
+        let suppression = *self.suppression.lock();
+        Ok(Transcript::from_text(&response.text, &suppression))
+    }
+}
+
+impl Transcriber for OpenAiTranscriber {
+    fn set_vocabulary(&self, vocabulary: &[String]) {
+        *self.prompt.lock() = vocabulary_prompt(vocabulary);
+    }
+
+    fn set_suppression(&self, suppression: Suppression) {
+        *self.suppression.lock() = suppression;
+    }
+
+    fn transcribe(
+        &self,
+        samples: &[f32],
+        language: &TranscriptionLanguage,
+    ) -> Result<Transcript, String> {
+        // The service detects the language when none is given.
+        let language = match language {
+            TranscriptionLanguage::Auto => None,
+            TranscriptionLanguage::Code(code) => Some(code.as_str()),
+        };
+        self.request("audio/transcriptions", samples, language)
+    }
+
+    fn translate(
+        &self,
+        samples: &[f32],
+        _language: &TranscriptionLanguage,
+    ) -> Result<Transcript, String> {
+        self.request("audio/translations", samples, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requires_https() {
+        assert!(check_endpoint("https://api.groq.com/openai/v1").is_ok());
+        assert!(check_endpoint("http://api.groq.com/openai/v1").is_err());
+        assert!(check_endpoint("ftp://api.groq.com/").is_err());
+        assert!(check_endpoint("api.groq.com").is_err());
+    }
+
+    #[test]
+    fn allows_http_to_this_machine() {
+        assert!(check_endpoint("http://localhost:8000/v1").is_ok());
+        assert!(check_endpoint("http://127.0.0.1:8000/v1").is_ok());
+        assert!(check_endpoint("http://[::1]:8000/v1").is_ok());
+        assert!(check_endpoint("http://localhost.example.com/v1").is_err());
+        assert!(check_endpoint("http://192.168.1.10:8000/v1").is_err());
+    }
+}
@@ -0,0 +1,109 @@
+use serde::Serialize;
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// whisper.cpp stops getting faster past this many threads and starts
+/// fighting the rest of the machine for cores.
+const MAX_THREADS: usize = 8;
+
+/// How whisper.cpp runs on this machine's CPU, detected once at startup.
+#[derive(Debug, Clone, Serialize)]
+pub struct ComputeConfig {
+    /// "aarch64" or "x86_64".
+    pub arch: &'static str,
+    /// The widest vector instructions whisper.cpp was compiled to use, e.g.
+    /// "NEON" or "AVX2". ggml picks these when it's built, not at runtime.
+    pub simd: &'static str,
+    /// The BLAS library whisper.cpp hands matrix work to, if any.
+    pub blas: Option<&'static str>,
+    /// An Intel build running on Apple Silicon through Rosetta, which is
+    /// several times slower than the native build.
+    pub translated: bool,
+    /// Threads each transcription decodes with.
+    pub threads: usize,
+}
+
+/// The configuration for this machine, detecting it on first use.
+pub fn compute_config() -> &'static ComputeConfig {
+    static CONFIG: OnceLock<ComputeConfig> = OnceLock::new();
+    CONFIG.get_or_init(|| ComputeConfig {
+        arch: std::env::consts::ARCH,
+        simd: simd(whisper_rs::print_system_info()),
+        blas: cfg!(target_os = "macos").then_some("Accelerate"),
+        translated: sysctl("sysctl.proc_translated") == Some(1),
+        threads: threads(),
+    })
+}
+
+/// The widest vector extension in whisper.cpp's system info, which lists
+/// each one ggml was compiled with as e.g. "AVX2 = 1".
+fn simd(info: &str) -> &'static str {
+    let has = |name: &str| {
+        info.split('|').any(|field| {
+            let field = field.rsplit(':').next().unwrap_or(field);
+            field
+                .split_once('=')
+                .is_some_and(|(key, value)| key.trim() == name && value.trim() == "1")
+        })
+    };
+    if has("AVX512") {
+        "AVX-512"
+    } else if has("AVX2") {
+        "AVX2"
+    } else if has("AVX") {
+        "AVX"
+    } else if has("NEON") && has("DOTPROD") {
+        "NEON (dot product)"
+    } else if has("NEON") {
+        "NEON"
+    } else if has("SSE3") {
+        "SSE3"
+    } else {
+        "none"
+    }
+}
+
+/// One thread per core that's worth decoding on. Apple Silicon's
+/// efficiency cores and hyper-threads only slow whisper.cpp down, since
+/// every thread waits for the slowest at each layer.
+fn threads() -> usize {
+    sysctl("hw.perflevel0.physicalcpu")
+        .unwrap_or_else(num_cpus::get_physical)
+        .clamp(1, MAX_THREADS)
+}
+
+/// A numeric macOS sysctl; `None` elsewhere or when the key doesn't exist.
+fn sysctl(key: &str) -> Option<usize> {
+    if !cfg!(target_os = "macos") {
+        return None;
+    }
+    let output = Command::new("sysctl").args(["-n", key]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_the_widest_compiled_extension() {
+        let info = "AVX = 1 | AVX2 = 1 | AVX512 = 0 | FMA = 1 | NEON = 0 | SSE3 = 1 | ";
+        assert_eq!(simd(info), "AVX2");
+    }
+
+    #[test]
+    fn reads_sectioned_system_info() {
+        let info =
+            "WHISPER : COREML = 0 | OPENVINO = 0 | CPU : NEON = 1 | ARM_FMA = 1 | DOTPROD = 1 | ";
+        assert_eq!(simd(info), "NEON (dot product)");
+    }
+
+    #[test]
+    fn ignores_extensions_that_were_not_compiled_in() {
+        let info = "AVX = 0 | AVX2 = 0 | AVX512 = 0 | NEON = 0 | SSE3 = 0 | AVX512_VBMI = 1 | ";
+        assert_eq!(simd(info), "none");
+    }
+}
@@ -0,0 +1,376 @@
+use super::cloud::require_https;
+use super::manifest::ModelEntry;
+use super::whisper::{account_tag, ModelId, WhisperTranscriber};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// The error a download returns when it was cancelled.
+pub const DOWNLOAD_CANCELLED: &str = "Download cancelled";
+
+/// Aborts a download from another thread. Clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Credentials sent with model downloads, for internal artifact servers. The
+/// token and password are kept in the OS credential store by the app, so
+/// they are never written out with the rest of the config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum DownloadAuth {
+    Bearer {
+        #[serde(default, skip_serializing)]
+        token: String,
+    },
+    Basic {
+        username: String,
+        #[serde(default, skip_serializing)]
+        password: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DownloadConfig {
+    /// Base URL serving the ggml files under their standard filenames, used
+    /// instead of Hugging Face when set.
+    pub mirror_url: Option<String>,
+    /// Sent to the mirror only, never to the default download URLs.
+    pub auth: Option<DownloadAuth>,
+}
+
+impl DownloadConfig {
+    /// The mirror gets the credentials and serves the models, so it must use
+    /// HTTPS unless it runs on this machine.
+    pub fn validate(&self) -> Result<(), String> {
+        match (&self.mirror_url, &self.auth) {
+            (Some(mirror), _) => require_https(mirror, "model mirror"),
+            (None, Some(_)) => Err("Download credentials need a mirror URL".to_string()),
+            (None, None) => Ok(()),
+        }
+    }
+
+    /// The credentials for the mirror; `None` without one.
+    fn mirror_auth(&self) -> Option<&DownloadAuth> {
+        self.mirror_url.as_ref().and(self.auth.as_ref())
+    }
+
+    pub fn model_url(&self, entry: &ModelEntry) -> String {
+        match &self.mirror_url {
+            Some(base) => format!("{}/{}", base.trim_end_matches('/'), entry.filename),
+            None => entry.url.clone(),
+        }
+    }
+}
+
+pub async fn download_model<F>(
+    entry: &ModelEntry,
+    config: &DownloadConfig,
+    cancel: &CancelToken,
+    progress_callback: F,
+) -> Result<PathBuf, String>
+where
+    F: Fn(u64, u64) + Send + 'static,
+{
+    let model = ModelId::parse(&entry.key)?;
+    if entry.is_downloaded() {
+        return Ok(model.path());
+    }
+    let model_path = model.download_path();
+
+    let partial = partial_path(
+        &model_path,
+        &config.model_url(entry),
+        entry.sha256.as_deref(),
+    );
+    download_entry(entry, config, &partial, cancel, progress_callback).await?;
+    // Replaces a truncated file from an earlier attempt, if there is one.
+    std::fs::rename(&partial, &model_path)
+        .map_err(|e| format!("Failed to move model into place: {}", e))?;
+    Ok(model_path)
+}
+
+/// Downloads a model and loads it before moving it into place, so whatever
+/// model is in use keeps working until the new one is known to be good.
+pub async fn download_verified_model<F>(
+    entry: &ModelEntry,
+    config: &DownloadConfig,
+    use_gpu: bool,
+    cancel: &CancelToken,
+    progress_callback: F,
+) -> Result<WhisperTranscriber, String>
+where
+    F: Fn(u64, u64) + Send + 'static,
+{
+    let model = ModelId::parse(&entry.key)?;
+    if entry.is_downloaded() {
+        return WhisperTranscriber::new(&model.path(), use_gpu);
+    }
+    let model_path = model.download_path();
+
+    let partial = partial_path(
+        &model_path,
+        &config.model_url(entry),
+        entry.sha256.as_deref(),
+    );
+    download_entry(entry, config, &partial, cancel, progress_callback).await?;
+
+    let transcriber = match WhisperTranscriber::new(&partial, use_gpu) {
+        Ok(transcriber) => transcriber,
+        Err(e) => {
+            std::fs::remove_file(&partial).ok();
+            return Err(format!("Downloaded model failed verification: {}", e));
+        }
+    };
+    std::fs::rename(&partial, &model_path)
+        .map_err(|e| format!("Failed to move model into place: {}", e))?;
+    Ok(transcriber)
+}
+
+async fn download_entry<F>(
+    entry: &ModelEntry,
+    config: &DownloadConfig,
+    path: &Path,
+    cancel: &CancelToken,
+    progress_callback: F,
+) -> Result<(), String>
+where
+    F: Fn(u64, u64) + Send + 'static,
+{
+    download_file(
+        &config.model_url(entry),
+        path,
+        config.mirror_auth(),
+        entry.sha256.as_deref(),
+        cancel,
+        progress_callback,
+    )
+    .await
+}
+
+/// Models are written here while downloading so an interrupted download never
+/// shows up as an installed model, and the next attempt can resume it. The
+/// name is keyed by `source` and the expected checksum, so a download only
+/// ever resumes a file from the same place, and this account's partial files
+/// of `path` from anywhere else are removed. It also names the account, since
+/// several may download the same model to the shared folder at once.
+pub(super) fn partial_path(path: &Path, source: &str, sha256: Option<&str>) -> PathBuf {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    hasher.update(source);
+    hasher.update([0]);
+    hasher.update(sha256.unwrap_or_default().to_ascii_lowercase());
+    let key: String = hasher.finalize()[..8]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let prefix = format!("{}.{}.", file_name, account_tag());
+    let partial = path.with_file_name(format!("{}{}.part", prefix, key));
+    let validator = etag_path(&partial);
+    let stale = std::fs::read_dir(path.parent().unwrap_or(Path::new(".")))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|other| *other != partial && *other != validator)
+        .filter(|other| {
+            other.file_name().is_some_and(|name| {
+                let name = name.to_string_lossy();
+                name.starts_with(&prefix) && (name.ends_with(".part") || name.ends_with(".etag"))
+            })
+        });
+    for other in stale {
+        std::fs::remove_file(other).ok();
+    }
+    partial
+}
+
+/// The server's ETag for a partial download, sent back with `If-Range` so a
+/// file that changed on the server is downloaded again rather than spliced.
+fn etag_path(partial: &Path) -> PathBuf {
+    partial.with_extension("etag")
+}
+
+/// Downloads `url` to `path`, resuming a partial file already there with a
+/// range request if the server's ETag still matches. A file that fails
+/// `expected_sha256` is deleted, and so is one whose download is cancelled.
+pub async fn download_file<F>(
+    url: &str,
+    path: &Path,
+    auth: Option<&DownloadAuth>,
+    expected_sha256: Option<&str>,
+    cancel: &CancelToken,
+    progress_callback: F,
+) -> Result<(), String>
+where
+    F: Fn(u64, u64) + Send + 'static,
+{
+    use futures_util::StreamExt;
+    use reqwest::header::{ETAG, IF_RANGE, RANGE};
+    use reqwest::StatusCode;
+    use sha2::{Digest, Sha256};
+
+    let mut request = reqwest::Client::new().get(url);
+    request = match auth {
+        Some(DownloadAuth::Bearer { token }) => request.bearer_auth(token),
+        Some(DownloadAuth::Basic { username, password }) => {
+            request.basic_auth(username, password.as_ref())
+        }
+        None => request,
+    };
+
+    // Pick up where an interrupted download left off. Without an ETag there's
+    // no telling whether the file changed since, so it starts over.
+    let validator = etag_path(path);
+    let resume_from = match std::fs::read_to_string(&validator) {
+        Ok(etag) => {
+            let len = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+            if len > 0 {
+                request = request
+                    .header(RANGE, format!("bytes={}-", len))
+                    .header(IF_RANGE, etag);
+            }
+            len
+        }
+        Err(_) => 0,
+    };
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download model: {}", e))?;
+
+    // A partial file the server has nothing past was fully downloaded; only
+    // the move into place was missed.
+    let response = if resume_from > 0 && response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        None
+    } else {
+        Some(
+            response
+                .error_for_status()
+                .map_err(|e| format!("Failed to download model: {}", e))?,
+        )
+    };
+    let resumed = match &response {
+        Some(response) => response.status() == StatusCode::PARTIAL_CONTENT,
+        None => true,
+    };
+    if !resumed {
+        // Only a strong ETag may validate a range.
+        let etag = response
+            .as_ref()
+            .and_then(|response| response.headers().get(ETAG))
+            .and_then(|etag| etag.to_str().ok())
+            .filter(|etag| !etag.starts_with("W/"));
+        if let Some(etag) = etag {
+            std::fs::write(&validator, etag).ok();
+        } else {
+            std::fs::remove_file(&validator).ok();
+        }
+    }
+
+    let mut downloaded: u64 = 0;
+    let mut hasher = Sha256::new();
+    let mut file = if resumed {
+        // The checksum covers the whole file, so hash what's already there.
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open partial download: {}", e))?;
+        downloaded = std::io::copy(&mut file, &mut hasher)
+            .map_err(|e| format!("Failed to read partial download: {}", e))?;
+        file
+    } else {
+        std::fs::File::create(path).map_err(|e| format!("Failed to create model file: {}", e))?
+    };
+
+    let total_size = downloaded
+        + response
+            .as_ref()
+            .and_then(|response| response.content_length())
+            .unwrap_or(0);
+    progress_callback(downloaded, total_size);
+
+    if let Some(response) = response {
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            if cancel.is_cancelled() {
+                drop(file);
+                std::fs::remove_file(path).ok();
+                std::fs::remove_file(&validator).ok();
+                return Err(DOWNLOAD_CANCELLED.to_string());
+            }
+            let chunk = chunk.map_err(|e| format!("Download error: {}", e))?;
+            std::io::Write::write_all(&mut file, &chunk)
+                .map_err(|e| format!("Failed to write chunk: {}", e))?;
+            hasher.update(&chunk);
+            downloaded += chunk.len() as u64;
+            progress_callback(downloaded, total_size);
+        }
+    }
+
+    if let Some(expected) = expected_sha256 {
+        let actual: String = hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        if !actual.eq_ignore_ascii_case(expected) {
+            drop(file);
+            std::fs::remove_file(path).ok();
+            std::fs::remove_file(&validator).ok();
+            return Err("Downloaded file does not match its checksum".to_string());
+        }
+    }
+
+    std::fs::remove_file(&validator).ok();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mirror(url: Option<&str>) -> DownloadConfig {
+        DownloadConfig {
+            mirror_url: url.map(str::to_string),
+            auth: Some(DownloadAuth::Bearer {
+                token: "secret".to_string(),
+            }),
+        }
+    }
+
+    #[test]
+    fn credentials_only_go_to_the_mirror() {
+        assert!(mirror(Some("https://models.example.com"))
+            .mirror_auth()
+            .is_some());
+        assert!(mirror(None).mirror_auth().is_none());
+        assert!(mirror(None).validate().is_err());
+    }
+
+    #[test]
+    fn the_mirror_must_use_https_unless_it_is_local() {
+        assert!(mirror(Some("https://models.example.com"))
+            .validate()
+            .is_ok());
+        assert!(mirror(Some("http://models.example.com"))
+            .validate()
+            .is_err());
+        assert!(mirror(Some("http://127.0.0.1:8080")).validate().is_ok());
+    }
+}
@@ -0,0 +1,114 @@
+use super::manifest::ModelManifest;
+use super::packs::{list_packs, remove_pack};
+use super::whisper::{get_model_dir, shared_model_dir, ModelId};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// A model file in the models folder.
+#[derive(Debug, Clone, Serialize)]
+pub struct InstalledModel {
+    /// Identifier accepted by `ModelId::parse`.
+    pub key: String,
+    pub name: String,
+    pub path: PathBuf,
+    /// Bytes used in the models folder; a linked import takes next to none.
+    pub size: u64,
+    /// In the shared model folder, used by every account.
+    pub shared: bool,
+}
+
+fn is_shared(path: &Path) -> bool {
+    shared_model_dir().is_some_and(|dir| path.starts_with(dir))
+}
+
+fn size_on_disk(path: &Path) -> u64 {
+    std::fs::symlink_metadata(path)
+        .map(|metadata| metadata.len())
+        .unwrap_or(0)
+}
+
+/// Every downloaded model, language pack and import, named from `manifest`
+/// where possible. Model files the manifest no longer lists are included
+/// under their file name so they can still be deleted.
+pub fn list_installed_models(manifest: &ModelManifest) -> Vec<InstalledModel> {
+    let mut installed: Vec<InstalledModel> = manifest
+        .models
+        .iter()
+        .filter_map(|entry| {
+            let path = ModelId::parse(&entry.key).ok()?.path();
+            path.exists().then(|| InstalledModel {
+                key: entry.key.clone(),
+                name: entry.name.clone(),
+                size: size_on_disk(&path),
+                shared: is_shared(&path),
+                path,
+            })
+        })
+        .collect();
+
+    let dirs = shared_model_dir().into_iter().chain([get_model_dir()]);
+    for entries in dirs.filter_map(|dir| std::fs::read_dir(dir).ok()) {
+        for path in entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+        {
+            if path.extension().and_then(|ext| ext.to_str()) != Some("bin") {
+                continue;
+            }
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let Ok(model) = ModelId::parse(&stem) else {
+                continue;
+            };
+            // A model in both folders is listed once, as the shared copy
+            // that gets loaded.
+            if installed.iter().any(|other| other.key == model.key()) {
+                continue;
+            }
+            installed.push(InstalledModel {
+                key: model.key(),
+                name: path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+                size: size_on_disk(&path),
+                shared: is_shared(&path),
+                path,
+            });
+        }
+    }
+
+    for pack in list_packs() {
+        let model = ModelId::Pack(pack.slug);
+        let path = model.path();
+        installed.push(InstalledModel {
+            key: model.key(),
+            name: pack.name,
+            size: size_on_disk(&path),
+            shared: false,
+            path,
+        });
+    }
+
+    installed
+}
+
+/// Deletes a model's file, and its Core ML encoder if it has one. The caller
+/// must unload the model first; Windows can't delete a file that is mapped.
+pub fn delete_model(model: &ModelId) -> Result<(), String> {
+    if let ModelId::Pack(slug) = model {
+        return remove_pack(slug);
+    }
+
+    let path = model.path();
+    std::fs::remove_file(&path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::PermissionDenied && is_shared(&path) {
+            "Shared models can only be deleted by an account that can write to the shared folder"
+                .to_string()
+        } else {
+            format!("Failed to delete model: {}", e)
+        }
+    })?;
+    std::fs::remove_dir_all(model.core_ml_encoder_path()).ok();
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use super::whisper::{Decoding, ModelId};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::Path;
+
+/// The models the app offers, shipped with it.
+const BUILTIN_MANIFEST: &str = include_str!("../../models.json");
+
+/// What whisper.cpp model files start with: `ggml` as a little-endian u32.
+const GGML_MAGIC: [u8; 4] = *b"lmgg";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelEntry {
+    pub key: String,
+    /// Label shown in the model picker.
+    pub name: String,
+    /// Upstream filename, also used to build mirror URLs.
+    pub filename: String,
+    pub url: String,
+    /// Hex-encoded SHA-256 of the file, checked after download when present.
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// Size in bytes.
+    pub size: u64,
+    /// Overrides the decoding the model would otherwise default to.
+    #[serde(default)]
+    pub decoding: Option<Decoding>,
+}
+
+impl ModelEntry {
+    /// Fails unless the file at `path` is this model in full: cut short or
+    /// overwritten files are caught by their size or their first bytes.
+    pub fn check_file(&self, path: &Path) -> Result<(), String> {
+        let size = std::fs::metadata(path)
+            .map_err(|e| format!("Failed to read model file: {}", e))?
+            .len();
+        if size != self.size {
+            return Err(format!(
+                "Model file is {} bytes instead of {}",
+                size, self.size
+            ));
+        }
+        let mut magic = [0; 4];
+        std::fs::File::open(path)
+            .and_then(|mut file| file.read_exact(&mut magic))
+            .map_err(|e| format!("Failed to read model file: {}", e))?;
+        if magic != GGML_MAGIC {
+            return Err("Not a Whisper model file".to_string());
+        }
+        Ok(())
+    }
+
+    /// Whether the model is installed in full. A file left over from a crash
+    /// or cut short some other way counts as missing, so it's downloaded
+    /// again rather than loaded.
+    pub fn is_downloaded(&self) -> bool {
+        ModelId::parse(&self.key).is_ok_and(|model| self.check_file(&model.path()).is_ok())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelManifest {
+    pub models: Vec<ModelEntry>,
+}
+
+impl ModelManifest {
+    pub fn builtin() -> Self {
+        serde_json::from_str(BUILTIN_MANIFEST).expect("bundled models.json is valid")
+    }
+
+    pub fn get(&self, key: &str) -> Option<&ModelEntry> {
+        self.models.iter().find(|entry| entry.key == key)
+    }
+}
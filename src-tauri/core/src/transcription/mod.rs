@@ -0,0 +1,30 @@
+mod backend;
+mod chapters;
+mod cloud;
+mod compute;
+mod download;
+mod installed;
+mod manifest;
+mod packs;
+mod subtitles;
+mod whisper;
+
+pub use backend::{
+    Segment, Suppression, Transcriber, Transcript, TranscriptionBackend, TranscriptionResult, Word,
+};
+pub use chapters::{chapter_texts, find_chapters, format_markdown, Chapter};
+pub use cloud::OpenAiTranscriber;
+pub use compute::{compute_config, ComputeConfig};
+pub use download::{
+    download_model, download_verified_model, CancelToken, DownloadAuth, DownloadConfig,
+    DOWNLOAD_CANCELLED,
+};
+pub use installed::{delete_model, list_installed_models, InstalledModel};
+pub use manifest::{ModelEntry, ModelManifest};
+pub use packs::{import_model, install_pack, list_packs, pack_slug, remove_pack, LanguagePack};
+pub use subtitles::{format_subtitles, SubtitleFormat};
+pub use whisper::{
+    core_ml_supported, get_model_dir, gpu_backend, is_model_downloaded, is_writable,
+    set_shared_model_dir, shared_model_dir, Decoding, ModelId, ModelSize, TranscriptionLanguage,
+    WhisperTranscriber, DEFAULT_BEAM_SIZE,
+};
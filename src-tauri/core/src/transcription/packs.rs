@@ -0,0 +1,182 @@
+use super::download::{download_file, partial_path, CancelToken};
+use super::whisper::{get_model_dir, WhisperTranscriber};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A community fine-tuned ggml model installed under a user-chosen name,
+/// e.g. KB-Whisper for Swedish.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguagePack {
+    pub slug: String,
+    pub name: String,
+    pub language: Option<String>,
+    /// URL or local path the pack was installed from.
+    pub source: String,
+}
+
+pub fn get_packs_dir() -> PathBuf {
+    let dir = get_model_dir().join("packs");
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+pub fn get_pack_path(slug: &str) -> PathBuf {
+    get_packs_dir().join(format!("{}.bin", slug))
+}
+
+fn pack_metadata_path(slug: &str) -> PathBuf {
+    get_packs_dir().join(format!("{}.json", slug))
+}
+
+/// Turns a display name into a file-system safe identifier.
+pub fn pack_slug(name: &str) -> String {
+    let slug: String = name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    slug.split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Slugs and catalog keys end up in a file name, so only plain slugs such
+/// as `pack_slug` makes are accepted.
+pub(super) fn check_slug(slug: &str) -> Result<(), String> {
+    if slug.is_empty() || pack_slug(slug) != slug {
+        return Err("Invalid model".to_string());
+    }
+    Ok(())
+}
+
+/// Installs a pack from an `http(s)://` URL or a local ggml file, and keeps it
+/// only if whisper can actually load it.
+pub async fn install_pack<F>(
+    name: &str,
+    language: Option<String>,
+    source: &str,
+    cancel: &CancelToken,
+    progress_callback: F,
+) -> Result<LanguagePack, String>
+where
+    F: Fn(u64, u64) + Send + 'static,
+{
+    let slug = pack_slug(name);
+    if slug.is_empty() {
+        return Err("Language pack name must contain letters or digits".to_string());
+    }
+
+    // The new file is checked beside the old one and only then replaces
+    // it, so a failed reinstall leaves the installed pack as it was.
+    let path = get_pack_path(&slug);
+    let partial = partial_path(&path, source, None);
+    if source.starts_with("http://") || source.starts_with("https://") {
+        // Credentials are only for the configured model server, never arbitrary URLs.
+        download_file(source, &partial, None, None, cancel, progress_callback).await?;
+    } else {
+        std::fs::copy(Path::new(source), &partial)
+            .map_err(|e| format!("Failed to copy language pack: {}", e))?;
+    }
+    // Loading only checks that the file is a model, so there's no need to
+    // touch the GPU.
+    if let Err(e) = WhisperTranscriber::new(&partial, false) {
+        std::fs::remove_file(&partial).ok();
+        return Err(format!("Not a valid Whisper model: {}", e));
+    }
+    std::fs::rename(&partial, &path)
+        .map_err(|e| format!("Failed to move language pack into place: {}", e))?;
+
+    save_pack(LanguagePack {
+        slug,
+        name: name.trim().to_string(),
+        language,
+        source: source.to_string(),
+    })
+}
+
+/// Imports a user-supplied ggml model from disk, such as a private
+/// fine-tune, under `name` (the file name by default). The file is checked
+/// in place before anything is copied; with `link` it is symlinked rather
+/// than copied, so a multi-gigabyte model isn't stored twice.
+pub fn import_model(path: &Path, name: Option<&str>, link: bool) -> Result<LanguagePack, String> {
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    };
+    let slug = pack_slug(&name);
+    if slug.is_empty() {
+        return Err("Model name must contain letters or digits".to_string());
+    }
+
+    if let Err(e) = WhisperTranscriber::new(&path.to_path_buf(), false) {
+        return Err(format!("Not a valid Whisper model: {}", e));
+    }
+
+    // Like a pack install, the copy or link is made beside an installed
+    // model of the same name and only then replaces it.
+    let target = get_pack_path(&slug);
+    let partial = partial_path(&target, &path.display().to_string(), None);
+    std::fs::remove_file(&partial).ok();
+    if link {
+        let source = path
+            .canonicalize()
+            .map_err(|e| format!("Failed to resolve {}: {}", path.display(), e))?;
+        #[cfg(unix)]
+        let linked = std::os::unix::fs::symlink(&source, &partial);
+        #[cfg(windows)]
+        let linked = std::os::windows::fs::symlink_file(&source, &partial);
+        linked.map_err(|e| format!("Failed to link model: {}", e))?;
+    } else if let Err(e) = std::fs::copy(path, &partial) {
+        std::fs::remove_file(&partial).ok();
+        return Err(format!("Failed to copy model: {}", e));
+    }
+    std::fs::rename(&partial, &target)
+        .map_err(|e| format!("Failed to move model into place: {}", e))?;
+
+    save_pack(LanguagePack {
+        slug,
+        name: name.trim().to_string(),
+        language: None,
+        source: path.display().to_string(),
+    })
+}
+
+fn save_pack(pack: LanguagePack) -> Result<LanguagePack, String> {
+    let json = serde_json::to_vec_pretty(&pack)
+        .map_err(|e| format!("Failed to serialize language pack: {}", e))?;
+    std::fs::write(pack_metadata_path(&pack.slug), json)
+        .map_err(|e| format!("Failed to write language pack metadata: {}", e))?;
+    Ok(pack)
+}
+
+pub fn list_packs() -> Vec<LanguagePack> {
+    let Ok(entries) = std::fs::read_dir(get_packs_dir()) else {
+        return Vec::new();
+    };
+
+    let mut packs: Vec<LanguagePack> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| std::fs::read(path).ok())
+        .filter_map(|bytes| serde_json::from_slice::<LanguagePack>(&bytes).ok())
+        .filter(|pack| get_pack_path(&pack.slug).exists())
+        .collect();
+
+    packs.sort_by(|a, b| a.name.cmp(&b.name));
+    packs
+}
+
+/// Removes a pack; for a linked import only the link goes.
+pub fn remove_pack(slug: &str) -> Result<(), String> {
+    check_slug(slug)?;
+    std::fs::remove_file(get_pack_path(slug))
+        .map_err(|e| format!("Failed to remove language pack: {}", e))?;
+    std::fs::remove_file(pack_metadata_path(slug)).ok();
+    Ok(())
+}
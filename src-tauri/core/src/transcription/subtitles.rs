@@ -0,0 +1,143 @@
+use super::backend::Segment;
+use super::chapters::Chapter;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// A subtitle file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleFormat {
+    /// SubRip, which most video editors and players read.
+    Srt,
+    /// WebVTT, for the HTML `<track>` element.
+    Vtt,
+}
+
+/// Formats the segments as subtitles, one cue per segment. Segments with no
+/// text are left out. The first cue of each chapter opens with its title in
+/// brackets.
+pub fn format_subtitles(
+    segments: &[Segment],
+    format: SubtitleFormat,
+    chapters: &[Chapter],
+) -> String {
+    let mut subtitles = String::new();
+    if format == SubtitleFormat::Vtt {
+        subtitles.push_str("WEBVTT\n\n");
+    }
+    let mut cue = 0;
+    let mut heading = None;
+    for (index, segment) in segments.iter().enumerate() {
+        if let Some(chapter) = chapters
+            .iter()
+            .find(|chapter| chapter.first_segment == index)
+        {
+            heading = Some(&chapter.title);
+        }
+        if segment.text.trim().is_empty() {
+            continue;
+        }
+        cue += 1;
+        if format == SubtitleFormat::Srt {
+            let _ = writeln!(subtitles, "{}", cue);
+        }
+        let _ = writeln!(
+            subtitles,
+            "{} --> {}",
+            timestamp(segment.start, format),
+            timestamp(segment.end.max(segment.start), format)
+        );
+        if let Some(title) = heading.take() {
+            let _ = writeln!(subtitles, "[{}]", title);
+        }
+        let _ = writeln!(subtitles, "{}\n", segment.text.trim());
+    }
+    subtitles
+}
+
+/// `hh:mm:ss,mmm` for SRT and `hh:mm:ss.mmm` for WebVTT.
+fn timestamp(secs: f32, format: SubtitleFormat) -> String {
+    let millis = (secs.max(0.0) * 1000.0).round() as u64;
+    let separator = match format {
+        SubtitleFormat::Srt => ',',
+        SubtitleFormat::Vtt => '.',
+    };
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        separator,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start: f32, end: f32, text: &str) -> Segment {
+        Segment {
+            start,
+            end,
+            text: text.to_string(),
+            confidence: None,
+            words: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn numbers_srt_cues_and_skips_empty_segments() {
+        let segments = [
+            segment(0.0, 1.5, " Hello there. "),
+            segment(1.5, 2.0, "  "),
+            segment(3661.25, 3662.0, "Later on."),
+        ];
+        assert_eq!(
+            format_subtitles(&segments, SubtitleFormat::Srt, &[]),
+            "1\n00:00:00,000 --> 00:00:01,500\nHello there.\n\n\
+             2\n01:01:01,250 --> 01:01:02,000\nLater on.\n\n"
+        );
+    }
+
+    #[test]
+    fn writes_a_webvtt_header_and_dotted_timestamps() {
+        let segments = [segment(0.5, 1.0, "Hi.")];
+        assert_eq!(
+            format_subtitles(&segments, SubtitleFormat::Vtt, &[]),
+            "WEBVTT\n\n00:00:00.500 --> 00:00:01.000\nHi.\n\n"
+        );
+    }
+
+    #[test]
+    fn never_ends_a_cue_before_it_starts() {
+        let segments = [
+            segment(2.0, 1.0, "Backwards."),
+            segment(-1.0, 0.0, "Early."),
+        ];
+        assert_eq!(
+            format_subtitles(&segments, SubtitleFormat::Srt, &[]),
+            "1\n00:00:02,000 --> 00:00:02,000\nBackwards.\n\n\
+             2\n00:00:00,000 --> 00:00:00,000\nEarly.\n\n"
+        );
+    }
+
+    #[test]
+    fn titles_a_chapter_on_its_first_cue_with_text() {
+        let segments = [
+            segment(0.0, 1.0, "Intro."),
+            segment(600.0, 601.0, ""),
+            segment(601.0, 602.0, "Budget."),
+        ];
+        let chapters = [Chapter {
+            start: 600.0,
+            title: "The budget".to_string(),
+            first_segment: 1,
+        }];
+        assert_eq!(
+            format_subtitles(&segments, SubtitleFormat::Vtt, &chapters),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:01.000\nIntro.\n\n\
+             00:10:01.000 --> 00:10:02.000\n[The budget]\nBudget.\n\n"
+        );
+    }
+}
@@ -0,0 +1,583 @@
+use super::backend::{
+    is_annotation, vocabulary_prompt, Segment, Suppression, Transcriber, Transcript,
+    TranscriptionResult, Word,
+};
+use super::compute::compute_config;
+use super::manifest::{ModelEntry, ModelManifest};
+use super::packs::{check_slug, get_pack_path};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+use whisper_rs::{
+    FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperState,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ModelSize {
+    Tiny,
+    Base,
+    #[default]
+    Small,
+    Medium,
+    Large,
+    Turbo,
+}
+
+impl ModelSize {
+    pub fn filename(&self) -> &'static str {
+        match self {
+            ModelSize::Tiny => "ggml-tiny.bin",
+            ModelSize::Base => "ggml-base.bin",
+            ModelSize::Small => "ggml-small.bin",
+            ModelSize::Medium => "ggml-medium.bin",
+            ModelSize::Large => "ggml-large-v3.bin",
+            ModelSize::Turbo => "ggml-large-v3-turbo.bin",
+        }
+    }
+
+    pub fn parse(size: &str) -> Result<Self, String> {
+        match size {
+            "tiny" => Ok(ModelSize::Tiny),
+            "base" => Ok(ModelSize::Base),
+            "small" => Ok(ModelSize::Small),
+            "medium" => Ok(ModelSize::Medium),
+            "large" => Ok(ModelSize::Large),
+            "turbo" => Ok(ModelSize::Turbo),
+            _ => Err("Invalid model size".to_string()),
+        }
+    }
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            ModelSize::Tiny => "tiny",
+            ModelSize::Base => "base",
+            ModelSize::Small => "small",
+            ModelSize::Medium => "medium",
+            ModelSize::Large => "large",
+            ModelSize::Turbo => "turbo",
+        }
+    }
+
+    /// A newer model that supersedes this one at similar accuracy, offered as
+    /// an opt-in upgrade.
+    pub fn successor(&self) -> Option<ModelSize> {
+        match self {
+            ModelSize::Large => Some(ModelSize::Turbo),
+            _ => None,
+        }
+    }
+
+    /// The small models make enough mistakes that beam search is worth its
+    /// cost; the large ones gain little from it for a lot of extra time.
+    pub fn default_decoding(&self) -> Decoding {
+        match self {
+            ModelSize::Tiny | ModelSize::Base | ModelSize::Small => Decoding::BeamSearch {
+                beam_size: DEFAULT_BEAM_SIZE,
+            },
+            ModelSize::Medium | ModelSize::Large | ModelSize::Turbo => Decoding::Greedy,
+        }
+    }
+}
+
+/// Candidates kept by beam search, as in the reference Whisper.
+pub const DEFAULT_BEAM_SIZE: u32 = 5;
+
+/// How whisper picks each token.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum Decoding {
+    /// The most likely token every time. The fastest.
+    #[default]
+    Greedy,
+    /// Follows the `beam_size` most likely transcriptions at once and keeps
+    /// the best, which avoids some mistakes greedy decoding makes, at about
+    /// `beam_size` times the decoding work.
+    BeamSearch { beam_size: u32 },
+}
+
+const PACK_PREFIX: &str = "pack:";
+
+/// Identifies a selectable model: one of the official sizes, a model only
+/// known from the model manifest, or an installed language pack
+/// (`pack:<slug>`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelId {
+    Official(ModelSize),
+    Catalog(String),
+    Pack(String),
+}
+
+impl Default for ModelId {
+    fn default() -> Self {
+        ModelId::Official(ModelSize::default())
+    }
+}
+
+impl ModelId {
+    pub fn parse(id: &str) -> Result<Self, String> {
+        if let Some(slug) = id.strip_prefix(PACK_PREFIX) {
+            check_slug(slug)?;
+            return Ok(ModelId::Pack(slug.to_string()));
+        }
+        if let Ok(size) = ModelSize::parse(id) {
+            return Ok(ModelId::Official(size));
+        }
+        check_slug(id)?;
+        Ok(ModelId::Catalog(id.to_string()))
+    }
+
+    pub fn key(&self) -> String {
+        match self {
+            ModelId::Official(size) => size.key().to_string(),
+            ModelId::Catalog(key) => key.clone(),
+            ModelId::Pack(slug) => format!("{}{}", PACK_PREFIX, slug),
+        }
+    }
+
+    /// The model's file: the copy in the shared model folder if there is
+    /// one, otherwise the one in this user's models folder.
+    pub fn path(&self) -> PathBuf {
+        let file_name = match self {
+            ModelId::Official(size) => size.filename().to_string(),
+            ModelId::Catalog(key) => format!("{}.bin", key),
+            // Packs are named by whoever installed them, so aren't shared.
+            ModelId::Pack(slug) => return get_pack_path(slug),
+        };
+        shared_model_dir()
+            .map(|dir| dir.join(&file_name))
+            .filter(|path| path.exists())
+            .unwrap_or_else(|| get_model_dir().join(file_name))
+    }
+
+    /// Where a download of the model is saved: the shared model folder when
+    /// this user can write to it, so other accounts can use it too.
+    pub fn download_path(&self) -> PathBuf {
+        let path = self.path();
+        match (self, shared_model_dir(), path.file_name()) {
+            (ModelId::Pack(_), _, _) => path,
+            (_, Some(dir), Some(file_name)) if is_writable(&dir) => dir.join(file_name),
+            _ => path,
+        }
+    }
+
+    /// Whether the model's file is there at all. `ModelEntry::is_downloaded`
+    /// also checks it's complete.
+    pub fn is_downloaded(&self) -> bool {
+        self.path().exists()
+    }
+
+    /// The decoding the model's manifest entry asks for, or its size's
+    /// default. Language packs and catalog models of unknown size decode
+    /// greedily.
+    pub fn default_decoding(&self, manifest: &ModelManifest) -> Decoding {
+        let listed = manifest.get(&self.key()).and_then(|entry| entry.decoding);
+        match (listed, self) {
+            (Some(decoding), _) => decoding,
+            (None, ModelId::Official(size)) => size.default_decoding(),
+            (None, _) => Decoding::default(),
+        }
+    }
+
+    /// The compiled Core ML encoder whisper.cpp looks for beside the model
+    /// in Core ML builds, e.g. `ggml-small-encoder.mlmodelc`.
+    pub fn core_ml_encoder_path(&self) -> PathBuf {
+        let path = self.path();
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        path.with_file_name(format!("{}-encoder.mlmodelc", stem))
+    }
+}
+
+/// The GPU backend whisper.cpp was built with. Without one, models run on
+/// the CPU whatever `use_gpu` says.
+pub fn gpu_backend() -> Option<&'static str> {
+    if cfg!(feature = "cuda") {
+        Some("CUDA")
+    } else if cfg!(feature = "metal") {
+        Some("Metal")
+    } else {
+        None
+    }
+}
+
+/// Whether whisper.cpp was built to run the encoder through Core ML, which
+/// needs [`ModelId::core_ml_encoder_path`] to exist for every model.
+pub fn core_ml_supported() -> bool {
+    cfg!(feature = "coreml")
+}
+
+/// The language whisper transcribes in: a fixed ISO 639-1 code, or
+/// detected from the audio.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranscriptionLanguage {
+    Auto,
+    Code(String),
+}
+
+impl Default for TranscriptionLanguage {
+    fn default() -> Self {
+        TranscriptionLanguage::Code("en".to_string())
+    }
+}
+
+impl TranscriptionLanguage {
+    pub fn parse(language: &str) -> Result<Self, String> {
+        let language = language.trim().to_lowercase();
+        if language == "auto" {
+            Ok(TranscriptionLanguage::Auto)
+        } else if whisper_rs::get_lang_id(&language).is_some() {
+            Ok(TranscriptionLanguage::Code(language))
+        } else {
+            Err(format!("Unsupported language: {}", language))
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        match self {
+            TranscriptionLanguage::Auto => "auto",
+            TranscriptionLanguage::Code(code) => code,
+        }
+    }
+}
+
+/// Segments Whisper thinks are more likely than this to hold no speech are
+/// dropped.
+const NO_SPEECH_THRESHOLD: f32 = 0.6;
+
+/// How much hotter each fallback decode runs, up to a temperature of 1.0.
+const FALLBACK_TEMPERATURE_STEP: f32 = 0.2;
+/// A decode whose tokens are less varied than this is repeating itself.
+const FALLBACK_ENTROPY_THRESHOLD: f32 = 2.4;
+/// A decode whose tokens average a lower log probability than this is
+/// probably garbled.
+const FALLBACK_LOGPROB_THRESHOLD: f32 = -1.0;
+
+pub struct WhisperTranscriber {
+    ctx: WhisperContext,
+    initial_prompt: Mutex<String>,
+    temperature_fallback: AtomicBool,
+    suppression: Mutex<Suppression>,
+    decoding: Mutex<Decoding>,
+}
+
+impl WhisperTranscriber {
+    /// Loads a model, on the GPU when `use_gpu` is set and this build has a
+    /// GPU backend (see [`gpu_backend`]).
+    pub fn new(model_path: &PathBuf, use_gpu: bool) -> Result<Self, String> {
+        let mut params = WhisperContextParameters::default();
+        params.use_gpu(use_gpu);
+        let ctx = WhisperContext::new_with_params(model_path.to_str().unwrap(), params)
+            .map_err(|e| format!("Failed to load Whisper model: {}", e))?;
+
+        Ok(Self {
+            ctx,
+            initial_prompt: Mutex::new(String::new()),
+            temperature_fallback: AtomicBool::new(true),
+            suppression: Mutex::new(Suppression::default()),
+            decoding: Mutex::new(Decoding::default()),
+        })
+    }
+
+    pub fn set_decoding(&self, decoding: Decoding) {
+        *self.decoding.lock() = decoding;
+    }
+
+    /// Whether a segment that decodes badly is retried at rising
+    /// temperatures, as whisper.cpp does by default. Retries cost time, so
+    /// it can be turned off for speed.
+    pub fn set_temperature_fallback(&self, enabled: bool) {
+        self.temperature_fallback.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Decodes the audio into segments. Dictation decodes it as one
+    /// segment; `single_segment` off lets whisper split it where the speech
+    /// pauses.
+    fn run(
+        &self,
+        samples: &[f32],
+        language: &TranscriptionLanguage,
+        translate: bool,
+        temperature: f32,
+        single_segment: bool,
+    ) -> Result<TranscriptionResult, String> {
+        let strategy = match *self.decoding.lock() {
+            Decoding::Greedy => SamplingStrategy::Greedy { best_of: 1 },
+            Decoding::BeamSearch { beam_size } => SamplingStrategy::BeamSearch {
+                beam_size: beam_size as i32,
+                // Whisper.cpp's default.
+                patience: -1.0,
+            },
+        };
+        let mut params = FullParams::new(strategy);
+        params.set_n_threads(compute_config().threads as i32);
+        params.set_temperature(temperature);
+        if self.temperature_fallback.load(Ordering::Relaxed) {
+            params.set_temperature_inc(FALLBACK_TEMPERATURE_STEP);
+            params.set_entropy_thold(FALLBACK_ENTROPY_THRESHOLD);
+            params.set_logprob_thold(FALLBACK_LOGPROB_THRESHOLD);
+        } else {
+            params.set_temperature_inc(0.0);
+        }
+        let prompt = self.initial_prompt.lock().clone();
+        if !prompt.is_empty() {
+            params.set_initial_prompt(&prompt);
+        }
+
+        // whisper.cpp detects the language when given "auto".
+        params.set_language(Some(language.key()));
+        params.set_translate(translate);
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(false);
+        params.set_suppress_blank(true);
+        let suppression = *self.suppression.lock();
+        params.set_suppress_non_speech_tokens(suppression.non_speech_tokens);
+        params.set_no_speech_thold(NO_SPEECH_THRESHOLD);
+        params.set_single_segment(single_segment);
+
+        let mut state = self
+            .ctx
+            .create_state()
+            .map_err(|e| format!("Failed to create state: {}", e))?;
+
+        state
+            .full(params, samples)
+            .map_err(|e| format!("Transcription failed: {}", e))?;
+
+        let num_segments = state.full_n_segments().map_err(|e| e.to_string())?;
+
+        let mut segments = Vec::new();
+        for i in 0..num_segments {
+            let Ok(text) = state.full_get_segment_text(i) else {
+                continue;
+            };
+            if suppression.noise_tags && is_annotation(&text) {
+                continue;
+            }
+            // whisper.cpp counts in centiseconds.
+            let start = state.full_get_segment_t0(i).unwrap_or(0) as f32 / 100.0;
+            let end = state.full_get_segment_t1(i).unwrap_or(0) as f32 / 100.0;
+            let words = self.words(&state, i);
+            let confidence = (!words.is_empty()).then(|| {
+                words.iter().map(|word| word.probability).sum::<f32>() / words.len() as f32
+            });
+            segments.push(Segment {
+                start,
+                end,
+                text,
+                confidence,
+                words,
+            });
+        }
+
+        let language = match language {
+            TranscriptionLanguage::Code(code) => Some(code.clone()),
+            TranscriptionLanguage::Auto => state
+                .full_lang_id_from_state()
+                .ok()
+                .and_then(whisper_rs::get_lang_str)
+                .map(str::to_string),
+        };
+        Ok(TranscriptionResult {
+            text: segments
+                .iter()
+                .map(|segment| segment.text.as_str())
+                .collect(),
+            segments,
+            language,
+        })
+    }
+
+    /// The words of a decoded segment. A token starting with a space begins
+    /// a word and the rest join the one before; timestamps and other special
+    /// tokens are skipped.
+    fn words(&self, state: &WhisperState, segment: i32) -> Vec<Word> {
+        let first_special = self.ctx.token_eot();
+        let mut words: Vec<Word> = Vec::new();
+        for token in 0..state.full_n_tokens(segment).unwrap_or(0) {
+            let special = state
+                .full_get_token_id(segment, token)
+                .map_or(true, |id| id >= first_special);
+            if special {
+                continue;
+            }
+            let (Ok(text), Ok(probability)) = (
+                state.full_get_token_text_lossy(segment, token),
+                state.full_get_token_prob(segment, token),
+            ) else {
+                continue;
+            };
+            match words.last_mut() {
+                Some(word) if !text.starts_with(' ') => {
+                    word.text.push_str(&text);
+                    word.probability = word.probability.min(probability);
+                }
+                _ => words.push(Word {
+                    text: text.trim_start().to_string(),
+                    probability,
+                }),
+            }
+        }
+        words
+    }
+
+    fn transcript(
+        &self,
+        samples: &[f32],
+        language: &TranscriptionLanguage,
+        translate: bool,
+        temperature: f32,
+    ) -> Result<Transcript, String> {
+        let result = self.run(samples, language, translate, temperature, true)?;
+        let suppression = *self.suppression.lock();
+        Ok(Transcript::from_text(&result.text, &suppression))
+    }
+}
+
+impl Transcriber for WhisperTranscriber {
+    fn set_vocabulary(&self, vocabulary: &[String]) {
+        *self.initial_prompt.lock() = vocabulary_prompt(vocabulary);
+    }
+
+    fn set_suppression(&self, suppression: Suppression) {
+        *self.suppression.lock() = suppression;
+    }
+
+    fn transcribe(
+        &self,
+        samples: &[f32],
+        language: &TranscriptionLanguage,
+    ) -> Result<Transcript, String> {
+        self.transcript(samples, language, false, 0.0)
+    }
+
+    /// Turbo models don't translate at all.
+    fn translate(
+        &self,
+        samples: &[f32],
+        language: &TranscriptionLanguage,
+    ) -> Result<Transcript, String> {
+        self.transcript(samples, language, true, 0.0)
+    }
+
+    /// Lets whisper split the audio where the speech pauses, rather than
+    /// decoding it as one segment as dictation does.
+    fn transcribe_detailed(
+        &self,
+        samples: &[f32],
+        language: &TranscriptionLanguage,
+    ) -> Result<TranscriptionResult, String> {
+        let suppression = *self.suppression.lock();
+        let result = self.run(samples, language, false, 0.0, false)?;
+        let text = Transcript::from_text(&result.text, &suppression).into_text();
+        let segments = if text.is_empty() {
+            Vec::new()
+        } else {
+            result
+                .segments
+                .into_iter()
+                .filter_map(|segment| {
+                    let text = suppression.filter(&segment.text);
+                    (!text.is_empty()).then_some(Segment { text, ..segment })
+                })
+                .collect()
+        };
+        Ok(TranscriptionResult {
+            text,
+            segments,
+            language: result.language,
+        })
+    }
+
+    /// whisper.cpp doesn't expose its beam hypotheses, so the others come
+    /// from decoding again at rising temperatures; each costs a pass.
+    fn alternatives(
+        &self,
+        samples: &[f32],
+        language: &TranscriptionLanguage,
+        count: usize,
+    ) -> Result<Vec<String>, String> {
+        let Transcript::Speech(best) = self.transcribe(samples, language)? else {
+            return Ok(Vec::new());
+        };
+        let mut alternatives = vec![best];
+        for attempt in 1..count * 2 {
+            if alternatives.len() >= count {
+                break;
+            }
+            let temperature = (attempt as f32 * 0.2).min(1.0);
+            if let Transcript::Speech(text) =
+                self.transcript(samples, language, false, temperature)?
+            {
+                if !alternatives.contains(&text) {
+                    alternatives.push(text);
+                }
+            }
+        }
+        Ok(alternatives)
+    }
+}
+
+pub fn get_model_dir() -> PathBuf {
+    let data_dir = dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("voice")
+        .join("models");
+    std::fs::create_dir_all(&data_dir).ok();
+    data_dir
+}
+
+static SHARED_MODEL_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// A folder of models shared by every account on the machine, such as
+/// `/Users/Shared/Voice/models`. Models found there are used instead of
+/// downloading a copy per user.
+pub fn shared_model_dir() -> Option<PathBuf> {
+    SHARED_MODEL_DIR.read().ok()?.clone()
+}
+
+pub fn set_shared_model_dir(dir: Option<PathBuf>) {
+    if let Ok(mut shared) = SHARED_MODEL_DIR.write() {
+        *shared = dir;
+    }
+}
+
+/// The account name, for files in the shared folder that only one account
+/// works on at a time, so two accounts never write to the same one.
+pub(super) fn account_tag() -> String {
+    let name = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default();
+    let tag: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+    if tag.is_empty() {
+        "user".to_string()
+    } else {
+        tag
+    }
+}
+
+/// Whether files can be created in `dir`. Other accounts may only be allowed
+/// to read a shared folder.
+pub fn is_writable(dir: &Path) -> bool {
+    let probe = dir.join(format!(".voice-write-test.{}", account_tag()));
+    let writable = std::fs::File::create(&probe).is_ok();
+    std::fs::remove_file(&probe).ok();
+    writable
+}
+
+/// Whether an official model is installed in full, going by its manifest
+/// entry.
+pub fn is_model_downloaded(size: ModelSize) -> bool {
+    ModelManifest::builtin().get(size.key()).map_or_else(
+        || ModelId::Official(size).is_downloaded(),
+        ModelEntry::is_downloaded,
+    )
+}
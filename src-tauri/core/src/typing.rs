@@ -0,0 +1,320 @@
+//! Types text into the focused app. Characters are sent as Unicode rather
+//! than as key codes, so the result is the same on every keyboard layout
+//! (Swedish å/ä/ö, German ß, French AZERTY, dead-key accents) and doesn't
+//! depend on which keys the layout has.
+
+/// What typing a piece of text takes.
+#[cfg(any(target_os = "macos", target_os = "windows", test))]
+#[derive(Debug, PartialEq, Eq)]
+enum Keystroke {
+    /// Characters sent as Unicode, never as key codes.
+    Text(String),
+    /// A line break, pressed as the Return key since a newline character
+    /// doesn't start a new line in most apps.
+    Return,
+}
+
+/// Splits `text` into keystrokes carrying at most `max_units` UTF-16 units
+/// each. Splits fall on character boundaries so surrogate pairs stay in one
+/// event, and `\r\n` is a single Return.
+#[cfg(any(target_os = "macos", target_os = "windows", test))]
+fn keystrokes(text: &str, max_units: usize) -> Vec<Keystroke> {
+    let mut keystrokes = Vec::new();
+    let mut units = 0;
+    for c in text.chars().filter(|&c| c != '\r') {
+        if c == '\n' {
+            keystrokes.push(Keystroke::Return);
+            continue;
+        }
+        match keystrokes.last_mut() {
+            Some(Keystroke::Text(chunk)) if units + c.len_utf16() <= max_units => chunk.push(c),
+            _ => {
+                keystrokes.push(Keystroke::Text(c.to_string()));
+                units = 0;
+            }
+        }
+        units += c.len_utf16();
+    }
+    keystrokes
+}
+
+/// Most UTF-16 units a single macOS keyboard event carries.
+#[cfg(target_os = "macos")]
+const MAX_EVENT_UNITS: usize = 20;
+
+/// Posts the text as keyboard events carrying Unicode strings, and line
+/// breaks as Return. Requires accessibility permission.
+#[cfg(target_os = "macos")]
+pub fn type_text(text: &str) -> Result<(), String> {
+    use core_graphics::event::{CGEvent, CGEventTapLocation, KeyCode};
+    use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+
+    let source = CGEventSource::new(CGEventSourceStateID::HIDSystemState)
+        .map_err(|_| "Failed to create keyboard event source".to_string())?;
+
+    for keystroke in keystrokes(text, MAX_EVENT_UNITS) {
+        for key_down in [true, false] {
+            let key = match keystroke {
+                Keystroke::Text(_) => 0,
+                Keystroke::Return => KeyCode::RETURN,
+            };
+            let event = CGEvent::new_keyboard_event(source.clone(), key, key_down)
+                .map_err(|_| "Failed to create keyboard event".to_string())?;
+            if let Keystroke::Text(chunk) = &keystroke {
+                event.set_string(chunk);
+            }
+            event.post(CGEventTapLocation::HID);
+        }
+    }
+    Ok(())
+}
+
+/// Sends each UTF-16 unit with `KEYEVENTF_UNICODE`, which bypasses the
+/// active layout, and line breaks as Enter.
+#[cfg(target_os = "windows")]
+pub fn type_text(text: &str) -> Result<(), String> {
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP, KEYEVENTF_UNICODE,
+        VK_RETURN,
+    };
+
+    let mut keys = Vec::new();
+    for keystroke in keystrokes(text, usize::MAX) {
+        match keystroke {
+            Keystroke::Text(chunk) => keys.extend(
+                chunk
+                    .encode_utf16()
+                    .map(|unit| (0, unit, KEYEVENTF_UNICODE)),
+            ),
+            Keystroke::Return => keys.push((VK_RETURN, 0, 0)),
+        }
+    }
+    let mut inputs = Vec::new();
+    for (key, scan, flags) in keys {
+        for up in [0, KEYEVENTF_KEYUP] {
+            inputs.push(INPUT {
+                r#type: INPUT_KEYBOARD,
+                Anonymous: INPUT_0 {
+                    ki: KEYBDINPUT {
+                        wVk: key,
+                        wScan: scan,
+                        dwFlags: flags | up,
+                        time: 0,
+                        dwExtraInfo: 0,
+                    },
+                },
+            });
+        }
+    }
+
+    let sent = unsafe {
+        SendInput(
+            inputs.len() as u32,
+            inputs.as_ptr(),
+            std::mem::size_of::<INPUT>() as i32,
+        )
+    };
+    if sent as usize != inputs.len() {
+        return Err("Typing was blocked by another application".to_string());
+    }
+    Ok(())
+}
+
+/// Presses Ctrl+V with `SendInput`. Like typing, it fails when the focused
+/// window belongs to an app running as administrator.
+#[cfg(target_os = "windows")]
+pub fn send_paste() -> Result<(), String> {
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP, VK_CONTROL, VK_V,
+    };
+
+    let keys = [
+        (VK_CONTROL, 0),
+        (VK_V, 0),
+        (VK_V, KEYEVENTF_KEYUP),
+        (VK_CONTROL, KEYEVENTF_KEYUP),
+    ];
+    let inputs: Vec<INPUT> = keys
+        .iter()
+        .map(|&(key, flags)| INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wVk: key,
+                    wScan: 0,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: 0,
+                },
+            },
+        })
+        .collect();
+
+    let sent = unsafe {
+        SendInput(
+            inputs.len() as u32,
+            inputs.as_ptr(),
+            std::mem::size_of::<INPUT>() as i32,
+        )
+    };
+    if sent as usize != inputs.len() {
+        return Err("Pasting was blocked by another application".to_string());
+    }
+    Ok(())
+}
+
+/// Uses `wtype` on Wayland and `xdotool` on X11; both remap key codes to
+/// produce each character whatever the layout.
+#[cfg(target_os = "linux")]
+pub fn type_text(text: &str) -> Result<(), String> {
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+    let (program, args): (&str, &[&str]) = if wayland {
+        ("wtype", &["--"])
+    } else {
+        (
+            "xdotool",
+            &["type", "--clearmodifiers", "--delay", "0", "--"],
+        )
+    };
+
+    let status = std::process::Command::new(program)
+        .args(args)
+        .arg(text)
+        .status()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if !status.success() {
+        return Err(format!("{} exited with {}", program, status));
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+pub fn type_text(_text: &str) -> Result<(), String> {
+    Err("Typing is not supported on this platform".to_string())
+}
+
+/// Whether an input method (as used for Chinese, Japanese and Korean) is the
+/// current input source. Keystrokes would be composed by it, so callers
+/// should paste instead of typing.
+#[cfg(target_os = "macos")]
+pub fn ime_active() -> bool {
+    use core_foundation::base::{CFRelease, CFTypeRef, TCFType};
+    use core_foundation::string::{CFString, CFStringRef};
+
+    #[link(name = "Carbon", kind = "framework")]
+    extern "C" {
+        static kTISPropertyInputSourceType: CFStringRef;
+        fn TISCopyCurrentKeyboardInputSource() -> CFTypeRef;
+        fn TISGetInputSourceProperty(source: CFTypeRef, key: CFStringRef) -> CFTypeRef;
+    }
+
+    unsafe {
+        let source = TISCopyCurrentKeyboardInputSource();
+        if source.is_null() {
+            return false;
+        }
+        let kind = TISGetInputSourceProperty(source, kTISPropertyInputSourceType);
+        // Plain layouts are `TISTypeKeyboardLayout`; input methods and their
+        // modes have other types.
+        let active = !kind.is_null()
+            && CFString::wrap_under_get_rule(kind as CFStringRef).to_string()
+                != "TISTypeKeyboardLayout";
+        CFRelease(source);
+        active
+    }
+}
+
+/// Windows doesn't expose another process's IME state, so this goes by the
+/// foreground window's keyboard language: CJK languages type through an IME.
+#[cfg(target_os = "windows")]
+pub fn ime_active() -> bool {
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::GetKeyboardLayout;
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        GetForegroundWindow, GetWindowThreadProcessId,
+    };
+
+    const LANG_CHINESE: usize = 0x04;
+    const LANG_JAPANESE: usize = 0x11;
+    const LANG_KOREAN: usize = 0x12;
+
+    let layout = unsafe {
+        let thread = GetWindowThreadProcessId(GetForegroundWindow(), std::ptr::null_mut());
+        GetKeyboardLayout(thread)
+    };
+    let primary_language = layout as usize & 0x3ff;
+    matches!(primary_language, LANG_CHINESE | LANG_JAPANESE | LANG_KOREAN)
+}
+
+/// Asks fcitx5, then IBus, whether an input method engine is switched on.
+#[cfg(target_os = "linux")]
+pub fn ime_active() -> bool {
+    let query = |program: &str, args: &[&str]| {
+        let output = std::process::Command::new(program)
+            .args(args)
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    // fcitx5-remote prints 2 while an input method is active, 1 when it is
+    // switched to the plain keyboard.
+    if let Some(state) = query("fcitx5-remote", &[]) {
+        return state == "2";
+    }
+    // IBus names plain layouts `xkb:<layout>:...`.
+    query("ibus", &["engine"]).is_some_and(|engine| !engine.starts_with("xkb:"))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+pub fn ime_active() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(chunk: &str) -> Keystroke {
+        Keystroke::Text(chunk.to_string())
+    }
+
+    #[test]
+    fn sends_european_letters_as_unicode() {
+        // Swedish, German, French and Czech letters that sit on different
+        // keys, or behind dead keys, from one layout to the next.
+        let sentence = "Smörgåsbord, Straße, crème brûlée, ça, Dvořák";
+        assert_eq!(keystrokes(sentence, usize::MAX), [text(sentence)]);
+    }
+
+    #[test]
+    fn splits_between_characters() {
+        let emoji = "👍".repeat(15);
+        let chunks = keystrokes(&emoji, 20);
+        assert_eq!(chunks.len(), 2);
+        for chunk in &chunks {
+            let Keystroke::Text(chunk) = chunk else {
+                panic!("expected text, got {:?}", chunk);
+            };
+            assert!(chunk.encode_utf16().count() <= 20);
+            assert!(chunk.chars().all(|c| c == '👍'));
+        }
+    }
+
+    #[test]
+    fn presses_return_for_line_breaks() {
+        assert_eq!(
+            keystrokes("Hej\r\nå\n\nö", 20),
+            [
+                text("Hej"),
+                Keystroke::Return,
+                text("å"),
+                Keystroke::Return,
+                Keystroke::Return,
+                text("ö"),
+            ]
+        );
+    }
+}
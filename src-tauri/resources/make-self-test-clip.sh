@@ -0,0 +1,16 @@
+#!/bin/sh
+# Records self-test.flac, the clip `run_self_test` transcribes: a 16 kHz
+# mono FLAC of selftest::EXPECTED_TEXT read aloud. Uses `say` on macOS and
+# espeak-ng elsewhere, and ffmpeg to convert. Re-run it if the text changes.
+set -e
+cd "$(dirname "$0")"
+text="The quick brown fox jumps over the lazy dog."
+if command -v say >/dev/null; then
+    say -o self-test.aiff "$text"
+    source=self-test.aiff
+else
+    espeak-ng -w self-test.wav "$text"
+    source=self-test.wav
+fi
+ffmpeg -y -loglevel error -i "$source" -ar 16000 -ac 1 -sample_fmt s16 self-test.flac
+rm "$source"
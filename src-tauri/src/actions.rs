@@ -0,0 +1,246 @@
+//! The actions the settings window's command palette offers. Each has a
+//! stable id, and `invoke` runs it by calling the same functions the window
+//! would, so a feature shows up in the palette by being added here.
+
+use crate::plugins::get_plugins_dir;
+use crate::session::SessionState;
+use crate::AppState;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_opener::OpenerExt;
+use voice_core::audio::get_recordings_dir;
+use voice_core::transcription::get_model_dir;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Action {
+    pub id: String,
+    /// What the palette shows, e.g. "Turn on hands-free mode".
+    pub title: String,
+    /// The group it is listed under, e.g. "Model".
+    pub category: &'static str,
+}
+
+/// A setting the palette switches on and off.
+struct Toggle {
+    id: &'static str,
+    /// What it turns on, as it reads after "Turn on".
+    name: &'static str,
+    get: fn(AppHandle) -> bool,
+    set: fn(AppHandle, bool) -> Result<(), String>,
+}
+
+const TOGGLES: &[Toggle] = &[
+    Toggle {
+        id: "toggle.hands_free",
+        name: "hands-free mode",
+        get: crate::get_hands_free,
+        set: crate::set_hands_free,
+    },
+    Toggle {
+        id: "toggle.overlay",
+        name: "the overlay",
+        get: crate::get_show_overlay,
+        set: |app, enabled| {
+            crate::set_show_overlay(app, enabled);
+            Ok(())
+        },
+    },
+    Toggle {
+        id: "toggle.live_preview",
+        name: "live preview",
+        get: crate::get_streaming_transcription,
+        set: crate::set_streaming_transcription,
+    },
+    Toggle {
+        id: "toggle.review",
+        name: "review mode",
+        get: crate::get_review_results,
+        set: crate::set_review_results,
+    },
+    Toggle {
+        id: "toggle.noise_suppression",
+        name: "noise suppression",
+        get: crate::get_noise_suppression,
+        set: crate::set_noise_suppression,
+    },
+    Toggle {
+        id: "toggle.trim_silence",
+        name: "silence trimming",
+        get: crate::get_trim_silence,
+        set: crate::set_trim_silence,
+    },
+    Toggle {
+        id: "toggle.normalize_loudness",
+        name: "loudness normalization",
+        get: crate::get_normalize_loudness,
+        set: crate::set_normalize_loudness,
+    },
+    Toggle {
+        id: "toggle.wake_word",
+        name: "the wake word",
+        get: crate::get_wake_word,
+        set: crate::set_wake_word,
+    },
+    Toggle {
+        id: "toggle.history",
+        name: "history",
+        get: crate::get_history_enabled,
+        set: crate::set_history_enabled,
+    },
+    Toggle {
+        id: "toggle.retain_recordings",
+        name: "keeping recordings",
+        get: crate::get_retain_recordings,
+        set: |app, enabled| {
+            crate::set_retain_recordings(app, enabled);
+            Ok(())
+        },
+    },
+    Toggle {
+        id: "toggle.meeting_detection",
+        name: "meeting detection",
+        get: crate::get_meeting_detection,
+        set: crate::set_meeting_detection,
+    },
+    Toggle {
+        id: "toggle.repair_dropouts",
+        name: "dropout repair",
+        get: crate::get_repair_dropouts,
+        set: crate::set_repair_dropouts,
+    },
+    Toggle {
+        id: "toggle.phone_remote",
+        name: "the phone remote",
+        get: crate::get_remote_control_enabled,
+        set: crate::set_remote_control_enabled,
+    },
+    Toggle {
+        id: "toggle.local_api",
+        name: "the local API",
+        get: crate::get_rpc_server_enabled,
+        set: crate::set_rpc_server_enabled,
+    },
+];
+
+/// Actions that are always offered, by id, title and category.
+const FIXED: [(&str, &str, &str); 7] = [
+    ("check.self_test", "Run the self-test", "Checks"),
+    ("check.diagnostics", "Show diagnostics", "Checks"),
+    ("open.models", "Open the models folder", "Folders"),
+    ("open.recordings", "Open the recordings folder", "Folders"),
+    ("open.plugins", "Open the plugins folder", "Folders"),
+    (
+        "open.microphone_settings",
+        "Open the microphone privacy settings",
+        "Permissions",
+    ),
+    (
+        "open.accessibility_settings",
+        "Open the accessibility settings",
+        "Permissions",
+    ),
+];
+
+/// Everything that can be done right now, with titles that fit the current
+/// state: a mode that is on is offered to be turned off, and only
+/// downloaded models other than the one in use are offered to switch to.
+pub fn list(app: &AppHandle) -> Vec<Action> {
+    let mut actions: Vec<Action> = TOGGLES
+        .iter()
+        .map(|toggle| {
+            let verb = if (toggle.get)(app.clone()) {
+                "Turn off"
+            } else {
+                "Turn on"
+            };
+            Action {
+                id: toggle.id.to_string(),
+                title: format!("{} {}", verb, toggle.name),
+                category: "Modes",
+            }
+        })
+        .collect();
+
+    let (current, _) = crate::get_model_info(app.clone());
+    actions.extend(
+        crate::get_available_models(app.clone())
+            .into_iter()
+            .filter(|(key, _, downloaded, _)| *downloaded && *key != current)
+            .map(|(key, name, _, _)| Action {
+                id: format!("model.{}", key),
+                title: format!("Switch to {}", name),
+                category: "Model",
+            }),
+    );
+
+    let meeting = match app.state::<AppState>().session.state() {
+        SessionState::Idle => Some(("meeting.start", "Start transcribing a meeting")),
+        SessionState::Meeting => Some(("meeting.stop", "Stop transcribing the meeting")),
+        _ => None,
+    };
+    actions.extend(meeting.map(|(id, title)| Action {
+        id: id.to_string(),
+        title: title.to_string(),
+        category: "Meeting",
+    }));
+
+    actions.extend(FIXED.iter().map(|&(id, title, category)| Action {
+        id: id.to_string(),
+        title: title.to_string(),
+        category,
+    }));
+    actions
+}
+
+/// Runs the action with this id. Checks return their report; everything
+/// else returns null.
+pub async fn invoke(app: &AppHandle, id: &str) -> Result<Value, String> {
+    if let Some(toggle) = TOGGLES.iter().find(|toggle| toggle.id == id) {
+        let enabled = (toggle.get)(app.clone());
+        (toggle.set)(app.clone(), !enabled)?;
+        return Ok(Value::Null);
+    }
+    if let Some(key) = id.strip_prefix("model.") {
+        let (app, key) = (app.clone(), key.to_string());
+        blocking(move || tauri::async_runtime::block_on(crate::set_model_size(app, key))).await?;
+        return Ok(Value::Null);
+    }
+
+    match id {
+        "meeting.start" => crate::start_meeting_mode(app.clone())?,
+        "meeting.stop" => crate::stop_meeting_mode(app.clone())?,
+        "check.self_test" => {
+            let app = app.clone();
+            return Ok(json!(blocking(move || crate::run_self_test(app)).await?));
+        }
+        "check.diagnostics" => return Ok(json!(crate::get_diagnostics(app.clone()))),
+        "open.models" => open_folder(app, &get_model_dir())?,
+        "open.recordings" => open_folder(app, &get_recordings_dir())?,
+        "open.plugins" => open_folder(app, &get_plugins_dir())?,
+        "open.microphone_settings" => crate::open_microphone_settings()?,
+        "open.accessibility_settings" => crate::open_accessibility_settings()?,
+        _ => return Err(format!("Unknown action: {}", id)),
+    }
+    Ok(Value::Null)
+}
+
+/// Runs `action` on a thread of its own, since loading a model,
+/// transcribing or waiting on an LLM would otherwise hold up the async
+/// runtime.
+pub(crate) async fn blocking<T: Send + 'static>(
+    action: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(action)
+        .await
+        .map_err(|e| format!("Failed to run the action: {}", e))?
+}
+
+fn open_folder(app: &AppHandle, dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    app.opener()
+        .open_path(dir.to_string_lossy(), None::<&str>)
+        .map_err(|e| format!("Failed to open {}: {}", dir.display(), e))
+}
@@ -1,5 +0,0 @@
-mod capture;
-mod processor;
-
-pub use capture::{list_input_devices, AudioRecorder};
-pub use processor::convert_to_whisper_format;
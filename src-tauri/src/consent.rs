@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+/// Spoken before the first dictation during a call when announcements are on.
+pub const ANNOUNCEMENT: &str = "This call is being transcribed.";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConsentSettings {
+    /// ISO 3166 code such as `US-CA`, used to word reminders.
+    pub region: Option<String>,
+    /// Speak [`ANNOUNCEMENT`] before the first dictation in a detected call.
+    pub announce_calls: bool,
+    /// Compliance switch: when off, system audio may never be captured.
+    pub allow_system_audio: bool,
+}
+
+impl Default for ConsentSettings {
+    fn default() -> Self {
+        Self {
+            region: None,
+            announce_calls: false,
+            allow_system_audio: true,
+        }
+    }
+}
+
+/// Who has to agree before a conversation may be recorded or transcribed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsentRule {
+    /// One participant's consent is enough.
+    OneParty,
+    /// Every participant has to consent.
+    AllParty,
+    /// No region is set, or it isn't in the table.
+    Unknown,
+}
+
+/// Regions that require every party's consent, by ISO 3166 code. US states
+/// without an entry are one-party. This is a reminder, not legal advice.
+const ALL_PARTY_REGIONS: &[&str] = &[
+    "US-CA", "US-CT", "US-DE", "US-FL", "US-IL", "US-MD", "US-MA", "US-MI", "US-MT", "US-NV",
+    "US-NH", "US-OR", "US-PA", "US-WA",
+];
+
+pub fn consent_rule(region: Option<&str>) -> ConsentRule {
+    let Some(region) = region.map(|r| r.trim().to_uppercase()) else {
+        return ConsentRule::Unknown;
+    };
+    if ALL_PARTY_REGIONS.contains(&region.as_str()) {
+        ConsentRule::AllParty
+    } else if region.starts_with("US-") || region == "US" {
+        ConsentRule::OneParty
+    } else {
+        ConsentRule::Unknown
+    }
+}
+
+/// What the user is shown when a call starts.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsentReminder {
+    pub app: String,
+    pub rule: ConsentRule,
+    pub message: String,
+}
+
+impl ConsentReminder {
+    pub fn new(app: &str, region: Option<&str>) -> Self {
+        let rule = consent_rule(region);
+        let message = match rule {
+            ConsentRule::AllParty => format!(
+                "Everyone on this {} call must agree before it is transcribed.",
+                app
+            ),
+            ConsentRule::OneParty => format!(
+                "Your consent is enough to transcribe this {} call, but let the others know.",
+                app
+            ),
+            ConsentRule::Unknown => format!(
+                "Check that everyone on this {} call agrees before transcribing it.",
+                app
+            ),
+        };
+        Self {
+            app: app.to_string(),
+            rule,
+            message,
+        }
+    }
+}
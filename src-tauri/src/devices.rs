@@ -0,0 +1,67 @@
+//! Notices microphones being plugged in and unplugged on Windows, where the
+//! device list would otherwise only be read when the settings open.
+
+use std::ffi::c_void;
+use windows_sys::core::GUID;
+use windows_sys::Win32::Devices::DeviceAndDriverInstallation::{
+    CM_Register_Notification, CM_NOTIFY_ACTION, CM_NOTIFY_ACTION_DEVICEINTERFACEARRIVAL,
+    CM_NOTIFY_ACTION_DEVICEINTERFACEREMOVAL, CM_NOTIFY_EVENT_DATA, CM_NOTIFY_FILTER,
+    CM_NOTIFY_FILTER_0, CM_NOTIFY_FILTER_0_2, CM_NOTIFY_FILTER_TYPE_DEVICEINTERFACE, CR_SUCCESS,
+    HCMNOTIFICATION,
+};
+use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+
+/// `DEVINTERFACE_AUDIO_CAPTURE`, the interface class of audio inputs.
+const AUDIO_CAPTURE: GUID = GUID::from_u128(0x2eef81be_33fa_4800_9670_1cd474972c3f);
+
+type Callback = Box<dyn Fn() + Send + Sync>;
+
+/// Calls `on_change` whenever an audio input is added or removed, for as
+/// long as the app runs. It is called on a system thread pool, so it should
+/// hand the work off rather than do it there.
+pub fn watch_input_devices(on_change: impl Fn() + Send + Sync + 'static) -> Result<(), String> {
+    let filter = CM_NOTIFY_FILTER {
+        cbSize: std::mem::size_of::<CM_NOTIFY_FILTER>() as u32,
+        Flags: 0,
+        FilterType: CM_NOTIFY_FILTER_TYPE_DEVICEINTERFACE,
+        Reserved: 0,
+        u: CM_NOTIFY_FILTER_0 {
+            DeviceInterface: CM_NOTIFY_FILTER_0_2 {
+                ClassGuid: AUDIO_CAPTURE,
+            },
+        },
+    };
+    // The registration is never undone, so neither is the callback freed.
+    let callback: Callback = Box::new(on_change);
+    let context: &'static Callback = Box::leak(Box::new(callback));
+    let mut handle: HCMNOTIFICATION = std::ptr::null_mut();
+    let result = unsafe {
+        CM_Register_Notification(
+            &filter,
+            context as *const Callback as *const c_void,
+            Some(notify),
+            &mut handle,
+        )
+    };
+    if result != CR_SUCCESS {
+        return Err(format!("Failed to watch audio devices: error {}", result));
+    }
+    Ok(())
+}
+
+unsafe extern "system" fn notify(
+    _handle: HCMNOTIFICATION,
+    context: *const c_void,
+    action: CM_NOTIFY_ACTION,
+    _data: *const CM_NOTIFY_EVENT_DATA,
+    _size: u32,
+) -> u32 {
+    if matches!(
+        action,
+        CM_NOTIFY_ACTION_DEVICEINTERFACEARRIVAL | CM_NOTIFY_ACTION_DEVICEINTERFACEREMOVAL
+    ) {
+        let on_change = &*(context as *const Callback);
+        on_change();
+    }
+    ERROR_SUCCESS
+}
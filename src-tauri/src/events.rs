@@ -0,0 +1,81 @@
+use crate::AppState;
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How many events are kept for windows that load late.
+const CAPACITY: usize = 64;
+
+/// Emitted many times a second and stale straight away, so never kept.
+const UNRECORDED: [&str; 2] = ["audio-level", "test-audio-level"];
+
+/// Progress and previews, where only the latest is worth catching up on.
+const LATEST_ONLY: [&str; 6] = [
+    "audio-devices-changed",
+    "download-progress",
+    "model-download-progress",
+    "file-transcription-progress",
+    "batch-transcription-progress",
+    "transcription-partial",
+];
+
+/// An event as it was emitted, numbered so a window can ask for the ones
+/// after the last it saw.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordedEvent {
+    pub id: u64,
+    pub event: String,
+    pub payload: Value,
+}
+
+/// The most recent events sent to the windows, for `get_recent_events`.
+#[derive(Default)]
+pub struct EventLog {
+    events: Mutex<VecDeque<RecordedEvent>>,
+}
+
+impl EventLog {
+    fn record(&self, event: &str, payload: Value) {
+        let mut events = self.events.lock();
+        let id = events.back().map_or(1, |last| last.id + 1);
+        if LATEST_ONLY.contains(&event) {
+            events.retain(|recorded| recorded.event != event);
+        }
+        if events.len() == CAPACITY {
+            events.pop_front();
+        }
+        events.push_back(RecordedEvent {
+            id,
+            event: event.to_string(),
+            payload,
+        });
+    }
+
+    /// Events after `id`, oldest first; all of them without one.
+    pub fn since(&self, id: Option<u64>) -> Vec<RecordedEvent> {
+        let after = id.unwrap_or(0);
+        self.events
+            .lock()
+            .iter()
+            .filter(|recorded| recorded.id > after)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Emits an event to every window and keeps it for windows that attach
+/// later.
+pub fn emit<S: Serialize + Clone>(app: &AppHandle, event: &str, payload: S) -> tauri::Result<()> {
+    if !UNRECORDED.contains(&event) {
+        if let Ok(value) = serde_json::to_value(&payload) {
+            let state = app.state::<AppState>();
+            if let Some(remote) = state.remote_server.lock().as_ref() {
+                remote.forward(event, &value);
+            }
+            state.events.record(event, value);
+        }
+    }
+    app.emit(event, payload)
+}
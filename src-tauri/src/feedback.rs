@@ -0,0 +1,114 @@
+use voice_core::audio::{list_recordings, read_flac, recording_audio_path, write_wav};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+/// A user-supplied correction for a retained recording. The audio is copied
+/// next to it so the pair survives the recording being deleted from history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Correction {
+    pub recording_id: String,
+    pub original_text: String,
+    pub corrected_text: String,
+}
+
+pub fn get_feedback_dir() -> PathBuf {
+    let dir = dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("voice")
+        .join("feedback");
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+pub fn submit_correction(recording_id: &str, corrected_text: &str) -> Result<Correction, String> {
+    let recording = list_recordings()
+        .into_iter()
+        .find(|r| r.id == recording_id)
+        .ok_or_else(|| "Recording not found; enable recording retention to collect feedback".to_string())?;
+
+    let dir = get_feedback_dir();
+    std::fs::copy(
+        recording_audio_path(recording_id)?,
+        dir.join(format!("{}.flac", recording_id)),
+    )
+    .map_err(|e| format!("Failed to copy recording audio: {}", e))?;
+
+    let correction = Correction {
+        recording_id: recording_id.to_string(),
+        original_text: recording.text,
+        corrected_text: corrected_text.trim().to_string(),
+    };
+    let json = serde_json::to_vec_pretty(&correction)
+        .map_err(|e| format!("Failed to serialize correction: {}", e))?;
+    std::fs::write(dir.join(format!("{}.json", recording_id)), json)
+        .map_err(|e| format!("Failed to write correction: {}", e))?;
+
+    Ok(correction)
+}
+
+pub fn list_corrections() -> Vec<Correction> {
+    let Ok(entries) = std::fs::read_dir(get_feedback_dir()) else {
+        return Vec::new();
+    };
+
+    let mut corrections: Vec<Correction> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| std::fs::read(path).ok())
+        .filter_map(|bytes| serde_json::from_slice(&bytes).ok())
+        .collect();
+
+    corrections.sort_by(|a, b| a.recording_id.cmp(&b.recording_id));
+    corrections
+}
+
+pub fn clear_corrections() -> Result<(), String> {
+    std::fs::remove_dir_all(get_feedback_dir())
+        .map_err(|e| format!("Failed to clear feedback: {}", e))
+}
+
+/// Exports every correction as an audiofolder-style dataset: `audio/NNNNN.wav`
+/// plus a `metadata.csv` with `file_name,transcription` columns. File names are
+/// sequential and no timestamps, ids, or original transcripts are written, so
+/// the folder can be shared without leaking when or what was dictated.
+pub fn export_dataset(dest: &Path) -> Result<usize, String> {
+    let corrections = list_corrections();
+    if corrections.is_empty() {
+        return Err("No corrections to export".to_string());
+    }
+
+    let audio_dir = dest.join("audio");
+    std::fs::create_dir_all(&audio_dir)
+        .map_err(|e| format!("Failed to create dataset folder: {}", e))?;
+
+    let feedback_dir = get_feedback_dir();
+    let mut metadata = String::from("file_name,transcription\n");
+
+    for (index, correction) in corrections.iter().enumerate() {
+        let samples = read_flac(&feedback_dir.join(format!("{}.flac", correction.recording_id)))?;
+        let file_name = format!("audio/{:05}.wav", index + 1);
+
+        let file = File::create(dest.join(&file_name))
+            .map_err(|e| format!("Failed to create {}: {}", file_name, e))?;
+        write_wav(BufWriter::new(file), &samples)?;
+
+        metadata.push_str(&format!("{},{}\n", file_name, csv_field(&correction.corrected_text)));
+    }
+
+    std::fs::write(dest.join("metadata.csv"), metadata)
+        .map_err(|e| format!("Failed to write metadata.csv: {}", e))?;
+
+    Ok(corrections.len())
+}
+
+fn csv_field(value: &str) -> String {
+    let value = value.replace('\n', " ");
+    if value.contains([',', '"']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
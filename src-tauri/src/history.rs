@@ -0,0 +1,289 @@
+use crate::migrations::{history_version, migrate_history};
+use rusqlite::{params, Connection, ErrorCode, Row};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use voice_core::transcription::{Segment, TranscriptionResult};
+
+/// One delivered transcription.
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryEntry {
+    pub id: i64,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// Length of the dictated audio.
+    pub duration_ms: u64,
+    pub model: String,
+    /// Input device, `None` for the system default.
+    pub device: Option<String>,
+    pub text: String,
+    /// The retained recording of the dictation, when recordings are kept.
+    pub recording_id: Option<String>,
+    /// The audio file transcribed, `None` for a dictation.
+    pub source: Option<String>,
+    /// When each part was said, in the model's own words. A dictation that
+    /// was reviewed has one segment spanning the audio.
+    pub segments: Vec<Segment>,
+}
+
+impl HistoryEntry {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let segments: Option<String> = row.get(7)?;
+        Ok(Self {
+            id: row.get(0)?,
+            timestamp: row.get(1)?,
+            duration_ms: row.get(2)?,
+            model: row.get(3)?,
+            device: row.get(4)?,
+            text: row.get(5)?,
+            recording_id: row.get(6)?,
+            source: row.get(8)?,
+            segments: segments
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Where the segment said at `seconds` ends, or `None` in a pause
+    /// between segments.
+    pub fn segment_end(&self, seconds: f32) -> Option<f32> {
+        self.segments
+            .iter()
+            .find(|segment| segment.start <= seconds && seconds < segment.end)
+            .map(|segment| segment.end)
+    }
+}
+
+const COLUMNS: &str =
+    "id, timestamp, duration_ms, model, device, text, recording_id, segments, source";
+
+pub fn get_history_path() -> PathBuf {
+    let dir = dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("voice");
+    std::fs::create_dir_all(&dir).ok();
+    dir.join("history.sqlite3")
+}
+
+/// What is wrong with the database at `path`, if the file itself is at fault:
+/// SQLite doesn't take it for a database, or `PRAGMA quick_check` finds it
+/// damaged. Anything else, such as another copy of Voice holding it locked,
+/// says nothing about the file and gives `None`.
+pub fn database_damage(path: &Path) -> Option<String> {
+    if !path.exists() {
+        return None;
+    }
+    let conn = Connection::open(path).ok()?;
+    match conn.query_row("PRAGMA quick_check", [], |row| row.get::<_, String>(0)) {
+        Ok(result) if result == "ok" => None,
+        Ok(result) => Some(format!("History is damaged: {}", result)),
+        Err(e) => match e.sqlite_error_code() {
+            Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase) => {
+                Some(format!("History is damaged: {}", e))
+            }
+            _ => None,
+        },
+    }
+}
+
+/// Every transcription Voice has delivered, in a SQLite database in the data
+/// directory.
+pub struct History {
+    conn: Connection,
+}
+
+impl History {
+    pub fn open() -> Result<Self, String> {
+        let mut conn = Connection::open(get_history_path())
+            .map_err(|e| format!("Failed to open history: {}", e))?;
+        migrate_history(&mut conn)?;
+        Ok(Self { conn })
+    }
+
+    /// The schema version, see [`crate::migrations::HISTORY_VERSION`].
+    pub fn version(&self) -> Result<u32, String> {
+        history_version(&self.conn)
+    }
+
+    pub fn record(
+        &self,
+        result: &TranscriptionResult,
+        duration_ms: u64,
+        model: &str,
+        device: Option<&str>,
+        recording_id: Option<&str>,
+        source: Option<&str>,
+    ) -> Result<HistoryEntry, String> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let segments_json = serde_json::to_string(&result.segments)
+            .map_err(|e| format!("Failed to serialize segments: {}", e))?;
+        self.conn
+            .execute(
+                "INSERT INTO history
+                     (timestamp, duration_ms, model, device, text, recording_id, segments, source)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    timestamp,
+                    duration_ms,
+                    model,
+                    device,
+                    result.text,
+                    recording_id,
+                    segments_json,
+                    source
+                ],
+            )
+            .map_err(|e| format!("Failed to save history: {}", e))?;
+
+        Ok(HistoryEntry {
+            id: self.conn.last_insert_rowid(),
+            timestamp,
+            duration_ms,
+            model: model.to_string(),
+            device: device.map(str::to_string),
+            text: result.text.clone(),
+            recording_id: recording_id.map(str::to_string),
+            source: source.map(str::to_string),
+            segments: result.segments.clone(),
+        })
+    }
+
+    /// Adds text and segments to the end of an entry and sets its duration,
+    /// for a transcript that grows while it is being recorded. Only the new
+    /// part is sent; the entry is extended in place.
+    pub fn append(
+        &self,
+        id: i64,
+        result: &TranscriptionResult,
+        duration_ms: u64,
+    ) -> Result<(), String> {
+        let segments_json = serde_json::to_string(&result.segments)
+            .map_err(|e| format!("Failed to serialize segments: {}", e))?;
+        // Both are compact JSON arrays, so joining them means dropping the
+        // first one's closing bracket and the second one's opening bracket.
+        let updated = self
+            .conn
+            .execute(
+                "UPDATE history SET
+                     text = CASE WHEN text = '' THEN ?1 ELSE text || ' ' || ?1 END,
+                     segments = CASE
+                         WHEN segments IS NULL OR segments = '[]' THEN ?2
+                         WHEN ?2 = '[]' THEN segments
+                         ELSE substr(segments, 1, length(segments) - 1) || ',' || substr(?2, 2)
+                     END,
+                     duration_ms = ?3
+                 WHERE id = ?4",
+                params![result.text, segments_json, duration_ms, id],
+            )
+            .map_err(|e| format!("Failed to update history entry: {}", e))?;
+        if updated == 0 {
+            return Err("History entry not found".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, id: i64) -> Result<HistoryEntry, String> {
+        self.query(
+            &format!("SELECT {} FROM history WHERE id = ?1", COLUMNS),
+            params![id],
+        )?
+        .pop()
+        .ok_or_else(|| "History entry not found".to_string())
+    }
+
+    /// Newest first; only those from `source` when one is given.
+    pub fn list(
+        &self,
+        limit: u32,
+        offset: u32,
+        source: Option<&str>,
+    ) -> Result<Vec<HistoryEntry>, String> {
+        self.query(
+            &format!(
+                "SELECT {} FROM history WHERE ?3 IS NULL OR source = ?3
+                 ORDER BY timestamp DESC, id DESC LIMIT ?1 OFFSET ?2",
+                COLUMNS
+            ),
+            params![limit, offset, source],
+        )
+    }
+
+    /// The latest dictations, newest first, leaving out transcribed files,
+    /// memos and meetings.
+    pub fn recent_dictations(&self, limit: u32) -> Result<Vec<HistoryEntry>, String> {
+        self.query(
+            &format!(
+                "SELECT {} FROM history WHERE source IS NULL
+                 ORDER BY timestamp DESC, id DESC LIMIT ?1",
+                COLUMNS
+            ),
+            params![limit],
+        )
+    }
+
+    /// Entries whose text contains `query`, ignoring ASCII case.
+    pub fn search(&self, query: &str, limit: u32) -> Result<Vec<HistoryEntry>, String> {
+        let pattern = format!(
+            "%{}%",
+            query
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        self.query(
+            &format!(
+                "SELECT {} FROM history WHERE text LIKE ?1 ESCAPE '\\'
+                 ORDER BY timestamp DESC, id DESC LIMIT ?2",
+                COLUMNS
+            ),
+            params![pattern, limit],
+        )
+    }
+
+    pub fn delete(&self, id: i64) -> Result<(), String> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM history WHERE id = ?1", params![id])
+            .map_err(|e| format!("Failed to delete history entry: {}", e))?;
+        if deleted == 0 {
+            return Err("History entry not found".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn clear(&self) -> Result<(), String> {
+        self.conn
+            .execute("DELETE FROM history", [])
+            .map_err(|e| format!("Failed to clear history: {}", e))?;
+        Ok(())
+    }
+
+    /// Memory SQLite holds for its page cache and statements. SQLite counts
+    /// it process-wide, and history is the only database Voice opens.
+    pub fn cache_bytes(&self) -> u64 {
+        let used = unsafe { rusqlite::ffi::sqlite3_memory_used() };
+        used.max(0) as u64
+    }
+
+    /// Frees as much of the cache as SQLite can.
+    pub fn shrink_cache(&self) -> Result<(), String> {
+        self.conn
+            .execute_batch("PRAGMA shrink_memory")
+            .map_err(|e| format!("Failed to shrink history cache: {}", e))
+    }
+
+    fn query(&self, sql: &str, params: impl rusqlite::Params) -> Result<Vec<HistoryEntry>, String> {
+        let mut statement = self
+            .conn
+            .prepare(sql)
+            .map_err(|e| format!("Failed to read history: {}", e))?;
+        let entries = statement
+            .query_map(params, HistoryEntry::from_row)
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .map_err(|e| format!("Failed to read history: {}", e))?;
+        Ok(entries)
+    }
+}
@@ -0,0 +1,194 @@
+//! Checks Voice's own files at launch. A corrupt one is renamed to `.bak`
+//! and Voice starts without it, rather than crashing or misbehaving on it.
+//! Files that merely can't be read or written right now are left in place.
+
+use crate::history::{database_damage, get_history_path, History};
+use crate::settings::{get_settings_path, parse_settings, save_settings, Settings};
+use serde::Serialize;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use voice_core::transcription::{ModelId, ModelManifest};
+
+/// The files SQLite keeps next to a database while it is open or was left
+/// mid-transaction, which belong to it.
+const DATABASE_SIDECARS: [&str; 3] = ["-journal", "-wal", "-shm"];
+
+/// A file found corrupt at launch and set aside.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoveredFile {
+    /// `"settings"`, `"history"` or `"model"`.
+    pub kind: &'static str,
+    /// Where the corrupt file was moved.
+    pub backup: PathBuf,
+    pub reason: String,
+}
+
+/// The saved settings, or the defaults if the file is missing, unreadable or
+/// invalid. Only an invalid one is set aside.
+pub fn check_settings(recovered: &mut Vec<RecoveredFile>) -> Settings {
+    let path = get_settings_path();
+    let bytes = match std::fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Settings::default(),
+        Err(e) => {
+            eprintln!("Failed to read settings: {}", e);
+            return Settings::default();
+        }
+    };
+    match parse_settings(&bytes) {
+        Ok((settings, migrated)) => {
+            if migrated {
+                if let Err(e) = save_settings(&settings) {
+                    eprintln!("{}", e);
+                }
+            }
+            settings
+        }
+        Err(e) => {
+            set_aside("settings", &path, &[], e, recovered);
+            Settings::default()
+        }
+    }
+}
+
+/// The history database, or a fresh one in place of a damaged one. `None`
+/// if it can't be opened, which leaves it in place for the next launch.
+pub fn check_history(recovered: &mut Vec<RecoveredFile>) -> Option<History> {
+    let path = get_history_path();
+    if let Some(reason) = database_damage(&path) {
+        set_aside("history", &path, &DATABASE_SIDECARS, reason, recovered);
+    }
+    History::open().map_err(|e| eprintln!("{}", e)).ok()
+}
+
+/// Sets aside downloaded models that are cut short or aren't models at all,
+/// so they show as not installed and can be downloaded again.
+pub fn check_models(manifest: &ModelManifest, recovered: &mut Vec<RecoveredFile>) {
+    for entry in &manifest.models {
+        let Ok(model) = ModelId::parse(&entry.key) else {
+            continue;
+        };
+        let path = model.path();
+        if !path.exists() {
+            continue;
+        }
+        if let Err(e) = entry.check_file(&path) {
+            set_aside("model", &path, &[], e, recovered);
+        }
+    }
+}
+
+fn set_aside(
+    kind: &'static str,
+    path: &Path,
+    sidecars: &[&str],
+    reason: String,
+    recovered: &mut Vec<RecoveredFile>,
+) {
+    eprintln!("Setting aside corrupt {}: {}", path.display(), reason);
+    match quarantine(path, sidecars) {
+        Ok(backup) => recovered.push(RecoveredFile {
+            kind,
+            backup,
+            reason,
+        }),
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+/// Renames `path` to the first free one of `<path>.bak`, `<path>.2.bak`, …
+/// so earlier backups are kept, and moves any of its `sidecars` (suffixes
+/// of its name) along with it.
+fn quarantine(path: &Path, sidecars: &[&str]) -> Result<PathBuf, String> {
+    let name = path.file_name().unwrap_or_default();
+    let backup = (1..)
+        .map(|n| {
+            let mut backup = name.to_os_string();
+            if n > 1 {
+                backup.push(format!(".{}", n));
+            }
+            backup.push(".bak");
+            path.with_file_name(backup)
+        })
+        .find(|backup| {
+            !backup.exists()
+                && sidecars
+                    .iter()
+                    .all(|sidecar| !with_suffix(backup, sidecar).exists())
+        })
+        .expect("an unused backup name");
+    std::fs::rename(path, &backup)
+        .map_err(|e| format!("Failed to set aside {}: {}", path.display(), e))?;
+    for sidecar in sidecars {
+        let from = with_suffix(path, sidecar);
+        if from.exists() {
+            std::fs::rename(&from, with_suffix(&backup, sidecar))
+                .map_err(|e| format!("Failed to set aside {}: {}", from.display(), e))?;
+        }
+    }
+    Ok(backup)
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("voice-integrity-{}", name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn quarantine_keeps_earlier_backups() {
+        let dir = scratch_dir("backups");
+        let path = dir.join("settings.json");
+        for contents in ["first", "second"] {
+            std::fs::write(&path, contents).unwrap();
+            quarantine(&path, &[]).unwrap();
+        }
+        let first = std::fs::read_to_string(dir.join("settings.json.bak")).unwrap();
+        let second = std::fs::read_to_string(dir.join("settings.json.2.bak")).unwrap();
+        assert_eq!((first.as_str(), second.as_str()), ("first", "second"));
+        assert!(!path.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn quarantine_moves_a_database_with_its_sidecars() {
+        let dir = scratch_dir("sidecars");
+        let path = dir.join("history.sqlite3");
+        std::fs::write(&path, "db").unwrap();
+        std::fs::write(with_suffix(&path, "-wal"), "wal").unwrap();
+        let backup = quarantine(&path, &DATABASE_SIDECARS).unwrap();
+        assert_eq!(backup, dir.join("history.sqlite3.bak"));
+        let wal = std::fs::read_to_string(dir.join("history.sqlite3.bak-wal")).unwrap();
+        assert_eq!(wal, "wal");
+        assert!(!with_suffix(&path, "-wal").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn only_a_file_that_isnt_a_database_counts_as_damaged() {
+        let dir = scratch_dir("damage");
+        let good = dir.join("good.sqlite3");
+        Connection::open(&good)
+            .unwrap()
+            .execute_batch("CREATE TABLE t (x INTEGER)")
+            .unwrap();
+        let bad = dir.join("bad.sqlite3");
+        std::fs::write(&bad, vec![b'x'; 4096]).unwrap();
+        assert_eq!(database_damage(&good), None);
+        assert_eq!(database_damage(&dir.join("missing.sqlite3")), None);
+        assert!(database_damage(&bad).is_some());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,24 +1,180 @@
-mod audio;
-mod transcription;
+mod actions;
+mod consent;
+#[cfg(target_os = "windows")]
+mod devices;
+mod events;
+mod feedback;
+mod history;
+mod integrity;
+mod meetings;
+mod migrations;
+mod obs;
+mod output;
+mod permissions;
+mod plugins;
+mod remote;
+#[cfg(target_os = "linux")]
+mod portal;
+mod postprocess;
+mod profiles;
+mod rpc;
+mod secrets;
+mod selftest;
+mod session;
+mod settings;
+mod sounds;
+mod translation;
+mod tray;
 
-use audio::{convert_to_whisper_format, list_input_devices, AudioRecorder};
+use actions::Action;
+use consent::{ConsentReminder, ConsentSettings, ANNOUNCEMENT};
+use events::{emit, EventLog, RecordedEvent};
+use feedback::{clear_corrections, export_dataset, list_corrections, Correction};
+use history::{History, HistoryEntry};
+use migrations::DataVersion;
+use obs::{CaptionSender, ObsConfig};
+use output::OutputScheduler;
 use parking_lot::Mutex;
-use std::sync::mpsc::channel;
-use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
-use tauri::tray::TrayIconBuilder;
-use tauri::{AppHandle, Emitter, Manager, RunEvent};
+use permissions::{PermissionStatus, ACCESSIBILITY_DENIED, MICROPHONE_DENIED};
+use plugins::{run_plugins, Plugin, PluginInput};
+use postprocess::{
+    append_to_file, apply_replacements, apply_rules, complete, dictionary_presets, mark_rtl,
+    output_targets, pipe_to_command, read_script, restore_punctuation, run_pipeline, run_script,
+    write_script, ConflictPolicy, DictionaryPack, DictionaryPreset, ImportSummary, OutputTarget,
+    PipelineStep, PunctuationMode, Replacement, TextRules,
+};
+use profiles::Profile;
+use remote::{RemotePairing, RemoteServer};
+use selftest::{word_error_rate, SelfTestReport};
+use serde::{Deserialize, Serialize};
+use session::{SessionManager, SessionState};
+use settings::{
+    save_settings, HorizontalAnchor, NoSpeechFeedback, OverlayPlacement, Settings, VerticalAnchor,
+};
+use sounds::{Cue, SoundCues};
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::Write;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::tray::TrayIcon;
+use tauri::{AppHandle, Manager, RunEvent};
 use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_dialog::DialogExt;
 use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
-use transcription::{
-    download_model, get_model_path, is_model_downloaded, ModelSize, WhisperTranscriber,
+use translation::{CaptionTranslation, LlmEndpoint};
+use voice_core::audio::{
+    decode_audio_file, generate_ssrc, is_wake_phrase, list_input_devices, list_recordings,
+    load_recording, read_flac, recording_to_wav, save_recording, split_at_pauses,
+    write_private_flac, write_wav, AudioPipeline, AudioRecorder, CaptureStats, DropoutRange,
+    NetworkMicrophone, RecordedAudio, RetainedRecording, SilenceDetector, StreamingConverter,
+    WakeWordDetector, AUDIO_FILE_EXTENSIONS, MAX_PREROLL, NETWORK_MICROPHONE_DEVICE,
+    NETWORK_MICROPHONE_PORT, SYSTEM_AUDIO_DEVICE, WHISPER_SAMPLE_RATE,
 };
+use voice_core::files::spill_dir;
+use voice_core::focus::{frontmost_window, FocusedWindow};
+use voice_core::keyboard::{chord_held, ChordKey};
+use voice_core::memory::resident_bytes;
+use voice_core::rpc::{api_schema, RpcServer};
+use voice_core::transcription::{
+    chapter_texts, compute_config, core_ml_supported, download_model, download_verified_model,
+    find_chapters, format_markdown, format_subtitles, gpu_backend, install_pack,
+    is_model_downloaded, is_writable, list_installed_models, list_packs, pack_slug, remove_pack,
+    shared_model_dir, CancelToken, Chapter, ComputeConfig, Decoding, DownloadAuth, DownloadConfig,
+    InstalledModel, LanguagePack, ModelEntry, ModelId, ModelManifest, ModelSize, Segment,
+    SubtitleFormat, Suppression, Transcriber, Transcript, TranscriptionBackend,
+    TranscriptionLanguage, TranscriptionResult, WhisperTranscriber,
+};
+use voice_core::typing::{ime_active, type_text};
 
 struct AppState {
     recorder: Mutex<Option<AudioRecorder>>,
     transcriber: Mutex<Option<WhisperTranscriber>>,
-    is_recording: Mutex<bool>,
+    /// Set while a cloud backend is selected, and used instead of the local
+    /// model.
+    remote_transcriber: Mutex<Option<Arc<dyn Transcriber>>>,
+    session: SessionManager,
     selected_device: Mutex<Option<String>>,
-    selected_model: Mutex<ModelSize>,
+    selected_model: Mutex<ModelId>,
+    transcription_language: Mutex<TranscriptionLanguage>,
+    retain_recordings: Mutex<bool>,
+    feedback_enabled: Mutex<bool>,
+    punctuation_mode: Mutex<PunctuationMode>,
+    last_transcription: Mutex<Option<String>>,
+    capture_window_context: Mutex<bool>,
+    recording_context: Mutex<Option<FocusedWindow>>,
+    permissions: Mutex<PermissionStatus>,
+    model_manifest: Mutex<ModelManifest>,
+    /// Downloads in progress by model key, for `cancel_model_download`.
+    downloads: Mutex<HashMap<String, CancelToken>>,
+    /// How long after releasing the shortcut a new press continues the same
+    /// dictation instead of starting another. Zero disables continuation.
+    continuation_window_ms: Mutex<u64>,
+    /// Audio from released presses waiting out the continuation window.
+    pending_dictation: Mutex<Vec<f32>>,
+    /// The part of the pending dictation moved to disk to stay under the
+    /// memory cap.
+    spilled_dictation: Mutex<Option<PathBuf>>,
+    /// Where audio dropped out in the pending dictation.
+    dictation_dropouts: Mutex<DictationDropouts>,
+    /// The model was unloaded to stay under the memory cap, and is loaded
+    /// again for the next transcription.
+    model_unloaded: Mutex<bool>,
+    /// Counts shortcut presses so a waiting dictation can tell it was continued.
+    dictation_presses: Mutex<u64>,
+    /// Counts overlay previews, so only the latest one hides the overlay.
+    overlay_previews: Mutex<u64>,
+    /// Between the shortcut's press and release events. Key repeat sends more
+    /// presses while it is held, which are ignored.
+    shortcut_held: Mutex<bool>,
+    /// The last memo or repaste shortcut released, and when, to drop the
+    /// second copy when both the plugin and the portal report it.
+    last_shortcut_release: Mutex<Option<(&'static str, Instant)>>,
+    /// When off, dictation gives feedback through the tray and sounds only, so
+    /// nothing appears on screen that could take focus from the paste target.
+    show_overlay: Mutex<bool>,
+    /// The transcription waiting in the overlay for a review key.
+    review: Mutex<Option<Review>>,
+    /// Translates the live captions while set; the final transcription is
+    /// left in the spoken language.
+    caption_translation: Mutex<Option<CaptionTranslation>>,
+    settings: Mutex<Settings>,
+    /// Answers the output command confirmation currently on screen.
+    command_confirmation: Mutex<Option<Sender<bool>>>,
+    rpc_server: Mutex<Option<RpcServer>>,
+    remote_server: Mutex<Option<RemoteServer>>,
+    remote_dictation: Mutex<rpc::RemoteDictation>,
+    /// Sends captions to OBS, connecting on the first one.
+    obs_captions: CaptionSender,
+    /// `None` if the history database couldn't be opened.
+    history: Mutex<Option<History>>,
+    /// Conferencing apps seen in a call at the last check.
+    active_calls: Mutex<Vec<&'static str>>,
+    /// Whether the announcement has been spoken in the current call.
+    call_announced: Mutex<bool>,
+    /// Recent events, replayed to windows that load after they were sent.
+    events: EventLog,
+    /// Spaces out and serializes clipboard writes and synthetic keystrokes.
+    output: OutputScheduler,
+    /// Stops the meeting transcription in progress.
+    meeting: Mutex<Option<CancelToken>>,
+    /// The running meeting was started for a detected call, and stops when
+    /// the call ends.
+    meeting_for_call: Mutex<bool>,
+    /// The last test recording from the settings window, as captured, so it
+    /// can be transcribed again with other gain and noise settings.
+    test_recording: Mutex<Option<RecordedAudio>>,
+    /// The wake-word listener has the microphone open.
+    wake_word_listening: Mutex<bool>,
+    /// Ends the quick memo being recorded before its time is up.
+    memo: Mutex<Option<CancelToken>>,
+    /// How the latest recording's audio arrived, for the diagnostics report.
+    last_capture: Mutex<Option<CaptureStats>>,
+    /// `None` until setup has built it.
+    tray: Mutex<Option<TrayIcon>>,
 }
 
 impl Default for AppState {
@@ -26,360 +182,4318 @@ impl Default for AppState {
         Self {
             recorder: Mutex::new(None),
             transcriber: Mutex::new(None),
-            is_recording: Mutex::new(false),
+            remote_transcriber: Mutex::new(None),
+            session: SessionManager::default(),
             selected_device: Mutex::new(None),
-            selected_model: Mutex::new(ModelSize::Small),
+            selected_model: Mutex::new(ModelId::Official(ModelSize::Small)),
+            transcription_language: Mutex::new(TranscriptionLanguage::default()),
+            retain_recordings: Mutex::new(false),
+            feedback_enabled: Mutex::new(false),
+            punctuation_mode: Mutex::new(PunctuationMode::default()),
+            last_transcription: Mutex::new(None),
+            capture_window_context: Mutex::new(false),
+            recording_context: Mutex::new(None),
+            permissions: Mutex::new(PermissionStatus::default()),
+            model_manifest: Mutex::new(ModelManifest::builtin()),
+            downloads: Mutex::new(HashMap::new()),
+            continuation_window_ms: Mutex::new(0),
+            pending_dictation: Mutex::new(Vec::new()),
+            spilled_dictation: Mutex::new(None),
+            dictation_dropouts: Mutex::new(DictationDropouts::default()),
+            model_unloaded: Mutex::new(false),
+            dictation_presses: Mutex::new(0),
+            overlay_previews: Mutex::new(0),
+            shortcut_held: Mutex::new(false),
+            last_shortcut_release: Mutex::new(None),
+            show_overlay: Mutex::new(true),
+            review: Mutex::new(None),
+            caption_translation: Mutex::new(None),
+            settings: Mutex::new(Settings::default()),
+            command_confirmation: Mutex::new(None),
+            rpc_server: Mutex::new(None),
+            remote_server: Mutex::new(None),
+            remote_dictation: Mutex::new(rpc::RemoteDictation::default()),
+            obs_captions: CaptionSender::new(),
+            history: Mutex::new(None),
+            active_calls: Mutex::new(Vec::new()),
+            call_announced: Mutex::new(false),
+            events: EventLog::default(),
+            output: OutputScheduler::default(),
+            meeting: Mutex::new(None),
+            meeting_for_call: Mutex::new(false),
+            test_recording: Mutex::new(None),
+            wake_word_listening: Mutex::new(false),
+            memo: Mutex::new(None),
+            last_capture: Mutex::new(None),
+            tray: Mutex::new(None),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AppStatus {
+    is_recording: bool,
+    /// Whether an input stream is open right now. Outside of an explicit
+    /// recording this is false, unless wake-word activation is listening.
+    microphone_active: bool,
+    model: String,
+    model_loaded: bool,
+}
+
+/// Events sent after `since`, or all that are kept without it, so a window
+/// that loads late can catch up on what it missed.
+#[tauri::command]
+fn get_recent_events(app: AppHandle, since: Option<u64>) -> Vec<RecordedEvent> {
+    app.state::<AppState>().events.since(since)
+}
+
+#[tauri::command]
+fn get_app_status(app: AppHandle) -> AppStatus {
+    let state = app.state::<AppState>();
+    let microphone_active = *state.wake_word_listening.lock()
+        || state
+            .recorder
+            .lock()
+            .as_ref()
+            .is_some_and(|recorder| recorder.is_stream_active());
+    let model = state.selected_model.lock().key();
+    let model_loaded = state.transcriber.lock().is_some();
+    let is_recording = state.session.is_recording();
+
+    AppStatus {
+        is_recording,
+        microphone_active,
+        model,
+        model_loaded,
+    }
+}
+
+/// Downloaded on first run so dictation works within seconds of install.
+const STARTER_MODEL: ModelSize = ModelSize::Tiny;
+
+/// Replaces the starter model in the background once it has downloaded.
+const DEFAULT_MODEL: ModelSize = ModelSize::Small;
+
+/// The best official model available locally for startup, if any.
+fn installed_startup_model() -> Option<ModelSize> {
+    [DEFAULT_MODEL, STARTER_MODEL]
+        .into_iter()
+        .find(|size| is_model_downloaded(*size))
+}
+
+/// Whether the model is installed in full. Packs aren't in the manifest, so
+/// for them it's enough that the file is there.
+fn is_installed(app: &AppHandle, model: &ModelId) -> bool {
+    let state = app.state::<AppState>();
+    let manifest = state.model_manifest.lock();
+    manifest
+        .get(&model.key())
+        .map_or_else(|| model.is_downloaded(), ModelEntry::is_downloaded)
+}
+
+fn manifest_entry(app: &AppHandle, key: &str) -> Result<ModelEntry, String> {
+    let state = app.state::<AppState>();
+    let manifest = state.model_manifest.lock();
+    manifest
+        .get(key)
+        .cloned()
+        .ok_or_else(|| format!("Model {} is not available for download", key))
+}
+
+fn use_gpu(app: &AppHandle) -> bool {
+    app.state::<AppState>().settings.lock().gpu
+}
+
+/// Runs `f` with the cloud transcriber when one is selected, otherwise with
+/// the local model.
+fn with_transcriber<T>(
+    state: &AppState,
+    f: impl FnOnce(&dyn Transcriber) -> Result<T, String>,
+) -> Result<T, String> {
+    let (vocabulary, _) = state.settings.lock().dictionary();
+    let accuracy_safeguard = state.settings.lock().accuracy_safeguard;
+    let suppression = state.settings.lock().suppression;
+    let decoding = {
+        let model = state.selected_model.lock().clone();
+        let manifest = state.model_manifest.lock();
+        state.settings.lock().decoding_for(&model, &manifest)
+    };
+    // Cloned out so a slow request doesn't hold the lock.
+    let remote = state.remote_transcriber.lock().clone();
+    if let Some(remote) = remote {
+        remote.set_vocabulary(&vocabulary);
+        remote.set_suppression(suppression);
+        return f(remote.as_ref());
+    }
+    let mut transcriber = state.transcriber.lock();
+    if transcriber.is_none() && *state.model_unloaded.lock() {
+        let model = state.selected_model.lock().clone();
+        let gpu = state.settings.lock().gpu;
+        *transcriber = Some(WhisperTranscriber::new(&model.path(), gpu)?);
+        *state.model_unloaded.lock() = false;
+    }
+    match transcriber.as_ref() {
+        Some(transcriber) => {
+            transcriber.set_vocabulary(&vocabulary);
+            transcriber.set_temperature_fallback(accuracy_safeguard);
+            transcriber.set_suppression(suppression);
+            transcriber.set_decoding(decoding);
+            f(transcriber)
+        }
+        None => Err("Transcriber not initialized".to_string()),
+    }
+}
+
+fn load_model(app: &AppHandle, model: ModelId) -> Result<(), String> {
+    let transcriber = WhisperTranscriber::new(&model.path(), use_gpu(app))?;
+    let state = app.state::<AppState>();
+    *state.transcriber.lock() = Some(transcriber);
+    *state.model_unloaded.lock() = false;
+    *state.selected_model.lock() = model;
+    Ok(())
+}
+
+/// Downloads `to` in the background and switches to it, unless the user has
+/// moved off `from` while it was downloading. The current model stays loaded
+/// until the new one has downloaded and loaded successfully.
+fn spawn_model_upgrade(app: AppHandle, from: ModelSize, to: ModelSize) {
+    tauri::async_runtime::spawn(async move {
+        let config = app.state::<AppState>().settings.lock().download.clone();
+        let gpu = use_gpu(&app);
+        let app_clone = app.clone();
+        let result = match manifest_entry(&app, to.key()) {
+            Ok(entry) => {
+                cancellable_download(&app, to.key(), |cancel| async move {
+                    download_verified_model(&entry, &config, gpu, &cancel, move |done, total| {
+                        let _ = emit(
+                            &app_clone,
+                            "model-download-progress",
+                            (to.key(), done, total),
+                        );
+                    })
+                    .await
+                })
+                .await
+            }
+            Err(e) => Err(e),
+        };
+
+        let transcriber = match result {
+            Ok(transcriber) => transcriber,
+            Err(e) => {
+                eprintln!("Background model download failed: {}", e);
+                let _ = emit(&app, "model-upgrade-failed", (to.key(), e));
+                return;
+            }
+        };
+
+        let state = app.state::<AppState>();
+        let mut selected = state.selected_model.lock();
+        if *selected == ModelId::Official(from) {
+            *state.transcriber.lock() = Some(transcriber);
+            *selected = ModelId::Official(to);
+            drop(selected);
+            let _ = emit(&app, "model-upgraded", to.key());
         }
+    });
+}
+
+/// Runs a download that `cancel_model_download` can abort by `key`, and
+/// emits `model-download-cancelled` with the key if it was.
+async fn cancellable_download<T, F, Fut>(
+    app: &AppHandle,
+    key: &str,
+    download: F,
+) -> Result<T, String>
+where
+    F: FnOnce(CancelToken) -> Fut,
+    Fut: Future<Output = Result<T, String>>,
+{
+    let cancel = CancelToken::default();
+    let state = app.state::<AppState>();
+    state.downloads.lock().insert(key.to_string(), cancel.clone());
+    let result = download(cancel.clone()).await;
+    state.downloads.lock().remove(key);
+    if result.is_err() && cancel.is_cancelled() {
+        let _ = emit(app, "model-download-cancelled", key);
     }
+    result
+}
+
+/// Aborts the download of `key` (a model size or `pack:<slug>`) and deletes
+/// its partial file.
+#[tauri::command]
+fn cancel_model_download(app: AppHandle, key: String) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let downloads = state.downloads.lock();
+    let cancel = downloads
+        .get(&key)
+        .ok_or_else(|| format!("No download of {} in progress", key))?;
+    cancel.cancel();
+    Ok(())
+}
+
+/// The newer model the selected one can be upgraded to, if not installed yet.
+#[tauri::command]
+fn get_model_upgrade(app: AppHandle) -> Option<String> {
+    let state = app.state::<AppState>();
+    let ModelId::Official(size) = *state.selected_model.lock() else {
+        return None;
+    };
+    size.successor()
+        .filter(|next| !is_model_downloaded(*next))
+        .map(|next| next.key().to_string())
+}
+
+#[tauri::command]
+fn start_model_upgrade(app: AppHandle) -> Result<(), String> {
+    let selected = app.state::<AppState>().selected_model.lock().clone();
+    let ModelId::Official(size) = selected else {
+        return Err("No upgrade available for language packs".to_string());
+    };
+    let next = size
+        .successor()
+        .ok_or_else(|| "No upgrade available for this model".to_string())?;
+    spawn_model_upgrade(app, size, next);
+    Ok(())
 }
 
 #[tauri::command]
 fn is_model_ready() -> bool {
-    is_model_downloaded(ModelSize::Small)
+    installed_startup_model().is_some()
 }
 
 #[tauri::command]
 async fn download_whisper_model(app: AppHandle) -> Result<(), String> {
+    let entry = manifest_entry(&app, STARTER_MODEL.key())?;
+    let config = app.state::<AppState>().settings.lock().download.clone();
     let app_clone = app.clone();
-    download_model(ModelSize::Small, move |downloaded, total| {
-        let _ = app_clone.emit("download-progress", (downloaded, total));
+    cancellable_download(&app, STARTER_MODEL.key(), |cancel| async move {
+        download_model(&entry, &config, &cancel, move |downloaded, total| {
+            let _ = emit(&app_clone, "download-progress", (downloaded, total));
+        })
+        .await
     })
     .await?;
 
-    let model_path = get_model_path(ModelSize::Small);
-    let transcriber = WhisperTranscriber::new(&model_path)?;
+    load_model(&app, ModelId::Official(STARTER_MODEL))?;
+    spawn_model_upgrade(app, STARTER_MODEL, DEFAULT_MODEL);
+
+    Ok(())
+}
+
+#[tauri::command]
+fn init_transcriber(app: AppHandle) -> Result<(), String> {
+    let size = installed_startup_model().ok_or_else(|| "Model not downloaded".to_string())?;
+    load_model(&app, ModelId::Official(size))
+}
 
+#[tauri::command]
+fn start_recording(app: AppHandle) -> Result<(), String> {
     let state = app.state::<AppState>();
-    *state.transcriber.lock() = Some(transcriber);
+    if !state.permissions.lock().microphone {
+        return Err(MICROPHONE_DENIED.to_string());
+    }
+
+    if let Err(e) = state.session.start_dictation(&app) {
+        return if state.session.is_recording() { Ok(()) } else { Err(e) };
+    }
+
+    let mut recorder_lock = state.recorder.lock();
+    let started = match recorder_lock.as_ref() {
+        Some(recorder) => recorder.start_recording(None),
+        None => open_recorder(&state).and_then(|recorder| {
+            recorder.start_recording(None)?;
+            *recorder_lock = Some(recorder);
+            Ok(())
+        }),
+    };
+    if started.is_err() {
+        state.session.reset(&app);
+    }
+    started
+}
+
+/// Stops the recorder and returns the audio in whisper format, with the
+/// stretches of it that dropouts may have damaged. The caller has already
+/// moved the session out of `Recording`.
+fn stop_recording(app: &AppHandle) -> Result<(Vec<f32>, Vec<DropoutRange>), String> {
+    let state = app.state::<AppState>();
+    let recorder_lock = state.recorder.lock();
+
+    if let Some(recorder) = recorder_lock.as_ref() {
+        let mut recorded = recorder.stop_recording()?;
+        *state.last_capture.lock() = Some(recorded.stats.clone());
+        let dropouts = dropout_ranges(app, &mut recorded);
+        if recorded.sample_rate_mismatch() {
+            let _ = emit(
+                app,
+                "sample-rate-mismatch",
+                (recorded.reported_sample_rate, recorded.sample_rate),
+            );
+        }
+        let pipeline = state.settings.lock().audio;
+        let (samples, trimmed) =
+            pipeline.process_timed(recorded.samples, recorded.sample_rate, recorded.channels);
+        let length = samples.len() as f32 / WHISPER_SAMPLE_RATE as f32;
+        let dropouts = dropouts
+            .into_iter()
+            .map(|range| {
+                let start = (range.start - trimmed).max(0.0);
+                (start, (range.end - trimmed).min(length))
+            })
+            .filter(|(start, end)| start <= end)
+            .map(|(start, end)| DropoutRange { start, end })
+            .collect();
+        Ok((samples, dropouts))
+    } else {
+        Err("No recorder available".to_string())
+    }
+}
+
+#[tauri::command]
+fn stop_recording_and_transcribe(app: AppHandle) -> Result<String, String> {
+    let state = app.state::<AppState>();
+    state
+        .session
+        .transition(&app, SessionState::Recording, SessionState::Transcribing)
+        .map_err(|_| "Not recording".to_string())?;
+    let result = stop_recording(&app).and_then(|(samples, dropouts)| {
+        state.dictation_dropouts.lock().add(dropouts, samples.len());
+        let mut dictation = take_pending_dictation(&state);
+        dictation.extend(samples);
+        report_dictation_dropouts(&app);
+        transcribe_dictation(&app, dictation)
+    });
+    state.session.reset(&app);
+    enforce_memory_cap(&app);
+    result.map(Transcript::into_text)
+}
+
+/// Transcribes a finished dictation and applies punctuation and retention.
+fn transcribe_dictation(app: &AppHandle, samples: Vec<f32>) -> Result<Transcript, String> {
+    let mut draft = transcribe_alternatives(app, samples, 1)?;
+    if draft.alternatives.is_empty() {
+        return Ok(Transcript::NoSpeech);
+    }
+    finish_draft(app, &mut draft, 0)?;
+    Ok(Transcript::Speech(draft.alternatives.swap_remove(0).text))
+}
+
+/// A dictation's alternative transcriptions, the best first, before
+/// plugins, the script and the pipeline have shaped the one delivered.
+struct Draft {
+    alternatives: Vec<TranscriptionResult>,
+    context: Option<FocusedWindow>,
+    sample_count: usize,
+    recording_id: Option<String>,
+}
+
+/// Transcribes a dictation into up to `count` alternative results, each's
+/// text shaped by punctuation, the text rules and replacements. A single
+/// result comes with segment timings; alternatives are decoded whole and
+/// have none. Only the best is retained. Empty when the audio has no speech.
+fn transcribe_alternatives(
+    app: &AppHandle,
+    samples: Vec<f32>,
+    count: usize,
+) -> Result<Draft, String> {
+    let state = app.state::<AppState>();
+
+    if samples.is_empty() {
+        return Err("No audio recorded".to_string());
+    }
+
+    let language = state.transcription_language.lock().clone();
+    let mut results = with_transcriber(&state, |transcriber| {
+        if count > 1 {
+            let texts = transcriber.alternatives(&samples, &language, count)?;
+            return Ok(texts
+                .into_iter()
+                .map(|text| TranscriptionResult::untimed(text, &samples, &language))
+                .collect());
+        }
+        let result = transcriber.transcribe_detailed(&samples, &language)?;
+        Ok(if result.text.is_empty() {
+            Vec::new()
+        } else {
+            vec![result]
+        })
+    })?;
+
+    let punctuation_mode = *state.punctuation_mode.lock();
+    if punctuation_mode.applies_to(&state.selected_model.lock()) {
+        for result in &mut results {
+            result.text = restore_punctuation(&result.text);
+        }
+    }
+
+    let context = state.recording_context.lock().take();
+    let mut recording_id = None;
+    if *state.retain_recordings.lock() {
+        let text = results.first().map_or("", |result| result.text.as_str());
+        match save_recording(&samples, text, context.clone()) {
+            Ok(recording) => recording_id = Some(recording.id),
+            Err(e) => eprintln!("Failed to retain recording: {}", e),
+        }
+    }
+
+    // Retained recordings keep the model's own words; the text rules,
+    // replacements, plugins and the pipeline only shape what gets delivered.
+    let rules = state.settings.lock().text_rules;
+    let (_, replacements) = state.settings.lock().dictionary();
+    for result in &mut results {
+        let text = apply_rules(&result.text, &rules, result.language.as_deref());
+        result.text = apply_replacements(&text, &replacements);
+    }
+    Ok(Draft {
+        alternatives: results,
+        context,
+        sample_count: samples.len(),
+        recording_id,
+    })
+}
+
+/// Runs plugins, the script and the pipeline on alternative `index`, the one
+/// to deliver, and records it in history. They run once, however many
+/// alternatives there are, since they may call out to an LLM or the shell.
+fn finish_draft(app: &AppHandle, draft: &mut Draft, index: usize) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let model = state.selected_model.lock().key();
+    let allow_shell = state.settings.lock().script_shell;
+    let steps = state.settings.lock().pipeline.clone();
+    let context = draft.context.as_ref();
+    let result = &mut draft.alternatives[index];
+    let text = run_plugins(&PluginInput::new(&result.text, &model, context));
+    let text = run_script(&text, context, allow_shell).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        text
+    });
+    result.text = run_pipeline(&steps, &text, context)?;
+    let recording_id = draft.recording_id.as_deref();
+    record_history(app, result, draft.sample_count, recording_id);
+    Ok(())
+}
 
+fn record_history(
+    app: &AppHandle,
+    result: &TranscriptionResult,
+    sample_count: usize,
+    recording_id: Option<&str>,
+) {
+    let state = app.state::<AppState>();
+    if !state.settings.lock().history {
+        return;
+    }
+
+    let duration_ms = sample_count as u64 * 1000 / WHISPER_SAMPLE_RATE as u64;
+    let model = state.selected_model.lock().key();
+    let device = state.selected_device.lock().clone();
+    if let Some(history) = state.history.lock().as_ref() {
+        if let Err(e) = history.record(
+            result,
+            duration_ms,
+            &model,
+            device.as_deref(),
+            recording_id,
+            None,
+        ) {
+            eprintln!("{}", e);
+        }
+    }
+    tray::show_recent(app);
+}
+
+fn with_history<T>(
+    app: &AppHandle,
+    f: impl FnOnce(&History) -> Result<T, String>,
+) -> Result<T, String> {
+    let state = app.state::<AppState>();
+    let history = state.history.lock();
+    let history = history
+        .as_ref()
+        .ok_or_else(|| "History is unavailable".to_string())?;
+    f(history)
+}
+
+/// Applies `change` to a copy of the settings and saves it, swapping it in
+/// only once it is on disk. Nothing is saved if `change` fails.
+fn update_settings<T>(
+    app: &AppHandle,
+    change: impl FnOnce(&mut Settings) -> Result<T, String>,
+) -> Result<T, String> {
+    let state = app.state::<AppState>();
+    let mut settings = state.settings.lock();
+    let mut updated = settings.clone();
+    let result = change(&mut updated)?;
+    save_settings(&updated)?;
+    *settings = updated;
+    Ok(result)
+}
+
+/// How often the live preview is refreshed while recording.
+const PARTIAL_INTERVAL: Duration = Duration::from_millis(1500);
+
+/// The preview transcribes at most whisper's 30 second window of the latest
+/// audio, so each pass stays cheap however long the recording runs.
+const PARTIAL_WINDOW_SECS: usize = 30;
+
+/// Re-transcribes the latest audio while recording and emits it as
+/// `transcription-partial`. The final text still comes from a full pass.
+fn spawn_partial_transcription(app: AppHandle) {
+    std::thread::spawn(move || {
+        let state = app.state::<AppState>();
+        loop {
+            std::thread::sleep(PARTIAL_INTERVAL);
+            if !state.session.is_recording() {
+                break;
+            }
+
+            let snapshot = match state.recorder.lock().as_ref() {
+                Some(recorder) => {
+                    recorder.snapshot(PARTIAL_WINDOW_SECS * recorder.sample_rate() as usize)
+                }
+                None => break,
+            };
+            let Ok(recorded) = snapshot else { break };
+            let pipeline = state.settings.lock().audio;
+            let samples =
+                pipeline.process(recorded.samples, recorded.sample_rate, recorded.channels);
+
+            let translation = state.caption_translation.lock().clone();
+            let language = state.transcription_language.lock().clone();
+            let partial = with_transcriber(&state, |transcriber| match &translation {
+                Some(translation) => translation.transcribe(transcriber, &samples),
+                None => transcriber.transcribe(&samples, &language),
+            });
+
+            // Recording may have ended during the pass; the final result wins.
+            if !state.session.is_recording() {
+                break;
+            }
+            match partial {
+                Ok(Transcript::Speech(text)) => {
+                    let caption = match &translation {
+                        Some(translation) => translation.finish(text),
+                        None => Ok(text),
+                    };
+                    match caption {
+                        // The LLM round trip may outlast the recording too.
+                        Ok(_) if !state.session.is_recording() => break,
+                        Ok(caption) => {
+                            send_obs_caption(&app, &caption);
+                            if translation.is_some()
+                                || state.settings.lock().streaming_transcription
+                            {
+                                let _ = emit(&app, "transcription-partial", caption);
+                            }
+                        }
+                        Err(e) => eprintln!("Caption translation failed: {}", e),
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("Partial transcription failed: {}", e);
+                    break;
+                }
+            }
+        }
+    });
+}
+
+/// Pushes a caption to OBS when captions are enabled, without waiting for
+/// it to arrive.
+fn send_obs_caption(app: &AppHandle, text: &str) {
+    let state = app.state::<AppState>();
+    let config = state.settings.lock().obs.clone();
+    state.obs_captions.send(config, text);
+}
+
+#[tauri::command]
+fn paste_text(app: AppHandle, text: String) -> Result<(), String> {
+    app.clipboard()
+        .write_text(&text)
+        .map_err(|e| format!("Failed to copy to clipboard: {}", e))?;
+
+    println!("Text copied to clipboard: {}", text);
+
+    // Text is in clipboard - user can paste with Cmd+V
+    // Auto-paste disabled for stability - requires accessibility permissions
+
+    Ok(())
+}
+
+#[tauri::command]
+fn repaste_last_transcription(app: AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let text = state
+        .last_transcription
+        .lock()
+        .clone()
+        .ok_or_else(|| "Nothing has been transcribed yet".to_string())?;
+
+    let debounce = Duration::from_millis(state.settings.lock().output_debounce_ms);
+    state.output.run(debounce, true, || {
+        paste_text(app.clone(), text)?;
+        if !state.permissions.lock().accessibility {
+            return Err(ACCESSIBILITY_DENIED.to_string());
+        }
+        send_paste_keystroke()
+    })
+}
+
+/// Sends a finished transcription wherever the pipeline's output steps say,
+/// the clipboard by default.
+fn deliver_text(app: &AppHandle, text: &str) -> Result<(), String> {
+    let (steps, rtl_marks, debounce_ms) = {
+        let settings = app.state::<AppState>().settings.lock();
+        (
+            settings.pipeline.clone(),
+            settings.rtl_marks,
+            settings.output_debounce_ms,
+        )
+    };
+    // Files and commands get the text as is; marks are for apps on screen.
+    let pasted = if rtl_marks {
+        mark_rtl(text)
+    } else {
+        text.to_string()
+    };
+    let targets = output_targets(&steps);
+    let keystrokes = targets
+        .iter()
+        .any(|target| matches!(target, OutputTarget::Paste | OutputTarget::Type));
+    let debounce = Duration::from_millis(debounce_ms);
+    app.state::<AppState>()
+        .output
+        .run(debounce, keystrokes, || deliver_to(app, targets, text, &pasted))
+}
+
+/// Sends the text to each target in turn; `pasted` is the version for apps
+/// on screen.
+fn deliver_to(
+    app: &AppHandle,
+    targets: Vec<OutputTarget>,
+    text: &str,
+    pasted: &str,
+) -> Result<(), String> {
+    for target in targets {
+        match target {
+            OutputTarget::Clipboard => paste_text(app.clone(), pasted.to_string())?,
+            OutputTarget::Paste => {
+                paste_text(app.clone(), pasted.to_string())?;
+                if !app.state::<AppState>().permissions.lock().accessibility {
+                    return Err(ACCESSIBILITY_DENIED.to_string());
+                }
+                send_paste_keystroke()?;
+            }
+            OutputTarget::Type => {
+                if !app.state::<AppState>().permissions.lock().accessibility {
+                    return Err(ACCESSIBILITY_DENIED.to_string());
+                }
+                // An input method would compose the keystrokes, so paste.
+                if ime_active() {
+                    paste_text(app.clone(), pasted.to_string())?;
+                    send_paste_keystroke()?;
+                } else {
+                    type_text(pasted)?;
+                }
+            }
+            OutputTarget::File { path } => append_to_file(std::path::Path::new(&path), text)?,
+            OutputTarget::Command { command } => {
+                let confirm = app.state::<AppState>().settings.lock().confirm_commands;
+                if confirm && !confirm_command(app, &command, text) {
+                    println!("Skipped command output: {}", command);
+                    continue;
+                }
+                pipe_to_command(&command, text)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// How long a command confirmation waits for an answer before declining.
+const COMMAND_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Serialize)]
+struct CommandConfirmation {
+    command: String,
+    text: String,
+}
+
+/// Asks the frontend whether to run an output command and blocks until it
+/// answers through `respond_command_confirmation`.
+fn confirm_command(app: &AppHandle, command: &str, text: &str) -> bool {
+    let (response_tx, response_rx) = channel();
+    *app.state::<AppState>().command_confirmation.lock() = Some(response_tx);
+
+    if let Some(window) = app.get_webview_window("settings") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    let _ = emit(
+        app,
+        "confirm-command",
+        CommandConfirmation {
+            command: command.to_string(),
+            text: text.to_string(),
+        },
+    );
+
+    let approved = response_rx
+        .recv_timeout(COMMAND_CONFIRMATION_TIMEOUT)
+        .unwrap_or(false);
+    app.state::<AppState>().command_confirmation.lock().take();
+    approved
+}
+
+#[tauri::command]
+fn respond_command_confirmation(app: AppHandle, approved: bool) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let response_tx = state
+        .command_confirmation
+        .lock()
+        .take()
+        .ok_or_else(|| "No command is waiting for confirmation".to_string())?;
+    let _ = response_tx.send(approved);
     Ok(())
 }
 
 #[tauri::command]
-fn init_transcriber(app: AppHandle) -> Result<(), String> {
-    let model_path = get_model_path(ModelSize::Small);
-    if !model_path.exists() {
-        return Err("Model not downloaded".to_string());
-    }
-
-    let transcriber = WhisperTranscriber::new(&model_path)?;
+fn get_rtl_marks(app: AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = state.settings.lock().rtl_marks;
+    enabled
+}
+
+#[tauri::command]
+fn set_rtl_marks(app: AppHandle, enabled: bool) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.rtl_marks = enabled;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn get_no_speech_feedback(app: AppHandle) -> NoSpeechFeedback {
+    let state = app.state::<AppState>();
+    let feedback = state.settings.lock().no_speech;
+    feedback
+}
+
+#[tauri::command]
+fn set_no_speech_feedback(app: AppHandle, feedback: NoSpeechFeedback) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.no_speech = feedback;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn get_confirm_commands(app: AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = state.settings.lock().confirm_commands;
+    enabled
+}
+
+#[tauri::command]
+fn set_confirm_commands(app: AppHandle, enabled: bool) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.confirm_commands = enabled;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn get_wake_word(app: AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = state.settings.lock().wake_word;
+    enabled
+}
+
+/// Turns listening for the wake phrase on or off. The microphone opens or
+/// closes within a moment.
+#[tauri::command]
+fn set_wake_word(app: AppHandle, enabled: bool) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.wake_word = enabled;
+        Ok(())
+    })
+}
+
+/// Dictations shorter than this would be cut off mid-sentence.
+const MIN_RECORDING_LIMIT_SECS: u64 = 10;
+
+#[tauri::command]
+fn get_max_recording_duration(app: AppHandle) -> Option<u64> {
+    let state = app.state::<AppState>();
+    let max_secs = state.settings.lock().max_recording_secs;
+    max_secs
+}
+
+/// Sets how many seconds a dictation may record before it is stopped and
+/// transcribed; `None` for no limit. Applies from the next dictation.
+#[tauri::command]
+fn set_max_recording_duration(app: AppHandle, secs: Option<u64>) -> Result<(), String> {
+    if secs.is_some_and(|secs| secs < MIN_RECORDING_LIMIT_SECS) {
+        return Err(format!(
+            "The limit must be at least {} seconds",
+            MIN_RECORDING_LIMIT_SECS
+        ));
+    }
+    update_settings(&app, |settings| {
+        settings.max_recording_secs = secs;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn get_preroll_ms(app: AppHandle) -> u64 {
+    let state = app.state::<AppState>();
+    let preroll_ms = state.settings.lock().preroll_ms;
+    preroll_ms
+}
+
+/// Sets how much audio from just before the shortcut registers opens each
+/// recording. Any pre-roll keeps the microphone open while idle; 0 turns it
+/// off.
+#[tauri::command]
+fn set_preroll_ms(app: AppHandle, ms: u64) -> Result<(), String> {
+    if Duration::from_millis(ms) > MAX_PREROLL {
+        return Err(format!(
+            "The pre-roll can be at most {} ms",
+            MAX_PREROLL.as_millis()
+        ));
+    }
+    update_settings(&app, |settings| {
+        settings.preroll_ms = ms;
+        Ok(())
+    })?;
+    apply_preroll(&app);
+    Ok(())
+}
+
+/// Opens the selected input device, holding no more of a recording in
+/// memory than the settings allow.
+fn open_recorder(state: &AppState) -> Result<AudioRecorder, String> {
+    let device = state.selected_device.lock().clone();
+    let recorder = new_recorder(state, device.as_deref())?;
+    let limit_mb = state.settings.lock().recording_memory_mb;
+    recorder.set_memory_limit(limit_mb.and_then(megabytes_to_bytes))?;
+    Ok(recorder)
+}
+
+/// `megabytes` in bytes, or `None` if that doesn't fit in a `usize`.
+fn megabytes_to_bytes(megabytes: u64) -> Option<usize> {
+    megabytes
+        .checked_mul(1024 * 1024)
+        .and_then(|bytes| usize::try_from(bytes).ok())
+}
+
+/// Opens `device`, or the network microphone as paired.
+fn new_recorder(state: &AppState, device: Option<&str>) -> Result<AudioRecorder, String> {
+    if device == Some(NETWORK_MICROPHONE_DEVICE) {
+        return Ok(AudioRecorder::network(network_microphone(state)?));
+    }
+    AudioRecorder::new(device)
+}
+
+/// How the network microphone listens, pairing it with a fresh SSRC the
+/// first time.
+fn network_microphone(state: &AppState) -> Result<NetworkMicrophone, String> {
+    let mut settings = state.settings.lock();
+    let ssrc = settings
+        .network_microphone_ssrc
+        .unwrap_or_else(generate_ssrc);
+    if settings.network_microphone_ssrc.is_none() {
+        let updated = Settings {
+            network_microphone_ssrc: Some(ssrc),
+            ..settings.clone()
+        };
+        save_settings(&updated)?;
+        *settings = updated;
+    }
+    Ok(NetworkMicrophone {
+        address: settings
+            .network_microphone_address
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+        ssrc,
+    })
+}
+
+/// The address the network microphone listens on, its port, and the SSRC
+/// a sender has to stream with.
+#[tauri::command]
+fn get_network_microphone(app: AppHandle) -> Result<(String, u16, u32), String> {
+    let state = app.state::<AppState>();
+    let microphone = network_microphone(&state)?;
+    Ok((
+        microphone.address.to_string(),
+        NETWORK_MICROPHONE_PORT,
+        microphone.ssrc,
+    ))
+}
+
+/// Listens for the network microphone on `address` only, such as the
+/// machine's Tailscale address, or on every interface for `None`.
+#[tauri::command]
+fn set_network_microphone_address(app: AppHandle, address: Option<String>) -> Result<(), String> {
+    let address = address
+        .map(|address| {
+            address
+                .trim()
+                .parse::<IpAddr>()
+                .map_err(|e| format!("Invalid address {}: {}", address, e))
+        })
+        .transpose()?;
+    let state = app.state::<AppState>();
+    update_settings(&app, |settings| {
+        settings.network_microphone_address = address;
+        Ok(())
+    })?;
+    if state.selected_device.lock().as_deref() == Some(NETWORK_MICROPHONE_DEVICE) {
+        *state.recorder.lock() = None;
+        apply_preroll(&app);
+    }
+    Ok(())
+}
+
+/// Hands the pre-roll setting to the recorder, opening one ahead of the
+/// first recording so there is audio to keep before it too.
+fn apply_preroll(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let preroll_ms = state.settings.lock().preroll_ms;
+    let mut recorder = state.recorder.lock();
+    if recorder.is_none() && preroll_ms > 0 && state.permissions.lock().microphone {
+        match open_recorder(&state) {
+            Ok(opened) => *recorder = Some(opened),
+            Err(e) => eprintln!("Failed to open the microphone for pre-roll: {}", e),
+        }
+    }
+    if let Some(recorder) = recorder.as_ref() {
+        if let Err(e) = recorder.set_preroll_ms(preroll_ms) {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+#[tauri::command]
+fn get_output_debounce(app: AppHandle) -> u64 {
+    let state = app.state::<AppState>();
+    let debounce_ms = state.settings.lock().output_debounce_ms;
+    debounce_ms
+}
+
+#[tauri::command]
+fn set_output_debounce(app: AppHandle, debounce_ms: u64) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.output_debounce_ms = debounce_ms;
+        Ok(())
+    })
+}
+
+/// Pastes the clipboard into the focused app with ⌘V, or Ctrl+V on Windows.
+/// Requires accessibility permission on macOS; elsewhere the text is left on
+/// the clipboard.
+fn send_paste_keystroke() -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        // `keystroke "v"` goes through the input source, which an active
+        // input method turns into composition; the V key code bypasses it.
+        let script = if ime_active() {
+            "tell application \"System Events\" to key code 9 using command down"
+        } else {
+            "tell application \"System Events\" to keystroke \"v\" using command down"
+        };
+        let status = std::process::Command::new("osascript")
+            .args(["-e", script])
+            .status()
+            .map_err(|e| format!("Failed to send paste keystroke: {}", e))?;
+        if !status.success() {
+            return Err("Paste keystroke rejected - check accessibility permission".to_string());
+        }
+    }
+    #[cfg(target_os = "windows")]
+    voice_core::typing::send_paste()?;
+    Ok(())
+}
+
+/// Input devices by name, with "System Audio" last where the machine can
+/// record its own output and the consent settings allow it.
+#[tauri::command]
+fn get_audio_devices(app: AppHandle) -> Result<Vec<String>, String> {
+    let mut devices = list_input_devices()?;
+    if !system_audio_allowed(&app) {
+        devices.retain(|device| device != SYSTEM_AUDIO_DEVICE);
+    }
+    Ok(devices)
+}
+
+fn system_audio_allowed(app: &AppHandle) -> bool {
+    let allowed = app.state::<AppState>().settings.lock().consent.allow_system_audio;
+    allowed
+}
+
+#[tauri::command]
+fn get_current_device(app: AppHandle) -> Option<String> {
+    let state = app.state::<AppState>();
+    let device = state.selected_device.lock().clone();
+    device
+}
+
+#[tauri::command]
+fn set_audio_device(app: AppHandle, device_name: Option<String>) -> Result<(), String> {
+    if device_name.as_deref() == Some(SYSTEM_AUDIO_DEVICE) && !system_audio_allowed(&app) {
+        return Err("System audio capture is turned off in the consent settings".to_string());
+    }
+    let state = app.state::<AppState>();
+    *state.selected_device.lock() = device_name;
+    *state.recorder.lock() = None;
+    apply_preroll(&app);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_model_info(app: AppHandle) -> (String, bool) {
+    let state = app.state::<AppState>();
+    let model = state.selected_model.lock().clone();
+    (model.key(), is_installed(&app, &model))
+}
+
+/// Each model's key, label, whether it is downloaded, and its size in bytes.
+#[tauri::command]
+fn get_available_models(app: AppHandle) -> Vec<(String, String, bool, u64)> {
+    let state = app.state::<AppState>();
+    let mut models: Vec<(String, String, bool, u64)> = state
+        .model_manifest
+        .lock()
+        .models
+        .iter()
+        .filter(|entry| ModelId::parse(&entry.key).is_ok())
+        .map(|entry| {
+            (
+                entry.key.clone(),
+                entry.name.clone(),
+                entry.is_downloaded(),
+                entry.size,
+            )
+        })
+        .collect();
+
+    for pack in list_packs() {
+        let label = match &pack.language {
+            Some(language) => format!("{} ({})", pack.name, language),
+            None => pack.name.clone(),
+        };
+        let model = ModelId::Pack(pack.slug);
+        let size = std::fs::metadata(model.path()).map(|m| m.len()).unwrap_or(0);
+        models.push((model.key(), label, true, size));
+    }
+
+    models
+}
+
+#[tauri::command]
+async fn set_model_size(app: AppHandle, size: String) -> Result<(), String> {
+    let model = ModelId::parse(&size)?;
+    if let ModelId::Catalog(key) = &model {
+        if !is_installed(&app, &model) {
+            manifest_entry(&app, key)?;
+        }
+    }
+
+    let state = app.state::<AppState>();
+    *state.selected_model.lock() = model.clone();
+
+    if is_installed(&app, &model) {
+        let transcriber = WhisperTranscriber::new(&model.path(), use_gpu(&app))?;
+        *state.transcriber.lock() = Some(transcriber);
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn download_model_size(app: AppHandle, size: String) -> Result<(), String> {
+    let model = ModelId::parse(&size)?;
+    let entry = manifest_entry(&app, &model.key())?;
+
+    let config = app.state::<AppState>().settings.lock().download.clone();
+    let app_clone = app.clone();
+    let key = size.clone();
+    let model_path = cancellable_download(&app, &key, |cancel| async move {
+        download_model(&entry, &config, &cancel, move |downloaded, total| {
+            let _ = emit(&app_clone, "model-download-progress", (size.clone(), downloaded, total));
+        })
+        .await
+    })
+    .await?;
+
+    let state = app.state::<AppState>();
+    if *state.selected_model.lock() == model {
+        let transcriber = WhisperTranscriber::new(&model_path, use_gpu(&app))?;
+        *state.transcriber.lock() = Some(transcriber);
+    }
+
+    Ok(())
+}
+
+/// Whisper.cpp decodes with at most this many candidates.
+const MAX_BEAM_SIZE: u32 = 8;
+
+/// How a model decodes: greedily or with beam search, as chosen for it with
+/// `set_model_decoding` or else by default for its size.
+#[tauri::command]
+fn get_model_decoding(app: AppHandle, model: String) -> Result<Decoding, String> {
+    let model = ModelId::parse(&model)?;
+    let state = app.state::<AppState>();
+    let manifest = state.model_manifest.lock();
+    let decoding = state.settings.lock().decoding_for(&model, &manifest);
+    Ok(decoding)
+}
+
+/// Sets how a model decodes, or with `None` goes back to its default.
+#[tauri::command]
+fn set_model_decoding(
+    app: AppHandle,
+    model: String,
+    decoding: Option<Decoding>,
+) -> Result<(), String> {
+    let model = ModelId::parse(&model)?;
+    if let Some(Decoding::BeamSearch { beam_size }) = decoding {
+        if !(1..=MAX_BEAM_SIZE).contains(&beam_size) {
+            return Err(format!("The beam size must be from 1 to {}", MAX_BEAM_SIZE));
+        }
+    }
+    update_settings(&app, |settings| {
+        match decoding {
+            Some(decoding) => settings.decoding.insert(model.key(), decoding),
+            None => settings.decoding.remove(&model.key()),
+        };
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn get_transcription_language(app: AppHandle) -> String {
+    let state = app.state::<AppState>();
+    let language = state.transcription_language.lock().key().to_string();
+    language
+}
+
+/// Takes an ISO 639-1 code such as "sv", or "auto" to detect the language.
+#[tauri::command]
+fn set_transcription_language(app: AppHandle, language: String) -> Result<(), String> {
+    let language = TranscriptionLanguage::parse(&language)?;
+    let state = app.state::<AppState>();
+    *state.transcription_language.lock() = language;
+    Ok(())
+}
+
+#[tauri::command]
+fn get_download_config(app: AppHandle) -> DownloadConfig {
+    let state = app.state::<AppState>();
+    let config = state.settings.lock().download.clone();
+    config
+}
+
+/// Keeps the mirror's token or password in the credential store; an empty
+/// one keeps what was stored before.
+#[tauri::command]
+fn set_download_config(app: AppHandle, mut config: DownloadConfig) -> Result<(), String> {
+    config.validate()?;
+    match &mut config.auth {
+        Some(auth) => {
+            store_or_load_download_secret(auth)?;
+        }
+        None => secrets::delete(secrets::DOWNLOAD_CREDENTIAL)?,
+    }
+    update_settings(&app, |settings| {
+        settings.download = config;
+        Ok(())
+    })
+}
+
+/// Whether the secret was in `auth` and has been stored, rather than loaded.
+fn store_or_load_download_secret(auth: &mut DownloadAuth) -> Result<bool, String> {
+    match auth {
+        DownloadAuth::Bearer { token } => {
+            let mut secret = Some(std::mem::take(token));
+            let stored = secrets::store_or_load(secrets::DOWNLOAD_CREDENTIAL, &mut secret);
+            *token = secret.unwrap_or_default();
+            stored
+        }
+        DownloadAuth::Basic { password, .. } => {
+            secrets::store_or_load(secrets::DOWNLOAD_CREDENTIAL, password)
+        }
+    }
+}
+
+#[tauri::command]
+async fn install_language_pack(
+    app: AppHandle,
+    name: String,
+    language: Option<String>,
+    source: String,
+) -> Result<LanguagePack, String> {
+    let app_clone = app.clone();
+    let progress_key = ModelId::Pack(pack_slug(&name)).key();
+    let key = progress_key.clone();
+    cancellable_download(&app, &key, |cancel| async move {
+        install_pack(&name, language, &source, &cancel, move |downloaded, total| {
+            let _ = emit(
+                &app_clone,
+                "model-download-progress",
+                (progress_key.clone(), downloaded, total),
+            );
+        })
+        .await
+    })
+    .await
+}
+
+/// Imports a ggml model from disk, e.g. a private fine-tune. It is listed
+/// and selected like a language pack, as `pack:<slug>`.
+#[tauri::command]
+async fn import_model(
+    path: String,
+    name: Option<String>,
+    link: bool,
+) -> Result<LanguagePack, String> {
+    actions::blocking(move || {
+        voice_core::transcription::import_model(Path::new(&path), name.as_deref(), link)
+    })
+    .await
+}
+
+#[tauri::command]
+fn get_language_packs() -> Vec<LanguagePack> {
+    list_packs()
+}
+
+#[tauri::command]
+fn remove_language_pack(app: AppHandle, slug: String) -> Result<(), String> {
+    release_model(&app, &ModelId::Pack(slug.clone()))?;
+    remove_pack(&slug)
+}
+
+/// Unloads `model` if it is the one in use so its file can be removed,
+/// falling back to the default model rather than leaving a dangling
+/// selection.
+fn release_model(app: &AppHandle, model: &ModelId) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    if *state.selected_model.lock() != *model {
+        return Ok(());
+    }
+
+    *state.transcriber.lock() = None;
+    *state.model_unloaded.lock() = false;
+    let fallback = ModelId::default();
+    if fallback != *model && is_installed(app, &fallback) {
+        let transcriber = WhisperTranscriber::new(&fallback.path(), use_gpu(app))?;
+        *state.transcriber.lock() = Some(transcriber);
+    }
+    *state.selected_model.lock() = fallback;
+    Ok(())
+}
+
+#[tauri::command]
+fn get_installed_models(app: AppHandle) -> Vec<InstalledModel> {
+    let state = app.state::<AppState>();
+    let manifest = state.model_manifest.lock();
+    list_installed_models(&manifest)
+}
+
+#[tauri::command]
+fn delete_model(app: AppHandle, key: String) -> Result<(), String> {
+    let model = ModelId::parse(&key)?;
+    release_model(&app, &model)?;
+    voice_core::transcription::delete_model(&model)
+}
+
+#[derive(Serialize)]
+struct SharedModelDir {
+    path: PathBuf,
+    /// Whether this account can add models; otherwise downloads go to its
+    /// own models folder.
+    writable: bool,
+}
+
+#[tauri::command]
+fn get_shared_model_dir() -> Option<SharedModelDir> {
+    shared_model_dir().map(|path| SharedModelDir {
+        writable: is_writable(&path),
+        path,
+    })
+}
+
+/// Points Voice at a folder of models shared by every account, such as
+/// `/Users/Shared/Voice/models`, or stops using one with `None`. Models
+/// already in this user's folder keep working.
+#[tauri::command]
+fn set_shared_model_dir(app: AppHandle, path: Option<PathBuf>) -> Result<(), String> {
+    if let Some(path) = &path {
+        if !path.is_dir() {
+            return Err(format!("Not a folder: {}", path.display()));
+        }
+    }
+
+    update_settings(&app, |settings| {
+        settings.shared_model_dir = path.clone();
+        Ok(())
+    })?;
+    voice_core::transcription::set_shared_model_dir(path);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_text_rules(app: AppHandle) -> TextRules {
+    let state = app.state::<AppState>();
+    let rules = state.settings.lock().text_rules;
+    rules
+}
+
+#[tauri::command]
+fn set_text_rules(app: AppHandle, rules: TextRules) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.text_rules = rules;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn list_replacements(app: AppHandle) -> Vec<Replacement> {
+    let state = app.state::<AppState>();
+    let (_, replacements) = state.settings.lock().dictionary();
+    replacements
+}
+
+fn save_replacements(app: &AppHandle, replacements: Vec<Replacement>) -> Result<(), String> {
+    update_settings(app, |settings| {
+        settings.replacements = replacements;
+        Ok(())
+    })
+}
+
+/// Adds a replacement, or changes what an existing `find` is replaced with.
+#[tauri::command]
+fn add_replacement(app: AppHandle, find: String, with: String) -> Result<(), String> {
+    let find = find.trim().to_string();
+    if find.is_empty() {
+        return Err("Replacement needs a word or phrase to find".to_string());
+    }
+
+    let key = find.to_lowercase();
+    let mut replacements = list_replacements(app.clone());
+    replacements.retain(|replacement| replacement.find.to_lowercase() != key);
+    replacements.push(Replacement { find, with });
+    save_replacements(&app, replacements)
+}
+
+#[tauri::command]
+fn remove_replacement(app: AppHandle, find: String) -> Result<(), String> {
+    let key = find.trim().to_lowercase();
+    let mut replacements = list_replacements(app.clone());
+    let count = replacements.len();
+    replacements.retain(|replacement| replacement.find.to_lowercase() != key);
+    if replacements.len() == count {
+        return Err(format!("No replacement for \"{}\"", find));
+    }
+    save_replacements(&app, replacements)
+}
+
+#[tauri::command]
+fn get_punctuation_mode(app: AppHandle) -> PunctuationMode {
+    let state = app.state::<AppState>();
+    let mode = *state.punctuation_mode.lock();
+    mode
+}
+
+#[tauri::command]
+fn set_punctuation_mode(app: AppHandle, mode: PunctuationMode) {
+    let state = app.state::<AppState>();
+    *state.punctuation_mode.lock() = mode;
+}
+
+#[tauri::command]
+fn get_pipeline(app: AppHandle) -> Vec<PipelineStep> {
+    let state = app.state::<AppState>();
+    let pipeline = state.settings.lock().pipeline.clone();
+    pipeline
+}
+
+#[tauri::command]
+fn set_pipeline(app: AppHandle, steps: Vec<PipelineStep>) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.pipeline = steps;
+        settings.validate()
+    })
+}
+
+#[tauri::command]
+fn get_script() -> Option<String> {
+    read_script()
+}
+
+#[tauri::command]
+fn set_script(source: String) -> Result<(), String> {
+    write_script(&source)
+}
+
+#[tauri::command]
+fn get_script_shell_enabled(app: AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = state.settings.lock().script_shell;
+    enabled
+}
+
+#[tauri::command]
+fn set_script_shell_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.script_shell = enabled;
+        Ok(())
+    })
+}
+
+/// Whether the JSON-RPC protocol is served for other frontends.
+#[tauri::command]
+fn get_rpc_server_enabled(app: AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = state.settings.lock().rpc_server;
+    enabled
+}
+
+#[tauri::command]
+fn set_rpc_server_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+    rpc::set_enabled(&app, enabled)
+}
+
+#[tauri::command]
+fn get_remote_control_enabled(app: AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = state.settings.lock().remote_control;
+    enabled
+}
+
+/// Turns the remote control page on or off. Phones paired before stay
+/// paired.
+#[tauri::command]
+fn set_remote_control_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+    remote::set_enabled(&app, enabled)
+}
+
+/// The address and QR code a phone pairs with.
+#[tauri::command]
+fn get_remote_pairing(app: AppHandle) -> Result<RemotePairing, String> {
+    remote::current_pairing(&app)
+}
+
+/// Pairs with a new token, unpairing every phone paired so far.
+#[tauri::command]
+fn reset_remote_pairing(app: AppHandle) -> Result<RemotePairing, String> {
+    remote::reset_pairing(&app)
+}
+
+/// Where captions are sent in OBS, and whether they are.
+#[tauri::command]
+fn get_obs_config(app: AppHandle) -> ObsConfig {
+    let state = app.state::<AppState>();
+    let config = state.settings.lock().obs.clone();
+    config
+}
+
+/// The password goes to the OS credential store; leaving it out keeps the
+/// one stored before.
+#[tauri::command]
+fn set_obs_config(app: AppHandle, mut config: ObsConfig) -> Result<(), String> {
+    config.validate()?;
+    secrets::store_or_load(secrets::OBS_PASSWORD, &mut config.password)?;
+    update_settings(&app, |settings| {
+        settings.obs = config;
+        Ok(())
+    })
+}
+
+/// History, newest first, 50 at a time by default. With a `source` such as
+/// `"memo"` or `"meeting"`, only the entries from there.
+#[tauri::command]
+fn get_history(
+    app: AppHandle,
+    limit: Option<u32>,
+    offset: Option<u32>,
+    source: Option<String>,
+) -> Result<Vec<HistoryEntry>, String> {
+    with_history(&app, |history| {
+        history.list(limit.unwrap_or(50), offset.unwrap_or(0), source.as_deref())
+    })
+}
+
+#[tauri::command]
+fn search_history(
+    app: AppHandle,
+    query: String,
+    limit: Option<u32>,
+) -> Result<Vec<HistoryEntry>, String> {
+    with_history(&app, |history| history.search(&query, limit.unwrap_or(50)))
+}
+
+#[tauri::command]
+fn delete_history_entry(app: AppHandle, id: i64) -> Result<(), String> {
+    with_history(&app, |history| history.delete(id))?;
+    tray::show_recent(&app);
+    Ok(())
+}
+
+#[tauri::command]
+fn clear_history(app: AppHandle) -> Result<(), String> {
+    with_history(&app, |history| history.clear())?;
+    tray::show_recent(&app);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_history_enabled(app: AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = state.settings.lock().history;
+    enabled
+}
+
+#[tauri::command]
+fn set_history_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.history = enabled;
+        Ok(())
+    })?;
+    tray::show_recent(&app);
+    Ok(())
+}
+
+/// The GPU acceleration this build offers, for the settings window.
+#[derive(Serialize)]
+struct GpuCapability {
+    /// "CUDA" or "Metal"; `None` for CPU-only builds.
+    backend: Option<&'static str>,
+    /// Built to run the encoder through Core ML.
+    core_ml: bool,
+    /// The selected model has a compiled Core ML encoder beside it.
+    core_ml_encoder: bool,
+    enabled: bool,
+    /// How the CPU side of whisper.cpp runs, GPU or not.
+    cpu: &'static ComputeConfig,
+}
+
+#[tauri::command]
+fn get_gpu_capability(app: AppHandle) -> GpuCapability {
+    let state = app.state::<AppState>();
+    let core_ml_encoder = state.selected_model.lock().core_ml_encoder_path().exists();
+    GpuCapability {
+        backend: gpu_backend(),
+        core_ml: core_ml_supported(),
+        core_ml_encoder,
+        enabled: use_gpu(&app),
+        cpu: compute_config(),
+    }
+}
+
+/// Files are transcribed in chunks of at most this long, so progress moves
+/// and a dictation can run between them.
+const FILE_CHUNK_SECS: f32 = 30.0;
+
+/// Transcribes a WAV, MP3, M4A or FLAC file in the transcription language,
+/// emitting "file-transcription-progress" with the path and the seconds
+/// done and in total after each chunk. The text rules and replacements
+/// apply as they do to dictation.
+#[tauri::command]
+async fn transcribe_file(app: AppHandle, path: String) -> Result<TranscriptionResult, String> {
+    actions::blocking(move || transcribe_audio_file(&app, Path::new(&path)))
+        .await
+        .map(|(result, _)| result)
+}
+
+/// The transcription of an audio file, with the file's length in
+/// milliseconds.
+fn transcribe_audio_file(
+    app: &AppHandle,
+    path: &Path,
+) -> Result<(TranscriptionResult, u64), String> {
+    let samples = decode_audio_file(path)?;
+    let state = app.state::<AppState>();
+    let language = state.transcription_language.lock().clone();
+    let rate = WHISPER_SAMPLE_RATE as f32;
+    let total = samples.len() as f32 / rate;
+    let key = path.to_string_lossy().into_owned();
+
+    let mut texts = Vec::new();
+    let mut segments = Vec::new();
+    let mut detected = None;
+    for chunk in split_at_pauses(&samples, FILE_CHUNK_SECS) {
+        let offset = chunk.start as f32 / rate;
+        let result = with_transcriber(&state, |transcriber| {
+            transcriber.transcribe_detailed(&samples[chunk.clone()], &language)
+        })?;
+        if !result.text.is_empty() {
+            texts.push(result.text);
+        }
+        segments.extend(result.segments.into_iter().map(|segment| Segment {
+            start: segment.start + offset,
+            end: segment.end + offset,
+            ..segment
+        }));
+        detected = detected.or(result.language);
+        let _ = emit(app, "file-transcription-progress", (&key, chunk.end as f32 / rate, total));
+    }
+
+    let rules = state.settings.lock().text_rules;
+    let (_, replacements) = state.settings.lock().dictionary();
+    let text = apply_rules(&texts.join(" "), &rules, detected.as_deref());
+    let result = TranscriptionResult {
+        text: apply_replacements(&text, &replacements),
+        segments,
+        language: detected,
+    };
+    Ok((result, (total * 1000.0) as u64))
+}
+
+/// Where `transcribe_directory` puts each file's transcription.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum BatchOutput {
+    /// A `.txt` file beside the audio, named after it. One already there is
+    /// kept and the new one numbered, e.g. `talk (2).txt`.
+    Files,
+    History,
+}
+
+#[derive(Serialize)]
+struct BatchFile {
+    path: String,
+    text: Option<String>,
+    error: Option<String>,
+}
+
+/// Transcribes every WAV, MP3, M4A and FLAC file in `dir`, not its
+/// subfolders, one at a time in name order. "batch-transcription-progress"
+/// gives the index, count and path of each file as it starts. A file that
+/// fails is reported and the rest carry on.
+#[tauri::command]
+async fn transcribe_directory(
+    app: AppHandle,
+    dir: String,
+    output: BatchOutput,
+) -> Result<Vec<BatchFile>, String> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .map_err(|e| format!("Failed to read folder: {}", e))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                    .is_some_and(|extension| {
+                        AUDIO_FILE_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+                    })
+        })
+        .collect();
+    paths.sort();
+    actions::blocking(move || Ok(transcribe_files(&app, &paths, output))).await
+}
+
+fn transcribe_files(app: &AppHandle, paths: &[PathBuf], output: BatchOutput) -> Vec<BatchFile> {
+    let count = paths.len();
+    let mut files = Vec::with_capacity(count);
+    for (index, path) in paths.iter().enumerate() {
+        let key = path.to_string_lossy().into_owned();
+        let _ = emit(app, "batch-transcription-progress", (index, count, &key));
+        let outcome = transcribe_audio_file(app, path).and_then(|(result, duration_ms)| {
+            save_batch_result(app, path, &result, duration_ms, output)?;
+            Ok(result.text)
+        });
+        files.push(match outcome {
+            Ok(text) => BatchFile {
+                path: key,
+                text: Some(text),
+                error: None,
+            },
+            Err(e) => BatchFile {
+                path: key,
+                text: None,
+                error: Some(e),
+            },
+        });
+    }
+    files
+}
+
+/// Creates the first of `talk.txt`, `talk (2).txt` and so on beside `path`
+/// that doesn't exist yet.
+fn create_transcript_file(path: &Path) -> std::io::Result<std::fs::File> {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let mut number = 1;
+    loop {
+        let name = match number {
+            1 => format!("{}.txt", stem),
+            n => format!("{} ({}).txt", stem, n),
+        };
+        let created = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path.with_file_name(name));
+        match created {
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => number += 1,
+            created => return created,
+        }
+    }
+}
+
+fn save_batch_result(
+    app: &AppHandle,
+    path: &Path,
+    result: &TranscriptionResult,
+    duration_ms: u64,
+    output: BatchOutput,
+) -> Result<(), String> {
+    match output {
+        BatchOutput::Files => create_transcript_file(path)
+            .and_then(|mut file| file.write_all(result.text.as_bytes()))
+            .map_err(|e| format!("Failed to write transcription: {}", e)),
+        BatchOutput::History => {
+            let model = app.state::<AppState>().selected_model.lock().key();
+            let source = path.to_string_lossy();
+            with_history(app, |history| {
+                history.record(result, duration_ms, &model, None, None, Some(&source))
+            })
+            .map(drop)
+        }
+    }
+}
+
+/// Asks where to save an export, starting in `suggested_path`'s folder with
+/// its name filled in. The system dialog confirms before replacing a file.
+/// `None` if it was cancelled.
+#[tauri::command]
+async fn choose_export_path(
+    app: AppHandle,
+    suggested_path: String,
+    extension: String,
+) -> Result<Option<String>, String> {
+    let filter = match extension.as_str() {
+        "srt" => "SubRip subtitles",
+        "vtt" => "WebVTT subtitles",
+        "md" => "Markdown",
+        _ => return Err(format!("Unknown export format: {}", extension)),
+    };
+    let suggested = Path::new(&suggested_path);
+    let mut dialog = app
+        .dialog()
+        .file()
+        .add_filter(filter, &[extension.as_str()]);
+    if let Some(window) = app.get_webview_window("settings") {
+        dialog = dialog.set_parent(&window);
+    }
+    if let Some(folder) = suggested.parent() {
+        dialog = dialog.set_directory(folder);
+    }
+    if let Some(name) = suggested.file_name() {
+        dialog = dialog.set_file_name(name.to_string_lossy());
+    }
+
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    dialog.save_file(move |path| {
+        let _ = sender.send(path);
+    });
+    match receiver.await {
+        Ok(Some(path)) => path
+            .into_path()
+            .map(|path| Some(path.to_string_lossy().into_owned()))
+            .map_err(|e| format!("Failed to save to the chosen file: {}", e)),
+        _ => Ok(None),
+    }
+}
+
+/// Writes a transcription's segments to `path` as SRT or WebVTT subtitles,
+/// marking where each chapter starts if it is long enough to have them.
+#[tauri::command]
+async fn export_subtitles(
+    app: AppHandle,
+    segments: Vec<Segment>,
+    format: SubtitleFormat,
+    path: String,
+) -> Result<(), String> {
+    actions::blocking(move || {
+        let chapters = chapters_for(&app, &segments);
+        std::fs::write(&path, format_subtitles(&segments, format, &chapters))
+            .map_err(|e| format!("Failed to write subtitles: {}", e))
+    })
+    .await
+}
+
+/// Writes a transcription to `path` as Markdown, with a heading and start
+/// time for each chapter if it is long enough to have them.
+#[tauri::command]
+async fn export_markdown(
+    app: AppHandle,
+    segments: Vec<Segment>,
+    path: String,
+) -> Result<(), String> {
+    actions::blocking(move || {
+        let chapters = chapters_for(&app, &segments);
+        std::fs::write(&path, format_markdown(&segments, &chapters))
+            .map_err(|e| format!("Failed to write transcript: {}", e))
+    })
+    .await
+}
+
+/// How much of a chapter the chapter LLM reads to name it.
+const CHAPTER_EXCERPT_CHARS: usize = 4000;
+
+const CHAPTER_TITLE_PROMPT: &str = "The user's message is part of a meeting or recording \
+     transcript. Reply with a title of at most six words for what it is about, in the \
+     language it is in, and nothing else.";
+
+/// Splits a long transcript into chapters at its longest pauses. With a
+/// chapter LLM set, it names them; if it fails they keep their opening words.
+fn chapters_for(app: &AppHandle, segments: &[Segment]) -> Vec<Chapter> {
+    let mut chapters = find_chapters(segments);
+    let llm = app.state::<AppState>().settings.lock().chapter_llm.clone();
+    let Some(llm) = llm else {
+        return chapters;
+    };
+    let texts = chapter_texts(segments, &chapters);
+    for (chapter, text) in chapters.iter_mut().zip(texts) {
+        let excerpt: String = text.chars().take(CHAPTER_EXCERPT_CHARS).collect();
+        let api_key = llm.api_key.as_deref();
+        match complete(
+            &llm.endpoint,
+            &llm.model,
+            CHAPTER_TITLE_PROMPT,
+            api_key,
+            &excerpt,
+        ) {
+            Ok(title) if !title.is_empty() => chapter.title = title.trim_matches('"').to_string(),
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Failed to name chapters: {}", e);
+                break;
+            }
+        }
+    }
+    chapters
+}
+
+#[tauri::command]
+fn get_chapter_llm(app: AppHandle) -> Option<LlmEndpoint> {
+    let state = app.state::<AppState>();
+    let llm = state.settings.lock().chapter_llm.clone();
+    llm
+}
+
+/// Sets the OpenAI-compatible endpoint that names chapters, or clears it so
+/// chapters are named after their opening words. The API key goes to the OS
+/// credential store; leaving it out keeps the one stored before.
+#[tauri::command]
+fn set_chapter_llm(app: AppHandle, mut llm: Option<LlmEndpoint>) -> Result<(), String> {
+    match &mut llm {
+        Some(llm) => {
+            llm.validate()?;
+            secrets::store_or_load(secrets::CHAPTER_LLM_API_KEY, &mut llm.api_key)?;
+        }
+        None => secrets::delete(secrets::CHAPTER_LLM_API_KEY)?,
+    }
+    update_settings(&app, |settings| {
+        settings.chapter_llm = llm;
+        Ok(())
+    })
+}
+
+/// The longest a quick memo records before it is filed.
+const MEMO_MAX_DURATION: Duration = Duration::from_secs(60);
+
+/// How often a recording memo checks whether it has been stopped.
+const MEMO_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The history source of quick memos.
+const MEMO_SOURCE: &str = "memo";
+
+/// Records a quick memo of up to `MEMO_MAX_DURATION`, or until
+/// `stop_quick_memo`, then transcribes it and files the audio and text in
+/// history with `memo` as the source. Nothing is pasted or copied, and only
+/// the cues say it is happening, so the app in front is left alone.
+#[tauri::command]
+fn start_quick_memo(app: AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    if !state.permissions.lock().microphone {
+        return Err(MICROPHONE_DENIED.to_string());
+    }
+    if !state.settings.lock().history {
+        return Err("Memos are kept in history, which is turned off".to_string());
+    }
+    state.session.transition(&app, SessionState::Idle, SessionState::Memo)?;
+
+    let started = {
+        let mut recorder = state.recorder.lock();
+        if recorder.is_none() {
+            match open_recorder(&state) {
+                Ok(opened) => *recorder = Some(opened),
+                Err(e) => {
+                    state.session.reset(&app);
+                    return Err(e);
+                }
+            }
+        }
+        match recorder.as_ref() {
+            Some(recorder) => recorder.start_recording(None),
+            None => Err("No recorder available".to_string()),
+        }
+    };
+    if let Err(e) = started {
+        state.session.reset(&app);
+        return Err(e);
+    }
+    let stop = CancelToken::default();
+    *state.memo.lock() = Some(stop.clone());
+    sounds::play(&app, Cue::RecordingStarted);
+    let _ = emit(&app, "memo-started", MEMO_MAX_DURATION.as_secs());
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let started = Instant::now();
+        while !stop.is_cancelled() && started.elapsed() < MEMO_MAX_DURATION {
+            std::thread::sleep(MEMO_POLL_INTERVAL);
+        }
+        let state = app.state::<AppState>();
+        state.memo.lock().take();
+        sounds::play(&app, Cue::RecordingStopped);
+        let filed = stop_recording(&app).and_then(|(samples, _)| file_memo(&app, samples));
+        state.session.reset(&app);
+        match filed {
+            Ok(entry) => {
+                sounds::play(&app, Cue::TranscriptionComplete);
+                let _ = emit(&app, "memo-saved", entry);
+            }
+            Err(e) => {
+                eprintln!("Quick memo failed: {}", e);
+                sounds::play(&app, Cue::TranscriptionFailed);
+                let _ = emit(&app, "memo-failed", e);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Ends the quick memo being recorded; it is filed as usual.
+#[tauri::command]
+fn stop_quick_memo(app: AppHandle) -> Result<(), String> {
+    let stop = app
+        .state::<AppState>()
+        .memo
+        .lock()
+        .take()
+        .ok_or_else(|| "No quick memo is recording".to_string())?;
+    stop.cancel();
+    Ok(())
+}
+
+/// Transcribes a memo and keeps its audio, which is the point of a memo, so
+/// it is kept whether or not recordings are retained.
+fn file_memo(app: &AppHandle, samples: Vec<f32>) -> Result<HistoryEntry, String> {
+    let state = app.state::<AppState>();
+    if samples.is_empty() {
+        return Err("No audio recorded".to_string());
+    }
+    let language = state.transcription_language.lock().clone();
+    let mut result = with_transcriber(&state, |transcriber| {
+        transcriber.transcribe_detailed(&samples, &language)
+    })?;
+    if result.text.is_empty() {
+        return Err("No speech detected".to_string());
+    }
+    let recording = save_recording(&samples, &result.text, None)?;
+
+    let rules = state.settings.lock().text_rules;
+    let (_, replacements) = state.settings.lock().dictionary();
+    result.text = apply_replacements(
+        &apply_rules(&result.text, &rules, result.language.as_deref()),
+        &replacements,
+    );
+    let duration_ms = samples.len() as u64 * 1000 / WHISPER_SAMPLE_RATE as u64;
+    let model = state.selected_model.lock().key();
+    let device = state.selected_device.lock().clone();
+    with_history(app, |history| {
+        history.record(
+            &result,
+            duration_ms,
+            &model,
+            device.as_deref(),
+            Some(recording.id.as_str()),
+            Some(MEMO_SOURCE),
+        )
+    })
+}
+
+/// Records until `stop_meeting_mode`, transcribing the audio a stretch at a
+/// time as pauses end each one. Every stretch with speech is emitted as a
+/// `meeting-segment` and added to a single history entry, so nothing longer
+/// than one stretch is ever held in memory.
+#[tauri::command]
+fn start_meeting_mode(app: AppHandle) -> Result<(), String> {
+    meetings::start(&app)
+}
+
+/// Ends meeting mode. The audio since the last pause is still transcribed
+/// before `meeting-stopped` is emitted.
+#[tauri::command]
+fn stop_meeting_mode(app: AppHandle) -> Result<(), String> {
+    meetings::stop(&app)
+}
+
+/// Transcribes the bundled clip and checks the result against what it says,
+/// proving the model, threads and GPU work before the first real dictation.
+#[tauri::command]
+fn run_self_test(app: AppHandle) -> Result<SelfTestReport, String> {
+    selftest::run(&app)
+}
+
+#[derive(Serialize)]
+struct MemoryUsage {
+    /// Everything Voice holds in RAM; `None` where it can't be read.
+    process_bytes: Option<u64>,
+    /// The loaded Whisper model, zero when none is loaded.
+    model_bytes: u64,
+    /// The recording in progress and dictation waiting out the continuation
+    /// window.
+    audio_bytes: u64,
+    history_cache_bytes: u64,
+    cap_bytes: Option<u64>,
+}
+
+impl MemoryUsage {
+    fn exceeds_cap(&self) -> bool {
+        let tracked = self.model_bytes + self.audio_bytes + self.history_cache_bytes;
+        let total = self.process_bytes.unwrap_or(tracked);
+        self.cap_bytes.is_some_and(|cap| total > cap)
+    }
+}
+
+fn memory_usage(state: &AppState) -> MemoryUsage {
+    let model_loaded = match state.transcriber.try_lock() {
+        Some(transcriber) => transcriber.is_some(),
+        // Held while transcribing, when the model is loaded.
+        None => true,
+    };
+    let model_bytes = if model_loaded {
+        let path = state.selected_model.lock().path();
+        std::fs::metadata(path).map_or(0, |metadata| metadata.len())
+    } else {
+        0
+    };
+    let recording = state
+        .recorder
+        .lock()
+        .as_ref()
+        .map_or(0, |recorder| recorder.buffered_samples());
+    let pending = state.pending_dictation.lock().len();
+    let history_cache_bytes = state
+        .history
+        .lock()
+        .as_ref()
+        .map_or(0, |history| history.cache_bytes());
+    MemoryUsage {
+        process_bytes: resident_bytes(),
+        model_bytes,
+        audio_bytes: ((recording + pending) * std::mem::size_of::<f32>()) as u64,
+        history_cache_bytes,
+        cap_bytes: state
+            .settings
+            .lock()
+            .memory_cap_mb
+            .and_then(|mb| mb.checked_mul(1024 * 1024)),
+    }
+}
+
+#[tauri::command]
+fn get_memory_usage(app: AppHandle) -> MemoryUsage {
+    memory_usage(&app.state::<AppState>())
+}
+
+/// What a report of choppy or missing audio needs to be looked into.
+#[derive(Serialize)]
+struct Diagnostics {
+    version: String,
+    os: &'static str,
+    arch: &'static str,
+    model: String,
+    gpu_backend: Option<&'static str>,
+    threads: usize,
+    device: Option<String>,
+    permissions: PermissionStatus,
+    memory: MemoryUsage,
+    data: DataVersion,
+    /// How the latest recording's audio arrived; `None` before the first.
+    capture: Option<CaptureStats>,
+}
+
+#[tauri::command]
+fn get_diagnostics(app: AppHandle) -> Diagnostics {
+    let state = app.state::<AppState>();
+    let model = state.selected_model.lock().key();
+    let device = state.selected_device.lock().clone();
+    let permissions = *state.permissions.lock();
+    let capture = state.last_capture.lock().clone();
+    Diagnostics {
+        version: app.package_info().version.to_string(),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        model,
+        gpu_backend: gpu_backend().filter(|_| use_gpu(&app)),
+        threads: compute_config().threads,
+        device,
+        permissions,
+        memory: memory_usage(&state),
+        data: get_data_version(app.clone()),
+        capture,
+    }
+}
+
+/// The versions of the settings file and history database on disk.
+#[tauri::command]
+fn get_data_version(app: AppHandle) -> DataVersion {
+    let state = app.state::<AppState>();
+    let settings = state.settings.lock().version;
+    let history = state
+        .history
+        .lock()
+        .as_ref()
+        .and_then(|history| history.version().ok());
+    DataVersion { settings, history }
+}
+
+/// Fills short gaps the driver left in a recording when that is turned on,
+/// and returns the stretches of it that may be damaged.
+fn dropout_ranges(app: &AppHandle, recorded: &mut RecordedAudio) -> Vec<DropoutRange> {
+    let repair = app.state::<AppState>().settings.lock().repair_dropouts;
+    if repair {
+        recorded.repair_dropouts()
+    } else {
+        recorded.dropout_ranges()
+    }
+}
+
+/// Where audio dropped out in a dictation, which is recorded in parts when
+/// it is continued.
+#[derive(Default)]
+struct DictationDropouts {
+    /// Samples of the dictation so far, including any spilled to disk.
+    samples: usize,
+    /// In seconds of the dictation's audio as it will be transcribed.
+    ranges: Vec<DropoutRange>,
+}
+
+impl DictationDropouts {
+    /// Adds the dropouts of the next part of the dictation, `samples` long,
+    /// placing them after the parts before.
+    fn add(&mut self, ranges: Vec<DropoutRange>, samples: usize) {
+        let offset = self.samples as f32 / WHISPER_SAMPLE_RATE as f32;
+        self.ranges
+            .extend(ranges.into_iter().map(|range| DropoutRange {
+                start: offset + range.start,
+                end: offset + range.end,
+            }));
+        self.samples += samples;
+    }
+}
+
+/// Tells the windows which stretches of the dictation about to be
+/// transcribed may be damaged, so what was transcribed from them can be
+/// flagged.
+fn report_dictation_dropouts(app: &AppHandle) {
+    let dropouts = std::mem::take(&mut *app.state::<AppState>().dictation_dropouts.lock());
+    if !dropouts.ranges.is_empty() {
+        eprintln!(
+            "Audio dropped out {} times in the dictation",
+            dropouts.ranges.len()
+        );
+        let _ = emit(app, "audio-dropouts", dropouts.ranges);
+    }
+}
+
+#[tauri::command]
+fn get_repair_dropouts(app: AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = state.settings.lock().repair_dropouts;
+    enabled
+}
+
+#[tauri::command]
+fn set_repair_dropouts(app: AppHandle, enabled: bool) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.repair_dropouts = enabled;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn get_memory_cap(app: AppHandle) -> Option<u64> {
+    let state = app.state::<AppState>();
+    let megabytes = state.settings.lock().memory_cap_mb;
+    megabytes
+}
+
+/// `None` removes the cap. A new cap applies straight away.
+#[tauri::command]
+fn set_memory_cap(app: AppHandle, megabytes: Option<u64>) -> Result<(), String> {
+    if megabytes.is_some_and(|mb| mb.checked_mul(1024 * 1024).is_none()) {
+        return Err("The memory cap is too large".to_string());
+    }
+    update_settings(&app, |settings| {
+        settings.memory_cap_mb = megabytes;
+        Ok(())
+    })?;
+    enforce_memory_cap(&app);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_recording_memory_limit(app: AppHandle) -> Option<u64> {
+    let state = app.state::<AppState>();
+    let megabytes = state.settings.lock().recording_memory_mb;
+    megabytes
+}
+
+/// `None` keeps whole recordings in memory. Applies to the recording in
+/// progress too.
+#[tauri::command]
+fn set_recording_memory_limit(app: AppHandle, megabytes: Option<u64>) -> Result<(), String> {
+    if megabytes == Some(0) {
+        return Err("The recording memory limit must be at least 1 MB".to_string());
+    }
+    if megabytes.is_some_and(|mb| megabytes_to_bytes(mb).is_none()) {
+        return Err("The recording memory limit is too large".to_string());
+    }
+    let state = app.state::<AppState>();
+    update_settings(&app, |settings| {
+        settings.recording_memory_mb = megabytes;
+        Ok(())
+    })?;
+    if let Some(recorder) = state.recorder.lock().as_ref() {
+        recorder.set_memory_limit(megabytes.and_then(megabytes_to_bytes))?;
+    }
+    Ok(())
+}
+
+/// Frees memory until Voice is back under the cap: the history cache first,
+/// then the recording in progress, whatever its own memory limit, then
+/// dictation audio waiting out the continuation window, then the model,
+/// which is loaded again for the next transcription.
+fn enforce_memory_cap(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    if !memory_usage(&state).exceeds_cap() {
+        return;
+    }
+    if let Some(history) = state.history.lock().as_ref() {
+        if let Err(e) = history.shrink_cache() {
+            eprintln!("{}", e);
+        }
+    }
+
+    if memory_usage(&state).exceeds_cap() {
+        if let Some(recorder) = state.recorder.lock().as_ref() {
+            if let Err(e) = recorder.spill() {
+                eprintln!("{}", e);
+            }
+        }
+    }
+
+    let waiting = !state.pending_dictation.lock().is_empty();
+    if waiting && memory_usage(&state).exceeds_cap() {
+        if let Err(e) = spill_pending_dictation(&state) {
+            eprintln!("{}", e);
+        }
+    }
+
+    // Not while a dictation waits to be transcribed, or is being.
+    let waiting = waiting || state.spilled_dictation.lock().is_some();
+    if waiting || state.session.state() != SessionState::Idle {
+        return;
+    }
+    if memory_usage(&state).exceeds_cap() {
+        if let Some(mut transcriber) = state.transcriber.try_lock() {
+            if transcriber.take().is_some() {
+                *state.model_unloaded.lock() = true;
+                println!("Unloaded the Whisper model to stay under the memory cap");
+            }
+        }
+    }
+}
+
+/// Moves the dictation waiting out the continuation window to a FLAC file
+/// only this user can read.
+fn spill_pending_dictation(state: &AppState) -> Result<(), String> {
+    let mut spilled = state.spilled_dictation.lock();
+    let mut samples = read_spilled_dictation(spilled.take());
+    samples.extend(std::mem::take(&mut *state.pending_dictation.lock()));
+    let path = spill_dir().join(format!("voice-dictation-{}.flac", std::process::id()));
+    // Left behind by an earlier run that had the same process id.
+    let _ = std::fs::remove_file(&path);
+    if let Err(e) = write_private_flac(&path, &samples) {
+        *state.pending_dictation.lock() = samples;
+        return Err(e);
+    }
+    *spilled = Some(path);
+    Ok(())
+}
+
+fn read_spilled_dictation(path: Option<PathBuf>) -> Vec<f32> {
+    let Some(path) = path else {
+        return Vec::new();
+    };
+    let samples = read_flac(&path).unwrap_or_else(|e| {
+        eprintln!("Lost spilled dictation audio: {}", e);
+        Vec::new()
+    });
+    let _ = std::fs::remove_file(&path);
+    samples
+}
+
+/// Takes the dictation waiting out the continuation window, including any
+/// of it spilled to disk.
+fn take_pending_dictation(state: &AppState) -> Vec<f32> {
+    let mut spilled = state.spilled_dictation.lock();
+    let mut samples = read_spilled_dictation(spilled.take());
+    samples.extend(std::mem::take(&mut *state.pending_dictation.lock()));
+    samples
+}
+
+/// Reloads the current model so the change applies to the next dictation.
+#[tauri::command]
+fn set_gpu_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+    if enabled && gpu_backend().is_none() {
+        return Err("This build of Voice has no GPU support".to_string());
+    }
+    let state = app.state::<AppState>();
+    update_settings(&app, |settings| {
+        settings.gpu = enabled;
+        Ok(())
+    })?;
+
+    if state.transcriber.lock().is_some() {
+        let model = state.selected_model.lock().clone();
+        load_model(&app, model)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn get_transcription_backend(app: AppHandle) -> TranscriptionBackend {
+    let state = app.state::<AppState>();
+    let backend = state.settings.lock().backend.clone();
+    backend
+}
+
+/// Switches between the local model and a cloud service. The local model
+/// stays loaded so switching back is instant. The API key goes to the OS
+/// credential store; leaving it empty keeps the one stored before.
+#[tauri::command]
+fn set_transcription_backend(
+    app: AppHandle,
+    mut backend: TranscriptionBackend,
+) -> Result<(), String> {
+    backend.validate()?;
+    if let TranscriptionBackend::OpenAi { api_key, .. } = &mut backend {
+        if api_key.is_empty() {
+            *api_key = secrets::load(secrets::CLOUD_API_KEY)?
+                .ok_or_else(|| "Enter an API key".to_string())?;
+        } else {
+            secrets::store(secrets::CLOUD_API_KEY, api_key)?;
+        }
+    }
+    update_settings(&app, |settings| {
+        settings.backend = backend.clone();
+        Ok(())
+    })?;
+    *app.state::<AppState>().remote_transcriber.lock() = backend.remote();
+    Ok(())
+}
+
+/// Fills in the cloud API key, the OBS password and the chapter LLM's API key
+/// from the credential store. Any still in the settings file from an older
+/// version are moved there, and the file saved without them.
+fn load_secrets(settings: &mut Settings) {
+    let mut moved = false;
+    if let TranscriptionBackend::OpenAi { api_key, .. } = &mut settings.backend {
+        let mut key = Some(std::mem::take(api_key));
+        moved |= load_secret(secrets::CLOUD_API_KEY, &mut key);
+        *api_key = key.unwrap_or_default();
+    }
+    moved |= load_secret(secrets::OBS_PASSWORD, &mut settings.obs.password);
+    if let Some(llm) = &mut settings.chapter_llm {
+        moved |= load_secret(secrets::CHAPTER_LLM_API_KEY, &mut llm.api_key);
+    }
+    if let Some(auth) = &mut settings.download.auth {
+        moved |= store_or_load_download_secret(auth).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            false
+        });
+    }
+    if moved {
+        if let Err(e) = save_settings(settings) {
+            eprintln!("Failed to move secrets out of the settings file: {}", e);
+        }
+    }
+}
+
+/// Whether the secret was found in the settings file and moved to the store.
+fn load_secret(name: &str, secret: &mut Option<String>) -> bool {
+    secrets::store_or_load(name, secret).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        false
+    })
+}
+
+#[tauri::command]
+fn get_accuracy_safeguard(app: AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = state.settings.lock().accuracy_safeguard;
+    enabled
+}
+
+/// Turns the local model's temperature fallback on or off. Off is faster but
+/// lets the odd garbled or repeated segment through.
+#[tauri::command]
+fn set_accuracy_safeguard(app: AppHandle, enabled: bool) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.accuracy_safeguard = enabled;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn get_suppression(app: AppHandle) -> Suppression {
+    let state = app.state::<AppState>();
+    let suppression = state.settings.lock().suppression;
+    suppression
+}
+
+#[tauri::command]
+fn set_suppression(app: AppHandle, suppression: Suppression) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.suppression = suppression;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn get_vocabulary(app: AppHandle) -> Vec<String> {
+    let state = app.state::<AppState>();
+    let vocabulary = state.settings.lock().vocabulary.clone();
+    vocabulary
+}
+
+/// Replaces the vocabulary, dropping blank and repeated terms. Takes effect
+/// from the next transcription.
+#[tauri::command]
+fn set_vocabulary(app: AppHandle, vocabulary: Vec<String>) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.set_vocabulary(vocabulary);
+        Ok(())
+    })
+}
+
+/// Writes the vocabulary and replacements to `path` as a dictionary pack
+/// that others can import.
+#[tauri::command]
+fn export_dictionary_pack(
+    app: AppHandle,
+    path: String,
+    name: Option<String>,
+) -> Result<(), String> {
+    let (vocabulary, replacements) = {
+        let settings = app.state::<AppState>().settings.lock();
+        (settings.vocabulary.clone(), settings.replacements.clone())
+    };
+    DictionaryPack::new(name.unwrap_or_default(), vocabulary, replacements).write(Path::new(&path))
+}
+
+/// Adds a dictionary pack's vocabulary and replacements to the user's.
+/// Where the pack replaces a word differently, `on_conflict` decides which
+/// stays; the user's by default. The conflicts are listed either way.
+#[tauri::command]
+fn import_dictionary_pack(
+    app: AppHandle,
+    path: String,
+    on_conflict: Option<ConflictPolicy>,
+) -> Result<ImportSummary, String> {
+    let pack = DictionaryPack::read(Path::new(&path))?;
+    update_settings(&app, |settings| {
+        Ok(pack.merge_into(
+            &mut settings.vocabulary,
+            &mut settings.replacements,
+            on_conflict.unwrap_or_default(),
+        ))
+    })
+}
+
+/// The dictionary packs that ship with Voice and whether each is in use for
+/// every profile, or for the named `profile` on top of those.
+#[tauri::command]
+fn list_dictionary_presets(
+    app: AppHandle,
+    profile: Option<String>,
+) -> Result<Vec<DictionaryPreset>, String> {
+    let state = app.state::<AppState>();
+    let settings = state.settings.lock();
+    let enabled = settings.dictionary_presets_for(profile.as_deref())?;
+    Ok(dictionary_presets(enabled))
+}
+
+/// Uses a built-in dictionary pack alongside the user's own vocabulary and
+/// replacements, or stops using it: for every profile, or only while the
+/// named `profile` is active. The user's own are left as they are.
+#[tauri::command]
+fn set_dictionary_preset(
+    app: AppHandle,
+    id: String,
+    enabled: bool,
+    profile: Option<String>,
+) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.set_dictionary_preset(id, enabled, profile.as_deref())
+    })
+}
+
+/// The protocol's methods with their params and results, for client authors.
+#[tauri::command]
+fn get_api_schema() -> serde_json::Value {
+    api_schema(rpc::METHODS)
+}
+
+/// What the command palette can offer right now.
+#[tauri::command]
+fn list_actions(app: AppHandle) -> Vec<Action> {
+    actions::list(&app)
+}
+
+/// Runs an action from `list_actions` by its id.
+#[tauri::command]
+async fn invoke_action(app: AppHandle, id: String) -> Result<serde_json::Value, String> {
+    actions::invoke(&app, &id).await
+}
+
+#[tauri::command]
+fn get_plugins() -> Vec<Plugin> {
+    plugins::list_plugins()
+}
+
+#[tauri::command]
+fn install_plugin(name: String, source: String) -> Result<Plugin, String> {
+    plugins::install_plugin(&name, &source)
+}
+
+#[tauri::command]
+fn set_plugin_enabled(slug: String, enabled: bool) -> Result<Plugin, String> {
+    plugins::set_plugin_enabled(&slug, enabled)
+}
+
+#[tauri::command]
+fn remove_plugin(slug: String) -> Result<(), String> {
+    plugins::remove_plugin(&slug)
+}
+
+#[tauri::command]
+fn get_show_overlay(app: AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = *state.show_overlay.lock();
+    enabled
+}
+
+#[tauri::command]
+fn set_show_overlay(app: AppHandle, enabled: bool) {
+    let state = app.state::<AppState>();
+    *state.show_overlay.lock() = enabled;
+}
+
+#[tauri::command]
+fn get_hands_free(app: AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = state.settings.lock().hands_free;
+    enabled
+}
+
+#[tauri::command]
+fn set_hands_free(app: AppHandle, enabled: bool) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.hands_free = enabled;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn get_auto_stop_silence(app: AppHandle) -> u64 {
+    let state = app.state::<AppState>();
+    let silence_ms = state.settings.lock().auto_stop_silence_ms;
+    silence_ms
+}
+
+#[tauri::command]
+fn set_auto_stop_silence(app: AppHandle, silence_ms: u64) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.auto_stop_silence_ms = silence_ms;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn get_review_results(app: AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = state.settings.lock().review_results;
+    enabled
+}
+
+#[tauri::command]
+fn set_review_results(app: AppHandle, enabled: bool) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.review_results = enabled;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+async fn review_action(app: AppHandle, action: ReviewAction) -> Result<(), String> {
+    actions::blocking(move || resolve_review(&app, action)).await
+}
+
+#[tauri::command]
+fn get_noise_suppression(app: AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = state.settings.lock().audio.noise_suppression;
+    enabled
+}
+
+#[tauri::command]
+fn set_noise_suppression(app: AppHandle, enabled: bool) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.audio.noise_suppression = enabled;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn get_input_gain(app: AppHandle) -> f32 {
+    let state = app.state::<AppState>();
+    let gain = state.settings.lock().audio.gain;
+    gain
+}
+
+#[tauri::command]
+fn set_input_gain(app: AppHandle, gain: f32) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.audio.gain = gain;
+        settings.audio.validate()
+    })
+}
+
+#[tauri::command]
+fn get_trim_silence(app: AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = state.settings.lock().audio.trim_silence;
+    enabled
+}
+
+#[tauri::command]
+fn set_trim_silence(app: AppHandle, enabled: bool) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.audio.trim_silence = enabled;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn get_normalize_loudness(app: AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = state.settings.lock().audio.normalize_loudness;
+    enabled
+}
+
+#[tauri::command]
+fn set_normalize_loudness(app: AppHandle, enabled: bool) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.audio.normalize_loudness = enabled;
+        Ok(())
+    })
+}
+
+/// What the settings window asks the user to read for a test recording.
+const PRACTICE_SENTENCE: &str =
+    "Please call Stella and ask her to bring these things with her from the store.";
+
+/// A test recording's transcription, without text rules or replacements so
+/// it shows what the model heard.
+#[derive(Serialize)]
+struct TestTranscription {
+    text: String,
+    practice_sentence: &'static str,
+    /// Measured against the practice sentence as the self-test measures it.
+    word_error_rate: f32,
+    /// The loudest sample after gain, from 0 to 1; at 1 the input clipped.
+    peak: f32,
+}
+
+/// Starts a test recording for the settings window. It is a session of its
+/// own, so the shortcut can't start a dictation on top of it, and its levels
+/// are emitted as "test-audio-level" instead of reaching the overlay.
+#[tauri::command]
+fn start_test_recording(app: AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    if !state.permissions.lock().microphone {
+        return Err(MICROPHONE_DENIED.to_string());
+    }
+    state.session.transition(&app, SessionState::Idle, SessionState::Testing)?;
+
+    let (level_tx, level_rx) = channel::<f32>();
+    let started = {
+        let mut recorder_lock = state.recorder.lock();
+        match recorder_lock.as_ref() {
+            Some(recorder) => recorder.start_recording(Some(level_tx)),
+            None => open_recorder(&state).and_then(|recorder| {
+                recorder.start_recording(Some(level_tx))?;
+                *recorder_lock = Some(recorder);
+                Ok(())
+            }),
+        }
+    };
+    if let Err(e) = started {
+        state.session.reset(&app);
+        return Err(e);
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        // Ends when the recording stops and the recorder drops the sender.
+        while let Ok(rms) = level_rx.recv() {
+            let gain = app.state::<AppState>().settings.lock().audio.gain;
+            let _ = emit(&app, "test-audio-level", (rms * gain * 25.0).min(1.0));
+        }
+    });
+    Ok(())
+}
+
+/// Stops the test recording and transcribes it.
+#[tauri::command]
+async fn stop_test_recording(app: AppHandle) -> Result<TestTranscription, String> {
+    {
+        let state = app.state::<AppState>();
+        if state.session.state() != SessionState::Testing {
+            return Err("No test recording is running".to_string());
+        }
+        let recorded = match state.recorder.lock().as_ref() {
+            Some(recorder) => recorder.stop_recording(),
+            None => Err("No recorder available".to_string()),
+        };
+        state.session.reset(&app);
+        let recorded = recorded?;
+        *state.last_capture.lock() = Some(recorded.stats.clone());
+        *state.test_recording.lock() = Some(recorded);
+    }
+    retranscribe_test_recording(app).await
+}
+
+/// Transcribes the last test recording again with the current gain and
+/// noise suppression, to compare settings on the same audio.
+#[tauri::command]
+async fn retranscribe_test_recording(app: AppHandle) -> Result<TestTranscription, String> {
+    actions::blocking(move || transcribe_test_recording(&app.state::<AppState>())).await
+}
+
+fn transcribe_test_recording(state: &AppState) -> Result<TestTranscription, String> {
+    let (samples, sample_rate, channels) = match state.test_recording.lock().as_ref() {
+        Some(recorded) => (
+            recorded.samples.clone(),
+            recorded.sample_rate,
+            recorded.channels,
+        ),
+        None => return Err("Make a test recording first".to_string()),
+    };
+    let pipeline = state.settings.lock().audio;
+    let peak = samples
+        .iter()
+        .fold(0.0f32, |peak, sample| {
+            peak.max((sample * pipeline.gain).abs())
+        })
+        .min(1.0);
+    let samples = pipeline.process(samples, sample_rate, channels);
+
+    // The practice sentence is in English, whatever the user dictates in.
+    let language = TranscriptionLanguage::Code("en".to_string());
+    let text = with_transcriber(state, |transcriber| {
+        transcriber.transcribe(&samples, &language)
+    })?
+    .into_text();
+    Ok(TestTranscription {
+        word_error_rate: word_error_rate(PRACTICE_SENTENCE, &text),
+        text,
+        practice_sentence: PRACTICE_SENTENCE,
+        peak,
+    })
+}
+
+/// Starts translating live captions for this session, from `source` (a
+/// language code or "auto") into `target`. Targets other than English need
+/// an LLM endpoint.
+#[tauri::command]
+fn start_caption_translation(
+    app: AppHandle,
+    source: String,
+    target: String,
+    llm: Option<LlmEndpoint>,
+) -> Result<(), String> {
+    let translation = CaptionTranslation::new(&source, &target, llm)?;
+    let state = app.state::<AppState>();
+    *state.caption_translation.lock() = Some(translation);
+    Ok(())
+}
+
+#[tauri::command]
+fn stop_caption_translation(app: AppHandle) {
+    let state = app.state::<AppState>();
+    *state.caption_translation.lock() = None;
+}
+
+/// The current session's source and target languages.
+#[tauri::command]
+fn get_caption_translation(app: AppHandle) -> Option<(String, String)> {
+    let state = app.state::<AppState>();
+    let languages = state
+        .caption_translation
+        .lock()
+        .as_ref()
+        .map(|t| (t.source().to_string(), t.target().to_string()));
+    languages
+}
+
+#[tauri::command]
+fn get_streaming_transcription(app: AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = state.settings.lock().streaming_transcription;
+    enabled
+}
+
+#[tauri::command]
+fn set_streaming_transcription(app: AppHandle, enabled: bool) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.streaming_transcription = enabled;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn get_continuation_window(app: AppHandle) -> u64 {
+    let state = app.state::<AppState>();
+    let window_ms = *state.continuation_window_ms.lock();
+    window_ms
+}
+
+#[tauri::command]
+fn set_continuation_window(app: AppHandle, window_ms: u64) {
+    let state = app.state::<AppState>();
+    *state.continuation_window_ms.lock() = window_ms;
+}
+
+#[tauri::command]
+fn get_retain_recordings(app: AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = *state.retain_recordings.lock();
+    enabled
+}
+
+#[tauri::command]
+fn set_retain_recordings(app: AppHandle, enabled: bool) {
+    let state = app.state::<AppState>();
+    *state.retain_recordings.lock() = enabled;
+}
+
+#[tauri::command]
+fn get_recordings(app_filter: Option<String>) -> Vec<RetainedRecording> {
+    let recordings = list_recordings();
+    match app_filter {
+        Some(query) => recordings
+            .into_iter()
+            .filter(|r| r.matches_app(&query))
+            .collect(),
+        None => recordings,
+    }
+}
+
+#[tauri::command]
+fn get_capture_window_context(app: AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = *state.capture_window_context.lock();
+    enabled
+}
+
+#[tauri::command]
+fn set_capture_window_context(app: AppHandle, enabled: bool) {
+    let state = app.state::<AppState>();
+    *state.capture_window_context.lock() = enabled;
+}
+
+#[tauri::command]
+fn get_recording_audio(id: String) -> Result<tauri::ipc::Response, String> {
+    recording_to_wav(&id).map(tauri::ipc::Response::new)
+}
+
+/// The kept audio of a history entry from `seconds` to the end of the segment
+/// said there, as a WAV file, so the history can play a single sentence.
+/// Plays to the end of the recording from a pause between segments.
+#[tauri::command]
+fn seek_history_audio(
+    app: AppHandle,
+    id: i64,
+    seconds: f32,
+) -> Result<tauri::ipc::Response, String> {
+    let entry = with_history(&app, |history| history.get(id))?;
+    let recording_id = entry
+        .recording_id
+        .ok_or_else(|| "No recording was kept for this entry".to_string())?;
+    let samples = load_recording(&recording_id)?;
+
+    let rate = WHISPER_SAMPLE_RATE as f32;
+    let to_sample = |seconds: f32| ((seconds.max(0.0) * rate) as usize).min(samples.len());
+    let end = entry.segment_end(seconds).map_or(samples.len(), to_sample);
+    let start = to_sample(seconds).min(end);
+
+    let mut wav = std::io::Cursor::new(Vec::new());
+    write_wav(&mut wav, &samples[start..end])?;
+    Ok(tauri::ipc::Response::new(wav.into_inner()))
+}
+
+#[tauri::command]
+fn retranscribe_recording(app: AppHandle, id: String) -> Result<String, String> {
+    let samples = load_recording(&id)?;
+    let state = app.state::<AppState>();
+    let language = state.transcription_language.lock().clone();
+    with_transcriber(&state, |transcriber| transcriber.transcribe(&samples, &language))
+        .map(Transcript::into_text)
+}
+
+#[tauri::command]
+fn delete_recording(id: String) -> Result<(), String> {
+    voice_core::audio::delete_recording(&id)
+}
+
+#[tauri::command]
+fn get_feedback_enabled(app: AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = *state.feedback_enabled.lock();
+    enabled
+}
+
+#[tauri::command]
+fn set_feedback_enabled(app: AppHandle, enabled: bool) {
+    let state = app.state::<AppState>();
+    *state.feedback_enabled.lock() = enabled;
+}
+
+#[tauri::command]
+fn submit_correction(
+    app: AppHandle,
+    recording_id: String,
+    corrected_text: String,
+) -> Result<Correction, String> {
+    let state = app.state::<AppState>();
+    if !*state.feedback_enabled.lock() {
+        return Err("Accuracy feedback is disabled".to_string());
+    }
+    feedback::submit_correction(&recording_id, &corrected_text)
+}
+
+#[tauri::command]
+fn get_corrections() -> Vec<Correction> {
+    list_corrections()
+}
+
+#[tauri::command]
+fn export_feedback_dataset(dest: String) -> Result<usize, String> {
+    export_dataset(std::path::Path::new(&dest))
+}
+
+#[tauri::command]
+fn clear_feedback() -> Result<(), String> {
+    clear_corrections()
+}
+
+#[tauri::command]
+fn check_microphone_permission() -> bool {
+    permissions::microphone_granted()
+}
+
+#[tauri::command]
+fn check_accessibility_permission() -> bool {
+    permissions::accessibility_granted()
+}
+
+/// Shows the system microphone prompt if Voice hasn't asked yet, and returns
+/// whether access is granted once the user answers.
+#[tauri::command]
+async fn request_microphone_permission(app: AppHandle) -> bool {
+    let granted = permissions::request_microphone().await;
+    refresh_permissions(&app);
+    granted
+}
+
+#[tauri::command]
+fn request_accessibility_permission(app: AppHandle) -> bool {
+    let granted = permissions::request_accessibility();
+    let state = app.state::<AppState>();
+    state.permissions.lock().accessibility = granted;
+    granted
+}
+
+#[tauri::command]
+fn get_permission_status(app: AppHandle) -> PermissionStatus {
+    let state = app.state::<AppState>();
+    let status = *state.permissions.lock();
+    status
+}
+
+#[tauri::command]
+fn open_accessibility_settings() -> Result<(), String> {
+    permissions::open_accessibility_settings()
+}
+
+#[tauri::command]
+fn open_microphone_settings() -> Result<(), String> {
+    permissions::open_microphone_settings()
+}
+
+#[tauri::command]
+fn close_settings_window(app: AppHandle) {
+    if let Some(window) = app.get_webview_window("settings") {
+        let _ = window.hide();
+    }
+}
+
+const PERMISSION_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Serialize)]
+struct PermissionChange {
+    permission: &'static str,
+    granted: bool,
+}
+
+/// Polls permission state so a revoked permission surfaces as a
+/// `permission-changed` event instead of opaque command failures. On first
+/// launch it asks for the microphone, so the first dictation isn't refused
+/// for a question that was never put.
+fn spawn_permission_watcher(app: AppHandle) {
+    std::thread::spawn(move || {
+        tauri::async_runtime::block_on(permissions::request_microphone());
+        loop {
+            refresh_permissions(&app);
+            std::thread::sleep(PERMISSION_POLL_INTERVAL);
+        }
+    });
+}
+
+/// Checks the permissions again and emits `permission-changed` for each that
+/// changed since the last check.
+fn refresh_permissions(app: &AppHandle) {
+    let current = PermissionStatus::current();
+    let previous = {
+        let state = app.state::<AppState>();
+        let mut permissions = state.permissions.lock();
+        std::mem::replace(&mut *permissions, current)
+    };
+
+    if current.microphone != previous.microphone {
+        if current.microphone {
+            apply_preroll(app);
+        }
+        let _ = emit(
+            app,
+            "permission-changed",
+            PermissionChange {
+                permission: "microphone",
+                granted: current.microphone,
+            },
+        );
+    }
+    if current.accessibility != previous.accessibility {
+        let _ = emit(
+            app,
+            "permission-changed",
+            PermissionChange {
+                permission: "accessibility",
+                granted: current.accessibility,
+            },
+        );
+    }
+}
+
+/// Plugging in a headset adds its devices one after another, so a change is
+/// handled once things have been quiet this long.
+#[cfg(target_os = "windows")]
+const DEVICE_CHANGE_SETTLE: Duration = Duration::from_millis(500);
+
+/// Emits `audio-devices-changed` with the input devices whenever one is
+/// plugged in or removed.
+#[cfg(target_os = "windows")]
+fn spawn_device_watcher(app: AppHandle) {
+    let (changed, changes) = std::sync::mpsc::channel();
+    let watched = devices::watch_input_devices(move || {
+        let _ = changed.send(());
+    });
+    if let Err(e) = watched {
+        eprintln!("{}", e);
+        return;
+    }
+    std::thread::spawn(move || {
+        while changes.recv().is_ok() {
+            std::thread::sleep(DEVICE_CHANGE_SETTLE);
+            while changes.try_recv().is_ok() {}
+            devices_changed(&app);
+        }
+    });
+}
+
+/// Drops the idle recorder so the next recording opens whichever device is
+/// now the default, and goes back to the default if the chosen device was
+/// unplugged.
+#[cfg(target_os = "windows")]
+fn devices_changed(app: &AppHandle) {
+    let devices = match get_audio_devices(app.clone()) {
+        Ok(devices) => devices,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    let state = app.state::<AppState>();
+    {
+        let mut selected = state.selected_device.lock();
+        let unplugged = selected
+            .as_ref()
+            .is_some_and(|device| !devices.contains(device));
+        if unplugged {
+            println!("Audio device unplugged, using the default");
+            *selected = None;
+        }
+    }
+    if state.session.state() == SessionState::Idle {
+        *state.recorder.lock() = None;
+        apply_preroll(app);
+    }
+    let _ = emit(app, "audio-devices-changed", devices);
+}
+
+const MEETING_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Emits `meeting-detected` when a conferencing app joins a call and
+/// `meeting-ended` when it leaves, while meeting detection is on. With
+/// `meeting_auto_start` the calls are transcribed as a meeting too.
+fn spawn_meeting_watcher(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        let state = app.state::<AppState>();
+        let current = if state.settings.lock().meeting_detection {
+            meetings::active_calls()
+        } else {
+            Vec::new()
+        };
+        let previous = std::mem::replace(&mut *state.active_calls.lock(), current.clone());
+
+        let region = state.settings.lock().consent.region.clone();
+        for name in current.iter().filter(|name| !previous.contains(name)) {
+            println!("Call detected in {}", name);
+            let _ = emit(&app, "meeting-detected", *name);
+            let _ = emit(&app, "consent-reminder", ConsentReminder::new(name, region.as_deref()));
+        }
+        for name in previous.iter().filter(|name| !current.contains(name)) {
+            let _ = emit(&app, "meeting-ended", *name);
+        }
+        if current.is_empty() {
+            *state.call_announced.lock() = false;
+        }
+        follow_calls(&app, previous.is_empty(), current.is_empty());
+
+        std::thread::sleep(MEETING_POLL_INTERVAL);
+    });
+}
+
+/// Starts meeting mode when the first call begins, if nothing else is
+/// recording, and stops it when the last one ends. Meetings started by hand
+/// are left for the user to stop.
+fn follow_calls(app: &AppHandle, was_in_call: bool, in_call: bool) {
+    let state = app.state::<AppState>();
+    if !was_in_call && in_call {
+        if !state.settings.lock().meeting_auto_start || state.session.state() != SessionState::Idle {
+            return;
+        }
+        *state.meeting_for_call.lock() = true;
+        if let Err(e) = start_meeting_mode(app.clone()) {
+            *state.meeting_for_call.lock() = false;
+            eprintln!("Failed to transcribe the call: {}", e);
+        }
+    } else if was_in_call && !in_call && std::mem::take(&mut *state.meeting_for_call.lock()) {
+        let _ = stop_meeting_mode(app.clone());
+    }
+}
+
+/// How often the wake-word listener collects the audio heard since it last
+/// looked. Longer wakes the CPU less; shorter answers the phrase sooner.
+const WAKE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Least time between two checks of a candidate with the model, so a noisy
+/// room can't keep it decoding.
+const WAKE_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long the listener waits to try again when the microphone won't open.
+const WAKE_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The microphone the wake-word listener has open.
+struct WakeListener {
+    device: Option<String>,
+    recorder: AudioRecorder,
+    // The driver's real rate is only known after a second or so of audio.
+    converter: Option<(u32, StreamingConverter)>,
+    detector: WakeWordDetector,
+}
+
+impl WakeListener {
+    fn open(state: &AppState, device: Option<String>) -> Result<Self, String> {
+        let recorder = new_recorder(state, device.as_deref())?;
+        recorder.start_recording(None)?;
+        Ok(Self {
+            device,
+            recorder,
+            converter: None,
+            detector: WakeWordDetector::default(),
+        })
+    }
+
+    /// Bursts heard since the last call that could be the wake phrase.
+    fn candidates(&mut self) -> Result<Vec<Vec<f32>>, String> {
+        let recorded = self.recorder.drain()?;
+        if self.converter.as_ref().map(|(rate, _)| *rate) != Some(recorded.sample_rate) {
+            let fresh = StreamingConverter::new(recorded.sample_rate, recorded.channels);
+            self.converter = Some((recorded.sample_rate, fresh));
+        }
+        let mut samples = Vec::new();
+        if let Some((_, converter)) = self.converter.as_mut() {
+            converter.push(&recorded.samples, &mut samples);
+        }
+        Ok(self.detector.push(&samples))
+    }
+}
+
+/// Listens for the wake phrase while wake-word activation is on and the
+/// session is idle, and starts a dictation when it is heard. The microphone
+/// is closed whenever anything else records, so the two never compete.
+///
+/// Most of the time this only measures loudness. A burst of speech the
+/// length of the phrase is checked with the loaded model, at most once per
+/// `WAKE_CHECK_INTERVAL`.
+fn spawn_wake_word_listener(app: AppHandle) {
+    std::thread::spawn(move || {
+        let state = app.state::<AppState>();
+        let mut listener: Option<WakeListener> = None;
+        let mut last_check: Option<Instant> = None;
+        loop {
+            std::thread::sleep(WAKE_POLL_INTERVAL);
+            let device = state.selected_device.lock().clone();
+            let wanted = state.settings.lock().wake_word
+                && state.permissions.lock().microphone
+                && state.session.state() == SessionState::Idle;
+            let stale = listener
+                .as_ref()
+                .is_some_and(|open| !wanted || open.device != device);
+            if stale {
+                close_wake_listener(&state, listener.take());
+            }
+            if !wanted {
+                continue;
+            }
+
+            let open = match listener.as_mut() {
+                Some(open) => open,
+                None => match WakeListener::open(&state, device) {
+                    Ok(opened) => {
+                        *state.wake_word_listening.lock() = true;
+                        listener.insert(opened)
+                    }
+                    Err(e) => {
+                        eprintln!("Wake word can't listen: {}", e);
+                        std::thread::sleep(WAKE_RETRY_INTERVAL);
+                        continue;
+                    }
+                },
+            };
+            let candidates = match open.candidates() {
+                Ok(candidates) => candidates,
+                Err(e) => {
+                    eprintln!("Wake word stopped listening: {}", e);
+                    close_wake_listener(&state, listener.take());
+                    continue;
+                }
+            };
+
+            for candidate in candidates {
+                if last_check.is_some_and(|checked| checked.elapsed() < WAKE_CHECK_INTERVAL) {
+                    continue;
+                }
+                last_check = Some(Instant::now());
+                if heard_wake_phrase(&state, &candidate) {
+                    close_wake_listener(&state, listener.take());
+                    let _ = emit(&app, "wake-word-detected", ());
+                    begin_dictation(&app, true);
+                    break;
+                }
+            }
+        }
+    });
+}
+
+fn close_wake_listener(state: &AppState, listener: Option<WakeListener>) {
+    if let Some(listener) = listener {
+        let _ = listener.recorder.stop_recording();
+    }
+    *state.wake_word_listening.lock() = false;
+}
+
+/// Checks a candidate against the wake phrase with the local model only, so
+/// audio heard while idle never leaves the machine. A model that is busy or
+/// not loaded isn't waited for; the candidate is dropped.
+fn heard_wake_phrase(state: &AppState, samples: &[f32]) -> bool {
+    let Some(transcriber) = state.transcriber.try_lock() else {
+        return false;
+    };
+    let language = TranscriptionLanguage::Code("en".to_string());
+    transcriber
+        .as_ref()
+        .and_then(|transcriber| transcriber.transcribe(samples, &language).ok())
+        .is_some_and(|transcript| is_wake_phrase(&transcript.into_text()))
+}
+
+/// Speaks the announcement before the first dictation in a detected call, so
+/// the other participants hear it before the microphone opens.
+fn announce_call(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    if !state.settings.lock().consent.announce_calls || state.active_calls.lock().is_empty() {
+        return;
+    }
+    if std::mem::replace(&mut *state.call_announced.lock(), true) {
+        return;
+    }
+    if let Err(e) = sounds::speak(ANNOUNCEMENT) {
+        eprintln!("{}", e);
+    }
+}
+
+#[tauri::command]
+fn get_profiles(app: AppHandle) -> Vec<Profile> {
+    let state = app.state::<AppState>();
+    let profiles = state.settings.lock().profiles.clone();
+    profiles
+}
+
+/// Adds a profile, or replaces the one with the same name.
+#[tauri::command]
+fn save_profile(app: AppHandle, profile: Profile) -> Result<(), String> {
+    update_settings(&app, |settings| settings.save_profile(profile))
+}
+
+#[tauri::command]
+fn delete_profile(app: AppHandle, name: String) -> Result<(), String> {
+    update_settings(&app, |settings| settings.delete_profile(&name))
+}
+
+#[tauri::command]
+fn get_active_profile(app: AppHandle) -> Option<Profile> {
+    let state = app.state::<AppState>();
+    let profile = state.settings.lock().active_profile().cloned();
+    profile
+}
+
+/// Sends dictations to the named profile, or to none. The overlay takes on
+/// its color and icon from the next recording, and a `profile-changed`
+/// event announces it.
+#[tauri::command]
+fn set_active_profile(app: AppHandle, name: Option<String>) -> Result<(), String> {
+    let profile = update_settings(&app, |settings| {
+        settings.active_profile = name.clone();
+        let profile = settings.active_profile().cloned();
+        if let (Some(name), None) = (&name, &profile) {
+            return Err(format!("No profile named {}", name));
+        }
+        Ok(profile)
+    })?;
+    let _ = emit(&app, "profile-changed", profile);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_sound_cues(app: AppHandle) -> SoundCues {
+    let state = app.state::<AppState>();
+    let cues = state.settings.lock().sound_cues;
+    cues
+}
+
+#[tauri::command]
+fn set_sound_cues(app: AppHandle, cues: SoundCues) -> Result<(), String> {
+    cues.validate()?;
+    update_settings(&app, |settings| {
+        settings.sound_cues = cues;
+        Ok(())
+    })
+}
+
+/// Plays a cue at the volume just chosen, so the slider can be tried out.
+#[tauri::command]
+fn preview_sound_cue(app: AppHandle) {
+    sounds::play(&app, Cue::TranscriptionComplete);
+}
+
+#[tauri::command]
+fn get_consent_settings(app: AppHandle) -> ConsentSettings {
+    let state = app.state::<AppState>();
+    let consent = state.settings.lock().consent.clone();
+    consent
+}
+
+#[tauri::command]
+fn set_consent_settings(app: AppHandle, consent: ConsentSettings) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let allow_system_audio = consent.allow_system_audio;
+    update_settings(&app, |settings| {
+        settings.consent = consent;
+        Ok(())
+    })?;
+    // Forbidding system audio switches back to the default microphone.
+    let mut selected = state.selected_device.lock();
+    if !allow_system_audio && selected.as_deref() == Some(SYSTEM_AUDIO_DEVICE) {
+        *selected = None;
+        *state.recorder.lock() = None;
+        drop(selected);
+        apply_preroll(&app);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn get_active_calls(app: AppHandle) -> Vec<&'static str> {
+    let state = app.state::<AppState>();
+    let calls = state.active_calls.lock().clone();
+    calls
+}
+
+#[tauri::command]
+fn get_meeting_detection(app: AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = state.settings.lock().meeting_detection;
+    enabled
+}
+
+#[tauri::command]
+fn set_meeting_detection(app: AppHandle, enabled: bool) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.meeting_detection = enabled;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn get_meeting_auto_start(app: AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let enabled = state.settings.lock().meeting_auto_start;
+    enabled
+}
+
+#[tauri::command]
+fn set_meeting_auto_start(app: AppHandle, enabled: bool) -> Result<(), String> {
+    update_settings(&app, |settings| {
+        settings.meeting_auto_start = enabled;
+        Ok(())
+    })
+}
+
+#[cfg(target_os = "macos")]
+const DICTATION_CHORD: &[ChordKey] = &[ChordKey::Shift, ChordKey::Meta, ChordKey::Space];
+
+#[cfg(not(target_os = "macos"))]
+const DICTATION_CHORD: &[ChordKey] = &[ChordKey::Shift, ChordKey::Control, ChordKey::Space];
+
+/// How often a held dictation checks that the shortcut is still down.
+const HOLD_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How often a recording checks that Voice is under the memory cap.
+const RECORDING_MEMORY_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A held dictation this long is assumed to have lost its key-up. This is
+/// all that catches it where key state can't be read, and is a backstop
+/// elsewhere.
+const MAX_HOLD: Duration = Duration::from_secs(10 * 60);
+
+/// Whether the shortcut was let go without its key-up reaching us, which
+/// happens e.g. when switching spaces mid-dictation. Takes two misses in a
+/// row so a key bouncing during a poll doesn't end the recording.
+fn release_missed(state: &AppState, held_since: Instant, misses: &mut u32) -> bool {
+    if !*state.shortcut_held.lock() || state.settings.lock().hands_free {
+        *misses = 0;
+        return false;
+    }
+    if held_since.elapsed() > MAX_HOLD {
+        return true;
+    }
+    match chord_held(DICTATION_CHORD) {
+        Some(false) => *misses += 1,
+        _ => *misses = 0,
+    }
+    *misses >= 2
+}
+
+/// How long a preview shows the overlay once the placement stops changing.
+const OVERLAY_PREVIEW_DURATION: Duration = Duration::from_secs(2);
+
+/// Sizes and places the overlay as the settings say. `width` and `height`
+/// are what its contents need at a scale of 1.
+fn place_overlay(window: &tauri::WebviewWindow, width: f64, height: f64) {
+    let placement = window.state::<AppState>().settings.lock().overlay.clone();
+    place_overlay_at(window, &placement, width, height);
+}
+
+fn place_overlay_at(
+    window: &tauri::WebviewWindow,
+    placement: &OverlayPlacement,
+    width: f64,
+    height: f64,
+) {
+    let (width, height) = (width * placement.scale, height * placement.scale);
+    let _ = window.set_size(tauri::LogicalSize::new(width, height));
+    let _ = window.set_zoom(placement.scale);
+    // A monitor that has been unplugged falls back to the current one.
+    let chosen = placement.monitor.as_ref().and_then(|name| {
+        window
+            .available_monitors()
+            .ok()?
+            .into_iter()
+            .find(|monitor| monitor.name() == Some(name))
+    });
+    let Some(monitor) = chosen.or_else(|| window.current_monitor().ok().flatten()) else {
+        return;
+    };
+    let scale = monitor.scale_factor();
+    let origin = monitor.position().to_logical::<f64>(scale);
+    let size = monitor.size().to_logical::<f64>(scale);
+    let x = match placement.horizontal {
+        HorizontalAnchor::Left => placement.offset_x,
+        HorizontalAnchor::Center => (size.width - width) / 2.0 + placement.offset_x,
+        HorizontalAnchor::Right => size.width - width - placement.offset_x,
+    };
+    let y = match placement.vertical {
+        VerticalAnchor::Top => placement.offset_y,
+        VerticalAnchor::Bottom => size.height - height - placement.offset_y,
+    };
+    let position = tauri::LogicalPosition::new(origin.x + x, origin.y + y);
+    let _ = window.set_position(tauri::Position::Logical(position));
+}
+
+#[tauri::command]
+fn get_overlay_placement(app: AppHandle) -> OverlayPlacement {
     let state = app.state::<AppState>();
-    *state.transcriber.lock() = Some(transcriber);
-    Ok(())
+    let placement = state.settings.lock().overlay.clone();
+    placement
 }
 
 #[tauri::command]
-fn start_recording(app: AppHandle) -> Result<(), String> {
-    let state = app.state::<AppState>();
-
-    let mut is_recording = state.is_recording.lock();
-    if *is_recording {
-        return Ok(());
-    }
+fn set_overlay_placement(app: AppHandle, placement: OverlayPlacement) -> Result<(), String> {
+    placement.validate()?;
+    update_settings(&app, |settings| {
+        settings.overlay = placement;
+        Ok(())
+    })
+}
 
-    let mut recorder_lock = state.recorder.lock();
-    if recorder_lock.is_none() {
-        *recorder_lock = Some(AudioRecorder::new()?);
-    }
+/// Names of the connected monitors the overlay can be shown on.
+#[tauri::command]
+fn list_monitors(app: AppHandle) -> Result<Vec<String>, String> {
+    let monitors = app
+        .available_monitors()
+        .map_err(|e| format!("Failed to list monitors: {}", e))?;
+    Ok(monitors
+        .iter()
+        .filter_map(|monitor| monitor.name().cloned())
+        .collect())
+}
 
-    if let Some(recorder) = recorder_lock.as_ref() {
-        recorder.start_recording(None)?;
-        *is_recording = true;
+/// Shows the overlay at `placement` without saving it, so it can be adjusted
+/// live from the settings. It hides again shortly after the last preview,
+/// unless a dictation has started meanwhile.
+#[tauri::command]
+fn preview_overlay_position(app: AppHandle, placement: OverlayPlacement) -> Result<(), String> {
+    placement.validate()?;
+    let state = app.state::<AppState>();
+    if state.session.state() != SessionState::Idle {
+        return Err("The overlay is in use".to_string());
     }
+    let window = app
+        .get_webview_window("overlay")
+        .ok_or_else(|| "The overlay window is missing".to_string())?;
+    place_overlay_at(&window, &placement, 240.0, 80.0);
+    let _ = window.show();
 
+    let preview = {
+        let mut previews = state.overlay_previews.lock();
+        *previews += 1;
+        *previews
+    };
+    std::thread::spawn(move || {
+        std::thread::sleep(OVERLAY_PREVIEW_DURATION);
+        let state = app.state::<AppState>();
+        if *state.overlay_previews.lock() == preview && state.session.state() == SessionState::Idle
+        {
+            let _ = window.hide();
+        }
+    });
     Ok(())
 }
 
-#[tauri::command]
-fn stop_recording_and_transcribe(app: AppHandle) -> Result<String, String> {
+/// How long a pause ends a dictation started by the wake phrase when the
+/// silence auto-stop is turned off, since nothing else would end it.
+const WOKEN_SILENCE: Duration = Duration::from_secs(2);
+
+/// The longest a dictation started by the wake phrase records without a
+/// maximum duration set, in case the room never goes quiet enough to end it.
+const WOKEN_MAX_RECORDING: Duration = Duration::from_secs(60);
+
+/// Starts recording a dictation: shows the overlay (or plays the cue) and
+/// streams levels until the recording stops. A dictation `woken` by the wake
+/// phrase has no shortcut to release, so it stops at the first pause as in
+/// hands-free mode, and after `WOKEN_MAX_RECORDING` at the latest.
+fn begin_dictation(app: &AppHandle, woken: bool) {
     let state = app.state::<AppState>();
+    if let Err(e) = state.session.start_dictation(app) {
+        eprintln!("Not starting a dictation: {}", e);
+        return;
+    }
+    let press = {
+        let mut presses = state.dictation_presses.lock();
+        *presses += 1;
+        *presses
+    };
+    // Dictating again means none of the reviewed alternatives was wanted; the
+    // best one still goes to history.
+    if state.review.lock().is_some() {
+        let _ = resolve_review(app, ReviewAction::Discard);
+    }
 
-    let samples = {
-        let recorder_lock = state.recorder.lock();
-        let mut is_recording = state.is_recording.lock();
+    // Capture before the overlay appears so it can't be mistaken for the
+    // dictation target. A continued dictation keeps the context of its first
+    // press.
+    let spilled = state.spilled_dictation.lock().is_some();
+    if !spilled && state.pending_dictation.lock().is_empty() {
+        *state.recording_context.lock() = if *state.capture_window_context.lock() {
+            frontmost_window()
+        } else {
+            None
+        };
+    }
 
-        if !*is_recording {
-            return Err("Not recording".to_string());
+    let silent = !*state.show_overlay.lock();
+    if silent || state.settings.lock().sound_cues.with_overlay {
+        sounds::play(app, Cue::RecordingStarted);
+    }
+    if silent {
+        tray::set_tooltip(app, "Voice - Recording…");
+    } else if let Some(window) = app.get_webview_window("overlay") {
+        let _ = window.show();
+        place_overlay(&window, 240.0, 80.0);
+    }
+    let profile = state.settings.lock().active_profile().cloned();
+    let _ = emit(app, "recording-started", profile);
+    // OBS captions are only live if partials are produced.
+    let partials = {
+        let settings = state.settings.lock();
+        settings.streaming_transcription || settings.obs.enabled
+    };
+    if partials || state.caption_translation.lock().is_some() {
+        spawn_partial_transcription(app.clone());
+    }
+    let app_clone = app.clone();
+    std::thread::spawn(move || {
+        announce_call(&app_clone);
+        let state = app_clone.state::<AppState>();
+        let mut recorder_lock = state.recorder.lock();
+        // The shortcut may have been released while the call announcement
+        // played, which ends the dictation before there's anything to record.
+        if !state.session.is_recording() || *state.dictation_presses.lock() != press {
+            return;
+        }
+        if recorder_lock.is_none() {
+            match open_recorder(&state) {
+                Ok(rec) => *recorder_lock = Some(rec),
+                Err(e) => {
+                    eprintln!("Failed to create recorder: {}", e);
+                    state.session.reset(&app_clone);
+                    return;
+                }
+            }
         }
 
-        *is_recording = false;
+        let (level_tx, level_rx) = channel::<f32>();
+        let max_duration = match state.settings.lock().max_recording_secs {
+            Some(secs) => Some(Duration::from_secs(secs)),
+            None => woken.then_some(WOKEN_MAX_RECORDING),
+        };
 
         if let Some(recorder) = recorder_lock.as_ref() {
-            let samples = recorder.stop_recording()?;
-            let sample_rate = recorder.sample_rate();
-            let channels = recorder.channels();
-            convert_to_whisper_format(samples, sample_rate, channels)
-        } else {
-            return Err("No recorder available".to_string());
+            let started = match max_duration {
+                Some(limit) => recorder.start_recording_with_limit(Some(level_tx), limit),
+                None => recorder.start_recording(Some(level_tx)),
+            };
+            if let Err(e) = started {
+                eprintln!("Failed to start recording: {}", e);
+                state.session.reset(&app_clone);
+                return;
+            }
         }
-    };
+        drop(recorder_lock);
 
-    if samples.is_empty() {
-        return Err("No audio recorded".to_string());
-    }
+        let (silence_ms, hands_free) = {
+            let settings = state.settings.lock();
+            (settings.auto_stop_silence_ms, settings.hands_free)
+        };
+        let silence = match silence_ms {
+            0 => woken.then_some(WOKEN_SILENCE),
+            ms => (woken || hands_free).then(|| Duration::from_millis(ms)),
+        };
+        let mut detector = silence.map(SilenceDetector::new);
+        let mut last_level = Instant::now();
+        let held_since = Instant::now();
+        let mut last_poll = Instant::now();
+        let mut last_memory_check = Instant::now();
+        let mut misses = 0;
+        while let Ok(rms) = level_rx.recv() {
+            let _ = emit(&app_clone, "audio-level", (rms * 25.0).min(1.0));
+            if last_poll.elapsed() >= HOLD_POLL_INTERVAL {
+                last_poll = Instant::now();
+                if release_missed(&state, held_since, &mut misses) {
+                    eprintln!("Shortcut no longer held; stopping the recording");
+                    *state.shortcut_held.lock() = false;
+                    finish_dictation(&app_clone);
+                }
+            }
+            if last_memory_check.elapsed() >= RECORDING_MEMORY_CHECK_INTERVAL {
+                last_memory_check = Instant::now();
+                enforce_memory_cap(&app_clone);
+            }
+            let elapsed = std::mem::replace(&mut last_level, Instant::now())
+                .elapsed();
+            if detector.as_mut().is_some_and(|d| d.push(rms, elapsed)) {
+                detector = None;
+                let _ = emit(&app_clone, "recording-auto-stopped", ());
+                finish_dictation(&app_clone);
+            }
+        }
 
-    let transcriber_lock = state.transcriber.lock();
-    if let Some(transcriber) = transcriber_lock.as_ref() {
-        transcriber.transcribe(&samples)
-    } else {
-        Err("Transcriber not initialized".to_string())
-    }
+        // The levels end when the recorder stops capturing by itself too.
+        let limited = state
+            .recorder
+            .lock()
+            .as_ref()
+            .is_some_and(AudioRecorder::limit_reached);
+        if limited && state.session.is_recording() {
+            eprintln!("Dictation reached its maximum duration; stopping the recording");
+            let max_secs = max_duration.map(|limit| limit.as_secs());
+            let _ = emit(&app_clone, "recording-max-duration-reached", max_secs);
+            finish_dictation(&app_clone);
+        }
+    });
 }
 
-#[tauri::command]
-fn paste_text(app: AppHandle, text: String) -> Result<(), String> {
-    app.clipboard()
-        .write_text(&text)
-        .map_err(|e| format!("Failed to copy to clipboard: {}", e))?;
+/// How many alternatives a reviewed dictation offers, one per number key.
+const REVIEW_ALTERNATIVES: usize = 5;
 
-    println!("Text copied to clipboard: {}", text);
+/// How long a review waits for a key before delivering the best result.
+const REVIEW_TIMEOUT: Duration = Duration::from_secs(15);
 
-    // Text is in clipboard - user can paste with Cmd+V
-    // Auto-paste disabled for stability - requires accessibility permissions
+/// The number keys that pick each alternative, on the main row and the keypad.
+const PICK_KEYS: [(Code, Code); REVIEW_ALTERNATIVES] = [
+    (Code::Digit1, Code::Numpad1),
+    (Code::Digit2, Code::Numpad2),
+    (Code::Digit3, Code::Numpad3),
+    (Code::Digit4, Code::Numpad4),
+    (Code::Digit5, Code::Numpad5),
+];
 
-    Ok(())
+struct Review {
+    press: u64,
+    draft: Draft,
+    /// Registered for the review only and released once it is resolved.
+    shortcuts: Vec<Shortcut>,
 }
 
-#[tauri::command]
-fn get_audio_devices() -> Result<Vec<String>, String> {
-    list_input_devices()
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+enum ReviewAction {
+    Pick { index: usize },
+    Rerecord,
+    Discard,
 }
 
-#[tauri::command]
-fn get_current_device(app: AppHandle) -> Option<String> {
-    let state = app.state::<AppState>();
-    let device = state.selected_device.lock().clone();
-    device
+fn review_action_for(key: Code) -> Option<ReviewAction> {
+    match key {
+        Code::Digit0 | Code::Numpad0 | Code::Escape => Some(ReviewAction::Discard),
+        Code::Digit9 | Code::Numpad9 => Some(ReviewAction::Rerecord),
+        _ => PICK_KEYS
+            .iter()
+            .position(|&(digit, numpad)| key == digit || key == numpad)
+            .map(|index| ReviewAction::Pick { index }),
+    }
 }
 
-#[tauri::command]
-fn set_audio_device(app: AppHandle, device_name: Option<String>) -> Result<(), String> {
+/// Shows the alternatives in the overlay and claims the number keys until
+/// one is chosen or the review times out.
+fn start_review(app: &AppHandle, draft: Draft) {
     let state = app.state::<AppState>();
-    *state.selected_device.lock() = device_name;
-    *state.recorder.lock() = None;
-    Ok(())
-}
+    let press = *state.dictation_presses.lock();
+    let mut keys = vec![Code::Digit0, Code::Numpad0, Code::Escape, Code::Digit9, Code::Numpad9];
+    let alternatives = &draft.alternatives;
+    for &(digit, numpad) in PICK_KEYS.iter().take(alternatives.len()) {
+        keys.extend([digit, numpad]);
+    }
+    let shortcuts: Vec<Shortcut> = keys.into_iter().map(|key| Shortcut::new(None, key)).collect();
 
-#[tauri::command]
-fn get_model_info(app: AppHandle) -> (String, bool) {
-    let state = app.state::<AppState>();
-    let size = *state.selected_model.lock();
-    let downloaded = is_model_downloaded(size);
-    (format!("{:?}", size).to_lowercase(), downloaded)
-}
+    if let Some(window) = app.get_webview_window("overlay") {
+        place_overlay(&window, 360.0, 96.0 + 28.0 * alternatives.len() as f64);
+    }
+    let texts: Vec<&str> = alternatives.iter().map(|result| result.text.as_str()).collect();
+    let _ = emit(app, "transcription-review", texts);
+    *state.review.lock() = Some(Review {
+        press,
+        draft,
+        shortcuts: shortcuts.clone(),
+    });
 
-#[tauri::command]
-fn get_available_models() -> Vec<(String, String, bool)> {
-    vec![
-        ("small".to_string(), "Small (~500MB) - Fast".to_string(), is_model_downloaded(ModelSize::Small)),
-        ("medium".to_string(), "Medium (~1.5GB) - Balanced".to_string(), is_model_downloaded(ModelSize::Medium)),
-        ("large".to_string(), "Large (~3GB) - Accurate".to_string(), is_model_downloaded(ModelSize::Large)),
-    ]
+    let registered = app.global_shortcut().on_shortcuts(shortcuts, {
+        let app = app.clone();
+        move |_app_handle, shortcut, event| {
+            // Act on release so the key is up before anything is pasted, and
+            // off the shortcut thread since resolving unregisters these keys.
+            if !matches!(event.state, ShortcutState::Released) {
+                return;
+            }
+            if let Some(action) = review_action_for(shortcut.key) {
+                let app = app.clone();
+                std::thread::spawn(move || {
+                    let _ = resolve_review(&app, action);
+                });
+            }
+        }
+    });
+    if let Err(e) = registered {
+        // Without the keys nothing could be chosen, so don't hold the text.
+        eprintln!("Failed to register review keys: {}", e);
+        let _ = resolve_review(app, ReviewAction::Pick { index: 0 });
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(REVIEW_TIMEOUT);
+        let pending = app.state::<AppState>().review.lock().as_ref().map(|r| r.press);
+        if pending == Some(press) {
+            let _ = resolve_review(&app, ReviewAction::Pick { index: 0 });
+        }
+    });
 }
 
-#[tauri::command]
-async fn set_model_size(app: AppHandle, size: String) -> Result<(), String> {
-    let model_size = match size.as_str() {
-        "small" => ModelSize::Small,
-        "medium" => ModelSize::Medium,
-        "large" => ModelSize::Large,
-        _ => return Err("Invalid model size".to_string()),
-    };
+/// Keeps the best alternative of a review that delivered nothing in history,
+/// shaped only by the text rules and replacements.
+fn record_best(app: &AppHandle, draft: &Draft) {
+    let best = &draft.alternatives[0];
+    record_history(app, best, draft.sample_count, draft.recording_id.as_deref());
+}
 
+/// Ends the pending review, delivering the picked alternative, dropping the
+/// result, or recording again.
+fn resolve_review(app: &AppHandle, action: ReviewAction) -> Result<(), String> {
     let state = app.state::<AppState>();
-    *state.selected_model.lock() = model_size;
+    let review = {
+        let mut pending = state.review.lock();
+        if let (ReviewAction::Pick { index }, Some(review)) = (action, pending.as_ref()) {
+            if index >= review.draft.alternatives.len() {
+                return Err(format!("No alternative {}", index + 1));
+            }
+        }
+        pending
+            .take()
+            .ok_or_else(|| "No transcription is waiting for review".to_string())?
+    };
+    if let Err(e) = app.global_shortcut().unregister_multiple(review.shortcuts) {
+        eprintln!("Failed to release review keys: {}", e);
+    }
+    if let Some(window) = app.get_webview_window("overlay") {
+        place_overlay(&window, 240.0, 80.0);
+    }
 
-    if is_model_downloaded(model_size) {
-        let model_path = get_model_path(model_size);
-        let transcriber = WhisperTranscriber::new(&model_path)?;
-        *state.transcriber.lock() = Some(transcriber);
+    match action {
+        ReviewAction::Pick { index } => {
+            let mut draft = review.draft;
+            let picked = finish_draft(app, &mut draft, index)
+                .map(|()| draft.alternatives.swap_remove(index));
+            match picked {
+                Ok(result) => {
+                    let text = result.text.clone();
+                    *state.last_transcription.lock() = Some(text.clone());
+                    let delivering = state
+                        .session
+                        .transition(app, SessionState::Idle, SessionState::Delivering)
+                        .is_ok();
+                    match deliver_text(app, &text) {
+                        Ok(_) => println!("Delivered transcription"),
+                        Err(e) => eprintln!("Output error: {}", e),
+                    }
+                    if delivering {
+                        state.session.reset(app);
+                    }
+                    send_obs_caption(app, &text);
+                    let _ = emit(app, "transcription-complete", result);
+                }
+                Err(e) => {
+                    eprintln!("Transcription error: {}", e);
+                    let _ = emit(app, "transcription-error", e);
+                }
+            }
+        }
+        ReviewAction::Discard => {
+            record_best(app, &review.draft);
+            let _ = emit(app, "transcription-discarded", ());
+        }
+        ReviewAction::Rerecord => {
+            record_best(app, &review.draft);
+            let _ = emit(app, "transcription-discarded", ());
+            if !state.permissions.lock().microphone {
+                let _ = emit(app, "recording-blocked", MICROPHONE_DENIED.to_string());
+            } else if state.session.state() == SessionState::Idle {
+                begin_dictation(app, false);
+                return Ok(());
+            }
+        }
     }
 
+    let app = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(1200));
+        let state = app.state::<AppState>();
+        if state.review.lock().is_some() || state.session.state() != SessionState::Idle {
+            return;
+        }
+        if let Some(window) = app.get_webview_window("overlay") {
+            let _ = window.hide();
+        }
+    });
     Ok(())
 }
 
-#[tauri::command]
-async fn download_model_size(app: AppHandle, size: String) -> Result<(), String> {
-    let model_size = match size.as_str() {
-        "small" => ModelSize::Small,
-        "medium" => ModelSize::Medium,
-        "large" => ModelSize::Large,
-        _ => return Err("Invalid model size".to_string()),
+/// Stops a dictation and transcribes and delivers it in the background,
+/// unless another press continues it within the continuation window.
+fn finish_dictation(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let window_ms = *state.continuation_window_ms.lock();
+    let next = if window_ms > 0 {
+        SessionState::Waiting
+    } else {
+        SessionState::Transcribing
     };
+    if state
+        .session
+        .transition(app, SessionState::Recording, next)
+        .is_err()
+    {
+        return;
+    }
+    let _ = emit(app, "recording-stopped", ());
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let state = app.state::<AppState>();
+        let press = *state.dictation_presses.lock();
+        match stop_recording(&app) {
+            Ok((samples, dropouts)) => {
+                state.dictation_dropouts.lock().add(dropouts, samples.len());
+                state.pending_dictation.lock().extend(samples);
+            }
+            Err(e) => eprintln!("Failed to stop recording: {}", e),
+        }
 
-    let app_clone = app.clone();
-    download_model(model_size, move |downloaded, total| {
-        let _ = app_clone.emit("model-download-progress", (size.clone(), downloaded, total));
-    })
-    .await?;
+        // Give the user a moment to press again and keep talking. That press
+        // can start recording while the session is waiting; whichever of the
+        // two leaves waiting first wins.
+        if next == SessionState::Waiting {
+            enforce_memory_cap(&app);
+            std::thread::sleep(Duration::from_millis(window_ms));
+            if *state.dictation_presses.lock() != press
+                || state
+                    .session
+                    .transition(&app, SessionState::Waiting, SessionState::Transcribing)
+                    .is_err()
+            {
+                return;
+            }
+        }
 
-    let state = app.state::<AppState>();
-    if *state.selected_model.lock() == model_size {
-        let model_path = get_model_path(model_size);
-        let transcriber = WhisperTranscriber::new(&model_path)?;
-        *state.transcriber.lock() = Some(transcriber);
-    }
+        let silent = !*state.show_overlay.lock();
+        let chime = silent || state.settings.lock().sound_cues.with_overlay;
+        if chime {
+            sounds::play(&app, Cue::RecordingStopped);
+        }
+        if silent {
+            tray::set_tooltip(&app, "Voice - Transcribing…");
+        }
 
-    Ok(())
+        let _ = emit(&app, "transcription-started", ());
+        let samples = take_pending_dictation(&state);
+        report_dictation_dropouts(&app);
+
+        let review = !silent && state.settings.lock().review_results;
+        let count = if review { REVIEW_ALTERNATIVES } else { 1 };
+        let transcribed = transcribe_alternatives(&app, samples, count).and_then(|mut draft| {
+            // A review finishes only the alternative that is picked.
+            if !review && !draft.alternatives.is_empty() {
+                finish_draft(&app, &mut draft, 0)?;
+            }
+            Ok(draft)
+        });
+        // `None` once feedback for the outcome has already been given.
+        let succeeded = match transcribed {
+            Ok(draft) if draft.alternatives.is_empty() => {
+                println!("No speech detected");
+                match state.settings.lock().no_speech {
+                    NoSpeechFeedback::Notify => {
+                        let _ = emit(
+                            &app,
+                            "transcription-error",
+                            "No speech detected".to_string(),
+                        );
+                        Some(false)
+                    }
+                    NoSpeechFeedback::Sound => {
+                        let _ = emit(&app, "transcription-discarded", ());
+                        sounds::play(&app, Cue::TranscriptionFailed);
+                        None
+                    }
+                    NoSpeechFeedback::Silent => {
+                        let _ = emit(&app, "transcription-discarded", ());
+                        None
+                    }
+                }
+            }
+            Ok(draft) if review => {
+                println!("Transcribed: {}", draft.alternatives[0].text);
+                // The review waits on the user, not the pipeline.
+                state.session.reset(&app);
+                start_review(&app, draft);
+                return;
+            }
+            Ok(mut draft) => {
+                let result = draft.alternatives.swap_remove(0);
+                let text = result.text.clone();
+                println!("Transcribed: {}", text);
+                let _ = state.session.transition(
+                    &app,
+                    SessionState::Transcribing,
+                    SessionState::Delivering,
+                );
+                *state.last_transcription.lock() = Some(text.clone());
+                match deliver_text(&app, &text) {
+                    Ok(_) => println!("Delivered transcription"),
+                    Err(e) => eprintln!("Output error: {}", e),
+                }
+                send_obs_caption(&app, &text);
+                let _ = emit(&app, "transcription-complete", result);
+                Some(true)
+            }
+            Err(e) => {
+                eprintln!("Transcription error: {}", e);
+                let _ = emit(&app, "transcription-error", e);
+                Some(false)
+            }
+        };
+        state.session.reset(&app);
+        enforce_memory_cap(&app);
+
+        if chime {
+            match succeeded {
+                Some(true) => sounds::play(&app, Cue::TranscriptionComplete),
+                Some(false) => sounds::play(&app, Cue::TranscriptionFailed),
+                None => {}
+            }
+        }
+        if silent {
+            tray::set_tooltip(&app, tray::TOOLTIP);
+        }
+
+        // Hide window after a delay, unless the next dictation has begun.
+        std::thread::sleep(std::time::Duration::from_millis(1200));
+        if state.session.state() != SessionState::Idle {
+            return;
+        }
+        if let Some(window) = app.get_webview_window("overlay") {
+            let _ = window.hide();
+        }
+    });
 }
 
-#[tauri::command]
-fn check_microphone_permission() -> bool {
+fn setup_global_shortcut(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
     #[cfg(target_os = "macos")]
-    {
-        true
-    }
+    let shortcut = Shortcut::new(Some(Modifiers::SHIFT | Modifiers::META), Code::Space);
+
     #[cfg(not(target_os = "macos"))]
-    {
-        true
-    }
+    let shortcut = Shortcut::new(Some(Modifiers::SHIFT | Modifiers::CONTROL), Code::Space);
+
+    app.global_shortcut().on_shortcut(shortcut, {
+        let app = app.clone();
+        move |_app_handle, _shortcut, event| match event.state {
+            ShortcutState::Pressed => dictation_shortcut_pressed(&app),
+            ShortcutState::Released => dictation_shortcut_released(&app),
+        }
+    })?;
+
+    Ok(())
 }
 
-#[tauri::command]
-fn check_accessibility_permission() -> bool {
-    #[cfg(target_os = "macos")]
-    {
-        use std::process::Command;
-        let output = Command::new("osascript")
-            .args(["-e", "tell application \"System Events\" to return true"])
-            .output();
-        output.map(|o| o.status.success()).unwrap_or(false)
+fn dictation_shortcut_pressed(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    if std::mem::replace(&mut *state.shortcut_held.lock(), true) {
+        return;
     }
-    #[cfg(not(target_os = "macos"))]
-    {
-        true
+    let is_recording = state.session.is_recording();
+    if is_recording && state.settings.lock().hands_free {
+        finish_dictation(app);
+    } else if !is_recording && !state.permissions.lock().microphone {
+        eprintln!("{}", MICROPHONE_DENIED);
+        let _ = emit(app, "recording-blocked", MICROPHONE_DENIED.to_string());
+    } else if !is_recording {
+        begin_dictation(app, false);
     }
 }
 
-#[tauri::command]
-fn open_accessibility_settings() -> Result<(), String> {
-    #[cfg(target_os = "macos")]
-    {
-        std::process::Command::new("open")
-            .arg("x-apple.systempreferences:com.apple.preference.security?Privacy_Accessibility")
-            .spawn()
-            .map_err(|e| e.to_string())?;
+/// Forgets the shortcut being held once a session ends, so a key-up that
+/// never arrived can't swallow the next press. Keys that are still down keep
+/// it, so their key repeat doesn't start another dictation.
+pub(crate) fn session_ended(app: &AppHandle) {
+    if chord_held(DICTATION_CHORD) != Some(true) {
+        *app.state::<AppState>().shortcut_held.lock() = false;
     }
-    Ok(())
 }
 
-#[tauri::command]
-fn open_microphone_settings() -> Result<(), String> {
-    #[cfg(target_os = "macos")]
-    {
-        std::process::Command::new("open")
-            .arg("x-apple.systempreferences:com.apple.preference.security?Privacy_Microphone")
-            .spawn()
-            .map_err(|e| e.to_string())?;
+fn dictation_shortcut_released(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    *state.shortcut_held.lock() = false;
+    // In hands-free mode the next press or silence stops it.
+    if !state.settings.lock().hands_free {
+        finish_dictation(app);
     }
-    Ok(())
 }
 
-#[tauri::command]
-fn close_settings_window(app: AppHandle) {
-    if let Some(window) = app.get_webview_window("settings") {
-        let _ = window.hide();
-    }
+/// A release of the same shortcut this soon after the last is the other
+/// source's copy of it.
+const SHORTCUT_ECHO: Duration = Duration::from_millis(300);
+
+/// Whether this release of the memo or repaste shortcut should be acted on.
+/// Under Wayland an X11 app with focus can see the keys through the plugin
+/// while the portal reports them too.
+fn shortcut_released(app: &AppHandle, id: &'static str) -> bool {
+    let state = app.state::<AppState>();
+    let mut last = state.last_shortcut_release.lock();
+    let now = Instant::now();
+    let echo =
+        last.is_some_and(|(last_id, at)| last_id == id && now.duration_since(at) < SHORTCUT_ECHO);
+    *last = Some((id, now));
+    !echo
 }
 
-fn setup_global_shortcut(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+/// Wayland compositors only hand shortcuts pressed in other apps to the
+/// GlobalShortcuts portal, so the dictation, memo and repaste shortcuts are
+/// bound there in one session; the plugin's shortcuts still work while an
+/// X11 app has focus, and `shortcut_held` and `shortcut_released` keep the
+/// two from doubling up. The desktop may ask the user to confirm the
+/// triggers or pick others, so this can block until they answer.
+#[cfg(target_os = "linux")]
+fn bind_portal_shortcuts(app: &AppHandle) -> Result<(), String> {
+    let shortcuts = [
+        portal::PortalShortcut {
+            id: "dictate",
+            description: "Hold to dictate",
+            preferred_trigger: "CTRL+SHIFT+space",
+        },
+        portal::PortalShortcut {
+            id: "repaste",
+            description: "Paste the last transcription again",
+            preferred_trigger: "SHIFT+ALT+space",
+        },
+        portal::PortalShortcut {
+            id: "memo",
+            description: "Start or stop a quick memo",
+            preferred_trigger: "SHIFT+ALT+m",
+        },
+    ];
+    let app = app.clone();
+    portal::bind_shortcuts(&shortcuts, move |id, pressed| match (id, pressed) {
+        ("dictate", true) => dictation_shortcut_pressed(&app),
+        ("dictate", false) => dictation_shortcut_released(&app),
+        ("repaste", false) => repaste_shortcut_released(&app),
+        ("memo", false) => memo_shortcut_released(&app),
+        _ => {}
+    })
+}
+
+/// Starts a quick memo, or files the one recording early. On macOS Option
+/// with a letter types a character (⌥⇧M is "Â"), which a global shortcut
+/// would take from every app, so there it is ⌃⌥M instead.
+fn setup_memo_shortcut(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
     #[cfg(target_os = "macos")]
-    let shortcut = Shortcut::new(Some(Modifiers::SHIFT | Modifiers::META), Code::Space);
+    let shortcut = Shortcut::new(Some(Modifiers::CONTROL | Modifiers::ALT), Code::KeyM);
 
     #[cfg(not(target_os = "macos"))]
-    let shortcut = Shortcut::new(Some(Modifiers::SHIFT | Modifiers::CONTROL), Code::Space);
+    let shortcut = Shortcut::new(Some(Modifiers::SHIFT | Modifiers::ALT), Code::KeyM);
 
     app.global_shortcut().on_shortcut(shortcut, {
         let app = app.clone();
         move |_app_handle, _shortcut, event| {
-            let state = app.state::<AppState>();
-
-            match event.state {
-                ShortcutState::Pressed => {
-                    let is_recording = *state.is_recording.lock();
-                    if !is_recording {
-                        if let Some(window) = app.get_webview_window("overlay") {
-                            let _ = window.show();
-                            // Position at bottom center of screen
-                            if let Ok(monitor) = window.current_monitor() {
-                                if let Some(monitor) = monitor {
-                                    let size = monitor.size();
-                                    let scale = monitor.scale_factor();
-                                    let win_width = 240.0;
-                                    let win_height = 80.0;
-                                    let x = (size.width as f64 / scale - win_width) / 2.0;
-                                    let y = size.height as f64 / scale - win_height - 200.0;
-                                    let _ = window.set_position(tauri::Position::Logical(tauri::LogicalPosition::new(x, y)));
-                                }
-                            }
-                        }
-                        let _ = app.emit("recording-started", ());
-                        let app_clone = app.clone();
-                        std::thread::spawn(move || {
-                            let state = app_clone.state::<AppState>();
-                            let mut recorder_lock = state.recorder.lock();
-                            if recorder_lock.is_none() {
-                                match AudioRecorder::new() {
-                                    Ok(rec) => *recorder_lock = Some(rec),
-                                    Err(e) => {
-                                        eprintln!("Failed to create recorder: {}", e);
-                                        return;
-                                    }
-                                }
-                            }
+            // On release, since key repeat sends more presses while it's held.
+            if let ShortcutState::Released = event.state {
+                memo_shortcut_released(&app);
+            }
+        }
+    })?;
 
-                            let (level_tx, level_rx) = channel::<f32>();
+    Ok(())
+}
 
-                            if let Some(recorder) = recorder_lock.as_ref() {
-                                if let Err(e) = recorder.start_recording(Some(level_tx)) {
-                                    eprintln!("Failed to start recording: {}", e);
-                                    return;
-                                }
-                                *state.is_recording.lock() = true;
-                            }
-                            drop(recorder_lock);
+fn memo_shortcut_released(app: &AppHandle) {
+    if !shortcut_released(app, "memo") {
+        return;
+    }
+    let recording = app.state::<AppState>().memo.lock().is_some();
+    let result = if recording {
+        stop_quick_memo(app.clone())
+    } else {
+        start_quick_memo(app.clone())
+    };
+    if let Err(e) = result {
+        eprintln!("Quick memo: {}", e);
+        let _ = emit(app, "memo-failed", e);
+    }
+}
 
-                            while let Ok(level) = level_rx.recv() {
-                                let _ = app_clone.emit("audio-level", level);
-                            }
-                        });
-                    }
-                }
-                ShortcutState::Released => {
-                    let is_recording = *state.is_recording.lock();
-                    if is_recording {
-                        let _ = app.emit("recording-stopped", ());
-                        let app_clone = app.clone();
-                        std::thread::spawn(move || {
-                            let _ = app_clone.emit("transcription-started", ());
-
-                            match stop_recording_and_transcribe(app_clone.clone()) {
-                                Ok(text) => {
-                                    println!("Transcribed: {}", text);
-                                    if !text.is_empty() && !text.contains("[BLANK_AUDIO]") {
-                                        match paste_text(app_clone.clone(), text.clone()) {
-                                            Ok(_) => println!("Copied to clipboard"),
-                                            Err(e) => eprintln!("Clipboard error: {}", e),
-                                        }
-                                        let _ = app_clone.emit("transcription-complete", text);
-                                    } else {
-                                        let _ = app_clone.emit("transcription-error", "No speech detected".to_string());
-                                    }
-                                }
-                                Err(e) => {
-                                    eprintln!("Transcription error: {}", e);
-                                    let _ = app_clone.emit("transcription-error", e);
-                                }
-                            }
+fn setup_repaste_shortcut(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let shortcut = Shortcut::new(Some(Modifiers::SHIFT | Modifiers::ALT), Code::Space);
 
-                            // Hide window after a delay
-                            std::thread::sleep(std::time::Duration::from_millis(1200));
-                            if let Some(window) = app_clone.get_webview_window("overlay") {
-                                let _ = window.hide();
-                            }
-                        });
-                    }
-                }
+    app.global_shortcut().on_shortcut(shortcut, {
+        let app = app.clone();
+        move |_app_handle, _shortcut, event| {
+            // Fire on release so the shortcut keys aren't mixed into the paste.
+            if let ShortcutState::Released = event.state {
+                repaste_shortcut_released(&app);
             }
         }
     })?;
@@ -387,6 +4501,15 @@ fn setup_global_shortcut(app: &AppHandle) -> Result<(), Box<dyn std::error::Erro
     Ok(())
 }
 
+fn repaste_shortcut_released(app: &AppHandle) {
+    if !shortcut_released(app, "repaste") {
+        return;
+    }
+    if let Err(e) = repaste_last_transcription(app.clone()) {
+        eprintln!("Repaste failed: {}", e);
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     // Set panic hook to log panics instead of crashing
@@ -398,23 +4521,194 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_dialog::init())
         .manage(AppState::default())
         .invoke_handler(tauri::generate_handler![
+            get_app_status,
+            get_recent_events,
             is_model_ready,
             download_whisper_model,
             init_transcriber,
             start_recording,
             stop_recording_and_transcribe,
             paste_text,
+            repaste_last_transcription,
             get_audio_devices,
             get_current_device,
             set_audio_device,
+            get_network_microphone,
+            set_network_microphone_address,
             get_model_info,
             get_available_models,
             set_model_size,
             download_model_size,
+            cancel_model_download,
+            get_model_decoding,
+            set_model_decoding,
+            get_model_upgrade,
+            start_model_upgrade,
+            get_transcription_language,
+            set_transcription_language,
+            get_download_config,
+            set_download_config,
+            install_language_pack,
+            import_model,
+            get_language_packs,
+            remove_language_pack,
+            get_installed_models,
+            delete_model,
+            get_shared_model_dir,
+            set_shared_model_dir,
+            list_replacements,
+            add_replacement,
+            remove_replacement,
+            get_text_rules,
+            set_text_rules,
+            get_punctuation_mode,
+            set_punctuation_mode,
+            get_pipeline,
+            set_pipeline,
+            respond_command_confirmation,
+            get_rtl_marks,
+            set_rtl_marks,
+            get_no_speech_feedback,
+            set_no_speech_feedback,
+            get_confirm_commands,
+            set_confirm_commands,
+            get_output_debounce,
+            set_output_debounce,
+            get_wake_word,
+            set_wake_word,
+            get_script,
+            set_script,
+            get_script_shell_enabled,
+            set_script_shell_enabled,
+            get_rpc_server_enabled,
+            set_rpc_server_enabled,
+            get_remote_control_enabled,
+            set_remote_control_enabled,
+            get_remote_pairing,
+            reset_remote_pairing,
+            get_api_schema,
+            list_actions,
+            invoke_action,
+            get_gpu_capability,
+            run_self_test,
+            transcribe_file,
+            transcribe_directory,
+            choose_export_path,
+            export_subtitles,
+            export_markdown,
+            get_chapter_llm,
+            set_chapter_llm,
+            start_quick_memo,
+            stop_quick_memo,
+            start_meeting_mode,
+            stop_meeting_mode,
+            get_memory_usage,
+            get_diagnostics,
+            get_data_version,
+            get_repair_dropouts,
+            set_repair_dropouts,
+            get_memory_cap,
+            set_memory_cap,
+            get_recording_memory_limit,
+            set_recording_memory_limit,
+            set_gpu_enabled,
+            get_transcription_backend,
+            set_transcription_backend,
+            get_accuracy_safeguard,
+            set_accuracy_safeguard,
+            get_suppression,
+            set_suppression,
+            get_vocabulary,
+            set_vocabulary,
+            export_dictionary_pack,
+            import_dictionary_pack,
+            list_dictionary_presets,
+            set_dictionary_preset,
+            get_history,
+            search_history,
+            delete_history_entry,
+            clear_history,
+            get_history_enabled,
+            set_history_enabled,
+            get_obs_config,
+            set_obs_config,
+            get_plugins,
+            install_plugin,
+            set_plugin_enabled,
+            remove_plugin,
+            get_show_overlay,
+            set_show_overlay,
+            get_hands_free,
+            set_hands_free,
+            get_auto_stop_silence,
+            set_auto_stop_silence,
+            get_review_results,
+            set_review_results,
+            review_action,
+            get_noise_suppression,
+            set_noise_suppression,
+            get_input_gain,
+            set_input_gain,
+            get_trim_silence,
+            set_trim_silence,
+            get_normalize_loudness,
+            set_normalize_loudness,
+            start_test_recording,
+            stop_test_recording,
+            retranscribe_test_recording,
+            start_caption_translation,
+            stop_caption_translation,
+            get_caption_translation,
+            get_streaming_transcription,
+            set_streaming_transcription,
+            get_continuation_window,
+            set_continuation_window,
+            get_retain_recordings,
+            set_retain_recordings,
+            get_recordings,
+            get_capture_window_context,
+            set_capture_window_context,
+            get_recording_audio,
+            seek_history_audio,
+            retranscribe_recording,
+            delete_recording,
+            get_feedback_enabled,
+            set_feedback_enabled,
+            submit_correction,
+            get_corrections,
+            export_feedback_dataset,
+            clear_feedback,
             check_microphone_permission,
             check_accessibility_permission,
+            request_microphone_permission,
+            request_accessibility_permission,
+            get_permission_status,
+            get_active_calls,
+            get_meeting_detection,
+            set_meeting_detection,
+            get_meeting_auto_start,
+            set_meeting_auto_start,
+            get_consent_settings,
+            set_consent_settings,
+            get_max_recording_duration,
+            set_max_recording_duration,
+            get_preroll_ms,
+            set_preroll_ms,
+            get_overlay_placement,
+            set_overlay_placement,
+            list_monitors,
+            preview_overlay_position,
+            get_profiles,
+            save_profile,
+            delete_profile,
+            get_active_profile,
+            set_active_profile,
+            get_sound_cues,
+            set_sound_cues,
+            preview_sound_cue,
             open_accessibility_settings,
             open_microphone_settings,
             close_settings_window,
@@ -422,54 +4716,78 @@ pub fn run() {
         .setup(|app| {
             let handle = app.handle().clone();
 
-            // Create system tray menu
-            let settings_item = MenuItem::with_id(app, "settings", "Settings...", true, None::<&str>)?;
-            let separator = PredefinedMenuItem::separator(app)?;
-            let quit_item = MenuItem::with_id(app, "quit", "Quit Voice", true, None::<&str>)?;
-            let menu = Menu::with_items(app, &[&settings_item, &separator, &quit_item])?;
-
-            // Create system tray
-            #[cfg(target_os = "macos")]
-            let tooltip = "Voice - ⇧⌘Space to record";
-
-            #[cfg(not(target_os = "macos"))]
-            let tooltip = "Voice - Shift+Ctrl+Space to record";
-
-            TrayIconBuilder::new()
-                .icon(app.default_window_icon().unwrap().clone())
-                .menu(&menu)
-                .tooltip(tooltip)
-                .on_menu_event(|app, event| {
-                    match event.id.as_ref() {
-                        "settings" => {
-                            if let Some(window) = app.get_webview_window("settings") {
-                                let _ = window.show();
-                                let _ = window.set_focus();
-                            }
-                        }
-                        "quit" => {
-                            app.exit(0);
-                        }
-                        _ => {}
-                    }
-                })
-                .build(app)?;
+            let tray = tray::build(&handle)?;
+            *handle.state::<AppState>().tray.lock() = Some(tray);
+
+            // Settings come first, since loading the model honours the GPU setting.
+            let mut recovered = Vec::new();
+            let mut settings = integrity::check_settings(&mut recovered);
+            load_secrets(&mut settings);
+            {
+                let state = handle.state::<AppState>();
+                *state.remote_transcriber.lock() = settings.backend.remote();
+                voice_core::transcription::set_shared_model_dir(settings.shared_model_dir.clone());
+                *state.settings.lock() = settings;
+                integrity::check_models(&state.model_manifest.lock(), &mut recovered);
+            }
+
+            let cpu = compute_config();
+            println!(
+                "Whisper CPU path: {} {}, {} threads",
+                cpu.arch, cpu.simd, cpu.threads
+            );
+            if cpu.translated {
+                eprintln!(
+                    "Running the Intel build under Rosetta; the Apple Silicon build is much faster"
+                );
+            }
 
             // Load transcriber if model exists
-            if is_model_downloaded(ModelSize::Small) {
-                let model_path = get_model_path(ModelSize::Small);
-                match WhisperTranscriber::new(&model_path) {
-                    Ok(transcriber) => {
-                        let state = handle.state::<AppState>();
-                        *state.transcriber.lock() = Some(transcriber);
+            match installed_startup_model() {
+                Some(size) => match load_model(&handle, ModelId::Official(size)) {
+                    Ok(()) => {
                         println!("Whisper model loaded successfully");
+                        if size == STARTER_MODEL {
+                            spawn_model_upgrade(handle.clone(), STARTER_MODEL, DEFAULT_MODEL);
+                        }
                     }
                     Err(e) => {
                         eprintln!("Failed to load Whisper model: {}", e);
                     }
+                },
+                None => {
+                    println!("Whisper model not found, will download on first use");
                 }
-            } else {
-                println!("Whisper model not found, will download on first use");
+            }
+
+            *handle.state::<AppState>().history.lock() = integrity::check_history(&mut recovered);
+            tray::show_recent(&handle);
+            if !recovered.is_empty() {
+                let _ = emit(&handle, "data-recovered", recovered);
+            }
+
+            spawn_permission_watcher(handle.clone());
+            spawn_meeting_watcher(handle.clone());
+            spawn_wake_word_listener(handle.clone());
+            #[cfg(target_os = "windows")]
+            spawn_device_watcher(handle.clone());
+            apply_preroll(&handle);
+
+            let rpc_enabled = handle.state::<AppState>().settings.lock().rpc_server;
+            if let Err(e) = rpc::apply_server(&handle, rpc_enabled) {
+                eprintln!("Failed to start RPC server: {}", e);
+            }
+
+            let remote_token = {
+                let state = handle.state::<AppState>();
+                let settings = state.settings.lock();
+                settings
+                    .remote_token
+                    .clone()
+                    .filter(|_| settings.remote_control)
+            };
+            if let Err(e) = remote::apply(&handle, remote_token.as_deref()) {
+                eprintln!("Failed to start remote control: {}", e);
             }
 
             // Setup global shortcut
@@ -483,6 +4801,35 @@ pub fn run() {
                 println!("Global shortcut registered: Shift+Ctrl+Space");
             }
 
+            if let Err(e) = setup_repaste_shortcut(&handle) {
+                eprintln!("Failed to setup repaste shortcut: {}", e);
+            } else {
+                #[cfg(target_os = "macos")]
+                println!("Repaste shortcut registered: ⌥⇧Space");
+
+                #[cfg(not(target_os = "macos"))]
+                println!("Repaste shortcut registered: Shift+Alt+Space");
+            }
+
+            if let Err(e) = setup_memo_shortcut(&handle) {
+                eprintln!("Failed to setup memo shortcut: {}", e);
+            } else {
+                #[cfg(target_os = "macos")]
+                println!("Memo shortcut registered: ⌃⌥M");
+
+                #[cfg(not(target_os = "macos"))]
+                println!("Memo shortcut registered: Shift+Alt+M");
+            }
+
+            #[cfg(target_os = "linux")]
+            if portal::wayland() {
+                let handle = handle.clone();
+                std::thread::spawn(move || match bind_portal_shortcuts(&handle) {
+                    Ok(()) => println!("Shortcuts bound through the desktop portal"),
+                    Err(e) => eprintln!("{}", e),
+                });
+            }
+
             Ok(())
         })
         .build(tauri::generate_context!())
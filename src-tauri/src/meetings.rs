@@ -0,0 +1,373 @@
+use crate::events::emit;
+use crate::permissions::MICROPHONE_DENIED;
+use crate::postprocess::{apply_replacements, apply_rules};
+use crate::session::SessionState;
+use crate::{announce_call, open_recorder, with_history, with_transcriber, AppState};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use voice_core::audio::{AudioChunk, SilenceChunker, StreamingConverter, WHISPER_SAMPLE_RATE};
+use voice_core::transcription::{CancelToken, Segment, TranscriptionResult};
+
+/// How often meeting mode collects the audio recorded since it last looked.
+const MEETING_DRAIN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The history source of meeting transcripts.
+const MEETING_SOURCE: &str = "meeting";
+
+/// A stretch of a meeting between pauses, as `meeting-segment` reports it.
+#[derive(Clone, Serialize)]
+struct MeetingSegment {
+    /// Seconds from the start of the meeting.
+    start: f32,
+    end: f32,
+    text: String,
+}
+
+/// Where the meeting's transcript is kept. Each stretch is appended to it
+/// as it is transcribed rather than held here.
+#[derive(Default)]
+struct MeetingTranscript {
+    /// The history entry the transcript is kept in, once it has text.
+    history_id: Option<i64>,
+}
+
+/// Records until `stop`, transcribing the audio a stretch at a time as pauses
+/// end each one. Every stretch with speech is emitted as a `meeting-segment`
+/// and added to a single history entry, so nothing longer than one stretch is
+/// ever held in memory.
+pub fn start(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    if !state.permissions.lock().microphone {
+        return Err(MICROPHONE_DENIED.to_string());
+    }
+    state
+        .session
+        .transition(app, SessionState::Idle, SessionState::Meeting)?;
+
+    {
+        let mut recorder = state.recorder.lock();
+        if recorder.is_none() {
+            match open_recorder(&state) {
+                Ok(opened) => *recorder = Some(opened),
+                Err(e) => {
+                    state.session.reset(app);
+                    return Err(e);
+                }
+            }
+        }
+    }
+    let stop = CancelToken::default();
+    *state.meeting.lock() = Some(stop.clone());
+    let _ = emit(app, "meeting-started", ());
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        announce_call(&app);
+        run_meeting(&app, &stop);
+        let state = app.state::<AppState>();
+        *state.meeting_for_call.lock() = false;
+        state.session.reset(&app);
+        let _ = emit(&app, "meeting-stopped", ());
+    });
+    Ok(())
+}
+
+/// Ends meeting mode. The audio since the last pause is still transcribed
+/// before `meeting-stopped` is emitted.
+pub fn stop(app: &AppHandle) -> Result<(), String> {
+    let stop = app
+        .state::<AppState>()
+        .meeting
+        .lock()
+        .take()
+        .ok_or_else(|| "Meeting mode isn't running".to_string())?;
+    stop.cancel();
+    Ok(())
+}
+
+fn run_meeting(app: &AppHandle, stop: &CancelToken) {
+    let state = app.state::<AppState>();
+    let started = match state.recorder.lock().as_ref() {
+        Some(recorder) => recorder.start_recording(None),
+        None => Err("No recorder available".to_string()),
+    };
+    if let Err(e) = started {
+        eprintln!("Failed to start meeting mode: {}", e);
+        state.meeting.lock().take();
+        return;
+    }
+
+    // The driver's real rate is only known after a second or so of audio.
+    let mut converter: Option<(u32, StreamingConverter)> = None;
+    let mut chunker = SilenceChunker::default();
+    let mut transcript = MeetingTranscript::default();
+    loop {
+        std::thread::sleep(MEETING_DRAIN_INTERVAL);
+        let stopping = stop.is_cancelled();
+        let recorded = match state.recorder.lock().as_ref() {
+            Some(recorder) if stopping => recorder.stop_recording(),
+            Some(recorder) => recorder.drain(),
+            None => Err("The recorder was closed".to_string()),
+        };
+        let mut recorded = match recorded {
+            Ok(recorded) => recorded,
+            Err(e) => {
+                eprintln!("Meeting mode stopped: {}", e);
+                state.meeting.lock().take();
+                let chunks: Vec<AudioChunk> = chunker.finish().into_iter().collect();
+                transcribe_meeting_chunks(app, &mut transcript, chunks);
+                return;
+            }
+        };
+        *state.last_capture.lock() = Some(recorded.stats.clone());
+        if state.settings.lock().repair_dropouts {
+            recorded.repair_dropouts();
+        }
+
+        let mut samples = Vec::new();
+        if converter.as_ref().map(|(rate, _)| *rate) != Some(recorded.sample_rate) {
+            if let Some((_, mut previous)) = converter.take() {
+                previous.finish(&mut samples);
+            }
+            let fresh = StreamingConverter::new(recorded.sample_rate, recorded.channels);
+            converter = Some((recorded.sample_rate, fresh));
+        }
+        if let Some((_, converter)) = converter.as_mut() {
+            converter.push(&recorded.samples, &mut samples);
+            if stopping {
+                converter.finish(&mut samples);
+            }
+        }
+
+        let mut chunks = chunker.push(&samples);
+        if stopping {
+            chunks.extend(chunker.finish());
+        }
+        transcribe_meeting_chunks(app, &mut transcript, chunks);
+        if stopping {
+            return;
+        }
+    }
+}
+
+fn transcribe_meeting_chunks(
+    app: &AppHandle,
+    transcript: &mut MeetingTranscript,
+    chunks: Vec<AudioChunk>,
+) {
+    let state = app.state::<AppState>();
+    let rate = WHISPER_SAMPLE_RATE as f32;
+    for chunk in chunks {
+        let end = chunk.start + chunk.samples.len() as f32 / rate;
+        let pipeline = state.settings.lock().audio;
+        let (samples, trimmed) = pipeline.process_timed(chunk.samples, WHISPER_SAMPLE_RATE, 1);
+        let offset = chunk.start + trimmed;
+        let language = state.transcription_language.lock().clone();
+        let result = match with_transcriber(&state, |transcriber| {
+            transcriber.transcribe_detailed(&samples, &language)
+        }) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("Meeting transcription failed: {}", e);
+                continue;
+            }
+        };
+        if result.text.is_empty() {
+            continue;
+        }
+
+        let rules = state.settings.lock().text_rules;
+        let (_, replacements) = state.settings.lock().dictionary();
+        let text = apply_replacements(
+            &apply_rules(&result.text, &rules, result.language.as_deref()),
+            &replacements,
+        );
+        let stretch = TranscriptionResult {
+            text: text.clone(),
+            segments: result
+                .segments
+                .into_iter()
+                .map(|segment| Segment {
+                    start: segment.start + offset,
+                    end: segment.end + offset,
+                    ..segment
+                })
+                .collect(),
+            language: result.language,
+        };
+        let _ = emit(
+            app,
+            "meeting-segment",
+            MeetingSegment {
+                start: chunk.start,
+                end,
+                text,
+            },
+        );
+        if let Err(e) = save_meeting_transcript(app, transcript, &stretch, end) {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+/// Appends a stretch to the meeting's history entry, creating the entry on
+/// the first stretch with speech. `end` is how far into the meeting it ends.
+fn save_meeting_transcript(
+    app: &AppHandle,
+    transcript: &mut MeetingTranscript,
+    result: &TranscriptionResult,
+    end: f32,
+) -> Result<(), String> {
+    if !app.state::<AppState>().settings.lock().history {
+        return Ok(());
+    }
+    let duration_ms = (end * 1000.0) as u64;
+    match transcript.history_id {
+        Some(id) => with_history(app, |history| history.append(id, result, duration_ms)),
+        None => {
+            let state = app.state::<AppState>();
+            let model = state.selected_model.lock().key();
+            let device = state.selected_device.lock().clone();
+            let entry = with_history(app, |history| {
+                history.record(
+                    result,
+                    duration_ms,
+                    &model,
+                    device.as_deref(),
+                    None,
+                    Some(MEETING_SOURCE),
+                )
+            })?;
+            transcript.history_id = Some(entry.id);
+            Ok(())
+        }
+    }
+}
+
+/// A conferencing app, recognised by the process or package names it
+/// captures the microphone under.
+struct ConferencingApp {
+    name: &'static str,
+    /// Lowercase substrings matched against capturing process names.
+    identifiers: &'static [&'static str],
+}
+
+const KNOWN_APPS: &[ConferencingApp] = &[
+    ConferencingApp {
+        name: "Zoom",
+        // CptHost only runs while a Zoom meeting is open.
+        identifiers: &["zoom", "cpthost"],
+    },
+    ConferencingApp {
+        name: "Microsoft Teams",
+        identifiers: &["teams"],
+    },
+    ConferencingApp {
+        name: "Webex",
+        identifiers: &["webex", "ciscocollabhost"],
+    },
+    ConferencingApp {
+        name: "Slack",
+        identifiers: &["slack"],
+    },
+    ConferencingApp {
+        name: "Discord",
+        identifiers: &["discord"],
+    },
+    ConferencingApp {
+        // Google Meet and other web calls show up as the browser.
+        name: "Browser call",
+        identifiers: &["chrome", "firefox", "msedge", "brave", "safari"],
+    },
+];
+
+/// Conferencing apps that are in a call right now, judged by which processes
+/// hold the microphone. Each app is listed once.
+pub fn active_calls() -> Vec<&'static str> {
+    let clients: Vec<String> = microphone_clients()
+        .into_iter()
+        .map(|client| client.to_lowercase())
+        .collect();
+
+    KNOWN_APPS
+        .iter()
+        .filter(|app| {
+            clients
+                .iter()
+                .any(|client| app.identifiers.iter().any(|id| client.contains(id)))
+        })
+        .map(|app| app.name)
+        .collect()
+}
+
+/// Names of the processes capturing audio input, from PulseAudio/PipeWire.
+#[cfg(target_os = "linux")]
+fn microphone_clients() -> Vec<String> {
+    let Ok(output) = std::process::Command::new("pactl")
+        .args(["list", "source-outputs"])
+        .output()
+    else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("application.process.binary = "))
+        .map(|binary| binary.trim_matches('"').to_string())
+        .collect()
+}
+
+/// Apps Windows lists as currently using the microphone: their consent store
+/// entry has a `LastUsedTimeStop` of zero while capture is ongoing.
+#[cfg(target_os = "windows")]
+fn microphone_clients() -> Vec<String> {
+    let Ok(output) = std::process::Command::new("reg")
+        .args([
+            "query",
+            r"HKCU\Software\Microsoft\Windows\CurrentVersion\CapabilityAccessManager\ConsentStore\microphone",
+            "/s",
+            "/v",
+            "LastUsedTimeStop",
+        ])
+        .output()
+    else {
+        return Vec::new();
+    };
+
+    let mut clients = Vec::new();
+    let mut current_key = "";
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for line in stdout.lines() {
+        if line.starts_with("HKEY_") {
+            // Desktop apps are keyed by their exe path with `#` separators,
+            // packaged apps by their package family name.
+            current_key = line.rsplit(['\\', '#']).next().unwrap_or(line);
+        } else if line.trim().starts_with("LastUsedTimeStop") && line.trim().ends_with("0x0") {
+            clients.push(current_key.to_string());
+        }
+    }
+    clients
+}
+
+/// macOS doesn't say which app holds the microphone, so this looks for
+/// helper processes that only run during a call.
+#[cfg(target_os = "macos")]
+fn microphone_clients() -> Vec<String> {
+    let Ok(output) = std::process::Command::new("ps")
+        .args(["-Axo", "comm="])
+        .output()
+    else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|path| path.rsplit('/').next())
+        .filter(|name| *name == "CptHost")
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+fn microphone_clients() -> Vec<String> {
+    Vec::new()
+}
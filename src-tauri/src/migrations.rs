@@ -0,0 +1,264 @@
+//! Upgrades the settings file and the history database written by older
+//! versions of Voice. Each list holds one step per version, and the data
+//! records how many have run. Add steps at the end; never change one that
+//! has shipped.
+
+use rusqlite::Connection;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use voice_core::transcription::TranscriptionBackend;
+
+type SettingsMigration = fn(&mut Map<String, Value>);
+type HistoryMigration = fn(&Connection) -> rusqlite::Result<()>;
+
+const SETTINGS_MIGRATIONS: &[SettingsMigration] = &[
+    unversioned_settings,
+    drop_tls_obs,
+    drop_insecure_backend,
+    resave_privately,
+];
+
+const HISTORY_MIGRATIONS: &[HistoryMigration] = &[create_history, add_history_details];
+
+/// The settings format this build writes.
+pub const SETTINGS_VERSION: u32 = SETTINGS_MIGRATIONS.len() as u32;
+
+/// The history schema this build writes.
+pub const HISTORY_VERSION: u32 = HISTORY_MIGRATIONS.len() as u32;
+
+/// The versions of the stored data, for diagnostics.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct DataVersion {
+    pub settings: u32,
+    /// `None` while history is unavailable.
+    pub history: Option<u32>,
+}
+
+/// Brings settings JSON up to [`SETTINGS_VERSION`]. Returns whether anything
+/// ran. Settings from a newer version are left alone, since the fields this
+/// build knows are read from them as they are.
+pub fn migrate_settings(settings: &mut Map<String, Value>) -> bool {
+    let version = settings.get("version").and_then(Value::as_u64).unwrap_or(0) as usize;
+    if version >= SETTINGS_MIGRATIONS.len() {
+        return false;
+    }
+    for migration in &SETTINGS_MIGRATIONS[version..] {
+        migration(settings);
+    }
+    settings.insert("version".to_string(), SETTINGS_VERSION.into());
+    true
+}
+
+/// Brings the history database up to [`HISTORY_VERSION`], one transaction
+/// per step so a failed upgrade leaves the last good version behind. A
+/// database from a newer version is used as it is.
+pub fn migrate_history(conn: &mut Connection) -> Result<(), String> {
+    run_history_migrations(conn, HISTORY_MIGRATIONS)
+}
+
+fn run_history_migrations(
+    conn: &mut Connection,
+    migrations: &[HistoryMigration],
+) -> Result<(), String> {
+    let version = history_version(conn)? as usize;
+    if version > migrations.len() {
+        eprintln!(
+            "History is at version {}, newer than this build's {}",
+            version,
+            migrations.len()
+        );
+    }
+    for (index, migration) in migrations.iter().enumerate().skip(version) {
+        let next = index + 1;
+        let upgrade = conn.transaction().and_then(|transaction| {
+            migration(&transaction)?;
+            transaction.pragma_update(None, "user_version", next)?;
+            transaction.commit()
+        });
+        upgrade.map_err(|e| format!("Failed to upgrade history to version {}: {}", next, e))?;
+    }
+    Ok(())
+}
+
+pub fn history_version(conn: &Connection) -> Result<u32, String> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(|e| format!("Failed to read history version: {}", e))
+}
+
+/// Settings from before versioning need nothing changed: every field they
+/// lack has a default.
+fn unversioned_settings(_settings: &mut Map<String, Value>) {}
+
+/// wss:// OBS addresses were accepted but could never connect, and now fail
+/// validation, so they go back to the default.
+fn drop_tls_obs(settings: &mut Map<String, Value>) {
+    let url = settings
+        .get("obs")
+        .and_then(|obs| obs.get("url"))
+        .and_then(Value::as_str);
+    if url.is_some_and(|url| url.starts_with("wss://")) {
+        settings.remove("obs");
+    }
+}
+
+/// Cloud endpoints now have to use HTTPS unless they are on this machine, so
+/// one that doesn't goes back to local transcription rather than failing
+/// validation.
+fn drop_insecure_backend(settings: &mut Map<String, Value>) {
+    let backend = settings.get("backend").cloned();
+    let insecure = backend
+        .and_then(|backend| serde_json::from_value::<TranscriptionBackend>(backend).ok())
+        .is_some_and(|backend| backend.validate().is_err());
+    if insecure {
+        settings.remove("backend");
+    }
+}
+
+/// Settings are now written so only this user can read them. Nothing in
+/// them changes, but running a step saves an older, world-readable file
+/// again that way.
+fn resave_privately(_settings: &mut Map<String, Value>) {}
+
+/// Databases from before versioning may already have the table and some of
+/// the later columns, so these steps check before adding anything.
+fn create_history(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS history (
+             id INTEGER PRIMARY KEY,
+             timestamp INTEGER NOT NULL,
+             duration_ms INTEGER NOT NULL,
+             model TEXT NOT NULL,
+             device TEXT,
+             text TEXT NOT NULL
+         );
+         CREATE INDEX IF NOT EXISTS history_timestamp ON history (timestamp);",
+    )
+}
+
+/// Retained recordings, segment timings and the transcribed file.
+fn add_history_details(conn: &Connection) -> rusqlite::Result<()> {
+    add_column(conn, "recording_id", "TEXT")?;
+    add_column(conn, "segments", "TEXT")?;
+    add_column(conn, "source", "TEXT")
+}
+
+fn add_column(conn: &Connection, name: &str, definition: &str) -> rusqlite::Result<()> {
+    let exists = conn
+        .prepare("SELECT 1 FROM pragma_table_info('history') WHERE name = ?1")?
+        .exists([name])?;
+    if !exists {
+        conn.execute_batch(&format!(
+            "ALTER TABLE history ADD COLUMN {} {}",
+            name, definition
+        ))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn settings(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    fn columns(conn: &Connection) -> Vec<String> {
+        let mut statement = conn
+            .prepare("SELECT name FROM pragma_table_info('history')")
+            .unwrap();
+        let names = statement.query_map([], |row| row.get(0)).unwrap();
+        names.map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn unversioned_settings_are_brought_up_to_date() {
+        let mut unversioned = settings(json!({
+            "gpu": true,
+            "obs": { "url": "wss://localhost:4455" },
+        }));
+        assert!(migrate_settings(&mut unversioned));
+        assert_eq!(unversioned["version"], json!(SETTINGS_VERSION));
+        assert_eq!(unversioned["gpu"], json!(true));
+        assert!(!unversioned.contains_key("obs"));
+    }
+
+    #[test]
+    fn current_settings_are_left_alone() {
+        let mut current = settings(json!({ "version": SETTINGS_VERSION, "gpu": true }));
+        let before = current.clone();
+        assert!(!migrate_settings(&mut current));
+        assert_eq!(current, before);
+    }
+
+    #[test]
+    fn settings_from_a_newer_version_are_left_alone() {
+        let mut newer = settings(json!({
+            "version": SETTINGS_VERSION + 1,
+            "obs": { "url": "wss://localhost:4455" },
+        }));
+        let before = newer.clone();
+        assert!(!migrate_settings(&mut newer));
+        assert_eq!(newer, before);
+    }
+
+    #[test]
+    fn a_fresh_database_gets_the_whole_schema() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate_history(&mut conn).unwrap();
+        assert_eq!(history_version(&conn).unwrap(), HISTORY_VERSION);
+        let columns = columns(&conn);
+        for column in ["id", "text", "recording_id", "segments", "source"] {
+            assert!(columns.iter().any(|name| name == column), "{:?}", columns);
+        }
+    }
+
+    #[test]
+    fn a_database_from_before_versioning_keeps_its_rows_and_columns() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE history (
+                 id INTEGER PRIMARY KEY,
+                 timestamp INTEGER NOT NULL,
+                 duration_ms INTEGER NOT NULL,
+                 model TEXT NOT NULL,
+                 device TEXT,
+                 text TEXT NOT NULL,
+                 recording_id TEXT
+             );
+             INSERT INTO history (timestamp, duration_ms, model, text, recording_id)
+             VALUES (1, 1000, 'base', 'hello', 'rec-1');",
+        )
+        .unwrap();
+        migrate_history(&mut conn).unwrap();
+        assert_eq!(history_version(&conn).unwrap(), HISTORY_VERSION);
+        assert_eq!(columns(&conn).len(), 9);
+        let (text, recording): (String, String) = conn
+            .query_row("SELECT text, recording_id FROM history", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((text.as_str(), recording.as_str()), ("hello", "rec-1"));
+    }
+
+    #[test]
+    fn a_failed_step_rolls_back_to_the_last_good_version() {
+        fn half_done(conn: &Connection) -> rusqlite::Result<()> {
+            add_column(conn, "recording_id", "TEXT")?;
+            conn.execute_batch("ALTER TABLE missing ADD COLUMN x TEXT")
+        }
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        let failed = run_history_migrations(&mut conn, &[create_history, half_done]);
+        assert!(failed.is_err());
+        assert_eq!(history_version(&conn).unwrap(), 1);
+        assert!(!columns(&conn).iter().any(|name| name == "recording_id"));
+
+        migrate_history(&mut conn).unwrap();
+        assert_eq!(history_version(&conn).unwrap(), HISTORY_VERSION);
+    }
+}
@@ -0,0 +1,233 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{channel, Sender};
+use std::time::Duration;
+use tungstenite::http::Uri;
+use tungstenite::{Message, WebSocket};
+
+/// How long connecting to OBS or waiting on a reply may take before the
+/// caption is given up on.
+const OBS_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Where live captions go in OBS.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ObsTarget {
+    /// OBS's native stream captions, sent while streaming.
+    StreamCaptions,
+    /// Replaces the text of a Text (GDI+/FreeType 2) source.
+    TextSource { input_name: String },
+}
+
+/// Connection to obs-websocket (OBS 28+, Tools → WebSocket Server Settings).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ObsConfig {
+    pub enabled: bool,
+    pub url: String,
+    /// Kept in the OS credential store by the app, so it is never written
+    /// out with the rest of the settings.
+    #[serde(skip_serializing)]
+    pub password: Option<String>,
+    pub target: ObsTarget,
+}
+
+impl Default for ObsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: "ws://127.0.0.1:4455".to_string(),
+            password: None,
+            target: ObsTarget::StreamCaptions,
+        }
+    }
+}
+
+impl ObsConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        // Voice is built without TLS, and OBS only serves plain WebSockets.
+        if !self.url.starts_with("ws://") {
+            return Err("OBS address must be a ws:// URL".to_string());
+        }
+        if let ObsTarget::TextSource { input_name } = &self.target {
+            if input_name.trim().is_empty() {
+                return Err("OBS text source needs a source name".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sends captions to OBS from a thread of its own, so a slow or stalled OBS
+/// never holds up a dictation. Captions that queue up while one is being
+/// sent are skipped for the newest.
+pub struct CaptionSender {
+    sender: Sender<(ObsConfig, String)>,
+}
+
+impl CaptionSender {
+    pub fn new() -> Self {
+        let (sender, receiver) = channel::<(ObsConfig, String)>();
+        std::thread::spawn(move || {
+            let mut client = None;
+            while let Ok(mut caption) = receiver.recv() {
+                while let Ok(newer) = receiver.try_recv() {
+                    caption = newer;
+                }
+                let (config, text) = caption;
+                send_caption(&mut client, &config, &text);
+            }
+        });
+        Self { sender }
+    }
+
+    /// Queues `text` for OBS under `config`. A disabled config closes the
+    /// connection instead.
+    pub fn send(&self, config: ObsConfig, text: &str) {
+        let _ = self.sender.send((config, text.to_string()));
+    }
+}
+
+/// Failures are logged and the next caption reconnects, so OBS restarting
+/// mid-stream recovers.
+fn send_caption(client: &mut Option<ObsClient>, config: &ObsConfig, text: &str) {
+    if !config.enabled {
+        *client = None;
+        return;
+    }
+    if !client.as_ref().is_some_and(|c| c.config == *config) {
+        match ObsClient::connect(config) {
+            Ok(connected) => *client = Some(connected),
+            Err(e) => {
+                eprintln!("{}", e);
+                *client = None;
+                return;
+            }
+        }
+    }
+    if let Some(connected) = client.as_mut() {
+        if let Err(e) = connected.send_caption(text) {
+            eprintln!("{}", e);
+            *client = None;
+        }
+    }
+}
+
+/// An identified obs-websocket session.
+struct ObsClient {
+    socket: WebSocket<TcpStream>,
+    config: ObsConfig,
+    next_request: u64,
+}
+
+impl ObsClient {
+    /// Connects and authenticates, without subscribing to any events.
+    fn connect(config: &ObsConfig) -> Result<Self, String> {
+        let uri: Uri = config
+            .url
+            .parse()
+            .map_err(|e| format!("Invalid OBS address: {}", e))?;
+        let host = uri.host().unwrap_or_default();
+        let address = (host.trim_matches(['[', ']']), uri.port_u16().unwrap_or(80))
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addresses| addresses.next())
+            .ok_or_else(|| format!("Failed to find OBS at {}", config.url))?;
+        let stream = TcpStream::connect_timeout(&address, OBS_TIMEOUT)
+            .map_err(|e| format!("Failed to connect to OBS: {}", e))?;
+        stream
+            .set_read_timeout(Some(OBS_TIMEOUT))
+            .and_then(|()| stream.set_write_timeout(Some(OBS_TIMEOUT)))
+            .map_err(|e| format!("Failed to connect to OBS: {}", e))?;
+        let (socket, _) = tungstenite::client(config.url.as_str(), stream)
+            .map_err(|e| format!("Failed to connect to OBS: {}", e))?;
+        let mut client = Self {
+            socket,
+            config: config.clone(),
+            next_request: 0,
+        };
+
+        let hello = client.read_op(0)?;
+        let mut identify = json!({ "rpcVersion": 1, "eventSubscriptions": 0 });
+        if let Some(auth) = hello.get("authentication") {
+            let password = config
+                .password
+                .as_deref()
+                .ok_or_else(|| "OBS requires a password".to_string())?;
+            let salt = auth["salt"].as_str().unwrap_or_default();
+            let challenge = auth["challenge"].as_str().unwrap_or_default();
+            identify["authentication"] = authentication(password, salt, challenge).into();
+        }
+        client.send_op(1, identify)?;
+        client.read_op(2)?;
+        Ok(client)
+    }
+
+    /// Shows `text` as the current caption.
+    fn send_caption(&mut self, text: &str) -> Result<(), String> {
+        let (request_type, request_data) = match &self.config.target {
+            ObsTarget::StreamCaptions => ("SendStreamCaption", json!({ "captionText": text })),
+            ObsTarget::TextSource { input_name } => (
+                "SetInputSettings",
+                json!({ "inputName": input_name, "inputSettings": { "text": text } }),
+            ),
+        };
+
+        self.next_request += 1;
+        self.send_op(
+            6,
+            json!({
+                "requestType": request_type,
+                "requestId": self.next_request.to_string(),
+                "requestData": request_data,
+            }),
+        )?;
+
+        let response = self.read_op(7)?;
+        let status = &response["requestStatus"];
+        if status["result"].as_bool() != Some(true) {
+            return Err(format!(
+                "OBS rejected {}: {}",
+                request_type,
+                status["comment"].as_str().unwrap_or("no reason given")
+            ));
+        }
+        Ok(())
+    }
+
+    fn send_op(&mut self, op: u8, data: Value) -> Result<(), String> {
+        let message = json!({ "op": op, "d": data }).to_string();
+        self.socket
+            .send(Message::Text(message))
+            .map_err(|e| format!("Failed to send to OBS: {}", e))
+    }
+
+    /// Reads messages until one with opcode `op` arrives and returns its data.
+    fn read_op(&mut self, op: u64) -> Result<Value, String> {
+        loop {
+            let message = self
+                .socket
+                .read()
+                .map_err(|e| format!("Lost connection to OBS: {}", e))?;
+            let Message::Text(text) = message else {
+                continue;
+            };
+            let mut value: Value = serde_json::from_str(&text)
+                .map_err(|e| format!("Invalid message from OBS: {}", e))?;
+            if value["op"].as_u64() == Some(op) {
+                return Ok(value["d"].take());
+            }
+        }
+    }
+}
+
+/// obs-websocket's challenge response:
+/// `base64(sha256(base64(sha256(password + salt)) + challenge))`.
+fn authentication(password: &str, salt: &str, challenge: &str) -> String {
+    let base64 = base64::engine::general_purpose::STANDARD;
+    let secret = base64.encode(Sha256::digest(format!("{}{}", password, salt)));
+    base64.encode(Sha256::digest(format!("{}{}", secret, challenge)))
+}
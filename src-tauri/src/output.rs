@@ -0,0 +1,69 @@
+use parking_lot::Mutex;
+use std::time::{Duration, Instant};
+use voice_core::keyboard::{any_held, ChordKey};
+
+/// Keys that must be up before a paste: the modifiers, which would turn ⌘V
+/// into another chord, and Space, which the shortcuts share.
+const RELEASE_KEYS: &[ChordKey] = &[
+    ChordKey::Shift,
+    ChordKey::Control,
+    ChordKey::Alt,
+    ChordKey::Meta,
+    ChordKey::Space,
+];
+
+/// How long a paste waits for the user to let go of the keys before it goes
+/// ahead anyway.
+const KEY_RELEASE_TIMEOUT: Duration = Duration::from_secs(2);
+const KEY_POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// Time for the focused app to see the key-ups once they've been seen here,
+/// so they can't land after the synthetic keystroke.
+const KEY_RELEASE_SETTLE: Duration = Duration::from_millis(50);
+
+/// Runs clipboard writes and synthetic keystrokes one delivery at a time, so
+/// dictations finishing close together can't interleave with each other or
+/// with the user's own typing.
+#[derive(Default)]
+pub struct OutputScheduler {
+    /// When the last delivery finished. Locked for the whole of a delivery.
+    last_delivery: Mutex<Option<Instant>>,
+}
+
+impl OutputScheduler {
+    /// Runs `deliver` once no other delivery is running and `debounce` has
+    /// passed since the last one finished. With `keystrokes`, it also waits
+    /// for the shortcut's keys to be released, since a paste fired while ⇧⌘
+    /// is still held from the recording shortcut sends the wrong chord.
+    pub fn run<T>(&self, debounce: Duration, keystrokes: bool, deliver: impl FnOnce() -> T) -> T {
+        let mut last_delivery = self.last_delivery.lock();
+        if let Some(finished) = *last_delivery {
+            let elapsed = finished.elapsed();
+            if elapsed < debounce {
+                std::thread::sleep(debounce - elapsed);
+            }
+        }
+        if keystrokes {
+            wait_for_key_release();
+        }
+        let result = deliver();
+        *last_delivery = Some(Instant::now());
+        result
+    }
+}
+
+/// Polls the physical key state until none of `RELEASE_KEYS` is held. Where
+/// key state can't be read this returns straight away.
+fn wait_for_key_release() {
+    let started = Instant::now();
+    if any_held(RELEASE_KEYS) != Some(true) {
+        return;
+    }
+    while any_held(RELEASE_KEYS) == Some(true) {
+        if started.elapsed() > KEY_RELEASE_TIMEOUT {
+            eprintln!("Shortcut keys still held, delivering anyway");
+            return;
+        }
+        std::thread::sleep(KEY_POLL_INTERVAL);
+    }
+    std::thread::sleep(KEY_RELEASE_SETTLE);
+}
@@ -0,0 +1,340 @@
+use serde::Serialize;
+
+pub const MICROPHONE_DENIED: &str =
+    "Microphone access is not granted. Enable Voice under Privacy & Security > Microphone.";
+
+pub const ACCESSIBILITY_DENIED: &str =
+    "Accessibility access is not granted, so text was only copied to the clipboard. Enable Voice under Privacy & Security > Accessibility to paste automatically.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PermissionStatus {
+    pub microphone: bool,
+    pub accessibility: bool,
+}
+
+impl Default for PermissionStatus {
+    /// Assume granted until the first check says otherwise, so startup isn't
+    /// blocked on a slow probe.
+    fn default() -> Self {
+        Self {
+            microphone: true,
+            accessibility: true,
+        }
+    }
+}
+
+impl PermissionStatus {
+    pub fn current() -> Self {
+        Self {
+            microphone: microphone_granted(),
+            accessibility: accessibility_granted(),
+        }
+    }
+}
+
+pub fn microphone_granted() -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        macos::microphone_authorized()
+    }
+    #[cfg(target_os = "windows")]
+    {
+        windows::microphone_allowed()
+    }
+    #[cfg(target_os = "linux")]
+    {
+        linux::microphone_allowed()
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        true
+    }
+}
+
+/// Shows the system microphone prompt if the user hasn't been asked yet and
+/// waits for the answer without holding up a thread. Once asked, macOS only
+/// changes it in System Settings, so this just reports the current state.
+/// Windows never asks desktop apps, so there it only reports; on Linux only
+/// a Flatpak asks.
+pub async fn request_microphone() -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        macos::request_microphone().await
+    }
+    #[cfg(target_os = "linux")]
+    {
+        // The portal call blocks until the user answers.
+        tauri::async_runtime::spawn_blocking(linux::request_microphone)
+            .await
+            .unwrap_or(true)
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    {
+        microphone_granted()
+    }
+}
+
+pub fn accessibility_granted() -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        macos::ax_is_trusted(false)
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        true
+    }
+}
+
+/// Shows the system accessibility prompt if permission hasn't been granted.
+/// Meant to be triggered deliberately from onboarding, not from polling.
+pub fn request_accessibility() -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        macos::ax_is_trusted(true)
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        true
+    }
+}
+
+pub fn open_microphone_settings() -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        open_uri("x-apple.systempreferences:com.apple.preference.security?Privacy_Microphone")
+    }
+    #[cfg(target_os = "windows")]
+    {
+        open_uri("ms-settings:privacy-microphone")
+    }
+    #[cfg(target_os = "linux")]
+    {
+        spawn_first(&[
+            ("gnome-control-center", &["sound"]),
+            ("systemsettings", &["kcm_pulseaudio"]),
+            ("pavucontrol", &["--tab=4"]),
+        ])
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        Ok(())
+    }
+}
+
+pub fn open_accessibility_settings() -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        open_uri("x-apple.systempreferences:com.apple.preference.security?Privacy_Accessibility")
+    }
+    #[cfg(target_os = "windows")]
+    {
+        open_uri("ms-settings:easeofaccess")
+    }
+    #[cfg(target_os = "linux")]
+    {
+        spawn_first(&[
+            ("gnome-control-center", &["universal-access"]),
+            ("systemsettings", &["kcm_access"]),
+        ])
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn open_uri(uri: &str) -> Result<(), String> {
+    std::process::Command::new("open")
+        .arg(uri)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn open_uri(uri: &str) -> Result<(), String> {
+    // The empty argument is the window title `start` expects before the target.
+    std::process::Command::new("cmd")
+        .args(["/C", "start", "", uri])
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Desktop environments ship different settings apps, so try each in turn.
+#[cfg(target_os = "linux")]
+fn spawn_first(candidates: &[(&str, &[&str])]) -> Result<(), String> {
+    for (program, args) in candidates {
+        if std::process::Command::new(program).args(*args).spawn().is_ok() {
+            return Ok(());
+        }
+    }
+    Err("No supported settings application found".to_string())
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use block2::RcBlock;
+    use core_foundation::base::TCFType;
+    use core_foundation::boolean::CFBoolean;
+    use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+    use core_foundation::string::{CFString, CFStringRef};
+    use objc2::runtime::{AnyObject, Bool};
+    use objc2::{class, msg_send};
+    use std::sync::Mutex;
+    use tokio::sync::oneshot;
+
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        static kAXTrustedCheckOptionPrompt: CFStringRef;
+        fn AXIsProcessTrustedWithOptions(options: CFDictionaryRef) -> u8;
+    }
+
+    #[link(name = "AVFoundation", kind = "framework")]
+    extern "C" {
+        static AVMediaTypeAudio: &'static AnyObject;
+    }
+
+    /// `AVAuthorizationStatus` values.
+    const NOT_DETERMINED: isize = 0;
+    const AUTHORIZED: isize = 3;
+
+    /// The TCC decision for microphone access, as AVFoundation reports it.
+    fn microphone_status() -> isize {
+        unsafe {
+            msg_send![
+                class!(AVCaptureDevice),
+                authorizationStatusForMediaType: AVMediaTypeAudio
+            ]
+        }
+    }
+
+    pub fn microphone_authorized() -> bool {
+        microphone_status() == AUTHORIZED
+    }
+
+    pub async fn request_microphone() -> bool {
+        if microphone_status() != NOT_DETERMINED {
+            return microphone_authorized();
+        }
+        let (answer_tx, answer_rx) = oneshot::channel();
+        {
+            // Called once, on an arbitrary queue, when the user answers. A
+            // block may be called more than once as far as Rust knows, so the
+            // sender is taken out rather than moved.
+            let answer_tx = Mutex::new(Some(answer_tx));
+            let handler = RcBlock::new(move |granted: Bool| {
+                if let Some(answer_tx) = answer_tx.lock().ok().and_then(|mut tx| tx.take()) {
+                    let _ = answer_tx.send(granted.as_bool());
+                }
+            });
+            // AVFoundation copies the block, so ours can go before the answer
+            // comes; it isn't `Send`, so it must not be held across the await.
+            unsafe {
+                let _: () = msg_send![
+                    class!(AVCaptureDevice),
+                    requestAccessForMediaType: AVMediaTypeAudio,
+                    completionHandler: &*handler
+                ];
+            }
+        }
+        answer_rx.await.unwrap_or(false)
+    }
+
+    pub fn ax_is_trusted(prompt: bool) -> bool {
+        unsafe {
+            let key = CFString::wrap_under_get_rule(kAXTrustedCheckOptionPrompt);
+            let value = if prompt {
+                CFBoolean::true_value()
+            } else {
+                CFBoolean::false_value()
+            };
+            let options = CFDictionary::from_CFType_pairs(&[(key, value)]);
+            AXIsProcessTrustedWithOptions(options.as_concrete_TypeRef()) != 0
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows {
+    use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+    use windows_sys::Win32::System::Registry::{
+        RegGetValueW, HKEY, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, RRF_RT_REG_SZ,
+    };
+
+    const CONSENT_STORE: &str = r"Software\Microsoft\Windows\CurrentVersion\CapabilityAccessManager\ConsentStore\microphone";
+
+    /// Windows has three switches in Privacy & security > Microphone that
+    /// can each keep Voice off the microphone: access for the whole device,
+    /// access for the user, and access for desktop apps. Each is granted
+    /// unless it is set to "Deny"; switches never touched have no value.
+    pub fn microphone_allowed() -> bool {
+        let desktop_apps = format!(r"{}\NonPackaged", CONSENT_STORE);
+        let allowed = [
+            (HKEY_LOCAL_MACHINE, CONSENT_STORE),
+            (HKEY_CURRENT_USER, CONSENT_STORE),
+            (HKEY_CURRENT_USER, desktop_apps.as_str()),
+        ]
+        .into_iter()
+        .all(|(root, key)| consent_value(root, key).as_deref() != Some("Deny"));
+        allowed
+    }
+
+    /// The `Value` of a consent store key, "Allow" or "Deny".
+    fn consent_value(root: HKEY, key: &str) -> Option<String> {
+        let wide = |text: &str| text.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
+        let (key, name) = (wide(key), wide("Value"));
+        let mut data = [0u16; 16];
+        let mut size = std::mem::size_of_val(&data) as u32;
+        let status = unsafe {
+            RegGetValueW(
+                root,
+                key.as_ptr(),
+                name.as_ptr(),
+                RRF_RT_REG_SZ,
+                std::ptr::null_mut(),
+                data.as_mut_ptr().cast(),
+                &mut size,
+            )
+        };
+        if status != ERROR_SUCCESS {
+            return None;
+        }
+        let value = String::from_utf16_lossy(&data[..size as usize / 2]);
+        Some(value.trim_end_matches('\0').to_string())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use crate::portal;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// The device portal's answer. It can't be checked without asking, so
+    /// the answer stands until the next request.
+    static REFUSED: AtomicBool = AtomicBool::new(false);
+
+    pub fn microphone_allowed() -> bool {
+        !REFUSED.load(Ordering::Relaxed)
+    }
+
+    /// Outside a Flatpak any app can open the microphone. Inside one, the
+    /// device portal asks the user; desktops without it leave the
+    /// microphone to the sandbox's PulseAudio permission.
+    pub fn request_microphone() -> bool {
+        if !portal::sandboxed() {
+            return true;
+        }
+        match portal::request_microphone() {
+            Ok(granted) => {
+                REFUSED.store(!granted, Ordering::Relaxed);
+                granted
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                true
+            }
+        }
+    }
+}
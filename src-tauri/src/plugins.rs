@@ -0,0 +1,231 @@
+use voice_core::focus::FocusedWindow;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use wasmi::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+/// Instructions a plugin may execute per transcription before it is stopped.
+const PLUGIN_FUEL: u64 = 100_000_000;
+
+/// Linear memory a plugin may grow to.
+const PLUGIN_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// A post-processing plugin: a WebAssembly module exporting `memory`,
+/// `alloc(len) -> ptr` and `process(ptr, len) -> (ptr << 32 | len)`.
+/// `process` receives a [`PluginInput`] as JSON and returns the new text as
+/// UTF-8. Modules get no imports, so they can't touch files, the network or
+/// the clock.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Plugin {
+    pub slug: String,
+    pub name: String,
+    pub enabled: bool,
+    /// Path the plugin was installed from.
+    pub source: String,
+}
+
+/// What a plugin receives for each transcription.
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginInput<'a> {
+    pub text: &'a str,
+    pub model: &'a str,
+    pub app_name: Option<&'a str>,
+    pub window_title: Option<&'a str>,
+}
+
+impl<'a> PluginInput<'a> {
+    pub fn new(text: &'a str, model: &'a str, context: Option<&'a FocusedWindow>) -> Self {
+        Self {
+            text,
+            model,
+            app_name: context.map(|c| c.app_name.as_str()),
+            window_title: context.and_then(|c| c.window_title.as_deref()),
+        }
+    }
+}
+
+pub fn get_plugins_dir() -> PathBuf {
+    let dir = dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("voice")
+        .join("plugins");
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+fn plugin_module_path(slug: &str) -> PathBuf {
+    get_plugins_dir().join(format!("{}.wasm", slug))
+}
+
+fn plugin_metadata_path(slug: &str) -> PathBuf {
+    get_plugins_dir().join(format!("{}.json", slug))
+}
+
+/// Copies a `.wasm` file into the plugins folder after checking it exports
+/// the plugin interface. New plugins start enabled.
+pub fn install_plugin(name: &str, source: &str) -> Result<Plugin, String> {
+    let slug = voice_core::transcription::pack_slug(name);
+    if slug.is_empty() {
+        return Err("Plugin name must contain letters or digits".to_string());
+    }
+
+    let bytes = std::fs::read(Path::new(source))
+        .map_err(|e| format!("Failed to read plugin: {}", e))?;
+    PluginRuntime::load(&bytes).map_err(|e| format!("Not a valid plugin: {}", e))?;
+
+    std::fs::write(plugin_module_path(&slug), &bytes)
+        .map_err(|e| format!("Failed to install plugin: {}", e))?;
+
+    let plugin = Plugin {
+        slug,
+        name: name.trim().to_string(),
+        enabled: true,
+        source: source.to_string(),
+    };
+    write_metadata(&plugin)?;
+    Ok(plugin)
+}
+
+pub fn list_plugins() -> Vec<Plugin> {
+    let Ok(entries) = std::fs::read_dir(get_plugins_dir()) else {
+        return Vec::new();
+    };
+
+    let mut plugins: Vec<Plugin> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| std::fs::read(path).ok())
+        .filter_map(|bytes| serde_json::from_slice::<Plugin>(&bytes).ok())
+        .filter(|plugin| plugin_module_path(&plugin.slug).exists())
+        .collect();
+
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    plugins
+}
+
+pub fn set_plugin_enabled(slug: &str, enabled: bool) -> Result<Plugin, String> {
+    let mut plugin = list_plugins()
+        .into_iter()
+        .find(|plugin| plugin.slug == slug)
+        .ok_or_else(|| "Plugin not found".to_string())?;
+    plugin.enabled = enabled;
+    write_metadata(&plugin)?;
+    Ok(plugin)
+}
+
+pub fn remove_plugin(slug: &str) -> Result<(), String> {
+    std::fs::remove_file(plugin_module_path(slug))
+        .map_err(|e| format!("Failed to remove plugin: {}", e))?;
+    std::fs::remove_file(plugin_metadata_path(slug)).ok();
+    Ok(())
+}
+
+/// Runs every enabled plugin in name order. A failing plugin is skipped so a
+/// broken third-party module can't lose a dictation.
+pub fn run_plugins(input: &PluginInput) -> String {
+    let mut text = input.text.to_string();
+    for plugin in list_plugins().into_iter().filter(|plugin| plugin.enabled) {
+        let stage = PluginInput {
+            text: &text,
+            ..input.clone()
+        };
+        match run_plugin(&plugin.slug, &stage) {
+            Ok(output) => text = output,
+            Err(e) => eprintln!("Plugin {} failed: {}", plugin.name, e),
+        }
+    }
+    text
+}
+
+fn run_plugin(slug: &str, input: &PluginInput) -> Result<String, String> {
+    let bytes = std::fs::read(plugin_module_path(slug))
+        .map_err(|e| format!("Failed to read plugin: {}", e))?;
+    let json = serde_json::to_vec(input).map_err(|e| format!("Failed to encode input: {}", e))?;
+    PluginRuntime::load(&bytes)?.process(&json)
+}
+
+fn write_metadata(plugin: &Plugin) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(plugin)
+        .map_err(|e| format!("Failed to serialize plugin metadata: {}", e))?;
+    std::fs::write(plugin_metadata_path(&plugin.slug), json)
+        .map_err(|e| format!("Failed to write plugin metadata: {}", e))
+}
+
+struct PluginRuntime {
+    store: Store<StoreLimits>,
+    instance: wasmi::Instance,
+}
+
+impl PluginRuntime {
+    fn load(bytes: &[u8]) -> Result<Self, String> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, bytes).map_err(|e| e.to_string())?;
+
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(PLUGIN_MEMORY_LIMIT)
+            .build();
+        let mut store = Store::new(&engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(PLUGIN_FUEL).map_err(|e| e.to_string())?;
+
+        let instance = Linker::new(&engine)
+            .instantiate(&mut store, &module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|e| e.to_string())?;
+
+        let runtime = Self { store, instance };
+        runtime.memory()?;
+        runtime.alloc_fn()?;
+        runtime.process_fn()?;
+        Ok(runtime)
+    }
+
+    fn memory(&self) -> Result<wasmi::Memory, String> {
+        self.instance
+            .get_memory(&self.store, "memory")
+            .ok_or_else(|| "missing `memory` export".to_string())
+    }
+
+    fn alloc_fn(&self) -> Result<wasmi::TypedFunc<i32, i32>, String> {
+        self.instance
+            .get_typed_func(&self.store, "alloc")
+            .map_err(|e| format!("missing `alloc` export: {}", e))
+    }
+
+    fn process_fn(&self) -> Result<wasmi::TypedFunc<(i32, i32), i64>, String> {
+        self.instance
+            .get_typed_func(&self.store, "process")
+            .map_err(|e| format!("missing `process` export: {}", e))
+    }
+
+    fn process(mut self, input: &[u8]) -> Result<String, String> {
+        let memory = self.memory()?;
+        let len = i32::try_from(input.len()).map_err(|_| "input too large".to_string())?;
+
+        let ptr = self
+            .alloc_fn()?
+            .call(&mut self.store, len)
+            .map_err(|e| e.to_string())?;
+        memory
+            .write(&mut self.store, ptr as u32 as usize, input)
+            .map_err(|e| e.to_string())?;
+
+        let packed = self
+            .process_fn()?
+            .call(&mut self.store, (ptr, len))
+            .map_err(|e| e.to_string())?;
+        let out_ptr = (packed as u64 >> 32) as usize;
+        let out_len = (packed as u64 & 0xffff_ffff) as usize;
+
+        // The length comes from the plugin, so it is checked against the
+        // plugin's memory before anything is allocated for it.
+        let output = memory
+            .data(&self.store)
+            .get(out_ptr..out_ptr.saturating_add(out_len))
+            .ok_or_else(|| "plugin returned output outside its memory".to_string())?
+            .to_vec();
+        String::from_utf8(output).map_err(|_| "plugin returned invalid UTF-8".to_string())
+    }
+}
@@ -0,0 +1,160 @@
+//! The xdg-desktop-portal interfaces Voice needs on Linux: global shortcuts,
+//! which Wayland compositors don't let apps grab for themselves, and
+//! microphone access from inside a Flatpak.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use zbus::blocking::{Connection, Proxy};
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+
+const PORTAL: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+
+/// The `Activated` and `Deactivated` signals: the session, the shortcut's
+/// id, a timestamp and options.
+type ShortcutSignal = (OwnedObjectPath, String, u64, HashMap<String, OwnedValue>);
+
+/// Makes each request's handle token unique.
+static NEXT_TOKEN: AtomicU32 = AtomicU32::new(0);
+
+/// Whether this is a Wayland session, where the global shortcut plugin only
+/// sees keys pressed in X11 apps.
+pub fn wayland() -> bool {
+    std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+/// Whether Voice runs in a Flatpak sandbox.
+pub fn sandboxed() -> bool {
+    Path::new("/.flatpak-info").exists()
+}
+
+/// A shortcut to bind through the portal.
+pub struct PortalShortcut {
+    pub id: &'static str,
+    /// Shown in the desktop's shortcut settings.
+    pub description: &'static str,
+    /// In the portal's format, e.g. `CTRL+SHIFT+space`. The desktop may ask
+    /// the user to confirm it or pick another.
+    pub preferred_trigger: &'static str,
+}
+
+/// Binds `shortcuts` through the GlobalShortcuts portal, then calls
+/// `on_event` with a shortcut's id and whether it was pressed (`true`) or
+/// released, for as long as the app runs.
+pub fn bind_shortcuts(
+    shortcuts: &[PortalShortcut],
+    on_event: impl Fn(&str, bool) + Send + Sync + 'static,
+) -> Result<(), String> {
+    let connection = Connection::session().map_err(portal_error)?;
+    let portal = Proxy::new(
+        &connection,
+        PORTAL,
+        PORTAL_PATH,
+        "org.freedesktop.portal.GlobalShortcuts",
+    )
+    .map_err(portal_error)?;
+
+    let created = request(&connection, |token| {
+        let options = HashMap::from([
+            ("handle_token", Value::from(token)),
+            ("session_handle_token", Value::from(token)),
+        ]);
+        portal.call("CreateSession", &(options,))
+    })?
+    .ok_or("Global shortcuts were refused")?;
+    let session = created
+        .get("session_handle")
+        .and_then(|handle| String::try_from(handle.clone()).ok())
+        .ok_or("The desktop portal didn't open a shortcut session")?;
+    let session = ObjectPath::try_from(session).map_err(portal_error)?;
+
+    let bindings: Vec<(&str, HashMap<&str, Value>)> = shortcuts
+        .iter()
+        .map(|shortcut| {
+            let properties = HashMap::from([
+                ("description", Value::from(shortcut.description)),
+                ("preferred_trigger", Value::from(shortcut.preferred_trigger)),
+            ]);
+            (shortcut.id, properties)
+        })
+        .collect();
+    request(&connection, |token| {
+        let options = HashMap::from([("handle_token", Value::from(token))]);
+        portal.call("BindShortcuts", &(&session, bindings, "", options))
+    })?
+    .ok_or("The shortcuts were not bound")?;
+
+    let on_event = Arc::new(on_event);
+    for (signal, pressed) in [("Activated", true), ("Deactivated", false)] {
+        let events = portal.receive_signal(signal).map_err(portal_error)?;
+        let on_event = on_event.clone();
+        std::thread::spawn(move || {
+            for message in events {
+                if let Ok((_, id, _, _)) = message.body().deserialize::<ShortcutSignal>() {
+                    on_event(&id, pressed);
+                }
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Asks the Device portal for the microphone, which shows the desktop's
+/// permission dialog the first time. `Ok(false)` if the user refused.
+pub fn request_microphone() -> Result<bool, String> {
+    let connection = Connection::session().map_err(portal_error)?;
+    let portal = Proxy::new(
+        &connection,
+        PORTAL,
+        PORTAL_PATH,
+        "org.freedesktop.portal.Device",
+    )
+    .map_err(portal_error)?;
+    let answer = request(&connection, |token| {
+        let options = HashMap::from([("handle_token", Value::from(token))]);
+        portal.call(
+            "AccessDevice",
+            &(std::process::id(), vec!["microphone"], options),
+        )
+    })?;
+    Ok(answer.is_some())
+}
+
+/// Makes a portal call that answers through the `Response` signal of a
+/// request object, and returns the results, or `None` if the user cancelled
+/// or refused. The signal is subscribed to at the path the portal will use
+/// before the call is made, so a quick answer can't be missed.
+fn request(
+    connection: &Connection,
+    call: impl FnOnce(&str) -> zbus::Result<OwnedObjectPath>,
+) -> Result<Option<HashMap<String, OwnedValue>>, String> {
+    let token = format!("voice{}", NEXT_TOKEN.fetch_add(1, Ordering::Relaxed));
+    let sender = connection
+        .unique_name()
+        .map(|name| name.trim_start_matches(':').replace('.', "_"))
+        .unwrap_or_default();
+    let path = format!("{}/request/{}/{}", PORTAL_PATH, sender, token);
+    let request = Proxy::new(
+        connection,
+        PORTAL,
+        path.as_str(),
+        "org.freedesktop.portal.Request",
+    )
+    .map_err(portal_error)?;
+    let mut responses = request.receive_signal("Response").map_err(portal_error)?;
+
+    call(&token).map_err(portal_error)?;
+    let response = responses
+        .next()
+        .ok_or("The desktop portal closed the request")?;
+    let (code, results): (u32, HashMap<String, OwnedValue>) =
+        response.body().deserialize().map_err(portal_error)?;
+    // 1 is cancelled by the user, 2 ended some other way.
+    Ok((code == 0).then_some(results))
+}
+
+fn portal_error(e: impl std::fmt::Display) -> String {
+    format!("Desktop portal request failed: {}", e)
+}
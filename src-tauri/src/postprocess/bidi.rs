@@ -0,0 +1,74 @@
+/// Right-to-left mark: a zero-width character with strong RTL direction.
+const RLM: char = '\u{200F}';
+
+/// Right-to-left isolate and its closing pop directional isolate.
+const RLI: char = '\u{2067}';
+const PDI: char = '\u{2069}';
+
+/// Letters of the Hebrew, Arabic, Syriac, Thaana and N'Ko scripts and their
+/// presentation forms.
+pub fn is_rtl(c: char) -> bool {
+    c.is_alphabetic()
+        && matches!(
+            c,
+            '\u{0590}'..='\u{08FF}' | '\u{FB1D}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFF}'
+        )
+}
+
+pub fn is_arabic(c: char) -> bool {
+    c.is_alphabetic()
+        && matches!(
+            c,
+            '\u{0600}'..='\u{06FF}'
+                | '\u{0750}'..='\u{077F}'
+                | '\u{08A0}'..='\u{08FF}'
+                | '\u{FB50}'..='\u{FDFF}'
+                | '\u{FE70}'..='\u{FEFF}'
+        )
+}
+
+fn first_letter(text: &str) -> Option<char> {
+    text.chars().find(|c| c.is_alphabetic())
+}
+
+/// Whether the text reads right to left, judged like the Unicode
+/// bidi algorithm by its first letter.
+pub fn is_rtl_text(text: &str) -> bool {
+    first_letter(text).is_some_and(is_rtl)
+}
+
+/// Swaps the ASCII question mark, comma and semicolon whisper sometimes
+/// writes in Arabic text for the Arabic forms.
+pub fn localize_punctuation(text: &str) -> String {
+    if !first_letter(text).is_some_and(is_arabic) {
+        return text.to_string();
+    }
+    text.chars()
+        .map(|c| match c {
+            '?' => '؟',
+            ',' => '،',
+            ';' => '؛',
+            other => other,
+        })
+        .collect()
+}
+
+/// Isolates RTL text before it is placed inside left-to-right text, so
+/// neighbouring punctuation and numbers aren't reordered around it.
+pub fn isolate(text: &str, surrounding: &str) -> String {
+    if is_rtl_text(text) && first_letter(surrounding).is_some_and(|c| !is_rtl(c)) {
+        format!("{}{}{}", RLI, text, PDI)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Brackets RTL text with right-to-left marks for apps that would otherwise
+/// lay it out left to right, e.g. with the final period on the wrong side.
+pub fn mark_rtl(text: &str) -> String {
+    if is_rtl_text(text) {
+        format!("{}{}{}", RLM, text, RLM)
+    } else {
+        text.to_string()
+    }
+}
@@ -0,0 +1,137 @@
+use super::replacements::Replacement;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The pack format written by [`DictionaryPack::write`]. Packs from a newer
+/// version are refused rather than half understood.
+pub const DICTIONARY_PACK_VERSION: u32 = 1;
+
+/// Vocabulary and replacements shared as a standalone JSON file, so a team
+/// can hand around a medical, legal or engineering dictionary.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DictionaryPack {
+    pub version: u32,
+    /// What the pack is for, e.g. "Cardiology".
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub vocabulary: Vec<String>,
+    #[serde(default)]
+    pub replacements: Vec<Replacement>,
+}
+
+/// Which side wins when a pack replaces a word the user already replaces
+/// with something else.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    /// The user's own replacement stays.
+    #[default]
+    Keep,
+    /// The pack's replacement takes its place.
+    Replace,
+}
+
+/// A pack replacement for a word the user already replaces differently.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplacementConflict {
+    pub find: String,
+    pub existing: String,
+    pub incoming: String,
+}
+
+/// What importing a pack changed.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportSummary {
+    pub vocabulary_added: usize,
+    pub replacements_added: usize,
+    /// Settled by the [`ConflictPolicy`]; with `Replace` these were taken
+    /// from the pack.
+    pub conflicts: Vec<ReplacementConflict>,
+}
+
+impl DictionaryPack {
+    pub fn new(name: String, vocabulary: Vec<String>, replacements: Vec<Replacement>) -> Self {
+        Self {
+            version: DICTIONARY_PACK_VERSION,
+            name,
+            vocabulary,
+            replacements,
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read dictionary pack: {}", e))?;
+        Self::parse(&json)
+    }
+
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let pack: Self =
+            serde_json::from_str(json).map_err(|e| format!("Not a dictionary pack: {}", e))?;
+        if pack.version > DICTIONARY_PACK_VERSION {
+            return Err(format!(
+                "The dictionary pack is version {}, newer than this version of Voice reads",
+                pack.version
+            ));
+        }
+        Ok(pack)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize dictionary pack: {}", e))?;
+        std::fs::write(path, json).map_err(|e| format!("Failed to write dictionary pack: {}", e))
+    }
+
+    /// Adds the pack's terms and replacements to the user's. Terms already
+    /// there are skipped; replacements are matched on `find` ignoring case,
+    /// like `add_replacement`, and conflicting ones settled by `policy`.
+    pub fn merge_into(
+        &self,
+        vocabulary: &mut Vec<String>,
+        replacements: &mut Vec<Replacement>,
+        policy: ConflictPolicy,
+    ) -> ImportSummary {
+        let mut summary = ImportSummary::default();
+        for term in &self.vocabulary {
+            let term = term.trim();
+            if !term.is_empty() && !vocabulary.iter().any(|t| t == term) {
+                vocabulary.push(term.to_string());
+                summary.vocabulary_added += 1;
+            }
+        }
+
+        for incoming in &self.replacements {
+            let find = incoming.find.trim();
+            if find.is_empty() {
+                continue;
+            }
+            let key = find.to_lowercase();
+            match replacements
+                .iter_mut()
+                .find(|existing| existing.find.to_lowercase() == key)
+            {
+                None => {
+                    replacements.push(Replacement {
+                        find: find.to_string(),
+                        with: incoming.with.clone(),
+                    });
+                    summary.replacements_added += 1;
+                }
+                Some(existing) if existing.with == incoming.with => {}
+                Some(existing) => {
+                    summary.conflicts.push(ReplacementConflict {
+                        find: existing.find.clone(),
+                        existing: existing.with.clone(),
+                        incoming: incoming.with.clone(),
+                    });
+                    if policy == ConflictPolicy::Replace {
+                        existing.with = incoming.with.clone();
+                    }
+                }
+            }
+        }
+        summary
+    }
+}
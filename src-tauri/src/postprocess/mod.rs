@@ -0,0 +1,21 @@
+mod bidi;
+mod dictionary;
+mod pipeline;
+mod presets;
+mod punctuation;
+mod replacements;
+mod rules;
+mod script;
+mod spacing;
+
+pub use bidi::mark_rtl;
+pub use dictionary::{ConflictPolicy, DictionaryPack, ImportSummary};
+pub use pipeline::{
+    append_to_file, complete, output_targets, pipe_to_command, run_pipeline, validate_pipeline,
+    OutputTarget, PipelineStep,
+};
+pub use presets::{dictionary_preset, dictionary_presets, DictionaryPreset};
+pub use punctuation::{restore_punctuation, PunctuationMode};
+pub use replacements::{apply_replacements, Replacement};
+pub use rules::{apply_rules, TextRules};
+pub use script::{read_script, run_script, write_script};
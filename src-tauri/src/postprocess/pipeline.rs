@@ -0,0 +1,237 @@
+use super::bidi::isolate;
+use super::spacing::join_words;
+use voice_core::focus::FocusedWindow;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+
+/// Where the finished text is sent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "target", rename_all = "lowercase")]
+pub enum OutputTarget {
+    Clipboard,
+    /// Copies to the clipboard and sends the paste keystroke.
+    Paste,
+    /// Types the text into the focused app without touching the clipboard.
+    Type,
+    /// Appends the text as a line to a file.
+    File { path: String },
+    /// Pipes the text to a shell command's stdin, e.g. `todoist add`.
+    Command { command: String },
+}
+
+/// One step of the post-transcription pipeline. Steps run in order; output
+/// steps come last and say where the result goes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "step", rename_all = "lowercase")]
+pub enum PipelineStep {
+    Trim,
+    Replace {
+        find: String,
+        with: String,
+        #[serde(default)]
+        case_sensitive: bool,
+    },
+    /// Sends the text to an OpenAI-compatible chat completions endpoint with
+    /// `prompt` as the system message and uses the reply.
+    Llm {
+        endpoint: String,
+        model: String,
+        prompt: String,
+        #[serde(default)]
+        api_key: Option<String>,
+    },
+    /// Wraps the text; `{text}`, `{app}` and `{window}` are substituted.
+    Template { template: String },
+    Output(OutputTarget),
+}
+
+/// Checks a pipeline before it is stored, so a typo fails loudly in settings
+/// rather than silently mid-dictation.
+pub fn validate_pipeline(steps: &[PipelineStep]) -> Result<(), String> {
+    let mut seen_output = false;
+    for (index, step) in steps.iter().enumerate() {
+        let position = index + 1;
+        let is_output = matches!(step, PipelineStep::Output(_));
+        if seen_output && !is_output {
+            return Err(format!("Step {}: only output steps may follow an output step", position));
+        }
+        seen_output |= is_output;
+
+        match step {
+            PipelineStep::Replace { find, .. } if find.is_empty() => {
+                return Err(format!("Step {}: replace needs a non-empty `find`", position));
+            }
+            PipelineStep::Llm { endpoint, .. }
+                if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") =>
+            {
+                return Err(format!("Step {}: llm endpoint must be an http(s) URL", position));
+            }
+            PipelineStep::Template { template } if !template.contains("{text}") => {
+                return Err(format!("Step {}: template must contain {{text}}", position));
+            }
+            PipelineStep::Output(OutputTarget::File { path }) if path.trim().is_empty() => {
+                return Err(format!("Step {}: file output needs a path", position));
+            }
+            PipelineStep::Output(OutputTarget::Command { command }) if command.trim().is_empty() => {
+                return Err(format!("Step {}: command output needs a command", position));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Runs the text-transforming steps. Output steps are handled by the caller
+/// through [`output_targets`].
+pub fn run_pipeline(
+    steps: &[PipelineStep],
+    text: &str,
+    context: Option<&FocusedWindow>,
+) -> Result<String, String> {
+    let mut text = text.to_string();
+    for step in steps {
+        text = match step {
+            PipelineStep::Trim => join_words(text.split_whitespace()),
+            PipelineStep::Replace {
+                find,
+                with,
+                case_sensitive,
+            } => replace(&text, find, with, *case_sensitive),
+            PipelineStep::Llm {
+                endpoint,
+                model,
+                prompt,
+                api_key,
+            } => complete(endpoint, model, prompt, api_key.as_deref(), &text)?,
+            PipelineStep::Template { template } => {
+                let filled = template
+                    .replace("{app}", context.map(|c| c.app_name.as_str()).unwrap_or(""))
+                    .replace(
+                        "{window}",
+                        context.and_then(|c| c.window_title.as_deref()).unwrap_or(""),
+                    );
+                let surrounding = filled.replace("{text}", "");
+                filled.replace("{text}", &isolate(&text, &surrounding))
+            }
+            PipelineStep::Output(_) => text,
+        };
+    }
+    Ok(text)
+}
+
+/// Where the pipeline sends its result; the clipboard when it has no output
+/// steps.
+pub fn output_targets(steps: &[PipelineStep]) -> Vec<OutputTarget> {
+    let targets: Vec<OutputTarget> = steps
+        .iter()
+        .filter_map(|step| match step {
+            PipelineStep::Output(target) => Some(target.clone()),
+            _ => None,
+        })
+        .collect();
+
+    if targets.is_empty() {
+        vec![OutputTarget::Clipboard]
+    } else {
+        targets
+    }
+}
+
+pub fn append_to_file(path: &Path, text: &str) -> Result<(), String> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    writeln!(file, "{}", text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Runs `command` through the platform shell with the text on stdin.
+pub fn pipe_to_command(command: &str, text: &str) -> Result<(), String> {
+    use std::process::{Command, Stdio};
+
+    #[cfg(target_os = "windows")]
+    let (program, flag) = ("cmd", "/C");
+    #[cfg(not(target_os = "windows"))]
+    let (program, flag) = ("sh", "-c");
+
+    let mut child = Command::new(program)
+        .args([flag, command])
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run `{}`: {}", command, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(text.as_bytes())
+            .map_err(|e| format!("Failed to write to `{}`: {}", command, e))?;
+    }
+
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to run `{}`: {}", command, e))?;
+    if !status.success() {
+        return Err(format!("`{}` exited with {}", command, status));
+    }
+    Ok(())
+}
+
+fn replace(text: &str, find: &str, with: &str, case_sensitive: bool) -> String {
+    if case_sensitive {
+        return text.replace(find, with);
+    }
+
+    let needle: Vec<char> = find.chars().collect();
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let matches = chars.len() - i >= needle.len()
+            && chars[i..i + needle.len()]
+                .iter()
+                .zip(&needle)
+                .all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()));
+        if matches {
+            out.push_str(with);
+            i += needle.len();
+        } else {
+            out.push(chars[i]);
+            i += 1;
+        }
+    }
+    out
+}
+
+/// Sends `text` to an OpenAI-compatible chat completions endpoint with
+/// `prompt` as the system message and returns the reply.
+pub fn complete(
+    endpoint: &str,
+    model: &str,
+    prompt: &str,
+    api_key: Option<&str>,
+    text: &str,
+) -> Result<String, String> {
+    let body = serde_json::json!({
+        "model": model,
+        "messages": [
+            { "role": "system", "content": prompt },
+            { "role": "user", "content": text },
+        ],
+    });
+
+    let mut request = reqwest::blocking::Client::new().post(endpoint).json(&body);
+    if let Some(key) = api_key {
+        request = request.bearer_auth(key);
+    }
+
+    let response: serde_json::Value = request
+        .send()
+        .and_then(|r| r.error_for_status())
+        .and_then(|r| r.json())
+        .map_err(|e| format!("LLM step failed: {}", e))?;
+
+    response["choices"][0]["message"]["content"]
+        .as_str()
+        .map(|content| content.trim().to_string())
+        .ok_or_else(|| "LLM step returned no text".to_string())
+}
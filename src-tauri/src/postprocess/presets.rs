@@ -0,0 +1,48 @@
+use super::dictionary::DictionaryPack;
+use serde::Serialize;
+
+/// Dictionary packs that ship with Voice, by id.
+const PRESETS: [(&str, &str); 3] = [
+    ("software", include_str!("../../dictionaries/software.json")),
+    ("medical", include_str!("../../dictionaries/medical.json")),
+    ("legal", include_str!("../../dictionaries/legal.json")),
+];
+
+/// A built-in dictionary pack, as `list_dictionary_presets` reports it.
+#[derive(Debug, Clone, Serialize)]
+pub struct DictionaryPreset {
+    pub id: &'static str,
+    pub name: String,
+    pub terms: usize,
+    pub replacements: usize,
+    pub enabled: bool,
+}
+
+/// The built-in pack with this id.
+pub fn dictionary_preset(id: &str) -> Option<DictionaryPack> {
+    let (_, json) = PRESETS.iter().find(|(preset, _)| *preset == id)?;
+    match DictionaryPack::parse(json) {
+        Ok(pack) => Some(pack),
+        Err(e) => {
+            eprintln!("Built-in dictionary {:?} is broken: {}", id, e);
+            None
+        }
+    }
+}
+
+/// Every built-in pack, marked with whether it is among `enabled`.
+pub fn dictionary_presets(enabled: &[String]) -> Vec<DictionaryPreset> {
+    PRESETS
+        .iter()
+        .filter_map(|(id, _)| {
+            let pack = dictionary_preset(id)?;
+            Some(DictionaryPreset {
+                id,
+                name: pack.name,
+                terms: pack.vocabulary.len(),
+                replacements: pack.replacements.len(),
+                enabled: enabled.iter().any(|enabled| enabled == id),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,101 @@
+use super::bidi::localize_punctuation;
+use super::spacing::{is_cjk, needs_space};
+use voice_core::transcription::{ModelId, ModelSize};
+use serde::{Deserialize, Serialize};
+
+const SENTENCE_END: [char; 3] = ['.', '!', '?'];
+
+const QUESTION_WORDS: [&str; 16] = [
+    "who", "what", "when", "where", "why", "how", "which", "is", "are", "do", "does", "did",
+    "can", "could", "would", "should",
+];
+
+/// Arabic and Hebrew words that open a question. Arabic من and ما are left
+/// out since they also mean "from" and "not".
+const RTL_QUESTION_WORDS: [&str; 12] = [
+    "هل", "ماذا", "لماذا", "كيف", "متى", "أين", "האם", "מה", "למה", "איך", "מתי", "איפה",
+];
+
+/// When to run the rule-based punctuation/capitalization pass.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PunctuationMode {
+    Off,
+    /// Only for tiny/base models, whose own punctuation is unreliable.
+    #[default]
+    Auto,
+    Always,
+}
+
+impl PunctuationMode {
+    pub fn applies_to(&self, model: &ModelId) -> bool {
+        match self {
+            PunctuationMode::Off => false,
+            PunctuationMode::Always => true,
+            PunctuationMode::Auto => matches!(
+                model,
+                ModelId::Official(ModelSize::Tiny) | ModelId::Official(ModelSize::Base)
+            ),
+        }
+    }
+}
+
+/// Capitalizes sentence starts and the pronoun "I", and terminates the text
+/// with a period (or question mark when it opens with a question word).
+/// Chinese and Japanese text gets a full-width `。` and no added spaces, and
+/// Arabic text Arabic question marks and commas.
+pub fn restore_punctuation(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 1);
+    let mut capitalize_next = true;
+
+    for word in text.split_whitespace() {
+        if needs_space(&out, word) {
+            out.push(' ');
+        }
+
+        if capitalize_next || is_pronoun_i(word) {
+            push_capitalized(&mut out, word);
+        } else {
+            out.push_str(word);
+        }
+        capitalize_next = word.ends_with(SENTENCE_END);
+    }
+
+    if out.ends_with(|c: char| c.is_alphanumeric() && is_cjk(c)) {
+        out.push('。');
+    } else if out.ends_with(|c: char| c.is_alphanumeric()) {
+        let first_word = text
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        if QUESTION_WORDS.contains(&first_word.as_str())
+            || RTL_QUESTION_WORDS.contains(&first_word.as_str())
+        {
+            out.push('?');
+        } else {
+            out.push('.');
+        }
+    }
+
+    localize_punctuation(&out)
+}
+
+fn is_pronoun_i(word: &str) -> bool {
+    match word.strip_prefix('i') {
+        Some(rest) => {
+            rest.is_empty()
+                || rest.starts_with(['\'', '’'])
+                || rest.chars().all(|c| c.is_ascii_punctuation())
+        }
+        None => false,
+    }
+}
+
+fn push_capitalized(out: &mut String, word: &str) {
+    let mut chars = word.chars();
+    if let Some(first) = chars.next() {
+        out.extend(first.to_uppercase());
+        out.push_str(chars.as_str());
+    }
+}
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+/// A user's correction, applied to every transcription: `find` is matched as
+/// a whole word or phrase, ignoring case, and replaced with `with`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Replacement {
+    pub find: String,
+    pub with: String,
+}
+
+/// Applies the replacements in one pass over the text, so one replacement's
+/// output isn't matched again by another. Where several match, the longest
+/// `find` wins, so "new york" beats "york".
+pub fn apply_replacements(text: &str, replacements: &[Replacement]) -> String {
+    let mut needles: Vec<(Vec<char>, &str)> = replacements
+        .iter()
+        .filter(|replacement| !replacement.find.is_empty())
+        .map(|replacement| {
+            (
+                replacement.find.chars().collect(),
+                replacement.with.as_str(),
+            )
+        })
+        .collect();
+    needles.sort_by_key(|(find, _)| std::cmp::Reverse(find.len()));
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        match needles
+            .iter()
+            .find(|(find, _)| matches_word(&chars, i, find))
+        {
+            Some((find, with)) => {
+                out.push_str(with);
+                i += find.len();
+            }
+            None => {
+                out.push(chars[i]);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Whether `find` is at `start`, ignoring case, and isn't part of a longer
+/// word there, so "eta" doesn't match in "beta". An apostrophe ends a word,
+/// so a name still matches in its possessive.
+fn matches_word(chars: &[char], start: usize, find: &[char]) -> bool {
+    let end = start + find.len();
+    if end > chars.len()
+        || !chars[start..end]
+            .iter()
+            .zip(find)
+            .all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()))
+    {
+        return false;
+    }
+    let starts_word =
+        start == 0 || !chars[start - 1].is_alphanumeric() || !find[0].is_alphanumeric();
+    let ends_word = end == chars.len()
+        || !chars[end].is_alphanumeric()
+        || !find[find.len() - 1].is_alphanumeric();
+    starts_word && ends_word
+}
@@ -0,0 +1,274 @@
+use super::spacing::join_words;
+use serde::{Deserialize, Serialize};
+
+/// Hesitations dropped by [`TextRules::remove_fillers`] in any language.
+const FILLERS: [&str; 7] = ["umm", "uhh", "uhm", "erm", "hm", "hmm", "mhm"];
+
+/// Hesitations that are words, or abbreviations, in other languages (German
+/// "um" and "er", "mm" for millimetres), so they are only dropped from a
+/// transcription known to be in one of these.
+const LANGUAGE_FILLERS: [(&str, &[&str]); 7] = [
+    ("en", &["um", "uh", "er", "mm"]),
+    ("de", &["äh", "ähm", "öh", "öhm"]),
+    ("sv", &["eh", "ehm", "öh", "öhm"]),
+    ("nl", &["eh", "ehm", "uh"]),
+    ("fr", &["euh", "heu"]),
+    ("es", &["eh", "ehm", "mmm"]),
+    ("it", &["eh", "ehm", "mmm"]),
+];
+
+/// Spoken punctuation and what it becomes, longest phrases first.
+const SPOKEN_PUNCTUATION: [(&[&str], &str); 10] = [
+    (&["new", "paragraph"], "\n\n"),
+    (&["new", "line"], "\n"),
+    (&["full", "stop"], "."),
+    (&["question", "mark"], "?"),
+    (&["exclamation", "mark"], "!"),
+    (&["exclamation", "point"], "!"),
+    (&["semicolon"], ";"),
+    (&["colon"], ":"),
+    (&["comma"], ","),
+    (&["period"], "."),
+];
+
+const SENTENCE_END: [char; 3] = ['.', '!', '?'];
+
+/// Clean-up rules run on every transcription before plugins and the
+/// pipeline, each switched on or off in settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextRules {
+    /// Drop hesitations such as "um" and "uh", as far as the language of the
+    /// transcription is known.
+    pub remove_fillers: bool,
+    /// Turn spoken "comma", "question mark", "new line" and so on into the
+    /// characters they name.
+    pub spoken_punctuation: bool,
+    /// Collapse runs of spaces into one and trim them around line breaks.
+    pub collapse_whitespace: bool,
+}
+
+impl Default for TextRules {
+    fn default() -> Self {
+        Self {
+            remove_fillers: false,
+            spoken_punctuation: false,
+            collapse_whitespace: true,
+        }
+    }
+}
+
+/// A word and the whitespace before it, so text can be rebuilt with its
+/// original spacing.
+#[derive(Debug, Clone)]
+struct Token {
+    space: String,
+    word: String,
+}
+
+/// `language` is the ISO 639-1 code of the transcription, if known.
+pub fn apply_rules(text: &str, rules: &TextRules, language: Option<&str>) -> String {
+    let mut tokens = tokenize(text);
+    if rules.remove_fillers {
+        tokens = remove_fillers(tokens, language);
+    }
+    if rules.spoken_punctuation {
+        tokens = convert_spoken_punctuation(tokens);
+    }
+
+    let text: String = tokens
+        .iter()
+        .flat_map(|token| [token.space.as_str(), token.word.as_str()])
+        .collect();
+    if rules.collapse_whitespace {
+        collapse_whitespace(&text)
+    } else {
+        text
+    }
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut space = String::new();
+    let mut word = String::new();
+    for c in text.chars() {
+        if !c.is_whitespace() {
+            word.push(c);
+            continue;
+        }
+        if !word.is_empty() {
+            tokens.push(Token {
+                space: std::mem::take(&mut space),
+                word: std::mem::take(&mut word),
+            });
+        }
+        space.push(c);
+    }
+    if !word.is_empty() || !space.is_empty() {
+        tokens.push(Token { space, word });
+    }
+    tokens
+}
+
+/// The word without surrounding punctuation, lowercased for matching.
+fn bare(word: &str) -> String {
+    word.trim_matches(|c: char| c.is_ascii_punctuation())
+        .to_lowercase()
+}
+
+fn capitalize(word: &mut String) {
+    if let Some(first) = word.chars().next() {
+        let upper: String = first.to_uppercase().collect();
+        word.replace_range(..first.len_utf8(), &upper);
+    }
+}
+
+fn is_filler(word: &str, language: Option<&str>) -> bool {
+    let word = bare(word);
+    FILLERS.contains(&word.as_str())
+        || LANGUAGE_FILLERS
+            .iter()
+            .any(|(code, fillers)| Some(*code) == language && fillers.contains(&word.as_str()))
+}
+
+fn remove_fillers(tokens: Vec<Token>, language: Option<&str>) -> Vec<Token> {
+    let mut out: Vec<Token> = Vec::with_capacity(tokens.len());
+    let mut removed: Option<Token> = None;
+    for mut token in tokens {
+        if is_filler(&token.word, language) {
+            // "so um. Next" keeps its full stop as "so. Next".
+            if let (Some(previous), Some(end)) = (out.last_mut(), token.word.chars().last()) {
+                if SENTENCE_END.contains(&end)
+                    && !previous.word.ends_with(|c: char| c.is_ascii_punctuation())
+                {
+                    previous.word.push(end);
+                }
+            }
+            removed.get_or_insert(token);
+            continue;
+        }
+
+        if let Some(filler) = removed.take() {
+            // "Um, so" becomes "So".
+            if filler.word.starts_with(char::is_uppercase) {
+                capitalize(&mut token.word);
+            }
+            if out.is_empty() {
+                token.space = filler.space;
+            }
+        }
+        out.push(token);
+    }
+    out
+}
+
+/// The punctuation spoken at the start of `tokens`, and how many words it
+/// took.
+fn spoken_mark(tokens: &[Token]) -> Option<(usize, &'static str)> {
+    SPOKEN_PUNCTUATION.iter().find_map(|(phrase, mark)| {
+        let matches = phrase.len() <= tokens.len()
+            && phrase
+                .iter()
+                .zip(tokens)
+                .all(|(word, token)| bare(&token.word) == *word);
+        matches.then_some((phrase.len(), *mark))
+    })
+}
+
+fn convert_spoken_punctuation(tokens: Vec<Token>) -> Vec<Token> {
+    let mut out: Vec<Token> = Vec::with_capacity(tokens.len());
+    let mut after_break = false;
+    let mut capitalize_next = false;
+    let mut i = 0;
+    while i < tokens.len() {
+        if let Some((len, mark)) = spoken_mark(&tokens[i..]) {
+            let is_break = mark.starts_with('\n');
+            match out.last_mut() {
+                Some(previous) if !is_break => {
+                    // Whisper often punctuates around the spoken word
+                    // itself, as in "Hello, comma, world".
+                    let kept = previous.word.trim_end_matches([',', '.', ';', ':']).len();
+                    previous.word.truncate(kept);
+                    previous.word.push_str(mark);
+                }
+                _ => out.push(Token {
+                    space: String::new(),
+                    word: mark.to_string(),
+                }),
+            }
+            after_break = is_break;
+            capitalize_next = mark.ends_with(SENTENCE_END) || mark == "\n\n";
+            i += len;
+            continue;
+        }
+
+        let mut token = tokens[i].clone();
+        if after_break {
+            token.space.clear();
+        }
+        if capitalize_next {
+            capitalize(&mut token.word);
+        }
+        after_break = false;
+        capitalize_next = false;
+        out.push(token);
+        i += 1;
+    }
+    out
+}
+
+/// Single spaces within each line, none at either end of one, keeping the
+/// line breaks themselves.
+fn collapse_whitespace(text: &str) -> String {
+    text.split('\n')
+        .map(|line| join_words(line.split_whitespace()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn without_fillers(text: &str, language: Option<&str>) -> String {
+        let rules = TextRules {
+            remove_fillers: true,
+            ..TextRules::default()
+        };
+        apply_rules(text, &rules, language)
+    }
+
+    #[test]
+    fn removes_english_fillers() {
+        assert_eq!(
+            without_fillers("Um, so I think uh we should go.", Some("en")),
+            "So I think we should go."
+        );
+        assert_eq!(
+            without_fillers("It was so um. Next one.", Some("en")),
+            "It was so. Next one."
+        );
+    }
+
+    #[test]
+    fn keeps_words_of_other_languages() {
+        let german = "Er kommt um acht und bringt 5 mm Schrauben.";
+        assert_eq!(without_fillers(german, Some("de")), german);
+        assert_eq!(
+            without_fillers("Äh, er kommt ähm um acht.", Some("de")),
+            "Er kommt um acht."
+        );
+    }
+
+    #[test]
+    fn unknown_languages_lose_only_unambiguous_fillers() {
+        assert_eq!(
+            without_fillers("Hmm, er kommt um acht.", None),
+            "Er kommt um acht."
+        );
+        assert_eq!(
+            without_fillers("Hmm, er kommt um acht.", Some("pl")),
+            "Er kommt um acht."
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use voice_core::focus::FocusedWindow;
+use rhai::{Engine, EvalAltResult, Map, Scope, AST};
+use std::path::PathBuf;
+
+const HOOK: &str = "on_transcription";
+
+/// Caps how much work a script may do per transcription.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+pub fn get_script_path() -> PathBuf {
+    let dir = dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("voice");
+    std::fs::create_dir_all(&dir).ok();
+    dir.join("script.rhai")
+}
+
+/// The user's script, if one has been written.
+pub fn read_script() -> Option<String> {
+    std::fs::read_to_string(get_script_path()).ok()
+}
+
+/// Saves the script after checking it compiles and defines the hook. An empty
+/// script removes it.
+pub fn write_script(source: &str) -> Result<(), String> {
+    if source.trim().is_empty() {
+        std::fs::remove_file(get_script_path()).ok();
+        return Ok(());
+    }
+
+    compile(&engine(false), source)?;
+    std::fs::write(get_script_path(), source).map_err(|e| format!("Failed to write script: {}", e))
+}
+
+/// Runs `on_transcription(text, context)` from the user's script, where
+/// `context` has `app` and `window`. `shell(command)` is only available when
+/// `allow_shell` is set.
+pub fn run_script(
+    text: &str,
+    context: Option<&FocusedWindow>,
+    allow_shell: bool,
+) -> Result<String, String> {
+    let Some(source) = read_script() else {
+        return Ok(text.to_string());
+    };
+
+    let engine = engine(allow_shell);
+    let ast = compile(&engine, &source)?;
+
+    let mut context_map = Map::new();
+    context_map.insert(
+        "app".into(),
+        context.map(|c| c.app_name.clone()).unwrap_or_default().into(),
+    );
+    context_map.insert(
+        "window".into(),
+        context
+            .and_then(|c| c.window_title.clone())
+            .unwrap_or_default()
+            .into(),
+    );
+
+    engine
+        .call_fn::<String>(&mut Scope::new(), &ast, HOOK, (text.to_string(), context_map))
+        .map_err(|e| format!("Script failed: {}", e))
+}
+
+fn engine(allow_shell: bool) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    if allow_shell {
+        engine.register_fn("shell", shell);
+    }
+    engine
+}
+
+fn compile(engine: &Engine, source: &str) -> Result<AST, String> {
+    let ast = engine
+        .compile(source)
+        .map_err(|e| format!("Script error: {}", e))?;
+    if !ast.iter_functions().any(|f| f.name == HOOK && f.params.len() == 2) {
+        return Err(format!("Script must define fn {}(text, context)", HOOK));
+    }
+    Ok(ast)
+}
+
+/// Runs a command through the platform shell and returns its trimmed stdout.
+fn shell(command: &str) -> Result<String, Box<EvalAltResult>> {
+    #[cfg(target_os = "windows")]
+    let output = std::process::Command::new("cmd").args(["/C", command]).output();
+    #[cfg(not(target_os = "windows"))]
+    let output = std::process::Command::new("sh").args(["-c", command]).output();
+
+    let output = output.map_err(|e| format!("Failed to run command: {}", e))?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,37 @@
+/// Whether `c` belongs to a script written without spaces between words:
+/// Chinese characters, kana, and their punctuation and fullwidth forms.
+/// Hangul isn't included, since Korean is written with spaces.
+pub fn is_cjk(c: char) -> bool {
+    matches!(
+        c,
+        '\u{3000}'..='\u{303F}' // CJK symbols and punctuation
+            | '\u{3040}'..='\u{30FF}' // Hiragana and katakana
+            | '\u{3400}'..='\u{4DBF}' // CJK extension A
+            | '\u{4E00}'..='\u{9FFF}' // CJK unified ideographs
+            | '\u{F900}'..='\u{FAFF}' // CJK compatibility ideographs
+            | '\u{FF00}'..='\u{FFEF}' // Fullwidth forms
+            | '\u{20000}'..='\u{2FFFF}' // Supplementary ideographs
+    )
+}
+
+/// Whether a space belongs between `before` and the word that follows. Not
+/// between two CJK characters, where whisper sometimes leaves one at a
+/// segment boundary; mixed text such as "用 Rust 写" keeps its spaces.
+pub fn needs_space(before: &str, word: &str) -> bool {
+    match before.chars().last() {
+        Some(last) => !(is_cjk(last) && word.starts_with(is_cjk)),
+        None => false,
+    }
+}
+
+/// Joins words with single spaces, leaving them out within CJK text.
+pub fn join_words<'a>(words: impl IntoIterator<Item = &'a str>) -> String {
+    let mut out = String::new();
+    for word in words {
+        if needs_space(&out, word) {
+            out.push(' ');
+        }
+        out.push_str(word);
+    }
+    out
+}
@@ -0,0 +1,49 @@
+//! Named looks for the overlay, so a glance shows which profile a dictation
+//! is going to, e.g. "Swedish" in blue and "Code" in green.
+
+use crate::postprocess::dictionary_preset;
+use serde::{Deserialize, Serialize};
+
+/// Icons are an emoji or a short symbol, not text.
+const MAX_ICON_CHARS: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    /// `#rrggbb`, which the overlay is bordered in.
+    pub color: String,
+    /// Shown before the name, e.g. a flag.
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Ids of built-in dictionary packs used while this profile is active,
+    /// on top of those turned on for every profile.
+    #[serde(default)]
+    pub dictionary_presets: Vec<String>,
+}
+
+impl Profile {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("A profile needs a name".to_string());
+        }
+        let hex = self.color.strip_prefix('#').unwrap_or_default();
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("Not a #rrggbb color: {}", self.color));
+        }
+        if self
+            .icon
+            .as_ref()
+            .is_some_and(|icon| icon.chars().count() > MAX_ICON_CHARS)
+        {
+            return Err("A profile icon is an emoji or a short symbol".to_string());
+        }
+        if let Some(id) = self
+            .dictionary_presets
+            .iter()
+            .find(|id| dictionary_preset(id).is_none())
+        {
+            return Err(format!("No built-in dictionary {:?}", id));
+        }
+        Ok(())
+    }
+}
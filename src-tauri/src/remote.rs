@@ -0,0 +1,447 @@
+//! Lets a phone on the same network act as the dictation button. Pairing
+//! shows a QR code for a page Voice serves over plain HTTP; the page holds a
+//! push-to-talk button and shows the transcript as it arrives. Every call but
+//! the page itself must carry the pairing token the code holds.
+
+use crate::permissions::MICROPHONE_DENIED;
+use crate::session::SessionState;
+use crate::update_settings;
+use crate::AppState;
+use parking_lot::Mutex;
+use qrcode::render::svg;
+use qrcode::QrCode;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{IpAddr, Ipv4Addr, TcpListener, TcpStream, UdpSocket};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use voice_core::rpc::generate_token;
+
+/// Fixed, so a paired phone finds Voice again after a restart.
+pub const PORT: u16 = 47820;
+
+const PAGE: &str = include_str!("remote.html");
+
+/// Events the page shows.
+const FORWARDED: [&str; 5] = [
+    "session-state",
+    "transcription-partial",
+    "transcription-complete",
+    "transcription-error",
+    "transcription-discarded",
+];
+
+/// A request that hasn't arrived by then is dropped.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The request line and headers together; longer requests are turned away
+/// unread.
+const MAX_REQUEST_HEAD: u64 = 8 * 1024;
+
+/// Connections served at once, event streams included. More are closed
+/// straight away, so a flood of them can't exhaust threads.
+const MAX_CONNECTIONS: usize = 16;
+
+/// While its button is down the page says so this often (see `remote.html`),
+/// and a hold not heard of for [`HOLD_TIMEOUT`] is released, so a phone that
+/// drops off the network can't leave Voice recording.
+const HOLD_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How often held buttons are checked for [`HOLD_TIMEOUT`].
+const HOLD_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Event streams send a comment this often, so a phone that went away is
+/// noticed.
+const KEEPALIVE: Duration = Duration::from_secs(15);
+
+/// What a phone needs to pair.
+#[derive(Debug, Clone, Serialize)]
+pub struct RemotePairing {
+    /// The page to open on the phone, token included.
+    pub url: String,
+    /// `url` as a QR code to scan with the phone's camera.
+    pub qr_svg: String,
+}
+
+struct Shared {
+    /// From [`voice_core::rpc::generate_token`], so from the OS's secure
+    /// random number generator.
+    token: Mutex<String>,
+    /// Each open event stream.
+    listeners: Mutex<Vec<Sender<String>>>,
+    /// When the phone holding the button down last said it still was.
+    held: Mutex<Option<Instant>>,
+    /// Connections being served.
+    connections: AtomicUsize,
+}
+
+/// The listening remote control page. Each connection is served on its own
+/// thread.
+pub struct RemoteServer {
+    shared: Arc<Shared>,
+    stopped: Arc<AtomicBool>,
+    accept: JoinHandle<()>,
+}
+
+impl RemoteServer {
+    /// Listens on [`PORT`] on every interface.
+    pub fn start(app: AppHandle, token: String) -> Result<Self, String> {
+        let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, PORT))
+            .map_err(|e| format!("Failed to listen on port {}: {}", PORT, e))?;
+        let shared = Arc::new(Shared {
+            token: Mutex::new(token),
+            listeners: Mutex::new(Vec::new()),
+            held: Mutex::new(None),
+            connections: AtomicUsize::new(0),
+        });
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let shared_clone = shared.clone();
+        let stopped_clone = stopped.clone();
+        let app_clone = app.clone();
+        let accept = std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stopped_clone.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                if shared_clone.connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                    shared_clone.connections.fetch_sub(1, Ordering::SeqCst);
+                    continue;
+                }
+                let app = app_clone.clone();
+                let shared = shared_clone.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = serve(&app, stream, &shared) {
+                        eprintln!("Remote control connection closed: {}", e);
+                    }
+                    shared.connections.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        let shared_clone = shared.clone();
+        let stopped_clone = stopped.clone();
+        std::thread::spawn(move || {
+            while !stopped_clone.load(Ordering::SeqCst) {
+                std::thread::sleep(HOLD_CHECK_INTERVAL);
+                let lost = shared_clone
+                    .held
+                    .lock()
+                    .is_some_and(|heard| heard.elapsed() > HOLD_TIMEOUT);
+                if lost {
+                    eprintln!("Lost touch with the remote holding the button");
+                    release(&app, &shared_clone);
+                }
+            }
+        });
+
+        Ok(Self {
+            shared,
+            stopped,
+            accept,
+        })
+    }
+
+    /// Pairs with a new token. Phones paired with the old one are turned
+    /// away from then on.
+    pub fn set_token(&self, token: String) {
+        *self.shared.token.lock() = token;
+        self.shared.listeners.lock().clear();
+    }
+
+    /// Sends an event on to the paired phones if the page shows it.
+    pub fn forward(&self, event: &str, payload: &Value) {
+        if !FORWARDED.contains(&event) {
+            return;
+        }
+        let message = json!({ "event": event, "payload": payload }).to_string();
+        self.shared
+            .listeners
+            .lock()
+            .retain(|listener| listener.send(message.clone()).is_ok());
+    }
+
+    /// Stops listening and ends the event streams, returning once the port
+    /// is free again.
+    pub fn shutdown(self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        self.shared.listeners.lock().clear();
+        // Wake the accept loop so it sees the flag.
+        let _ = TcpStream::connect((Ipv4Addr::LOCALHOST, PORT));
+        let _ = self.accept.join();
+    }
+}
+
+/// Serves the remote control page to phones paired with `token`, or stops
+/// serving it.
+pub fn apply(app: &AppHandle, token: Option<&str>) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let mut server = state.remote_server.lock();
+    match (token, server.take()) {
+        (Some(token), Some(running)) => {
+            running.set_token(token.to_string());
+            *server = Some(running);
+        }
+        (Some(token), None) => {
+            let started = RemoteServer::start(app.clone(), token.to_string())?;
+            println!("Remote control listening on port {}", PORT);
+            *server = Some(started);
+        }
+        (None, Some(running)) => running.shutdown(),
+        (None, None) => {}
+    }
+    Ok(())
+}
+
+/// Turns the remote control page on or off. Phones paired before stay
+/// paired.
+pub fn set_enabled(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    update_settings(app, |settings| {
+        let token = settings.remote_token.clone().unwrap_or_else(generate_token);
+        apply(app, enabled.then_some(token.as_str()))?;
+        settings.remote_control = enabled;
+        settings.remote_token = Some(token);
+        Ok(())
+    })
+}
+
+/// The address and QR code a phone pairs with while the page is served.
+pub fn current_pairing(app: &AppHandle) -> Result<RemotePairing, String> {
+    let state = app.state::<AppState>();
+    let settings = state.settings.lock();
+    match (&settings.remote_token, settings.remote_control) {
+        (Some(token), true) => pairing(token),
+        _ => Err("Remote control is off".to_string()),
+    }
+}
+
+/// Pairs with a new token, unpairing every phone paired so far.
+pub fn reset_pairing(app: &AppHandle) -> Result<RemotePairing, String> {
+    let token = update_settings(app, |settings| {
+        if !settings.remote_control {
+            return Err("Remote control is off".to_string());
+        }
+        let token = generate_token();
+        apply(app, Some(&token))?;
+        settings.remote_token = Some(token.clone());
+        Ok(token)
+    })?;
+    pairing(&token)
+}
+
+/// The address and QR code for pairing with `token`.
+fn pairing(token: &str) -> Result<RemotePairing, String> {
+    let url = format!("http://{}:{}/?token={}", lan_address()?, PORT, token);
+    let qr_svg = QrCode::new(&url)
+        .map_err(|e| format!("Failed to create the pairing code: {}", e))?
+        .render::<svg::Color>()
+        .min_dimensions(240, 240)
+        .build();
+    Ok(RemotePairing { url, qr_svg })
+}
+
+/// The address other devices on the network reach this one at. Connecting
+/// a UDP socket sends nothing; it only picks the interface the route leaves
+/// from.
+fn lan_address() -> Result<IpAddr, String> {
+    let address = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|socket| {
+            socket.connect((Ipv4Addr::new(8, 8, 8, 8), 80))?;
+            socket.local_addr()
+        })
+        .map_err(|e| format!("Failed to find this computer's address: {}", e))?
+        .ip();
+    if address.is_unspecified() || address.is_loopback() {
+        return Err("Not connected to a network".to_string());
+    }
+    Ok(address)
+}
+
+/// Answers one request. The page is served to anyone; everything else needs
+/// the token.
+fn serve(app: &AppHandle, stream: TcpStream, shared: &Shared) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let reader = BufReader::new(stream.try_clone()?);
+    let Some(request_line) = read_request_head(reader)? else {
+        return respond(
+            stream,
+            "431 Request Header Fields Too Large",
+            "text/plain",
+            "Request too large",
+        );
+    };
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let token = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="));
+    let authorized = token.is_some_and(|token| tokens_match(token, &shared.token.lock()));
+
+    match (method, path) {
+        ("GET", "/") => respond(stream, "200 OK", "text/html; charset=utf-8", PAGE),
+        _ if !authorized => respond(
+            stream,
+            "401 Unauthorized",
+            "text/plain",
+            "Scan the pairing code in Voice again",
+        ),
+        ("GET", "/events") => stream_events(app, stream, shared),
+        ("POST", "/press") => match press(app, shared) {
+            Ok(()) => respond(stream, "204 No Content", "text/plain", ""),
+            Err(e) => respond(stream, "409 Conflict", "text/plain", &e),
+        },
+        ("POST", "/hold") => {
+            hold(shared);
+            respond(stream, "204 No Content", "text/plain", "")
+        }
+        ("POST", "/release") => {
+            release(app, shared);
+            respond(stream, "204 No Content", "text/plain", "")
+        }
+        _ => respond(stream, "404 Not Found", "text/plain", "Not found"),
+    }
+}
+
+/// Reads the request line and the headers after it, returning the request
+/// line, or `None` if they run past [`MAX_REQUEST_HEAD`] or the connection
+/// ends before they do. Nothing in the headers matters, but they have to be
+/// read.
+fn read_request_head(reader: impl BufRead) -> std::io::Result<Option<String>> {
+    let mut reader = reader.take(MAX_REQUEST_HEAD);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 {
+        if header.ends_with('\n') && header.trim().is_empty() {
+            return Ok(Some(request_line));
+        }
+        header.clear();
+    }
+    Ok(None)
+}
+
+/// Whether `presented` is the pairing token, compared in the same time
+/// wherever they first differ, so the token can't be found a character at a
+/// time.
+fn tokens_match(presented: &str, token: &str) -> bool {
+    let (presented, token) = (presented.as_bytes(), token.as_bytes());
+    presented.len() == token.len()
+        && presented
+            .iter()
+            .zip(token)
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+fn respond(
+    mut stream: TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+         Cache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// Sends the forwarded events as server-sent events until the phone goes
+/// away or is unpaired.
+fn stream_events(app: &AppHandle, mut stream: TcpStream, shared: &Shared) -> std::io::Result<()> {
+    stream.write_all(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-store\r\n\r\n",
+    )?;
+    let (listener, messages) = channel();
+    // Starts with where the session is, so the page needn't wait for a change.
+    let session = app.state::<AppState>().session.state();
+    let _ = listener.send(json!({ "event": "session-state", "payload": session }).to_string());
+    shared.listeners.lock().push(listener);
+
+    let outcome = loop {
+        let chunk = match messages.recv_timeout(KEEPALIVE) {
+            Ok(message) => format!("data: {}\n\n", message),
+            Err(RecvTimeoutError::Timeout) => ": keepalive\n\n".to_string(),
+            Err(RecvTimeoutError::Disconnected) => break Ok(()),
+        };
+        if let Err(e) = stream.write_all(chunk.as_bytes()) {
+            break Err(e);
+        }
+    };
+    // A phone that drops off mid-dictation mustn't leave it recording.
+    release(app, shared);
+    outcome
+}
+
+fn press(app: &AppHandle, shared: &Shared) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    if !state.permissions.lock().microphone {
+        return Err(MICROPHONE_DENIED.to_string());
+    }
+    if !matches!(
+        state.session.state(),
+        SessionState::Idle | SessionState::Waiting
+    ) {
+        return Err("Voice is busy".to_string());
+    }
+    *shared.held.lock() = Some(Instant::now());
+    crate::begin_dictation(app, false);
+    Ok(())
+}
+
+/// The page saying its button is still down. Never starts a dictation, so a
+/// late one can't undo a release.
+fn hold(shared: &Shared) {
+    if let Some(heard) = shared.held.lock().as_mut() {
+        *heard = Instant::now();
+    }
+}
+
+fn release(app: &AppHandle, shared: &Shared) {
+    let held = shared.held.lock().take().is_some();
+    if held && app.state::<AppState>().session.is_recording() {
+        crate::finish_dictation(app);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_match_only_the_same_token() {
+        assert!(tokens_match("0a1b2c", "0a1b2c"));
+        assert!(!tokens_match("0a1b2d", "0a1b2c"));
+        assert!(!tokens_match("0a1b2", "0a1b2c"));
+        assert!(!tokens_match("", "0a1b2c"));
+    }
+
+    #[test]
+    fn reads_the_request_line_once_the_headers_end() {
+        let request = "POST /press?token=t HTTP/1.1\r\nHost: voice\r\n\r\n";
+        let line = read_request_head(request.as_bytes()).unwrap();
+        assert_eq!(line.as_deref(), Some("POST /press?token=t HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn turns_away_oversized_or_unfinished_requests() {
+        let huge = format!("GET / HTTP/1.1\r\nX: {}\r\n\r\n", "a".repeat(10_000));
+        assert_eq!(read_request_head(huge.as_bytes()).unwrap(), None);
+        let cut_short = "GET / HTTP/1.1\r\nHost: voice\r\n";
+        assert_eq!(read_request_head(cut_short.as_bytes()).unwrap(), None);
+    }
+}
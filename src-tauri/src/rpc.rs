@@ -0,0 +1,327 @@
+use crate::postprocess::{OutputTarget, PipelineStep, PunctuationMode};
+use crate::session::SessionState;
+use crate::update_settings;
+use crate::AppState;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use voice_core::rpc::{
+    parse_params, Handler, MethodSpec, ParamSpec, RpcError, RpcServer, METHOD_NOT_FOUND,
+};
+
+const fn param(name: &'static str, kind: &'static str, optional: bool) -> ParamSpec {
+    ParamSpec {
+        name,
+        kind,
+        optional,
+    }
+}
+
+/// Every method the app serves. `get_api_schema` and the `schema` method
+/// publish this table, so the documentation can't drift from the handler.
+pub const METHODS: &[MethodSpec] = &[
+    MethodSpec {
+        name: "status",
+        description: "Whether the app is recording and which model is loaded.",
+        params: &[],
+        result: "{ is_recording: boolean, microphone_active: boolean, model: string, model_loaded: boolean }",
+    },
+    MethodSpec {
+        name: "recording.start",
+        description: "Starts recording from the selected microphone.",
+        params: &[],
+        result: "null",
+    },
+    MethodSpec {
+        name: "recording.stop",
+        description: "Stops recording and waits for the transcription.",
+        params: &[],
+        result: "{ text: string }",
+    },
+    MethodSpec {
+        name: "dictation.toggle",
+        description: "Starts a dictation, or stops the current one and transcribes it in the \
+                      background. Poll dictation.status and fetch dictation.result.",
+        params: &[],
+        result: "{ state: \"recording\" | \"transcribing\" }",
+    },
+    MethodSpec {
+        name: "dictation.status",
+        description: "What dictation is doing and the id of the latest finished result.",
+        params: &[],
+        result: "{ state: \"idle\" | \"recording\" | \"transcribing\" | \"meeting\" \
+                 | \"testing\" | \"memo\", \
+                 result_id: number | null }",
+    },
+    MethodSpec {
+        name: "dictation.result",
+        description: "The latest finished dictation; exactly one of text and error is set.",
+        params: &[],
+        result: "{ id: number, text: string | null, error: string | null } | null",
+    },
+    MethodSpec {
+        name: "models.list",
+        description: "Models in the catalog and installed language packs.",
+        params: &[],
+        result: "[{ key: string, name: string, downloaded: boolean, size: number }]",
+    },
+    MethodSpec {
+        name: "models.select",
+        description: "Switches to a model, loading it if it is downloaded.",
+        params: &[param("key", "string", false)],
+        result: "null",
+    },
+    MethodSpec {
+        name: "models.download",
+        description: "Downloads a model and waits until it is installed.",
+        params: &[param("key", "string", false)],
+        result: "null",
+    },
+    MethodSpec {
+        name: "settings.get",
+        description: "Current settings.",
+        params: &[],
+        result: "{ language, punctuation_mode, pipeline, show_overlay, continuation_window_ms, \
+                 retain_recordings, capture_window_context }",
+    },
+    MethodSpec {
+        name: "settings.set",
+        description: "Changes the settings given and leaves the rest alone.",
+        params: &[
+            param("language", "string", true),
+            param("punctuation_mode", "string", true),
+            param("pipeline", "array", true),
+            param("show_overlay", "boolean", true),
+            param("continuation_window_ms", "number", true),
+            param("retain_recordings", "boolean", true),
+            param("capture_window_context", "boolean", true),
+        ],
+        result: "null",
+    },
+];
+
+/// Starts or stops serving the protocol.
+pub fn apply_server(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let mut server = state.rpc_server.lock();
+    match (enabled, server.is_some()) {
+        (true, false) => {
+            let started = RpcServer::start(Arc::new(AppHandler::new(app.clone())))?;
+            println!("RPC server listening on port {}", started.port());
+            *server = Some(started);
+        }
+        (false, true) => {
+            if let Some(stopped) = server.take() {
+                stopped.shutdown();
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Turns the protocol server on or off and remembers the choice.
+pub fn set_enabled(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    apply_server(app, enabled)?;
+    update_settings(app, |settings| {
+        settings.rpc_server = enabled;
+        Ok(())
+    })
+}
+
+/// A dictation started over the protocol, for clients such as launcher
+/// extensions that trigger and then poll rather than hold a connection open.
+#[derive(Default)]
+pub struct RemoteDictation {
+    transcribing: bool,
+    result: Option<DictationResult>,
+}
+
+#[derive(Clone, Serialize)]
+struct DictationResult {
+    id: u64,
+    text: Option<String>,
+    error: Option<String>,
+}
+
+/// Answers protocol requests by calling the same functions the settings
+/// window invokes, so every frontend sees one engine state.
+pub struct AppHandler {
+    app: AppHandle,
+}
+
+impl AppHandler {
+    pub fn new(app: AppHandle) -> Self {
+        Self { app }
+    }
+}
+
+#[derive(Deserialize)]
+struct ModelParams {
+    key: String,
+}
+
+/// `settings.set` params; only the fields present are changed.
+#[derive(Deserialize)]
+struct SettingsPatch {
+    language: Option<String>,
+    punctuation_mode: Option<PunctuationMode>,
+    pipeline: Option<Vec<PipelineStep>>,
+    show_overlay: Option<bool>,
+    continuation_window_ms: Option<u64>,
+    retain_recordings: Option<bool>,
+    capture_window_context: Option<bool>,
+}
+
+impl Handler for AppHandler {
+    fn methods(&self) -> &'static [MethodSpec] {
+        METHODS
+    }
+
+    fn handle(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        let app = &self.app;
+        match method {
+            "status" => Ok(json!(crate::get_app_status(app.clone()))),
+            "recording.start" => {
+                crate::start_recording(app.clone())?;
+                Ok(Value::Null)
+            }
+            "recording.stop" => {
+                let text = crate::stop_recording_and_transcribe(app.clone())?;
+                Ok(json!({ "text": text }))
+            }
+            "dictation.toggle" => toggle_dictation(app),
+            "dictation.status" => {
+                let state = app.state::<AppState>();
+                let remote = state.remote_dictation.lock();
+                let status = match state.session.state() {
+                    SessionState::Recording => "recording",
+                    SessionState::Waiting
+                    | SessionState::Transcribing
+                    | SessionState::Delivering => "transcribing",
+                    SessionState::Idle if remote.transcribing => "transcribing",
+                    SessionState::Idle => "idle",
+                    SessionState::Meeting => "meeting",
+                    SessionState::Testing => "testing",
+                    SessionState::Memo => "memo",
+                };
+                Ok(json!({
+                    "state": status,
+                    "result_id": remote.result.as_ref().map(|result| result.id),
+                }))
+            }
+            "dictation.result" => {
+                let state = app.state::<AppState>();
+                let result = state.remote_dictation.lock().result.clone();
+                Ok(json!(result))
+            }
+            "models.list" => {
+                let models: Vec<Value> = crate::get_available_models(app.clone())
+                    .into_iter()
+                    .map(|(key, name, downloaded, size)| {
+                        json!({ "key": key, "name": name, "downloaded": downloaded, "size": size })
+                    })
+                    .collect();
+                Ok(json!(models))
+            }
+            "models.select" => {
+                let ModelParams { key } = parse_params(params)?;
+                tauri::async_runtime::block_on(crate::set_model_size(app.clone(), key))?;
+                Ok(Value::Null)
+            }
+            "models.download" => {
+                let ModelParams { key } = parse_params(params)?;
+                tauri::async_runtime::block_on(crate::download_model_size(app.clone(), key))?;
+                Ok(Value::Null)
+            }
+            "settings.get" => Ok(json!({
+                "language": crate::get_transcription_language(app.clone()),
+                "punctuation_mode": crate::get_punctuation_mode(app.clone()),
+                "pipeline": crate::get_pipeline(app.clone()),
+                "show_overlay": crate::get_show_overlay(app.clone()),
+                "continuation_window_ms": crate::get_continuation_window(app.clone()),
+                "retain_recordings": crate::get_retain_recordings(app.clone()),
+                "capture_window_context": crate::get_capture_window_context(app.clone()),
+            })),
+            "settings.set" => {
+                let patch: SettingsPatch = parse_params(params)?;
+                if let Some(language) = patch.language {
+                    crate::set_transcription_language(app.clone(), language)?;
+                }
+                if let Some(steps) = patch.pipeline {
+                    check_commands(app, &steps)?;
+                    crate::set_pipeline(app.clone(), steps)?;
+                }
+                if let Some(mode) = patch.punctuation_mode {
+                    crate::set_punctuation_mode(app.clone(), mode);
+                }
+                if let Some(enabled) = patch.show_overlay {
+                    crate::set_show_overlay(app.clone(), enabled);
+                }
+                if let Some(window_ms) = patch.continuation_window_ms {
+                    crate::set_continuation_window(app.clone(), window_ms);
+                }
+                if let Some(enabled) = patch.retain_recordings {
+                    crate::set_retain_recordings(app.clone(), enabled);
+                }
+                if let Some(enabled) = patch.capture_window_context {
+                    crate::set_capture_window_context(app.clone(), enabled);
+                }
+                Ok(Value::Null)
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method: {}", method),
+            )),
+        }
+    }
+}
+
+/// A command output runs a program with the user's rights, so the protocol
+/// may keep or drop the ones already set but not add new ones. Those are
+/// only set in the settings window.
+fn check_commands(app: &AppHandle, steps: &[PipelineStep]) -> Result<(), String> {
+    let current: Vec<OutputTarget> = crate::get_pipeline(app.clone())
+        .into_iter()
+        .filter_map(|step| match step {
+            PipelineStep::Output(target) => Some(target),
+            _ => None,
+        })
+        .collect();
+    let adds_command = steps.iter().any(|step| {
+        matches!(step, PipelineStep::Output(target @ OutputTarget::Command { .. })
+            if !current.contains(target))
+    });
+    if adds_command {
+        return Err("Command outputs can only be added in the settings window".to_string());
+    }
+    Ok(())
+}
+
+fn toggle_dictation(app: &AppHandle) -> Result<Value, RpcError> {
+    let state = app.state::<AppState>();
+    if !state.session.is_recording() {
+        if state.remote_dictation.lock().transcribing {
+            return Err("Still transcribing the previous dictation".to_string().into());
+        }
+        crate::start_recording(app.clone())?;
+        return Ok(json!({ "state": "recording" }));
+    }
+
+    state.remote_dictation.lock().transcribing = true;
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let outcome = crate::stop_recording_and_transcribe(app.clone());
+        let state = app.state::<AppState>();
+        let mut remote = state.remote_dictation.lock();
+        let id = remote.result.as_ref().map_or(1, |result| result.id + 1);
+        let (text, error) = match outcome {
+            Ok(text) => (Some(text), None),
+            Err(e) => (None, Some(e)),
+        };
+        remote.result = Some(DictationResult { id, text, error });
+        remote.transcribing = false;
+    });
+    Ok(json!({ "state": "transcribing" }))
+}
@@ -0,0 +1,65 @@
+//! Secrets kept in the OS credential store (the macOS Keychain, Windows
+//! Credential Manager or the Secret Service on Linux) rather than in the
+//! settings file.
+
+use keyring::Entry;
+
+/// Groups the app's entries in the credential store.
+const SERVICE: &str = "com.antonstjernquist.voice";
+
+/// The API key of the cloud transcription backend.
+pub const CLOUD_API_KEY: &str = "cloud-api-key";
+
+/// The obs-websocket server password.
+pub const OBS_PASSWORD: &str = "obs-password";
+
+/// The API key of the LLM endpoint that names chapters.
+pub const CHAPTER_LLM_API_KEY: &str = "chapter-llm-api-key";
+
+/// The token or password for the model download mirror.
+pub const DOWNLOAD_CREDENTIAL: &str = "download-credential";
+
+/// `None` if nothing has been stored under `name`.
+pub fn load(name: &str) -> Result<Option<String>, String> {
+    match entry(name)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!(
+            "Failed to read {} from the credential store: {}",
+            name, e
+        )),
+    }
+}
+
+pub fn store(name: &str, secret: &str) -> Result<(), String> {
+    entry(name)?
+        .set_password(secret)
+        .map_err(|e| format!("Failed to save {} to the credential store: {}", name, e))
+}
+
+/// Forgets what was stored under `name`, if anything.
+pub fn delete(name: &str) -> Result<(), String> {
+    match entry(name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!(
+            "Failed to remove {} from the credential store: {}",
+            name, e
+        )),
+    }
+}
+
+/// Stores `secret` under `name` if it holds one, and otherwise fills it in
+/// with what was stored before. Returns whether it was stored.
+pub fn store_or_load(name: &str, secret: &mut Option<String>) -> Result<bool, String> {
+    match secret.as_deref().filter(|secret| !secret.is_empty()) {
+        Some(value) => store(name, value).map(|()| true),
+        None => {
+            *secret = load(name)?;
+            Ok(false)
+        }
+    }
+}
+
+fn entry(name: &str) -> Result<Entry, String> {
+    Entry::new(SERVICE, name).map_err(|e| format!("Failed to open the credential store: {}", e))
+}
@@ -0,0 +1,154 @@
+use crate::{use_gpu, with_transcriber, AppState};
+use serde::Serialize;
+use std::time::Instant;
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager};
+use voice_core::audio::{read_flac, WHISPER_SAMPLE_RATE};
+use voice_core::transcription::{compute_config, gpu_backend, TranscriptionLanguage};
+
+/// Where the clip is bundled, relative to the app's resource directory: a
+/// 16 kHz mono FLAC of someone reading [`EXPECTED_TEXT`].
+pub const CLIP_PATH: &str = "resources/self-test.flac";
+
+/// What the bundled clip says.
+pub const EXPECTED_TEXT: &str = "The quick brown fox jumps over the lazy dog.";
+
+/// Share of words the transcription may get wrong and still pass, since the
+/// smaller models slip on a word now and then.
+const MAX_WORD_ERROR_RATE: f32 = 0.2;
+
+/// The outcome of transcribing the bundled clip.
+#[derive(Debug, Serialize)]
+pub struct SelfTestReport {
+    pub passed: bool,
+    pub expected: &'static str,
+    pub transcribed: String,
+    /// Words substituted, dropped or added, as a share of the expected words.
+    pub word_error_rate: f32,
+    /// Seconds of audio transcribed per second taken; below 1 is slower than
+    /// real time.
+    pub speed: f32,
+    pub model: String,
+    pub gpu: bool,
+    pub threads: usize,
+}
+
+impl SelfTestReport {
+    pub fn new(
+        transcribed: String,
+        clip_secs: f32,
+        elapsed_secs: f32,
+        model: String,
+        gpu: bool,
+        threads: usize,
+    ) -> Self {
+        let word_error_rate = word_error_rate(EXPECTED_TEXT, &transcribed);
+        Self {
+            passed: word_error_rate <= MAX_WORD_ERROR_RATE,
+            expected: EXPECTED_TEXT,
+            transcribed,
+            word_error_rate,
+            speed: clip_secs / elapsed_secs.max(f32::EPSILON),
+            model,
+            gpu,
+            threads,
+        }
+    }
+}
+
+/// Transcribes the bundled clip with the selected model and reports how it
+/// did.
+pub fn run(app: &AppHandle) -> Result<SelfTestReport, String> {
+    let clip = app
+        .path()
+        .resolve(CLIP_PATH, BaseDirectory::Resource)
+        .map_err(|e| format!("Failed to find the self-test clip: {}", e))?;
+    let samples = read_flac(&clip)?;
+
+    let state = app.state::<AppState>();
+    // The clip is in English, whatever the user dictates in.
+    let language = TranscriptionLanguage::Code("en".to_string());
+    let started = Instant::now();
+    let transcript = with_transcriber(&state, |transcriber| {
+        transcriber.transcribe(&samples, &language)
+    })?;
+    let elapsed = started.elapsed().as_secs_f32();
+
+    let model = state.selected_model.lock().key();
+    Ok(SelfTestReport::new(
+        transcript.into_text(),
+        samples.len() as f32 / WHISPER_SAMPLE_RATE as f32,
+        elapsed,
+        model,
+        use_gpu(app) && gpu_backend().is_some(),
+        compute_config().threads,
+    ))
+}
+
+/// Lowercase words without punctuation, so "Dog." matches "dog".
+fn words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// The word-level edit distance between the texts over the number of
+/// expected words.
+pub fn word_error_rate(expected: &str, actual: &str) -> f32 {
+    let expected = words(expected);
+    let actual = words(actual);
+    let mut previous: Vec<usize> = (0..=actual.len()).collect();
+    for (i, expected_word) in expected.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, actual_word) in actual.iter().enumerate() {
+            let substitution = previous[j] + usize::from(expected_word != actual_word);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[actual.len()] as f32 / expected.len().max(1) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignores_case_and_punctuation() {
+        assert_eq!(
+            word_error_rate(EXPECTED_TEXT, "the quick brown fox jumps over the lazy dog"),
+            0.0
+        );
+    }
+
+    #[test]
+    fn counts_substituted_dropped_and_added_words() {
+        let expected = "one two three four";
+        assert_eq!(word_error_rate(expected, "one too three four"), 0.25);
+        assert_eq!(word_error_rate(expected, "one three four"), 0.25);
+        assert_eq!(word_error_rate(expected, "one two two three four"), 0.25);
+        assert_eq!(word_error_rate(expected, ""), 1.0);
+    }
+
+    #[test]
+    fn can_exceed_one_when_words_are_added() {
+        assert_eq!(word_error_rate("yes", "no no no"), 3.0);
+        assert_eq!(word_error_rate("", "anything"), 1.0);
+    }
+
+    #[test]
+    fn passes_within_the_allowed_error_rate() {
+        let report =
+            |text: &str| SelfTestReport::new(text.to_string(), 3.0, 1.5, "tiny".into(), false, 4);
+        assert!(report("The quick brown fox jumps over the lazy dog.").passed);
+        assert!(report("The quick brown fox jumped over the lazy dog.").passed);
+        assert!(!report("A quick brown box jumped over a lazy dog.").passed);
+        assert_eq!(report("").speed, 2.0);
+    }
+}
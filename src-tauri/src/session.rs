@@ -0,0 +1,108 @@
+use crate::events::emit;
+use crate::tray;
+use parking_lot::Mutex;
+use serde::Serialize;
+use tauri::AppHandle;
+
+/// Where the dictation pipeline is. A new dictation can only start from
+/// `Idle`, so a second trigger can't start another recording on top of the
+/// first, and a stop that arrives before the start has nothing to stop.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionState {
+    #[default]
+    Idle,
+    Recording,
+    /// A dictation has stopped and waits out the continuation window, so
+    /// another press can carry on recording it.
+    Waiting,
+    Transcribing,
+    Delivering,
+    /// Meeting mode is capturing and transcribing until it is stopped.
+    Meeting,
+    /// A test recording from the settings window, which is never delivered.
+    Testing,
+    /// A quick memo is recording, to be filed in history rather than pasted.
+    Memo,
+}
+
+impl SessionState {
+    fn can_become(self, next: SessionState) -> bool {
+        use SessionState::*;
+        matches!(
+            (self, next),
+            (Idle, Recording)
+                | (Recording, Transcribing)
+                | (Recording, Waiting)
+                | (Waiting, Recording)
+                | (Waiting, Transcribing)
+                | (Transcribing, Delivering)
+                // Picking a reviewed transcription delivers it after the
+                // dictation itself has finished.
+                | (Idle, Delivering)
+                | (Idle, Meeting)
+                | (Idle, Testing)
+                | (Idle, Memo)
+        )
+    }
+}
+
+/// Owns the session state. Every change goes through here and is announced
+/// to the windows as a `session-state` event.
+#[derive(Default)]
+pub struct SessionManager {
+    state: Mutex<SessionState>,
+}
+
+impl SessionManager {
+    pub fn state(&self) -> SessionState {
+        *self.state.lock()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.state() == SessionState::Recording
+    }
+
+    /// Starts recording a dictation, or carries on with the one waiting out
+    /// the continuation window.
+    pub fn start_dictation(&self, app: &AppHandle) -> Result<(), String> {
+        let from = match self.state() {
+            SessionState::Waiting => SessionState::Waiting,
+            _ => SessionState::Idle,
+        };
+        self.transition(app, from, SessionState::Recording)
+    }
+
+    /// Moves from `from` to `to`, failing if the session is somewhere else or
+    /// the move isn't one the pipeline makes.
+    pub fn transition(
+        &self,
+        app: &AppHandle,
+        from: SessionState,
+        to: SessionState,
+    ) -> Result<(), String> {
+        let mut state = self.state.lock();
+        if *state != from || !from.can_become(to) {
+            return Err(format!(
+                "Can't go from {:?} to {:?} while {:?}",
+                from, to, *state
+            ));
+        }
+        *state = to;
+        drop(state);
+        let _ = emit(app, "session-state", to);
+        tray::show_state(app, to);
+        Ok(())
+    }
+
+    /// Returns to `Idle` from wherever the session is, at the end of a
+    /// dictation or when one fails.
+    pub fn reset(&self, app: &AppHandle) {
+        let previous = std::mem::take(&mut *self.state.lock());
+        if previous != SessionState::Idle {
+            let _ = emit(app, "session-state", SessionState::Idle);
+            tray::show_state(app, SessionState::Idle);
+            crate::session_ended(app);
+        }
+    }
+}
@@ -0,0 +1,415 @@
+use crate::consent::ConsentSettings;
+use crate::migrations::{migrate_settings, SETTINGS_VERSION};
+use crate::obs::ObsConfig;
+use crate::postprocess::{
+    dictionary_preset, validate_pipeline, ConflictPolicy, PipelineStep, Replacement, TextRules,
+};
+use crate::profiles::Profile;
+use crate::sounds::SoundCues;
+use crate::translation::LlmEndpoint;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use voice_core::audio::AudioPipeline;
+use voice_core::files::write_private;
+use voice_core::transcription::{
+    Decoding, DownloadConfig, ModelId, ModelManifest, Suppression, TranscriptionBackend,
+};
+
+/// How a dictation without speech is reported.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoSpeechFeedback {
+    /// Close the overlay without comment.
+    Silent,
+    /// Show the error in the overlay.
+    #[default]
+    Notify,
+    /// Play the failure sound.
+    Sound,
+}
+
+/// The edge of the screen the overlay sits against.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerticalAnchor {
+    Top,
+    #[default]
+    Bottom,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HorizontalAnchor {
+    Left,
+    #[default]
+    Center,
+    Right,
+}
+
+/// Where the overlay appears and how large it is.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OverlayPlacement {
+    pub vertical: VerticalAnchor,
+    pub horizontal: HorizontalAnchor,
+    /// Logical pixels in from the anchored edge; when centered, to the right
+    /// of center.
+    pub offset_x: f64,
+    pub offset_y: f64,
+    /// Name of the monitor to show it on; `None` for the one it was last on.
+    pub monitor: Option<String>,
+    /// Zoom of the overlay and its contents, from 0.5 to 2.
+    pub scale: f64,
+}
+
+impl Default for OverlayPlacement {
+    fn default() -> Self {
+        Self {
+            vertical: VerticalAnchor::Bottom,
+            horizontal: HorizontalAnchor::Center,
+            offset_x: 0.0,
+            offset_y: 200.0,
+            monitor: None,
+            scale: 1.0,
+        }
+    }
+}
+
+impl OverlayPlacement {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.5..=2.0).contains(&self.scale) {
+            return Err(format!(
+                "Overlay scale must be between 0.5 and 2, not {}",
+                self.scale
+            ));
+        }
+        if !self.offset_x.is_finite() || !self.offset_y.is_finite() {
+            return Err("Overlay offset must be a number".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// User settings persisted across launches as `settings.json` in the config
+/// directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// The format the file was written in, see [`SETTINGS_VERSION`].
+    pub version: u32,
+    /// Post-transcription steps; empty means copy the text to the clipboard.
+    pub pipeline: Vec<PipelineStep>,
+    /// Lets the user script call `shell(command)`.
+    pub script_shell: bool,
+    /// Ask before piping a transcription to a command output.
+    pub confirm_commands: bool,
+    /// Serve the JSON-RPC protocol to other frontends on a localhost socket.
+    pub rpc_server: bool,
+    /// Serve the remote control page to paired phones on the local network.
+    pub remote_control: bool,
+    /// The pairing token in the remote control QR code; `None` until the
+    /// first pairing.
+    pub remote_token: Option<String>,
+    /// Live captions pushed to OBS.
+    pub obs: ObsConfig,
+    /// Keep every delivered transcription in the searchable history.
+    pub history: bool,
+    /// Watch for calls in conferencing apps and announce them.
+    pub meeting_detection: bool,
+    /// Transcribe detected calls as meetings, from when the call starts until
+    /// it ends.
+    pub meeting_auto_start: bool,
+    /// Consent reminders and announcements for calls.
+    pub consent: ConsentSettings,
+    /// Run whisper on the GPU when the build has a GPU backend.
+    pub gpu: bool,
+    /// Bracket right-to-left text with RLM marks when pasting or typing it.
+    pub rtl_marks: bool,
+    /// What happens when a dictation turns out to hold no speech.
+    pub no_speech: NoSpeechFeedback,
+    /// Local Whisper or a cloud transcription service.
+    pub backend: TranscriptionBackend,
+    /// Names and jargon transcription should recognise.
+    pub vocabulary: Vec<String>,
+    /// Folder of models shared with the other accounts on this machine.
+    pub shared_model_dir: Option<PathBuf>,
+    /// Filler removal, spoken punctuation and whitespace clean-up.
+    pub text_rules: TextRules,
+    /// Re-decode garbled segments at higher temperatures.
+    pub accuracy_safeguard: bool,
+    /// The user's own corrections, such as "eta" to "ETA".
+    pub replacements: Vec<Replacement>,
+    /// Non-speech tokens, music and noise tags kept out of transcriptions.
+    pub suppression: Suppression,
+    /// Memory Voice tries to stay under, in megabytes; `None` for no cap.
+    pub memory_cap_mb: Option<u64>,
+    /// Most of a recording held in memory, in megabytes; older audio waits
+    /// in a temporary file. `None` keeps it all in memory.
+    pub recording_memory_mb: Option<u64>,
+    /// Least time between two deliveries, so pastes from quick consecutive
+    /// dictations stay apart.
+    pub output_debounce_ms: u64,
+    /// Listen for the wake phrase while idle and start a dictation on it.
+    pub wake_word: bool,
+    /// Ids of the built-in dictionary packs used alongside the user's own
+    /// vocabulary and replacements, whichever profile is active. Profiles
+    /// can add their own.
+    pub dictionary_presets: Vec<String>,
+    /// Names the chapters of long transcripts from what is said in them;
+    /// without it they are named after their opening words.
+    pub chapter_llm: Option<LlmEndpoint>,
+    /// Decoding chosen for particular models, by model key. The rest use
+    /// their default.
+    pub decoding: BTreeMap<String, Decoding>,
+    /// Where models are downloaded from and the credentials for it.
+    pub download: DownloadConfig,
+    /// Fill short gaps the audio driver left in a recording instead of only
+    /// marking them.
+    pub repair_dropouts: bool,
+    /// Noise suppression, gain, trimming and loudness for recordings.
+    pub audio: AudioPipeline,
+    /// Chimes when a dictation starts, stops and is done.
+    pub sound_cues: SoundCues,
+    pub overlay: OverlayPlacement,
+    /// A dictation stops and is transcribed after this long, in case the
+    /// shortcut got stuck or hands-free mode was forgotten. `None` for no
+    /// limit.
+    pub max_recording_secs: Option<u64>,
+    /// The shortcut toggles recording instead of being held, and recording
+    /// also stops on its own after `auto_stop_silence_ms` of silence.
+    pub hands_free: bool,
+    /// Zero disables the silence auto-stop, except for dictations started by
+    /// the wake phrase, which use `WOKEN_SILENCE` instead.
+    pub auto_stop_silence_ms: u64,
+    /// Shows a live preview of the words in the overlay while recording.
+    pub streaming_transcription: bool,
+    /// Holds transcriptions in the overlay to be picked, re-recorded or
+    /// discarded from the keyboard before they are delivered.
+    pub review_results: bool,
+    /// Audio kept from just before each recording starts, in milliseconds.
+    /// Anything above 0 keeps the microphone open while idle.
+    pub preroll_ms: u64,
+    /// The address the network microphone listens on; `None` for every
+    /// interface.
+    pub network_microphone_address: Option<IpAddr>,
+    /// The RTP SSRC a network microphone sender pairs with, made up the
+    /// first time it is used.
+    pub network_microphone_ssrc: Option<u32>,
+    pub profiles: Vec<Profile>,
+    /// The name of the profile dictations go to, if any.
+    pub active_profile: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            pipeline: Vec::new(),
+            script_shell: false,
+            confirm_commands: true,
+            rpc_server: false,
+            remote_control: false,
+            remote_token: None,
+            obs: ObsConfig::default(),
+            history: true,
+            meeting_detection: false,
+            meeting_auto_start: false,
+            consent: ConsentSettings::default(),
+            gpu: true,
+            rtl_marks: false,
+            no_speech: NoSpeechFeedback::default(),
+            backend: TranscriptionBackend::default(),
+            vocabulary: Vec::new(),
+            shared_model_dir: None,
+            text_rules: TextRules::default(),
+            accuracy_safeguard: true,
+            replacements: Vec::new(),
+            suppression: Suppression::default(),
+            memory_cap_mb: None,
+            recording_memory_mb: Some(64),
+            output_debounce_ms: 250,
+            wake_word: false,
+            dictionary_presets: Vec::new(),
+            chapter_llm: None,
+            decoding: BTreeMap::new(),
+            download: DownloadConfig::default(),
+            repair_dropouts: false,
+            audio: AudioPipeline::default(),
+            sound_cues: SoundCues::default(),
+            overlay: OverlayPlacement::default(),
+            max_recording_secs: Some(300),
+            hands_free: false,
+            auto_stop_silence_ms: 2000,
+            streaming_transcription: false,
+            review_results: false,
+            preroll_ms: 0,
+            network_microphone_address: None,
+            network_microphone_ssrc: None,
+            profiles: Vec::new(),
+            active_profile: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_pipeline(&self.pipeline)?;
+        self.overlay.validate()?;
+        self.audio.validate()?;
+        self.backend.validate()?;
+        for profile in &self.profiles {
+            profile.validate()?;
+        }
+        self.obs.validate()?;
+        self.download.validate()
+    }
+
+    pub fn active_profile(&self) -> Option<&Profile> {
+        let name = self.active_profile.as_deref()?;
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    /// Adds a profile, or replaces the one with the same name.
+    pub fn save_profile(&mut self, profile: Profile) -> Result<(), String> {
+        let profile = Profile {
+            name: profile.name.trim().to_string(),
+            ..profile
+        };
+        profile.validate()?;
+        match self
+            .profiles
+            .iter_mut()
+            .find(|saved| saved.name == profile.name)
+        {
+            Some(saved) => *saved = profile,
+            None => self.profiles.push(profile),
+        }
+        Ok(())
+    }
+
+    /// Removes the named profile, and stops dictating to it if it was
+    /// active.
+    pub fn delete_profile(&mut self, name: &str) -> Result<(), String> {
+        if !self.profiles.iter().any(|profile| profile.name == name) {
+            return Err(format!("No profile named {}", name));
+        }
+        self.profiles.retain(|profile| profile.name != name);
+        if self.active_profile.as_deref() == Some(name) {
+            self.active_profile = None;
+        }
+        Ok(())
+    }
+
+    /// Replaces the vocabulary, dropping blank and repeated terms.
+    pub fn set_vocabulary(&mut self, vocabulary: Vec<String>) {
+        let mut terms: Vec<String> = Vec::new();
+        for term in vocabulary {
+            let term = term.trim();
+            if !term.is_empty() && !terms.iter().any(|t| t == term) {
+                terms.push(term.to_string());
+            }
+        }
+        self.vocabulary = terms;
+    }
+
+    /// The ids of the built-in dictionary packs in use for every profile, or
+    /// for the named `profile` on top of those.
+    pub fn dictionary_presets_for(&self, profile: Option<&str>) -> Result<&[String], String> {
+        match profile {
+            Some(name) => self
+                .profiles
+                .iter()
+                .find(|saved| saved.name == name)
+                .map(|saved| saved.dictionary_presets.as_slice())
+                .ok_or_else(|| format!("No profile named {}", name)),
+            None => Ok(&self.dictionary_presets),
+        }
+    }
+
+    /// Uses the built-in dictionary pack `id` for every profile, or only for
+    /// the named `profile`, or stops using it there.
+    pub fn set_dictionary_preset(
+        &mut self,
+        id: String,
+        enabled: bool,
+        profile: Option<&str>,
+    ) -> Result<(), String> {
+        if dictionary_preset(&id).is_none() {
+            return Err(format!("No built-in dictionary {:?}", id));
+        }
+        let presets = match profile {
+            Some(name) => {
+                let saved = self.profiles.iter_mut().find(|saved| saved.name == name);
+                &mut saved
+                    .ok_or_else(|| format!("No profile named {}", name))?
+                    .dictionary_presets
+            }
+            None => &mut self.dictionary_presets,
+        };
+        presets.retain(|preset| *preset != id);
+        if enabled {
+            presets.push(id);
+        }
+        Ok(())
+    }
+
+    /// The vocabulary and replacements to transcribe with: the user's own
+    /// and those of the presets enabled for every profile and for the active
+    /// one. The user's replacement wins where both replace a word, and the
+    /// user's terms come last since Whisper drops the start of a long prompt.
+    pub fn dictionary(&self) -> (Vec<String>, Vec<Replacement>) {
+        let profile_presets = self
+            .active_profile()
+            .map(|profile| profile.dictionary_presets.as_slice())
+            .unwrap_or_default();
+        let mut vocabulary = Vec::new();
+        let mut replacements = self.replacements.clone();
+        for id in self.dictionary_presets.iter().chain(profile_presets) {
+            if let Some(pack) = dictionary_preset(id) {
+                pack.merge_into(&mut vocabulary, &mut replacements, ConflictPolicy::Keep);
+            }
+        }
+        vocabulary.retain(|term| !self.vocabulary.contains(term));
+        vocabulary.extend(self.vocabulary.iter().cloned());
+        (vocabulary, replacements)
+    }
+
+    /// How `model` decodes: as chosen for it, or its default.
+    pub fn decoding_for(&self, model: &ModelId, manifest: &ModelManifest) -> Decoding {
+        self.decoding
+            .get(&model.key())
+            .copied()
+            .unwrap_or_else(|| model.default_decoding(manifest))
+    }
+}
+
+pub fn get_settings_path() -> PathBuf {
+    let dir = dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("voice");
+    std::fs::create_dir_all(&dir).ok();
+    dir.join("settings.json")
+}
+
+/// Parses and validates the contents of the settings file, bringing them up
+/// to date. Also returns whether a migration ran, so they should be saved.
+pub fn parse_settings(bytes: &[u8]) -> Result<(Settings, bool), String> {
+    let mut json: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_slice(bytes).map_err(|e| format!("Failed to parse settings: {}", e))?;
+    let migrated = migrate_settings(&mut json);
+    let settings: Settings = serde_json::from_value(json.into())
+        .map_err(|e| format!("Failed to parse settings: {}", e))?;
+    settings.validate()?;
+    Ok((settings, migrated))
+}
+
+/// Saves the settings where only this user can read them, since the remote
+/// control pairing token is among them.
+pub fn save_settings(settings: &Settings) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    write_private(&get_settings_path(), &json)
+        .map_err(|e| format!("Failed to write settings: {}", e))
+}
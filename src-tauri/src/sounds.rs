@@ -0,0 +1,131 @@
+use crate::AppState;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use voice_core::audio::{play_chime, Note};
+
+/// Short chimes that confirm a dictation without a look at the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cue {
+    RecordingStarted,
+    RecordingStopped,
+    TranscriptionComplete,
+    TranscriptionFailed,
+}
+
+impl Cue {
+    /// Rising to start and falling to stop; a bright pair for a finished
+    /// transcription and a low one for a failed one.
+    fn notes(self) -> [Note; 2] {
+        let (first, second, millis) = match self {
+            Cue::RecordingStarted => (660.0, 880.0, 70),
+            Cue::RecordingStopped => (880.0, 660.0, 70),
+            Cue::TranscriptionComplete => (784.0, 1047.0, 110),
+            Cue::TranscriptionFailed => (330.0, 247.0, 160),
+        };
+        let duration = Duration::from_millis(millis);
+        [
+            Note {
+                frequency: first,
+                duration,
+            },
+            Note {
+                frequency: second,
+                duration,
+            },
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundCues {
+    /// When off, no cue is ever played.
+    pub enabled: bool,
+    /// Chime while the overlay shows too, and not only when it is hidden and
+    /// the chimes are the only feedback.
+    pub with_overlay: bool,
+    /// From 0 to 1.
+    pub volume: f32,
+}
+
+impl Default for SoundCues {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            with_overlay: false,
+            volume: 0.5,
+        }
+    }
+}
+
+impl SoundCues {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.volume) {
+            return Err(format!(
+                "Volume must be between 0 and 1, not {}",
+                self.volume
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Plays a cue at the volume from the settings without blocking, unless cues
+/// are off. Failures are only logged since the cue is a courtesy.
+pub fn play(app: &AppHandle, cue: Cue) {
+    let cues = app.state::<AppState>().settings.lock().sound_cues;
+    if !cues.enabled {
+        return;
+    }
+    std::thread::spawn(move || {
+        if let Err(e) = play_chime(&cue.notes(), cues.volume) {
+            eprintln!("{}", e);
+        }
+    });
+}
+
+/// Reads `text` aloud with the system voice and waits until it has been
+/// spoken, so it can be heard before the microphone opens.
+pub fn speak(text: &str) -> Result<(), String> {
+    let mut command = speech_command(text)
+        .ok_or_else(|| "Speech is not supported on this platform".to_string())?;
+    let status = command
+        .status()
+        .map_err(|e| format!("Failed to speak: {}", e))?;
+    if !status.success() {
+        return Err(format!("Speech exited with {}", status));
+    }
+    Ok(())
+}
+
+fn speech_command(text: &str) -> Option<std::process::Command> {
+    #[cfg(target_os = "macos")]
+    {
+        let mut command = std::process::Command::new("say");
+        command.arg(text);
+        Some(command)
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let script = format!(
+            "Add-Type -AssemblyName System.Speech; \
+             (New-Object System.Speech.Synthesis.SpeechSynthesizer).Speak('{}')",
+            text.replace('\'', "''")
+        );
+        let mut command = std::process::Command::new("powershell");
+        command.args(["-NoProfile", "-Command", &script]);
+        Some(command)
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let mut command = std::process::Command::new("spd-say");
+        command.args(["--wait", text]);
+        Some(command)
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        let _ = text;
+        None
+    }
+}
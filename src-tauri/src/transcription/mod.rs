@@ -1,5 +0,0 @@
-mod whisper;
-
-pub use whisper::{
-    download_model, get_model_path, is_model_downloaded, ModelSize, WhisperTranscriber,
-};
@@ -1,132 +0,0 @@
-use std::path::PathBuf;
-use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
-
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
-pub enum ModelSize {
-    Tiny,
-    Base,
-    #[default]
-    Small,
-    Medium,
-    Large,
-}
-
-impl ModelSize {
-    pub fn filename(&self) -> &'static str {
-        match self {
-            ModelSize::Tiny => "ggml-tiny.bin",
-            ModelSize::Base => "ggml-base.bin",
-            ModelSize::Small => "ggml-small.bin",
-            ModelSize::Medium => "ggml-medium.bin",
-            ModelSize::Large => "ggml-large-v3.bin",
-        }
-    }
-
-    pub fn download_url(&self) -> &'static str {
-        match self {
-            ModelSize::Tiny => "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-tiny.bin",
-            ModelSize::Base => "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin",
-            ModelSize::Small => "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.bin",
-            ModelSize::Medium => "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-medium.bin",
-            ModelSize::Large => "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3.bin",
-        }
-    }
-}
-
-pub struct WhisperTranscriber {
-    ctx: WhisperContext,
-}
-
-impl WhisperTranscriber {
-    pub fn new(model_path: &PathBuf) -> Result<Self, String> {
-        let params = WhisperContextParameters::default();
-        let ctx = WhisperContext::new_with_params(model_path.to_str().unwrap(), params)
-            .map_err(|e| format!("Failed to load Whisper model: {}", e))?;
-
-        Ok(Self { ctx })
-    }
-
-    pub fn transcribe(&self, samples: &[f32]) -> Result<String, String> {
-        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
-
-        params.set_language(Some("en"));
-        params.set_print_special(false);
-        params.set_print_progress(false);
-        params.set_print_realtime(false);
-        params.set_print_timestamps(false);
-        params.set_suppress_blank(true);
-        params.set_single_segment(true);
-
-        let mut state = self
-            .ctx
-            .create_state()
-            .map_err(|e| format!("Failed to create state: {}", e))?;
-
-        state
-            .full(params, samples)
-            .map_err(|e| format!("Transcription failed: {}", e))?;
-
-        let num_segments = state.full_n_segments().map_err(|e| e.to_string())?;
-
-        let mut result = String::new();
-        for i in 0..num_segments {
-            if let Ok(segment) = state.full_get_segment_text(i) {
-                result.push_str(&segment);
-            }
-        }
-
-        Ok(result.trim().to_string())
-    }
-}
-
-pub fn get_model_dir() -> PathBuf {
-    let data_dir = dirs::data_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("voice")
-        .join("models");
-    std::fs::create_dir_all(&data_dir).ok();
-    data_dir
-}
-
-pub fn get_model_path(size: ModelSize) -> PathBuf {
-    get_model_dir().join(size.filename())
-}
-
-pub fn is_model_downloaded(size: ModelSize) -> bool {
-    get_model_path(size).exists()
-}
-
-pub async fn download_model<F>(size: ModelSize, progress_callback: F) -> Result<PathBuf, String>
-where
-    F: Fn(u64, u64) + Send + 'static,
-{
-    use futures_util::StreamExt;
-
-    let model_path = get_model_path(size);
-    if model_path.exists() {
-        return Ok(model_path);
-    }
-
-    let url = size.download_url();
-    let response = reqwest::get(url)
-        .await
-        .map_err(|e| format!("Failed to download model: {}", e))?;
-
-    let total_size = response.content_length().unwrap_or(0);
-
-    let mut file = std::fs::File::create(&model_path)
-        .map_err(|e| format!("Failed to create model file: {}", e))?;
-
-    let mut downloaded: u64 = 0;
-    let mut stream = response.bytes_stream();
-
-    while let Some(chunk) = stream.next().await {
-        let chunk = chunk.map_err(|e| format!("Download error: {}", e))?;
-        std::io::Write::write_all(&mut file, &chunk)
-            .map_err(|e| format!("Failed to write chunk: {}", e))?;
-        downloaded += chunk.len() as u64;
-        progress_callback(downloaded, total_size);
-    }
-
-    Ok(model_path)
-}
@@ -0,0 +1,111 @@
+use crate::postprocess::complete;
+use serde::{Deserialize, Serialize};
+use voice_core::transcription::{Transcriber, Transcript, TranscriptionLanguage};
+
+/// An OpenAI-compatible chat completions endpoint that translates captions
+/// into languages whisper can't produce itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmEndpoint {
+    pub endpoint: String,
+    pub model: String,
+    /// Kept in the OS credential store for the chapter endpoint, so it is
+    /// never written out with the settings.
+    #[serde(default, skip_serializing)]
+    pub api_key: Option<String>,
+}
+
+impl LlmEndpoint {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.endpoint.starts_with("http://") && !self.endpoint.starts_with("https://") {
+            return Err("LLM endpoint must be an http(s) URL".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Live captions translated from the spoken `source` language into `target`
+/// for one session.
+#[derive(Debug, Clone)]
+pub struct CaptionTranslation {
+    source: TranscriptionLanguage,
+    target: String,
+    llm: Option<LlmEndpoint>,
+}
+
+impl CaptionTranslation {
+    pub fn new(source: &str, target: &str, llm: Option<LlmEndpoint>) -> Result<Self, String> {
+        let source = TranscriptionLanguage::parse(source)?;
+        let TranscriptionLanguage::Code(target) = TranscriptionLanguage::parse(target)? else {
+            return Err("Captions need a target language".to_string());
+        };
+        match &llm {
+            None if target != "en" && source.key() != target => {
+                return Err(
+                    "Whisper only translates into English; set an LLM endpoint for other languages"
+                        .to_string(),
+                );
+            }
+            Some(llm) => llm.validate()?,
+            _ => {}
+        }
+        Ok(Self {
+            source,
+            target,
+            llm,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        self.source.key()
+    }
+
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// The whisper pass: English captions come straight out of whisper's
+    /// translate mode, anything else is transcribed for [`Self::finish`].
+    pub fn transcribe(
+        &self,
+        transcriber: &dyn Transcriber,
+        samples: &[f32],
+    ) -> Result<Transcript, String> {
+        if self.target == "en" && self.source.key() != "en" {
+            transcriber.translate(samples, &self.source)
+        } else {
+            transcriber.transcribe(samples, &self.source)
+        }
+    }
+
+    /// Translates the output of [`Self::transcribe`] with the LLM when
+    /// whisper couldn't. Kept separate so the model lock isn't held during
+    /// the request.
+    pub fn finish(&self, text: String) -> Result<String, String> {
+        let Some(llm) = &self.llm else {
+            return Ok(text);
+        };
+        if self.target == "en" || self.source.key() == self.target {
+            return Ok(text);
+        }
+
+        let prompt = match &self.source {
+            TranscriptionLanguage::Auto => format!(
+                "Translate the user's message into the language with ISO 639-1 code `{}`. \
+                 Reply with the translation only.",
+                self.target
+            ),
+            TranscriptionLanguage::Code(source) => format!(
+                "Translate the user's message from the language with ISO 639-1 code `{}` into \
+                 the one with code `{}`. Reply with the translation only.",
+                source, self.target
+            ),
+        };
+        complete(
+            &llm.endpoint,
+            &llm.model,
+            &prompt,
+            llm.api_key.as_deref(),
+            &text,
+        )
+    }
+}
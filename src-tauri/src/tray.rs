@@ -0,0 +1,169 @@
+//! The menu bar icon. It is badged while Voice records or transcribes, and
+//! its menu offers the latest dictations to copy again.
+
+use crate::history::HistoryEntry;
+use crate::rpc::apply_server;
+use crate::session::SessionState;
+use crate::{paste_text, with_history, AppState};
+use tauri::image::Image;
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::{TrayIcon, TrayIconBuilder};
+use tauri::{AppHandle, Manager};
+
+const TRAY_ID: &str = "main";
+
+#[cfg(target_os = "macos")]
+pub const TOOLTIP: &str = "Voice - ⇧⌘Space to record";
+
+#[cfg(not(target_os = "macos"))]
+pub const TOOLTIP: &str = "Voice - Shift+Ctrl+Space to record";
+
+/// How many dictations the menu offers to copy again.
+const RECENT_COUNT: u32 = 5;
+
+/// Menu items show this many characters of a dictation.
+const LABEL_CHARS: usize = 40;
+
+/// Menu item ids of recent dictations are this followed by the history id.
+const RECENT_PREFIX: &str = "recent:";
+
+/// The badge while audio is being captured.
+const RECORDING_BADGE: [u8; 3] = [0xe5, 0x48, 0x4d];
+
+/// The badge while a recording is transcribed and delivered.
+const TRANSCRIBING_BADGE: [u8; 3] = [0xf5, 0xa6, 0x23];
+
+pub fn build(app: &AppHandle) -> tauri::Result<TrayIcon> {
+    TrayIconBuilder::with_id(TRAY_ID)
+        .icon(app.default_window_icon().unwrap().clone())
+        .menu(&menu(app, &[])?)
+        .tooltip(TOOLTIP)
+        .on_menu_event(|app, event| on_menu_event(app, event.id.as_ref()))
+        .build(app)
+}
+
+pub fn set_tooltip(app: &AppHandle, tooltip: &str) {
+    let tray = app.state::<AppState>().tray.lock().clone();
+    if let Some(tray) = tray {
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+}
+
+/// Badges the icon while a session is capturing or transcribing audio.
+pub fn show_state(app: &AppHandle, session: SessionState) {
+    let badge = match session {
+        SessionState::Idle => None,
+        SessionState::Recording
+        | SessionState::Meeting
+        | SessionState::Testing
+        | SessionState::Memo => Some(RECORDING_BADGE),
+        SessionState::Waiting | SessionState::Transcribing | SessionState::Delivering => {
+            Some(TRANSCRIBING_BADGE)
+        }
+    };
+    let Some(icon) = app.default_window_icon() else {
+        return;
+    };
+    let icon = match badge {
+        Some(color) => badged(icon, color),
+        None => icon.clone(),
+    };
+    // Cloned out of the lock, since the tray is updated on the main thread.
+    let tray = app.state::<AppState>().tray.lock().clone();
+    if let Some(tray) = tray {
+        let _ = tray.set_icon(Some(icon));
+    }
+}
+
+/// Rebuilds the menu with the latest dictations, or without them while
+/// history is off.
+pub fn show_recent(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let enabled = state.settings.lock().history;
+    let recent = if enabled {
+        with_history(app, |history| history.recent_dictations(RECENT_COUNT)).unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    let tray = state.tray.lock().clone();
+    if let Some(tray) = tray {
+        match menu(app, &recent) {
+            Ok(menu) => {
+                let _ = tray.set_menu(Some(menu));
+            }
+            Err(e) => eprintln!("Failed to build the tray menu: {}", e),
+        }
+    }
+}
+
+fn menu(app: &AppHandle, recent: &[HistoryEntry]) -> tauri::Result<Menu<tauri::Wry>> {
+    let menu = Menu::new(app)?;
+    for entry in recent {
+        let id = format!("{}{}", RECENT_PREFIX, entry.id);
+        let item = MenuItem::with_id(app, id, label(&entry.text), true, None::<&str>)?;
+        menu.append(&item)?;
+    }
+    if !recent.is_empty() {
+        menu.append(&PredefinedMenuItem::separator(app)?)?;
+    }
+    let settings_item = MenuItem::with_id(app, "settings", "Settings...", true, None::<&str>)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+    let quit_item = MenuItem::with_id(app, "quit", "Quit Voice", true, None::<&str>)?;
+    menu.append_items(&[&settings_item, &separator, &quit_item])?;
+    Ok(menu)
+}
+
+fn on_menu_event(app: &AppHandle, id: &str) {
+    if let Some(id) = id.strip_prefix(RECENT_PREFIX) {
+        let text = id
+            .parse()
+            .map_err(|_| "History entry not found".to_string())
+            .and_then(|id| with_history(app, |history| history.get(id)))
+            .map(|entry| entry.text);
+        if let Err(e) = text.and_then(|text| paste_text(app.clone(), text)) {
+            eprintln!("{}", e);
+        }
+        return;
+    }
+    match id {
+        "settings" => {
+            if let Some(window) = app.get_webview_window("settings") {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        }
+        "quit" => {
+            let _ = apply_server(app, false);
+            app.exit(0);
+        }
+        _ => {}
+    }
+}
+
+/// A dictation on one line, cut short if it's long.
+fn label(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(LABEL_CHARS) {
+        Some((end, _)) => format!("{}…", text[..end].trim_end()),
+        None => text,
+    }
+}
+
+/// The app icon with a dot of `color` in its lower right corner.
+fn badged(icon: &Image<'_>, color: [u8; 3]) -> Image<'static> {
+    let (width, height) = (icon.width(), icon.height());
+    let mut rgba = icon.rgba().to_vec();
+    let radius = width.min(height) as f32 / 4.0;
+    let (center_x, center_y) = (width as f32 - radius, height as f32 - radius);
+    for y in 0..height {
+        for x in 0..width {
+            let dx = x as f32 + 0.5 - center_x;
+            let dy = y as f32 + 0.5 - center_y;
+            if dx * dx + dy * dy <= radius * radius {
+                let pixel = ((y * width + x) * 4) as usize;
+                rgba[pixel..pixel + 4].copy_from_slice(&[color[0], color[1], color[2], 255]);
+            }
+        }
+    }
+    Image::new_owned(rgba, width, height)
+}
@@ -6,6 +6,12 @@ import { invoke } from "@tauri-apps/api/core";
 
 type EventCallback = (event: { payload: unknown }) => void;
 
+const result = (text: string) => ({
+  text,
+  segments: [{ start: 0, end: 1, text }],
+  language: "en",
+});
+
 const mockListen = listen as ReturnType<typeof vi.fn>;
 const mockInvoke = invoke as ReturnType<typeof vi.fn>;
 
@@ -88,8 +94,12 @@ describe("Overlay", () => {
         expect(mockListen).toHaveBeenCalledWith("audio-level", expect.any(Function));
         expect(mockListen).toHaveBeenCalledWith("recording-stopped", expect.any(Function));
         expect(mockListen).toHaveBeenCalledWith("transcription-started", expect.any(Function));
+        expect(mockListen).toHaveBeenCalledWith("transcription-partial", expect.any(Function));
         expect(mockListen).toHaveBeenCalledWith("transcription-complete", expect.any(Function));
         expect(mockListen).toHaveBeenCalledWith("transcription-error", expect.any(Function));
+        expect(mockListen).toHaveBeenCalledWith("transcription-review", expect.any(Function));
+        expect(mockListen).toHaveBeenCalledWith("transcription-discarded", expect.any(Function));
+        expect(mockListen).toHaveBeenCalledWith("audio-dropouts", expect.any(Function));
       });
     });
   });
@@ -178,6 +188,163 @@ describe("Overlay", () => {
     });
   });
 
+  describe("profiles", () => {
+    it("shows the active profile's name, icon and color", async () => {
+      const { container, getByText } = render(<Overlay />);
+
+      await waitFor(() => {
+        expect(eventListeners.has("recording-started")).toBe(true);
+      });
+
+      emitEvent("recording-started", { name: "Swedish", color: "#ff8800", icon: "🇸🇪" });
+
+      await waitFor(() => {
+        expect(getByText("Swedish")).toBeInTheDocument();
+        expect(getByText("Swedish").parentElement).toHaveTextContent("🇸🇪 Swedish");
+        expect(container.firstChild).toHaveStyle({ borderColor: "#ff8800" });
+      });
+    });
+
+    it("keeps the default look without a profile", async () => {
+      const { queryByText } = render(<Overlay />);
+
+      await waitFor(() => {
+        expect(eventListeners.has("recording-started")).toBe(true);
+      });
+
+      emitEvent("recording-started", { name: "Swedish", color: "#ff8800", icon: null });
+      emitEvent("recording-started", null);
+
+      await waitFor(() => {
+        expect(queryByText("Swedish")).not.toBeInTheDocument();
+      });
+    });
+  });
+
+  describe("partial transcription", () => {
+    it("shows partial text while recording", async () => {
+      const { getByText } = render(<Overlay />);
+
+      await waitFor(() => {
+        expect(eventListeners.has("transcription-partial")).toBe(true);
+      });
+
+      emitEvent("recording-started");
+      emitEvent("transcription-partial", "Hello wor");
+
+      await waitFor(() => {
+        expect(getByText("Hello wor")).toBeInTheDocument();
+      });
+    });
+
+    it("clears partial text when transcription completes", async () => {
+      const { queryByText } = render(<Overlay />);
+
+      await waitFor(() => {
+        expect(eventListeners.has("transcription-partial")).toBe(true);
+      });
+
+      emitEvent("recording-started");
+      emitEvent("transcription-partial", "Hello wor");
+      emitEvent("recording-stopped");
+      emitEvent("transcription-complete", result("Hello world"));
+
+      await waitFor(() => {
+        expect(queryByText("Hello wor")).not.toBeInTheDocument();
+      });
+    });
+  });
+
+  describe("confidence", () => {
+    const withWords = (words: { text: string; probability: number }[]) => ({
+      text: words.map((word) => word.text).join(" "),
+      segments: [{ start: 0, end: 1, text: "", confidence: 0.7, words }],
+      language: "en",
+    });
+
+    it("lists low-confidence words to double-check", async () => {
+      const { getByText } = render(<Overlay />);
+
+      await waitFor(() => {
+        expect(eventListeners.has("transcription-complete")).toBe(true);
+      });
+
+      emitEvent("recording-started");
+      emitEvent(
+        "transcription-complete",
+        withWords([
+          { text: "Deploy", probability: 0.95 },
+          { text: "to", probability: 0.9 },
+          { text: "Kubernetes.", probability: 0.3 },
+        ]),
+      );
+
+      await waitFor(() => {
+        expect(getByText("Kubernetes")).toBeInTheDocument();
+      });
+    });
+
+    it("shows nothing to check when the model was confident", async () => {
+      const { queryByText } = render(<Overlay />);
+
+      await waitFor(() => {
+        expect(eventListeners.has("transcription-complete")).toBe(true);
+      });
+
+      emitEvent("recording-started");
+      emitEvent("transcription-complete", withWords([{ text: "Hello", probability: 0.9 }]));
+
+      await waitFor(() => {
+        expect(queryByText(/Check:/)).not.toBeInTheDocument();
+      });
+    });
+  });
+
+  describe("dropouts", () => {
+    const twoSegments = {
+      text: "Ship it today. Then tell the team.",
+      segments: [
+        { start: 0, end: 2, text: " Ship it today." },
+        { start: 2, end: 4, text: " Then tell the team." },
+      ],
+      language: "en",
+    };
+
+    it("shows what was transcribed where the audio dropped out", async () => {
+      const { getByText, queryByText } = render(<Overlay />);
+
+      await waitFor(() => {
+        expect(eventListeners.has("audio-dropouts")).toBe(true);
+      });
+
+      emitEvent("recording-started");
+      emitEvent("audio-dropouts", [{ start: 2.5, end: 2.6 }]);
+      emitEvent("transcription-complete", twoSegments);
+
+      await waitFor(() => {
+        expect(getByText("Then tell the team.")).toBeInTheDocument();
+      });
+      expect(queryByText("Ship it today.")).not.toBeInTheDocument();
+    });
+
+    it("forgets the dropouts of the previous dictation", async () => {
+      const { queryByText } = render(<Overlay />);
+
+      await waitFor(() => {
+        expect(eventListeners.has("audio-dropouts")).toBe(true);
+      });
+
+      emitEvent("recording-started");
+      emitEvent("audio-dropouts", [{ start: 0.5, end: 0.5 }]);
+      emitEvent("recording-started");
+      emitEvent("transcription-complete", twoSegments);
+
+      await waitFor(() => {
+        expect(queryByText(/Audio dropped/)).not.toBeInTheDocument();
+      });
+    });
+  });
+
   describe("processing state", () => {
     it("transitions to processing when recording stops", async () => {
       const { container } = render(<Overlay />);
@@ -225,7 +392,7 @@ describe("Overlay", () => {
       });
 
       emitEvent("recording-started");
-      emitEvent("transcription-complete", "Hello world");
+      emitEvent("transcription-complete", result("Hello world"));
 
       await waitFor(() => {
         const svg = container.querySelector("svg");
@@ -241,7 +408,7 @@ describe("Overlay", () => {
       });
 
       emitEvent("recording-started");
-      emitEvent("transcription-complete", "Test");
+      emitEvent("transcription-complete", result("Test"));
 
       await waitFor(() => {
         const path = container.querySelector('path[d="M5 13l4 4L19 7"]');
@@ -284,6 +451,68 @@ describe("Overlay", () => {
     });
   });
 
+  describe("review", () => {
+    it("lists numbered alternatives for review", async () => {
+      const { getByText } = render(<Overlay />);
+
+      await waitFor(() => {
+        expect(eventListeners.has("transcription-review")).toBe(true);
+      });
+
+      emitEvent("recording-started");
+      emitEvent("transcription-started");
+      emitEvent("transcription-review", ["Hello world", "Hello word"]);
+
+      await waitFor(() => {
+        expect(getByText("Hello world")).toBeInTheDocument();
+        expect(getByText("Hello word")).toBeInTheDocument();
+        expect(getByText("2")).toBeInTheDocument();
+      });
+    });
+
+    it("picks an alternative when clicked", async () => {
+      const { getByText } = render(<Overlay />);
+
+      await waitFor(() => {
+        expect(eventListeners.has("transcription-review")).toBe(true);
+      });
+
+      emitEvent("transcription-review", ["Hello world", "Hello word"]);
+
+      await waitFor(() => {
+        expect(getByText("Hello word")).toBeInTheDocument();
+      });
+
+      act(() => {
+        getByText("Hello word").click();
+      });
+
+      expect(mockInvoke).toHaveBeenCalledWith("review_action", {
+        action: { action: "pick", index: 1 },
+      });
+    });
+
+    it("clears the alternatives when discarded", async () => {
+      const { queryByText, getByText } = render(<Overlay />);
+
+      await waitFor(() => {
+        expect(eventListeners.has("transcription-review")).toBe(true);
+      });
+
+      emitEvent("transcription-review", ["Hello world"]);
+
+      await waitFor(() => {
+        expect(getByText("Hello world")).toBeInTheDocument();
+      });
+
+      emitEvent("transcription-discarded");
+
+      await waitFor(() => {
+        expect(queryByText("Hello world")).not.toBeInTheDocument();
+      });
+    });
+  });
+
   describe("state transitions", () => {
     it("transitions smoothly from recording to processing to done", async () => {
       const { container } = render(<Overlay />);
@@ -304,7 +533,7 @@ describe("Overlay", () => {
       bars = container.querySelectorAll(".rounded-full");
       expect(bars.length).toBe(18);
 
-      emitEvent("transcription-complete", "Test");
+      emitEvent("transcription-complete", result("Test"));
       vi.advanceTimersByTime(350);
 
       await waitFor(() => {
@@ -324,7 +553,7 @@ describe("Overlay", () => {
       emitEvent("audio-level", 0.5);
       emitEvent("recording-stopped");
       emitEvent("transcription-started");
-      emitEvent("transcription-complete", "Fast");
+      emitEvent("transcription-complete", result("Fast"));
 
       vi.advanceTimersByTime(500);
 
@@ -335,6 +564,30 @@ describe("Overlay", () => {
     });
   });
 
+  describe("late attach", () => {
+    it("catches up on events sent before it loaded", async () => {
+      mockInvoke.mockImplementation((command: string) => {
+        if (command === "is_model_ready") {
+          return Promise.resolve(true);
+        }
+        if (command === "get_recent_events") {
+          return Promise.resolve([
+            { id: 1, event: "recording-started", payload: null },
+            { id: 2, event: "transcription-complete", payload: result("Test") },
+          ]);
+        }
+        return Promise.resolve();
+      });
+
+      const { container } = render(<Overlay />);
+
+      await waitFor(() => {
+        const path = container.querySelector('path[d="M5 13l4 4L19 7"]');
+        expect(path).toBeInTheDocument();
+      });
+    });
+  });
+
   describe("cleanup", () => {
     it("cleans up event listeners on unmount", async () => {
       const { unmount } = render(<Overlay />);
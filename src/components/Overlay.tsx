@@ -4,8 +4,60 @@ import { invoke } from "@tauri-apps/api/core";
 
 type RecordingState = "idle" | "recording" | "processing" | "done" | "error";
 
+type TranscriptionResult = {
+  text: string;
+  segments: {
+    start: number;
+    end: number;
+    text: string;
+    confidence?: number | null;
+    words?: { text: string; probability: number }[];
+  }[];
+  language: string | null;
+};
+
+type Profile = {
+  name: string;
+  color: string;
+  icon: string | null;
+};
+
+type DropoutRange = {
+  start: number;
+  end: number;
+};
+
+type RecordedEvent = {
+  id: number;
+  event: string;
+  payload: unknown;
+};
+
+// Words the model was less sure of than this are shown to double-check.
+const LOW_CONFIDENCE = 0.5;
+
+function uncertainWords(result: TranscriptionResult): string[] {
+  return result.segments
+    .flatMap((segment) => segment.words ?? [])
+    .filter((word) => word.probability < LOW_CONFIDENCE)
+    .map((word) => word.text.replace(/^[^\p{L}\p{N}]+|[^\p{L}\p{N}]+$/gu, ""))
+    .filter((word) => word.length > 0);
+}
+
+// What was transcribed from where the audio dropped out, so it can be
+// double-checked.
+function droppedText(result: TranscriptionResult, dropouts: DropoutRange[]): string[] {
+  return result.segments
+    .filter((segment) =>
+      dropouts.some((dropout) => dropout.start <= segment.end && segment.start <= dropout.end)
+    )
+    .map((segment) => segment.text.trim())
+    .filter((text) => text.length > 0);
+}
+
 const creamColor = "rgba(255, 253, 245, 0.85)";
 const creamColorDim = "rgba(255, 253, 245, 0.5)";
+const borderColor = "rgba(255, 253, 245, 0.25)";
 
 function StateVisualizer({ state, level }: { state: RecordingState; level: number }) {
   const barCount = 18;
@@ -91,10 +143,47 @@ function StateVisualizer({ state, level }: { state: RecordingState; level: numbe
   );
 }
 
+function ReviewList({ alternatives }: { alternatives: string[] }) {
+  const pick = (index: number) => {
+    invoke("review_action", { action: { action: "pick", index } }).catch(console.error);
+  };
+
+  return (
+    <div className="flex w-full flex-col gap-1 px-3">
+      <ol className="flex flex-col gap-1">
+        {alternatives.map((text, index) => (
+          <li
+            key={index}
+            className="flex cursor-pointer gap-2 truncate text-xs"
+            style={{ color: index === 0 ? creamColor : creamColorDim }}
+            onClick={() => pick(index)}
+          >
+            <span>{index + 1}</span>
+            <bdi className="truncate">{text}</bdi>
+          </li>
+        ))}
+      </ol>
+      <p className="text-center text-[10px]" style={{ color: creamColorDim }}>
+        9 re-record · 0 discard
+      </p>
+    </div>
+  );
+}
+
 export function Overlay() {
   const [state, setState] = useState<RecordingState>("idle");
   const [modelReady, setModelReady] = useState(false);
   const [audioLevel, setAudioLevel] = useState(0);
+  const [partialText, setPartialText] = useState("");
+  const [alternatives, setAlternatives] = useState<string[]>([]);
+  const [uncertain, setUncertain] = useState<string[]>([]);
+  // Sent before the transcription they belong to.
+  const dropoutsRef = useRef<DropoutRange[]>([]);
+  // `null` when no audio dropped out; empty when it did where nothing was
+  // transcribed.
+  const [dropped, setDropped] = useState<string[] | null>(null);
+  // The profile the current dictation goes to, if any.
+  const [profile, setProfile] = useState<Profile | null>(null);
   const [downloadProgress, setDownloadProgress] = useState<{
     downloaded: number;
     total: number;
@@ -111,43 +200,85 @@ export function Overlay() {
 
   useEffect(() => {
     const unlisteners: (() => void)[] = [];
+    let unmounted = false;
 
-    listen("download-progress", (event) => {
-      const [downloaded, total] = event.payload as [number, number];
-      setDownloadProgress({ downloaded, total });
-      if (downloaded >= total) {
-        setModelReady(true);
-        setDownloadProgress(null);
-      }
-    }).then((unlisten) => unlisteners.push(unlisten));
-
-    listen("recording-started", () => {
-      setState("recording");
-      setAudioLevel(0);
-    }).then((unlisten) => unlisteners.push(unlisten));
-
-    listen<number>("audio-level", (event) => {
-      setAudioLevel(event.payload);
-    }).then((unlisten) => unlisteners.push(unlisten));
-
-    listen("recording-stopped", () => {
-      setState("processing");
-      setAudioLevel(0);
-    }).then((unlisten) => unlisteners.push(unlisten));
-
-    listen("transcription-started", () => {
-      setState("processing");
-    }).then((unlisten) => unlisteners.push(unlisten));
+    const handlers: Record<string, (payload: unknown) => void> = {
+      "download-progress": (payload) => {
+        const [downloaded, total] = payload as [number, number];
+        setDownloadProgress({ downloaded, total });
+        if (downloaded >= total) {
+          setModelReady(true);
+          setDownloadProgress(null);
+        }
+      },
+      "recording-started": (payload) => {
+        setState("recording");
+        setProfile((payload as Profile | null | undefined) ?? null);
+        setAudioLevel(0);
+        setPartialText("");
+        setAlternatives([]);
+        setUncertain([]);
+        setDropped(null);
+        dropoutsRef.current = [];
+      },
+      "audio-level": (payload) => {
+        setAudioLevel(payload as number);
+      },
+      "recording-stopped": () => {
+        setState("processing");
+        setAudioLevel(0);
+      },
+      "transcription-started": () => {
+        setState("processing");
+      },
+      "audio-dropouts": (payload) => {
+        dropoutsRef.current = payload as DropoutRange[];
+      },
+      "transcription-partial": (payload) => {
+        setPartialText(payload as string);
+      },
+      "transcription-complete": (payload) => {
+        setState("done");
+        setPartialText("");
+        setAlternatives([]);
+        setUncertain(uncertainWords(payload as TranscriptionResult));
+        const dropouts = dropoutsRef.current;
+        dropoutsRef.current = [];
+        setDropped(
+          dropouts.length > 0 ? droppedText(payload as TranscriptionResult, dropouts) : null
+        );
+      },
+      "transcription-review": (payload) => {
+        setAlternatives(payload as string[]);
+        setPartialText("");
+      },
+      "transcription-discarded": () => {
+        setState("idle");
+        setAlternatives([]);
+      },
+      "transcription-error": () => {
+        setState("error");
+        setPartialText("");
+      },
+    };
 
-    listen<string>("transcription-complete", () => {
-      setState("done");
-    }).then((unlisten) => unlisteners.push(unlisten));
+    const listening = Object.entries(handlers).map(([name, handle]) =>
+      listen(name, (event) => handle(event.payload)).then((unlisten) =>
+        unlisteners.push(unlisten)
+      )
+    );
 
-    listen<string>("transcription-error", () => {
-      setState("error");
-    }).then((unlisten) => unlisteners.push(unlisten));
+    // Catch up on anything sent before this window loaded.
+    Promise.all(listening)
+      .then(() => invoke<RecordedEvent[] | undefined>("get_recent_events"))
+      .then((events) => {
+        if (unmounted) return;
+        events?.forEach((recorded) => handlers[recorded.event]?.(recorded.payload));
+      })
+      .catch(console.error);
 
     return () => {
+      unmounted = true;
       unlisteners.forEach((unlisten) => unlisten());
     };
   }, []);
@@ -159,7 +290,7 @@ export function Overlay() {
     return (
       <div
         className="flex h-screen w-screen items-center justify-center bg-neutral-900/95 backdrop-blur-xl rounded-2xl border-2"
-        style={{ borderColor: "rgba(255, 253, 245, 0.25)" }}
+        style={{ borderColor }}
       >
         <div className="w-24 h-1 rounded-full overflow-hidden" style={{ backgroundColor: "rgba(255, 253, 245, 0.1)" }}>
           <div
@@ -174,11 +305,44 @@ export function Overlay() {
   return (
     <div
       className="flex h-screen w-screen items-center justify-center bg-neutral-900/95 backdrop-blur-xl rounded-2xl border-2"
-      style={{ borderColor: "rgba(255, 253, 245, 0.25)" }}
+      style={{ borderColor: profile?.color ?? borderColor }}
     >
-      {state !== "idle" && (
-        <StateVisualizer state={state} level={audioLevel} />
-      )}
+      <div className="flex w-full flex-col items-center">
+        {profile && alternatives.length === 0 && state !== "idle" && (
+          <p className="w-full truncate px-3 text-center text-[10px]" style={{ color: profile.color }}>
+            {profile.icon && `${profile.icon} `}
+            <bdi>{profile.name}</bdi>
+          </p>
+        )}
+        {alternatives.length > 0 && <ReviewList alternatives={alternatives} />}
+        {alternatives.length === 0 && state !== "idle" && (
+          <StateVisualizer state={state} level={audioLevel} />
+        )}
+        {partialText && (state === "recording" || state === "processing") && (
+          <p
+            className="w-full truncate px-3 text-center text-xs"
+            style={{ color: creamColorDim, direction: "rtl" }}
+          >
+            <bdi>{partialText}</bdi>
+          </p>
+        )}
+        {uncertain.length > 0 && state === "done" && (
+          <p className="w-full truncate px-3 text-center text-[10px]" style={{ color: creamColorDim }}>
+            Check: <bdi>{uncertain.join(", ")}</bdi>
+          </p>
+        )}
+        {dropped && state === "done" && (
+          <p className="w-full truncate px-3 text-center text-[10px]" style={{ color: creamColorDim }}>
+            {dropped.length > 0 ? (
+              <>
+                Audio dropped: <bdi>{dropped.join(" … ")}</bdi>
+              </>
+            ) : (
+              "Audio dropped out"
+            )}
+          </p>
+        )}
+      </div>
     </div>
   );
 }
@@ -1,13 +1,145 @@
 import { useEffect, useState } from "react";
 import { listen } from "@tauri-apps/api/event";
 import { invoke } from "@tauri-apps/api/core";
+import { getCurrentWebview } from "@tauri-apps/api/webview";
 
 const creamColor = "rgba(255, 253, 245, 0.85)";
 const creamColorDim = "rgba(255, 253, 245, 0.5)";
 const borderColor = "rgba(255, 253, 245, 0.25)";
 
-type ModelInfo = [string, string, boolean];
+// key, label, downloaded, size in bytes
+type ModelInfo = [string, string, boolean, number];
 type DownloadProgress = { size: string; downloaded: number; total: number };
+type Segment = { start: number; end: number; text: string };
+type FileTranscription = {
+  path: string;
+  name: string;
+  done: number;
+  total: number;
+  text: string | null;
+  segments: Segment[];
+  error: string | null;
+  saved: string | null;
+};
+type ExportFormat = "srt" | "vtt" | "md";
+type TestTranscription = { text: string; practice_sentence: string; word_error_rate: number; peak: number };
+
+// Reads a practice sentence into the microphone, showing the level live and
+// what the model heard, so gain and noise suppression can be tuned on the
+// same recording.
+function MicrophoneTest() {
+  const [recording, setRecording] = useState(false);
+  const [level, setLevel] = useState(0);
+  const [gain, setGain] = useState(1);
+  const [noiseSuppression, setNoiseSuppression] = useState(false);
+  const [result, setResult] = useState<TestTranscription | null>(null);
+  const [transcribing, setTranscribing] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    invoke<number>("get_input_gain").then(setGain).catch(console.error);
+    invoke<boolean>("get_noise_suppression").then(setNoiseSuppression).catch(console.error);
+    const unlisten = listen<number>("test-audio-level", (event) => setLevel(event.payload));
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  const transcribe = (command: string) => {
+    setTranscribing(true);
+    setError(null);
+    invoke<TestTranscription>(command)
+      .then(setResult)
+      .catch((e) => setError(String(e)))
+      .finally(() => setTranscribing(false));
+  };
+
+  const handleRecord = () => {
+    if (recording) {
+      setRecording(false);
+      setLevel(0);
+      transcribe("stop_test_recording");
+    } else {
+      setError(null);
+      invoke("start_test_recording")
+        .then(() => setRecording(true))
+        .catch((e) => setError(String(e)));
+    }
+  };
+
+  const handleGainChange = (value: number) => {
+    setGain(value);
+    invoke("set_input_gain", { gain: value }).catch(console.error);
+  };
+
+  const handleNoiseSuppressionChange = (enabled: boolean) => {
+    setNoiseSuppression(enabled);
+    invoke("set_noise_suppression", { enabled }).catch(console.error);
+  };
+
+  return (
+    <div className="flex flex-col gap-2 px-3 py-2.5 rounded-lg" style={{ border: `1px solid ${borderColor}` }}>
+      <span className="text-xs" style={{ color: creamColorDim }}>
+        Read aloud: “Please call Stella and ask her to bring these things with her from the store.”
+      </span>
+      <div className="flex items-center gap-3">
+        <button
+          onClick={handleRecord}
+          disabled={transcribing}
+          className="text-xs px-2 py-0.5 rounded hover:bg-white/10 transition-colors"
+          style={{ color: creamColorDim, border: `1px solid ${borderColor}` }}
+        >
+          {recording ? "Stop" : "Record"}
+        </button>
+        <div className="flex-1 h-1 rounded-full overflow-hidden" style={{ backgroundColor: "rgba(255, 253, 245, 0.1)" }}>
+          <div
+            className="h-full transition-all duration-75 rounded-full"
+            style={{ width: `${Math.round(level * 100)}%`, backgroundColor: creamColor }}
+          />
+        </div>
+      </div>
+      <label className="flex items-center gap-3 text-xs" style={{ color: creamColorDim }}>
+        Gain
+        <input
+          type="range"
+          min={0.5}
+          max={4}
+          step={0.1}
+          value={gain}
+          onChange={(e) => handleGainChange(Number(e.target.value))}
+          className="flex-1"
+        />
+        <span className="w-8 text-right">{gain.toFixed(1)}×</span>
+      </label>
+      <label className="flex items-center gap-2 text-xs" style={{ color: creamColorDim }}>
+        <input
+          type="checkbox"
+          checked={noiseSuppression}
+          onChange={(e) => handleNoiseSuppressionChange(e.target.checked)}
+        />
+        Noise suppression
+      </label>
+      {transcribing && <span className="text-xs" style={{ color: creamColorDim }}>Transcribing…</span>}
+      {result && !transcribing && (
+        <div className="flex flex-col gap-1 select-text">
+          <p className="text-sm" style={{ color: creamColor }}>{result.text || "(no speech heard)"}</p>
+          <span className="text-xs" style={{ color: creamColorDim }}>
+            {Math.round((1 - Math.min(result.word_error_rate, 1)) * 100)}% of words right
+            {result.peak >= 1 ? " · the input clipped, lower the gain" : ""}
+          </span>
+          <button
+            onClick={() => transcribe("retranscribe_test_recording")}
+            className="self-start text-xs px-2 py-0.5 rounded hover:bg-white/10 transition-colors"
+            style={{ color: creamColorDim, border: `1px solid ${borderColor}` }}
+          >
+            Try these settings
+          </button>
+        </div>
+      )}
+      {error !== null && <span className="text-xs" style={{ color: "#f87171" }}>{error}</span>}
+    </div>
+  );
+}
 
 export function Settings() {
   const [devices, setDevices] = useState<string[]>([]);
@@ -18,6 +150,7 @@ export function Settings() {
   const [accessibilityPermission, setAccessibilityPermission] = useState(false);
   const [downloading, setDownloading] = useState<string | null>(null);
   const [downloadProgress, setDownloadProgress] = useState<DownloadProgress | null>(null);
+  const [fileTranscription, setFileTranscription] = useState<FileTranscription | null>(null);
 
   useEffect(() => {
     invoke<string[]>("get_audio_devices").then(setDevices).catch(console.error);
@@ -28,6 +161,35 @@ export function Settings() {
     invoke<boolean>("check_accessibility_permission").then(setAccessibilityPermission).catch(console.error);
   }, []);
 
+  useEffect(() => {
+    // Sent on Windows when a microphone is plugged in or unplugged.
+    const unlisten = listen<string[]>("audio-devices-changed", (event) => {
+      setDevices(event.payload);
+      invoke<string | null>("get_current_device").then(setCurrentDevice).catch(console.error);
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    const unlisten = listen<{ permission: string; granted: boolean }>("permission-changed", (event) => {
+      const { permission, granted } = event.payload;
+      if (permission === "microphone") setMicPermission(granted);
+      if (permission === "accessibility") setAccessibilityPermission(granted);
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  const requestMicrophone = async () => {
+    const granted = await invoke<boolean>("request_microphone_permission");
+    setMicPermission(granted);
+    // Once refused, access can only be given in System Settings.
+    if (!granted) await invoke("open_microphone_settings");
+  };
+
   useEffect(() => {
     const unlisten = listen<[string, number, number]>("model-download-progress", (event) => {
       const [size, downloaded, total] = event.payload;
@@ -38,9 +200,55 @@ export function Settings() {
         invoke<ModelInfo[]>("get_available_models").then(setModels).catch(console.error);
       }
     });
-    return () => { unlisten.then(fn => fn()); };
+    const unlistenCancelled = listen<string>("model-download-cancelled", () => {
+      setDownloading(null);
+      setDownloadProgress(null);
+    });
+    return () => {
+      unlisten.then(fn => fn());
+      unlistenCancelled.then(fn => fn());
+    };
   }, []);
 
+  useEffect(() => {
+    const unlistenDrop = getCurrentWebview().onDragDropEvent((event) => {
+      if (event.payload.type !== "drop" || event.payload.paths.length === 0) return;
+      const path = event.payload.paths[0];
+      const name = path.split(/[\\/]/).pop() ?? path;
+      setFileTranscription({ path, name, done: 0, total: 0, text: null, segments: [], error: null, saved: null });
+      invoke<{ text: string; segments: Segment[] }>("transcribe_file", { path })
+        .then((result) =>
+          setFileTranscription((current) => current && { ...current, text: result.text, segments: result.segments })
+        )
+        .catch((e) => setFileTranscription((current) => current && { ...current, error: String(e) }));
+    });
+    const unlistenProgress = listen<[string, number, number]>("file-transcription-progress", (event) => {
+      const [, done, total] = event.payload;
+      setFileTranscription((current) => current && { ...current, done, total });
+    });
+    return () => {
+      unlistenDrop.then(fn => fn());
+      unlistenProgress.then(fn => fn());
+    };
+  }, []);
+
+  const handleExport = async (format: ExportFormat) => {
+    if (!fileTranscription) return;
+    // Offered beside the audio, e.g. episode.mp3 -> episode.srt.
+    const suggestedPath = fileTranscription.path.replace(/\.[^.\\/]*$/, "") + "." + format;
+    const segments = fileTranscription.segments;
+    try {
+      const path = await invoke<string | null>("choose_export_path", { suggestedPath, extension: format });
+      if (path === null) return;
+      await (format === "md"
+        ? invoke("export_markdown", { segments, path })
+        : invoke("export_subtitles", { segments, format, path }));
+      setFileTranscription((current) => current && { ...current, saved: path });
+    } catch (e) {
+      setFileTranscription((current) => current && { ...current, error: String(e) });
+    }
+  };
+
   const handleDeviceChange = async (deviceName: string) => {
     const device = deviceName === "default" ? null : deviceName;
     await invoke("set_audio_device", { deviceName: device });
@@ -56,11 +264,20 @@ export function Settings() {
       setSelectedModel(size);
     } else {
       setDownloading(size);
-      await invoke("download_model_size", { size });
-      setSelectedModel(size);
+      try {
+        await invoke("download_model_size", { size });
+        setSelectedModel(size);
+      } catch (e) {
+        console.error(e);
+        setDownloading(null);
+      }
     }
   };
 
+  const handleCancelDownload = (size: string) => {
+    invoke("cancel_model_download", { key: size }).catch(console.error);
+  };
+
   const handleClose = () => {
     invoke("close_settings_window");
   };
@@ -101,6 +318,13 @@ export function Settings() {
           </select>
         </section>
 
+        <section>
+          <label className="text-xs font-medium uppercase tracking-wider mb-3 block" style={{ color: creamColorDim }}>
+            Test Microphone
+          </label>
+          <MicrophoneTest />
+        </section>
+
         <section>
           <label className="text-xs font-medium uppercase tracking-wider mb-3 block" style={{ color: creamColorDim }}>
             Whisper Model
@@ -116,9 +340,8 @@ export function Settings() {
               return (
                 <button
                   key={size}
-                  onClick={() => handleModelSelect(size)}
-                  disabled={isDownloading}
-                  className="flex items-center gap-3 px-3 py-2.5 rounded-lg transition-colors text-left disabled:opacity-50"
+                  onClick={() => isDownloading ? handleCancelDownload(size) : handleModelSelect(size)}
+                  className="flex items-center gap-3 px-3 py-2.5 rounded-lg transition-colors text-left"
                   style={{
                     backgroundColor: isSelected ? "rgba(255, 253, 245, 0.1)" : "transparent",
                     border: `1px solid ${isSelected ? creamColorDim : borderColor}`,
@@ -143,9 +366,9 @@ export function Settings() {
                       </div>
                     )}
                   </div>
-                  {!downloaded && !isDownloading && (
+                  {!downloaded && (
                     <span className="text-xs px-2 py-0.5 rounded" style={{ color: creamColorDim, backgroundColor: "rgba(255, 253, 245, 0.1)" }}>
-                      Download
+                      {isDownloading ? "Cancel" : "Download"}
                     </span>
                   )}
                 </button>
@@ -154,6 +377,63 @@ export function Settings() {
           </div>
         </section>
 
+        <section>
+          <label className="text-xs font-medium uppercase tracking-wider mb-3 block" style={{ color: creamColorDim }}>
+            Transcribe a File
+          </label>
+          <div
+            className="flex flex-col gap-2 px-3 py-2.5 rounded-lg select-text"
+            style={{ border: `1px dashed ${borderColor}` }}
+          >
+            {!fileTranscription && (
+              <span className="text-sm" style={{ color: creamColorDim }}>
+                Drop a WAV, MP3, M4A or FLAC file here
+              </span>
+            )}
+            {fileTranscription && (
+              <>
+                <span className="text-sm truncate" style={{ color: creamColor }}>{fileTranscription.name}</span>
+                {fileTranscription.text === null && fileTranscription.error === null && (
+                  <div className="h-1 rounded-full overflow-hidden" style={{ backgroundColor: "rgba(255, 253, 245, 0.1)" }}>
+                    <div
+                      className="h-full transition-all duration-300 rounded-full"
+                      style={{
+                        width: `${fileTranscription.total ? Math.round((fileTranscription.done / fileTranscription.total) * 100) : 0}%`,
+                        backgroundColor: creamColor,
+                      }}
+                    />
+                  </div>
+                )}
+                {fileTranscription.text !== null && (
+                  <p className="text-xs max-h-32 overflow-y-auto" style={{ color: creamColorDim }}>{fileTranscription.text}</p>
+                )}
+                {fileTranscription.segments.length > 0 && (
+                  <div className="flex items-center gap-2">
+                    {(["srt", "vtt", "md"] as const).map((format) => (
+                      <button
+                        key={format}
+                        onClick={() => handleExport(format)}
+                        className="text-xs px-2 py-0.5 rounded hover:bg-white/10 transition-colors"
+                        style={{ color: creamColorDim, border: `1px solid ${borderColor}` }}
+                      >
+                        Save .{format}
+                      </button>
+                    ))}
+                    {fileTranscription.saved && (
+                      <span className="text-xs truncate" style={{ color: creamColorDim }}>
+                        Saved {fileTranscription.saved.split(/[\\/]/).pop()}
+                      </span>
+                    )}
+                  </div>
+                )}
+                {fileTranscription.error !== null && (
+                  <span className="text-xs" style={{ color: "#f87171" }}>{fileTranscription.error}</span>
+                )}
+              </>
+            )}
+          </div>
+        </section>
+
         <section>
           <label className="text-xs font-medium uppercase tracking-wider mb-3 block" style={{ color: creamColorDim }}>
             Permissions
@@ -174,11 +454,11 @@ export function Settings() {
                 </span>
                 {!micPermission && (
                   <button
-                    onClick={() => invoke("open_microphone_settings")}
+                    onClick={() => requestMicrophone().catch(console.error)}
                     className="text-xs px-2 py-0.5 rounded hover:bg-white/10 transition-colors"
                     style={{ color: creamColorDim, border: `1px solid ${borderColor}` }}
                   >
-                    Open
+                    Allow
                   </button>
                 )}
               </div>